@@ -63,6 +63,9 @@ pub enum Error {
 
     #[error("Cannot hide the wikitext for the latest page revision")]
     CannotHideLatestRevision,
+
+    #[error("The pages fetched for includes do not match those requested")]
+    IncludedPagesMismatch,
 }
 
 impl Error {
@@ -74,7 +77,7 @@ impl Error {
             Error::Localization(inner) => TideError::new(StatusCode::NotFound, inner),
             Error::Serde(inner) => TideError::new(StatusCode::InternalServerError, inner),
             Error::Web(inner) => inner,
-            Error::InvalidEnumValue => {
+            Error::InvalidEnumValue | Error::IncludedPagesMismatch => {
                 TideError::from_str(StatusCode::InternalServerError, "")
             }
             Error::BadRequest => TideError::from_str(StatusCode::BadRequest, ""),
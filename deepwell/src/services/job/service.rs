@@ -61,6 +61,18 @@ impl JobService {
 
         Self::queue_job(Job::RerenderPageId { site_id, page_id });
     }
+
+    /// Removes all jobs currently in the queue, without processing them.
+    #[cfg(test)]
+    pub fn take_queued() -> Vec<Job> {
+        let mut jobs = Vec::new();
+
+        while let Ok(job) = sink!().try_recv() {
+            jobs.push(job);
+        }
+
+        jobs
+    }
 }
 
 #[derive(Debug)]
@@ -232,27 +232,13 @@ impl LinkService {
         let mut connections_missing = HashMap::new();
         let mut external_links = HashMap::new();
 
-        // Get include stats
-        for include in &backlinks.included_pages {
+        // Get include and internal page link stats
+        for (page_ref, connection_type) in page_connections(backlinks) {
             count_connections(
                 ctx,
                 site_id,
-                include,
-                // TODO: update Backlinks so that it also tracks other kinds of includes and components
-                ConnectionType::IncludeMessy,
-                &mut connections,
-                &mut connections_missing,
-            )
-            .await?;
-        }
-
-        // Get internal page link stats
-        for link in &backlinks.internal_links {
-            count_connections(
-                ctx,
-                site_id,
-                link,
-                ConnectionType::Link,
+                page_ref,
+                connection_type,
                 &mut connections,
                 &mut connections_missing,
             )
@@ -276,6 +262,33 @@ impl LinkService {
     }
 }
 
+/// Lists the pages this page is connected to, and how.
+///
+/// Each reference is listed once per occurrence, so that they can be counted.
+fn page_connections<'a>(
+    backlinks: &'a Backlinks,
+) -> impl Iterator<Item = (&'a PageRef<'a>, ConnectionType)> {
+    // TODO: update Backlinks so that it also tracks components
+    let included_pages = backlinks
+        .included_pages
+        .iter()
+        .map(|page_ref| (page_ref, ConnectionType::IncludeMessy));
+
+    let included_elements = backlinks
+        .included_elements
+        .iter()
+        .map(|page_ref| (page_ref, ConnectionType::IncludeElements));
+
+    let internal_links = backlinks
+        .internal_links
+        .iter()
+        .map(|page_ref| (page_ref, ConnectionType::Link));
+
+    included_pages
+        .chain(included_elements)
+        .chain(internal_links)
+}
+
 // Update link helpers
 
 async fn update_connections(
@@ -503,3 +516,37 @@ async fn count_connections(
 
     Ok(())
 }
+
+#[test]
+fn test_page_connections() {
+    use std::borrow::Cow;
+
+    fn page_ref(page: &'static str) -> PageRef<'static> {
+        PageRef {
+            site: None,
+            page: Cow::Borrowed(page),
+        }
+    }
+
+    let backlinks = Backlinks {
+        included_pages: vec![page_ref("component:messy")],
+        included_elements: vec![page_ref("component:box"), page_ref("component:box")],
+        internal_links: vec![page_ref("scp-001")],
+        external_links: vec![Cow::Borrowed("https://example.com/")],
+    };
+
+    let connections = page_connections(&backlinks)
+        .map(|(page_ref, connection_type)| (page_ref.page(), connection_type))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        connections,
+        vec![
+            ("component:messy", ConnectionType::IncludeMessy),
+            ("component:box", ConnectionType::IncludeElements),
+            ("component:box", ConnectionType::IncludeElements),
+            ("scp-001", ConnectionType::Link),
+        ],
+        "Actual page connections don't match expected",
+    );
+}
@@ -19,6 +19,7 @@
  */
 
 use super::prelude::*;
+use crate::models::page_connection::Model as PageConnectionModel;
 use crate::services::{JobService, LinkService, PageService};
 use crate::web::{split_category_name, ConnectionType};
 
@@ -93,12 +94,7 @@ impl OutdateService {
         const CONNECTION_TYPES: &[ConnectionType] = &[ConnectionType::Link];
 
         let result = LinkService::get_to(ctx, page_id, Some(CONNECTION_TYPES)).await?;
-        let ids = result
-            .connections
-            .iter()
-            .map(|connection| (site_id, connection.from_page_id))
-            .filter(|&(_, to_page_id)| to_page_id != page_id)
-            .collect::<Vec<_>>();
+        let ids = connected_page_ids(site_id, page_id, &result.connections);
 
         Self::outdate(ids);
         Ok(())
@@ -116,12 +112,7 @@ impl OutdateService {
         ];
 
         let result = LinkService::get_to(ctx, page_id, Some(CONNECTION_TYPES)).await?;
-        let ids = result
-            .connections
-            .iter()
-            .map(|connection| (site_id, connection.from_page_id))
-            .filter(|&(_, to_page_id)| to_page_id != page_id)
-            .collect::<Vec<_>>();
+        let ids = connected_page_ids(site_id, page_id, &result.connections);
 
         Self::outdate(ids);
         Ok(())
@@ -159,3 +150,154 @@ impl OutdateService {
         Ok(())
     }
 }
+
+/// Gets the IDs of pages with connections to the given page.
+///
+/// Each page is only listed once, even if it has several kinds of connections,
+/// and the page itself is excluded.
+fn connected_page_ids(
+    site_id: i64,
+    page_id: i64,
+    connections: &[PageConnectionModel],
+) -> Vec<(i64, i64)> {
+    let mut ids = connections
+        .iter()
+        .map(|connection| connection.from_page_id)
+        .filter(|&from_page_id| from_page_id != page_id)
+        .map(|from_page_id| (site_id, from_page_id))
+        .collect::<Vec<_>>();
+
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+#[cfg(test)]
+fn connection(
+    from_page_id: i64,
+    to_page_id: i64,
+    connection_type: ConnectionType,
+) -> PageConnectionModel {
+    use crate::utils::now;
+
+    PageConnectionModel {
+        from_page_id,
+        to_page_id,
+        connection_type: str!(connection_type.name()),
+        created_at: now(),
+        updated_at: None,
+        count: 1,
+    }
+}
+
+#[test]
+fn test_connected_page_ids() {
+    macro_rules! check {
+        ($connections:expr, $expected:expr $(,)?) => {{
+            let connections: Vec<PageConnectionModel> = $connections;
+            let expected: Vec<(i64, i64)> = $expected;
+
+            assert_eq!(
+                connected_page_ids(1, 100, &connections),
+                expected,
+                "Actual pages to outdate don't match expected",
+            );
+        }};
+    }
+
+    check!(vec![], vec![]);
+
+    // Pages including an edited page are re-rendered
+    check!(
+        vec![
+            connection(10, 100, ConnectionType::IncludeMessy),
+            connection(11, 100, ConnectionType::IncludeElements),
+        ],
+        vec![(1, 10), (1, 11)],
+    );
+
+    // Pages with several connections are only re-rendered once
+    check!(
+        vec![
+            connection(12, 100, ConnectionType::IncludeMessy),
+            connection(10, 100, ConnectionType::IncludeMessy),
+            connection(12, 100, ConnectionType::IncludeElements),
+        ],
+        vec![(1, 10), (1, 12)],
+    );
+
+    // Pages including themselves aren't re-rendered
+    check!(
+        vec![
+            connection(100, 100, ConnectionType::IncludeMessy),
+            connection(10, 100, ConnectionType::IncludeMessy),
+        ],
+        vec![(1, 10)],
+    );
+}
+
+#[async_std::test]
+async fn test_process_page_edit() {
+    use crate::api::ServerState;
+    use crate::config::Config;
+    use crate::locales::Localizations;
+    use crate::services::job::Job;
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+    use std::sync::Arc;
+
+    // Page 100 is included by page 10, which is included by pages 11 and 12.
+    //
+    // Each edit fetches the connections to that page, in the order the
+    // pages are re-rendered.
+    let database = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![
+            vec![connection(10, 100, ConnectionType::IncludeElements)],
+            vec![
+                connection(11, 10, ConnectionType::IncludeMessy),
+                connection(12, 10, ConnectionType::IncludeElements),
+            ],
+            vec![],
+            vec![],
+        ])
+        .into_connection();
+
+    let localizations = Localizations::open("../locales")
+        .await
+        .expect("Unable to load localizations");
+
+    let state = Arc::new(ServerState {
+        config: Config::default(),
+        database,
+        localizations,
+    });
+
+    let txn = state
+        .database
+        .begin()
+        .await
+        .expect("Unable to begin transaction");
+    let ctx = ServiceContext::from_raw(&state, &txn);
+
+    // Edit the page, then re-render each queued page, as the job runner would
+    let mut edited = vec![(1, 100)];
+    let mut queued = Vec::new();
+
+    while let Some((site_id, page_id)) = edited.pop() {
+        let slug = format!("page-{page_id}");
+        OutdateService::process_page_edit(&ctx, site_id, page_id, &slug)
+            .await
+            .expect("Unable to process page edit");
+
+        for job in JobService::take_queued() {
+            let Job::RerenderPageId { site_id, page_id } = job;
+            queued.push((site_id, page_id));
+            edited.insert(0, (site_id, page_id));
+        }
+    }
+
+    assert_eq!(
+        queued,
+        vec![(1, 10), (1, 11), (1, 12)],
+        "Actual pages queued for re-rendering don't match expected",
+    );
+}
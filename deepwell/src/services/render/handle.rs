@@ -0,0 +1,164 @@
+/*
+ * services/render/handle.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::locales::Localizations;
+use fluent::FluentArgs;
+use ftml::data::UserInfo;
+use ftml::render::{DefaultHandle, Handle};
+use ftml::tree::{ImageSource, LinkLocation};
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use unic_langid::LanguageIdentifier;
+
+lazy_static! {
+    static ref FALLBACK_LOCALE: LanguageIdentifier =
+        LanguageIdentifier::from_bytes(b"en").expect("Fallback locale is invalid");
+}
+
+/// A renderer handle which localizes messages using the Fluent bundles.
+///
+/// Message keys from ftml are looked up in the `wiki-page` component,
+/// prefixed with `wiki-page-`. If the page's language has no translation,
+/// then English is used instead.
+///
+/// All other requests are passed to ftml's [`DefaultHandle`].
+#[derive(Debug)]
+pub struct LocalizedHandle<'a> {
+    localizations: &'a Localizations,
+}
+
+impl<'a> LocalizedHandle<'a> {
+    #[inline]
+    pub fn new(localizations: &'a Localizations) -> Self {
+        LocalizedHandle { localizations }
+    }
+
+    /// Localizes a message from the `wiki-page` component, with the given arguments.
+    ///
+    /// If the page's language has no translation, then English is used instead.
+    /// If neither does, then `None` is returned.
+    pub fn get_message_with_args(
+        &self,
+        language: &str,
+        message: &str,
+        args: &FluentArgs,
+    ) -> Option<String> {
+        let key = format!("wiki-page-{message}");
+
+        // Try the page's language first
+        match LanguageIdentifier::from_bytes(language.as_bytes()) {
+            Ok(locale) => {
+                if let Some(text) = self.translate(&locale, &key, args) {
+                    return Some(text);
+                }
+            }
+            Err(error) => {
+                tide::log::warn!("Invalid page language '{language}': {error}");
+            }
+        }
+
+        // Then the fallback locale
+        tide::log::debug!("No message {key} for language '{language}', using fallback");
+        if let Some(text) = self.translate(&FALLBACK_LOCALE, &key, args) {
+            return Some(text);
+        }
+
+        tide::log::warn!("No message {key} in fallback locale");
+        None
+    }
+
+    fn translate(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &str,
+        args: &FluentArgs,
+    ) -> Option<String> {
+        self.localizations
+            .translate(locale, key, args)
+            .ok()
+            .map(Cow::into_owned)
+    }
+}
+
+impl Handle for LocalizedHandle<'_> {
+    #[inline]
+    fn get_page_title(&self, link: &LinkLocation) -> String {
+        DefaultHandle.get_page_title(link)
+    }
+
+    #[inline]
+    fn get_user_info<'a>(&self, name: &'a str) -> Option<UserInfo<'a>> {
+        DefaultHandle.get_user_info(name)
+    }
+
+    #[inline]
+    fn get_image_link<'a>(
+        &self,
+        source: &ImageSource<'a>,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Option<Cow<'a, str>> {
+        DefaultHandle.get_image_link(source, info, settings)
+    }
+
+    fn get_message(&self, language: &str, message: &str) -> Cow<'static, str> {
+        match self.get_message_with_args(language, message, &FluentArgs::new()) {
+            Some(text) => Cow::Owned(text),
+
+            // Finally, ftml's built-in English message
+            None => DefaultHandle.get_message(language, message),
+        }
+    }
+
+    #[inline]
+    fn post_html(&self, info: &PageInfo, html: &str) -> String {
+        DefaultHandle.post_html(info, html)
+    }
+
+    #[inline]
+    fn post_code(&self, index: NonZeroUsize, code: &str) {
+        DefaultHandle.post_code(index, code)
+    }
+}
+
+#[async_std::test]
+async fn localized_messages() {
+    use ftml::render::messages::{english_message, MESSAGE_KEYS};
+
+    let localizations = Localizations::open("../locales")
+        .await
+        .expect("Unable to load localizations");
+
+    let handle = LocalizedHandle::new(&localizations);
+
+    for message in MESSAGE_KEYS {
+        let key = format!("wiki-page-{message}");
+        assert!(
+            localizations.has_message(&FALLBACK_LOCALE, &key),
+            "No English localization for message key {message}",
+        );
+
+        let english = english_message(message).unwrap();
+        assert_eq!(handle.get_message("en", message), english);
+        assert_eq!(handle.get_message("xx", message), english);
+        assert_eq!(handle.get_message("?", message), english);
+    }
+}
@@ -0,0 +1,427 @@
+/*
+ * services/render/includer.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::handle::LocalizedHandle;
+use super::prelude::*;
+use crate::locales::Localizations;
+use crate::services::{PageService, RevisionService, SiteService, TextService};
+use fluent::FluentArgs;
+use ftml::data::PageRef;
+use ftml::includes::{AsyncIncluder, FetchedPage, IncludeRef, Includer};
+use ftml::render::wikitext::escape_text;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use tide::utils::async_trait;
+use void::Void;
+use wikidot_normalize::normalize;
+
+/// The slug of the page holding a site's "no such include" template.
+///
+/// If this page exists, its wikitext is used in place of includes of
+/// pages which don't exist, with `{$page}` replaced by the missing page.
+/// The page is escaped, so that it appears as written.
+pub const NO_SUCH_INCLUDE_SLUG: &str = "_no-such-include";
+
+/// The "no such include" template used if a site does not specify one.
+const DEFAULT_NO_SUCH_INCLUDE: &str = "\
+[[div class=\"wj-error-block\"]]
+The page {$page} you want to include does not exist.
+[[/div]]";
+
+/// Includer which fetches pages from the database.
+///
+/// Page references without a site are resolved relative to the site being rendered.
+/// Those within included pages from other sites are already given that site by ftml.
+///
+/// Messages for include cycles are localized in the given language.
+#[derive(Debug)]
+pub struct DatabaseIncluder<'c, 'txn> {
+    ctx: &'c ServiceContext<'txn>,
+    site_id: i64,
+    language: &'c str,
+    no_such_include: Option<String>,
+}
+
+impl<'c, 'txn> DatabaseIncluder<'c, 'txn> {
+    #[inline]
+    pub fn new(ctx: &'c ServiceContext<'txn>, site_id: i64, language: &'c str) -> Self {
+        DatabaseIncluder {
+            ctx,
+            site_id,
+            language,
+            no_such_include: None,
+        }
+    }
+
+    /// Gets the wikitext of the given page, if it exists.
+    pub async fn fetch_page(&self, page_ref: &PageRef<'_>) -> Result<Option<String>> {
+        let site_id = match page_ref.site() {
+            None => self.site_id,
+            Some(site_slug) => {
+                match SiteService::get_optional(self.ctx, Reference::Slug(site_slug))
+                    .await?
+                {
+                    Some(site) => site.site_id,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let slug = normalize_slug(page_ref.page());
+        get_page_wikitext(self.ctx, site_id, &slug).await
+    }
+
+    /// Gets the site's "no such include" template, fetching it if needed.
+    pub async fn no_such_include_template(&mut self) -> Result<&str> {
+        if self.no_such_include.is_none() {
+            let template =
+                get_page_wikitext(self.ctx, self.site_id, NO_SUCH_INCLUDE_SLUG).await?;
+
+            self.no_such_include =
+                Some(template.unwrap_or_else(|| str!(DEFAULT_NO_SUCH_INCLUDE)));
+        }
+
+        Ok(self.no_such_include.as_deref().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl<'t> AsyncIncluder<'t> for DatabaseIncluder<'_, '_> {
+    type Error = Error;
+
+    async fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>> {
+        let mut pages = Vec::with_capacity(includes.len());
+
+        for include in includes {
+            let page_ref = include.page_ref().clone();
+            let content = self.fetch_page(&page_ref).await?.map(Cow::Owned);
+
+            tide::log::debug!(
+                "Fetched included page {page_ref} (exists: {})",
+                content.is_some(),
+            );
+
+            pages.push(FetchedPage { page_ref, content });
+        }
+
+        Ok(pages)
+    }
+
+    async fn no_such_include(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>> {
+        let template = self.no_such_include_template().await?;
+        Ok(Cow::Owned(no_such_include_wikitext(template, page_ref)))
+    }
+
+    async fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>> {
+        let handle = LocalizedHandle::new(&self.ctx.state().localizations);
+        Ok(Cow::Owned(include_error_wikitext(
+            &handle,
+            self.language,
+            "include-cycle",
+            page_ref,
+        )))
+    }
+
+    async fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>> {
+        let handle = LocalizedHandle::new(&self.ctx.state().localizations);
+        Ok(Cow::Owned(include_error_wikitext(
+            &handle,
+            self.language,
+            "include-depth-exceeded",
+            page_ref,
+        )))
+    }
+}
+
+/// Pages already fetched from the database, for `[[include-elements]]`.
+///
+/// The parser fetches pages as it reaches each block, so it cannot wait on
+/// database queries. Instead, pages which have not been fetched are recorded,
+/// and the wikitext is parsed again once they have been. Until then, they are
+/// treated as missing.
+///
+/// Messages for include cycles are localized in the given language.
+#[derive(Debug)]
+pub struct PrefetchedPages<'a> {
+    pages: HashMap<PageRef<'static>, Option<String>>,
+    unfetched: RefCell<Vec<PageRef<'static>>>,
+    no_such_include: String,
+    localizations: &'a Localizations,
+    language: &'a str,
+}
+
+impl<'a> PrefetchedPages<'a> {
+    #[inline]
+    pub fn new(
+        no_such_include: &str,
+        localizations: &'a Localizations,
+        language: &'a str,
+    ) -> Self {
+        PrefetchedPages {
+            pages: HashMap::new(),
+            unfetched: RefCell::new(Vec::new()),
+            no_such_include: str!(no_such_include),
+            localizations,
+            language,
+        }
+    }
+
+    /// Adds the wikitext of a page, or `None` if it does not exist.
+    pub fn insert(&mut self, page_ref: &PageRef, content: Option<String>) {
+        self.pages.insert(normalize_page_ref(page_ref), content);
+    }
+
+    /// Takes the pages which were requested by the parser, but have not been fetched.
+    pub fn take_unfetched(&self) -> Vec<PageRef<'static>> {
+        let mut unfetched = self.unfetched.take();
+        unfetched
+            .sort_unstable_by(|a, b| (a.site(), a.page()).cmp(&(b.site(), b.page())));
+        unfetched.dedup();
+        unfetched
+    }
+}
+
+impl<'t> Includer<'t> for &PrefetchedPages<'_> {
+    type Error = Void;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> StdResult<Vec<FetchedPage<'t>>, Void> {
+        let mut pages = Vec::with_capacity(includes.len());
+
+        for include in includes {
+            let page_ref = include.page_ref().clone();
+            let key = normalize_page_ref(&page_ref);
+            let content = match self.pages.get(&key) {
+                Some(content) => content.clone().map(Cow::Owned),
+                None => {
+                    self.unfetched.borrow_mut().push(key);
+                    None
+                }
+            };
+
+            pages.push(FetchedPage { page_ref, content });
+        }
+
+        Ok(pages)
+    }
+
+    fn no_such_include(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> StdResult<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(no_such_include_wikitext(
+            &self.no_such_include,
+            page_ref,
+        )))
+    }
+
+    fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> StdResult<Cow<'t, str>, Void> {
+        let handle = LocalizedHandle::new(self.localizations);
+        Ok(Cow::Owned(include_error_wikitext(
+            &handle,
+            self.language,
+            "include-cycle",
+            page_ref,
+        )))
+    }
+
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> StdResult<Cow<'t, str>, Void> {
+        let handle = LocalizedHandle::new(self.localizations);
+        Ok(Cow::Owned(include_error_wikitext(
+            &handle,
+            self.language,
+            "include-depth-exceeded",
+            page_ref,
+        )))
+    }
+}
+
+/// Substitutes the missing page into the site's "no such include" template.
+///
+/// The page is escaped, so that it is not interpreted as wikitext.
+fn no_such_include_wikitext(template: &str, page_ref: &PageRef) -> String {
+    let page = page_ref.to_string();
+    let mut parts = template.split("{$page}");
+    let mut output = str!(parts.next().unwrap_or_default());
+
+    for part in parts {
+        let line_start = output
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .is_empty();
+        output.push_str(&escape_text(&page, line_start));
+        output.push_str(part);
+    }
+
+    output
+}
+
+/// Produces the wikitext shown in place of an include which cannot be expanded,
+/// such as a page which includes itself.
+///
+/// The message is localized, and then escaped so that it is not interpreted as wikitext.
+fn include_error_wikitext(
+    handle: &LocalizedHandle,
+    language: &str,
+    message: &str,
+    page_ref: &PageRef,
+) -> String {
+    let page = page_ref.to_string();
+    let mut args = FluentArgs::new();
+    args.set("page", page.as_str());
+
+    let message = handle
+        .get_message_with_args(language, message, &args)
+        .unwrap_or(page);
+
+    format!(
+        "[[div class=\"wj-error-block\"]]\n{}\n[[/div]]",
+        escape_text(&message, true),
+    )
+}
+
+/// Gets the wikitext of the latest revision of the given page, if it exists.
+pub async fn get_page_wikitext(
+    ctx: &ServiceContext<'_>,
+    site_id: i64,
+    slug: &str,
+) -> Result<Option<String>> {
+    let page =
+        match PageService::get_optional(ctx, site_id, Reference::Slug(slug)).await? {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+
+    let revision = RevisionService::get_latest(ctx, site_id, page.page_id).await?;
+    let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+    Ok(Some(wikitext))
+}
+
+/// Normalizes a page slug, as written in an include, to match those in the database.
+pub fn normalize_slug(slug: &str) -> String {
+    let mut slug = str!(slug);
+    normalize(&mut slug);
+    slug
+}
+
+/// Normalizes a page reference, so that it matches the page in the database.
+pub fn normalize_page_ref(page_ref: &PageRef) -> PageRef<'static> {
+    PageRef {
+        site: page_ref.site().map(|site| Cow::Owned(str!(site))),
+        page: Cow::Owned(normalize_slug(page_ref.page())),
+    }
+}
+
+#[test]
+fn test_normalize_slug() {
+    assert_eq!(normalize_slug("page"), "page");
+    assert_eq!(normalize_slug("Some Page"), "some-page");
+    assert_eq!(
+        normalize_slug("component:Image-Block"),
+        "component:image-block"
+    );
+}
+
+#[test]
+fn test_no_such_include_wikitext() {
+    assert_eq!(
+        no_such_include_wikitext("Missing {$page}", &PageRef::page_only("component:box")),
+        "Missing component:box",
+    );
+    assert_eq!(
+        no_such_include_wikitext("Missing {$page}", &PageRef::page_only("**bold**")),
+        format!("Missing {}", escape_text("**bold**", false)),
+    );
+    assert_ne!(escape_text("**bold**", false), "**bold**");
+}
+
+#[async_std::test]
+async fn test_prefetched_pages() {
+    use ftml::settings::WikitextMode;
+
+    let localizations = Localizations::open("../locales")
+        .await
+        .expect("Unable to load localizations");
+
+    let page_info = PageInfo {
+        page: Cow::Borrowed("test"),
+        category: None,
+        site: Cow::Borrowed("test"),
+        title: Cow::Borrowed("Test page"),
+        alt_title: None,
+        rating: 0.0,
+        tags: vec![],
+        language: Cow::Borrowed("default"),
+    };
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let tokens = ftml::tokenize("[[include-elements Component:Box]]");
+    let mut pages = PrefetchedPages::new("Missing {$page}", &localizations, "en");
+
+    // Pages which have not been fetched yet are requested
+    let (tree, _) =
+        ftml::parse_with_includer(&tokens, &page_info, &settings, &pages).into();
+
+    assert_eq!(
+        pages.take_unfetched(),
+        vec![PageRef::page_only("component:box")],
+        "Actual unfetched pages don't match expected",
+    );
+    assert!(pages.take_unfetched().is_empty());
+
+    let html_output = HtmlRender::default().render(&tree, &page_info, &settings);
+    assert!(html_output.body.contains("Missing Component:Box"));
+
+    // Once fetched, they are included, and recorded in the backlinks
+    pages.insert(
+        &PageRef::page_only("component:box"),
+        Some(str!("[[include-elements Component:Box]] Inner text")),
+    );
+
+    let (tree, _) =
+        ftml::parse_with_includer(&tokens, &page_info, &settings, &pages).into();
+
+    assert!(pages.take_unfetched().is_empty());
+
+    let html_output = HtmlRender::default().render(&tree, &page_info, &settings);
+    assert!(html_output.body.contains("Inner text"));
+    assert!(html_output.body.contains("includes itself"));
+    assert_eq!(
+        html_output.backlinks.included_elements,
+        vec![
+            PageRef::page_only("Component:Box"),
+            PageRef::page_only("Component:Box"),
+        ],
+        "Actual included elements don't match expected",
+    );
+}
@@ -32,6 +32,8 @@ mod prelude {
     };
 }
 
+mod handle;
+mod includer;
 mod service;
 mod structs;
 
@@ -18,28 +18,97 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::handle::LocalizedHandle;
+use super::includer::{
+    get_page_wikitext, normalize_page_ref, DatabaseIncluder, PrefetchedPages,
+};
 use super::prelude::*;
 use crate::services::TextService;
+use ftml::includes::include_async;
 
 #[derive(Debug)]
 pub struct RenderService;
 
 impl RenderService {
+    /// Renders the wikitext for a page.
+    ///
+    /// If the page's category (or failing that, `_default`) has a live template,
+    /// then it is applied first. The `created_at` argument is the UNIX timestamp
+    /// of the page's creation, as used in templates.
+    ///
+    /// Included pages, both for messy includes and `[[include-elements]]`,
+    /// are fetched from the database, and are added to the backlinks of the output.
+    ///
+    /// Interface messages are localized according to the page's language.
     pub async fn render(
         ctx: &ServiceContext<'_>,
+        site_id: i64,
         mut wikitext: String,
         page_info: &PageInfo<'_>,
         settings: &WikitextSettings,
+        created_at: i64,
     ) -> Result<RenderOutput> {
         let compiled_generator = VERSION.clone();
 
-        // Run ftml to parse and render
-        // TODO include
+        // Apply the live template, if any
+        if let Some(template) = Self::get_template(ctx, site_id, page_info).await? {
+            wikitext = ftml::apply_template(&template, &wikitext, page_info, created_at);
+        }
+
+        // Substitute included pages
+        let includer = DatabaseIncluder::new(ctx, site_id, &page_info.language);
+        let (mut wikitext, included_pages) =
+            include_async(&wikitext, page_info, settings, includer, || {
+                Error::IncludedPagesMismatch
+            })
+            .await?;
+
+        // Run ftml to parse
+        //
+        // Pages for [[include-elements]] can only be fetched once the parser
+        // has found them, so it is run again after fetching any it requested.
         ftml::preprocess(&mut wikitext);
         let tokens = ftml::tokenize(&wikitext);
-        let result = ftml::parse(&tokens, page_info, settings);
-        let (tree, warnings) = result.into();
-        let html_output = HtmlRender.render(&tree, page_info, settings);
+        let mut includer = DatabaseIncluder::new(ctx, site_id, &page_info.language);
+        let mut pages = PrefetchedPages::new(
+            includer.no_such_include_template().await?,
+            &ctx.state().localizations,
+            &page_info.language,
+        );
+        let (tree, warnings) = loop {
+            let unfetched = {
+                let result =
+                    ftml::parse_with_includer(&tokens, page_info, settings, &pages);
+
+                let unfetched = pages.take_unfetched();
+                if unfetched.is_empty() {
+                    break result.into();
+                }
+
+                unfetched
+            };
+
+            for page_ref in unfetched {
+                let content = includer.fetch_page(&page_ref).await?;
+                pages.insert(&page_ref, content);
+            }
+        };
+
+        // Render
+        let handle = LocalizedHandle::new(&ctx.state().localizations);
+        let mut html_output = HtmlRender::new(&handle).render(&tree, page_info, settings);
+
+        // Record included pages, so their connections are stored
+        let backlinks = &mut html_output.backlinks;
+        backlinks.included_pages.extend(
+            included_pages
+                .iter()
+                .map(|included_page| normalize_page_ref(&included_page.page_ref)),
+        );
+
+        for page_ref in &mut backlinks.included_elements {
+            *page_ref = normalize_page_ref(page_ref);
+        }
 
         // Insert compiled HTML into text table
         let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
@@ -52,4 +121,73 @@ impl RenderService {
             compiled_generator,
         })
     }
+
+    /// Gets the wikitext of the nearest template page for this page, if any.
+    async fn get_template(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        page_info: &PageInfo<'_>,
+    ) -> Result<Option<String>> {
+        for slug in template_slugs(page_info) {
+            let wikitext = get_page_wikitext(ctx, site_id, &slug).await?;
+
+            if wikitext.is_some() {
+                tide::log::debug!("Found template page {slug} for rendering");
+                return Ok(wikitext);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Lists which template pages apply to the given page, in order of precedence.
+///
+/// This is `_template` in the page's own category, then `_template` in
+/// `_default`. Template pages themselves do not have templates applied.
+fn template_slugs(page_info: &PageInfo) -> Vec<String> {
+    if page_info.page == "_template" {
+        return vec![];
+    }
+
+    match page_info.category.as_deref() {
+        None | Some("_default") => vec![str!("_template")],
+        Some(category) => vec![format!("{category}:_template"), str!("_template")],
+    }
+}
+
+#[test]
+fn test_template_slugs() {
+    use std::borrow::Cow;
+
+    macro_rules! check {
+        ($category:expr, $page:expr, $expected:expr $(,)?) => {{
+            let page_info = PageInfo {
+                page: Cow::Borrowed($page),
+                category: $category.map(Cow::Borrowed),
+                site: Cow::Borrowed("test"),
+                title: Cow::Borrowed("Test page"),
+                alt_title: None,
+                rating: 0.0,
+                tags: vec![],
+                language: Cow::Borrowed("default"),
+            };
+
+            assert_eq!(
+                template_slugs(&page_info),
+                $expected,
+                "Actual template slugs don't match expected",
+            );
+        }};
+    }
+
+    check!(None, "apple", vec!["_template"]);
+    check!(Some("_default"), "apple", vec!["_template"]);
+    check!(
+        Some("fragment"),
+        "apple",
+        vec!["fragment:_template", "_template"],
+    );
+    check!(None, "_template", Vec::<String>::new());
+    check!(Some("fragment"), "_template", Vec::<String>::new());
 }
@@ -28,8 +28,8 @@ use crate::models::page_revision::{
 use crate::models::sea_orm_active_enums::RevisionType;
 use crate::services::render::RenderOutput;
 use crate::services::{
-    LinkService, OutdateService, ParentService, RenderService, ScoreService, SiteService,
-    TextService,
+    LinkService, OutdateService, PageService, ParentService, RenderService, ScoreService,
+    SiteService, TextService,
 };
 use crate::web::{split_category, split_category_name, FetchDirection};
 use ftml::data::PageInfo;
@@ -608,8 +608,9 @@ impl RevisionService {
             tags,
         }: RenderPageInfo<'_>,
     ) -> Result<RenderOutput> {
-        // Get site
+        // Get site and page
         let site = SiteService::get(ctx, Reference::from(site_id)).await?;
+        let page = PageService::get_direct(ctx, page_id).await?;
 
         // Set up parse context
         let settings = WikitextSettings::from_mode(WikitextMode::Page);
@@ -626,7 +627,15 @@ impl RevisionService {
         };
 
         // Parse and render
-        let output = RenderService::render(ctx, wikitext, &page_info, &settings).await?;
+        let output = RenderService::render(
+            ctx,
+            site_id,
+            wikitext,
+            &page_info,
+            &settings,
+            page.created_at.timestamp(),
+        )
+        .await?;
 
         // Update backlinks
         LinkService::update(ctx, site_id, page_id, &output.html_output.backlinks).await?;
@@ -1,6 +1,11 @@
 extern crate built;
+extern crate serde_json;
+extern crate toml;
 
+use serde_json::{json, Map, Value};
 use std::env;
+use std::fs;
+use std::path::Path;
 
 fn main() {
     // Generate build information
@@ -9,4 +14,80 @@ fn main() {
     }
 
     built::write_built_file().expect("Failed to compile build information!");
+
+    // Generate block and module information
+    let out_dir = env::var("OUT_DIR").expect("No output directory");
+    convert_spec("conf/blocks.toml", Path::new(&out_dir).join("blocks.json"));
+    convert_spec(
+        "conf/modules.toml",
+        Path::new(&out_dir).join("modules.json"),
+    );
+}
+
+/// Converts a block or module specification file into JSON for `ftml::info`.
+///
+/// Each table becomes an object in a list, with its key as `name`,
+/// and likewise for arguments. The `html-output` string, such as
+/// `html,div,wj-toc`, is split into its parts.
+fn convert_spec(input_path: &str, output_path: impl AsRef<Path>) {
+    let contents = fs::read_to_string(input_path).expect("Unable to read specification");
+    let spec: Map<String, Value> =
+        toml::from_str(&contents).expect("Unable to parse specification");
+
+    let entries: Vec<Value> = spec
+        .into_iter()
+        .map(|(name, value)| {
+            let mut entry = into_object(value);
+            entry.insert(String::from("name"), Value::String(name));
+
+            if let Some(arguments) = entry.remove("arguments") {
+                let arguments: Vec<Value> = into_object(arguments)
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let mut argument = into_object(value);
+                        argument.insert(String::from("name"), Value::String(name));
+
+                        if let Some(values) = argument.remove("enum") {
+                            argument.insert(String::from("values"), values);
+                        }
+
+                        Value::Object(argument)
+                    })
+                    .collect();
+
+                entry.insert(String::from("arguments"), Value::Array(arguments));
+            }
+
+            if let Some(Value::String(output)) = entry.remove("html-output") {
+                entry.insert(String::from("html-output"), convert_html_output(&output));
+            }
+
+            Value::Object(entry)
+        })
+        .collect();
+
+    let json =
+        serde_json::to_string(&entries).expect("Unable to serialize specification");
+    fs::write(output_path, json).expect("Unable to write specification");
+}
+
+fn convert_html_output(output: &str) -> Value {
+    let mut parts = output.split(',');
+
+    match parts.next() {
+        Some("html") => json!({
+            "type": "html",
+            "tag": parts.next().expect("No HTML tag in html-output"),
+            "class": parts.next(),
+        }),
+        Some(kind) => json!({ "type": kind }),
+        None => panic!("Empty html-output"),
+    }
+}
+
+fn into_object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => panic!("Specification value is not a table"),
+    }
 }
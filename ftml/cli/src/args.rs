@@ -0,0 +1,410 @@
+/*
+ * args.rs
+ *
+ * ftml-cli - Command-line tool for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use clap::{Arg, ArgMatches, Command};
+use ftml::data::PageInfo;
+use ftml::settings::{WikitextMode, WikitextSettings};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// What to output for each input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputKind {
+    Tokens,
+    Ast,
+    Html { body_only: bool },
+    Text,
+    Warnings,
+}
+
+impl OutputKind {
+    /// The file extension used when writing this output to a directory.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputKind::Tokens => "tokens.json",
+            OutputKind::Ast => "ast.json",
+            OutputKind::Html { body_only: true } => "html",
+            OutputKind::Html { body_only: false } => "html.json",
+            OutputKind::Text => "txt",
+            OutputKind::Warnings => "warnings.txt",
+        }
+    }
+}
+
+/// Where to read wikitext from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    /// The name of this input, for output files and messages.
+    pub fn name(&self) -> Cow<'_, str> {
+        match self {
+            Input::Stdin => Cow::Borrowed("stdin"),
+            Input::File(path) => path.to_string_lossy(),
+        }
+    }
+
+    /// The name of the page in this input, from its file name.
+    ///
+    /// Files are named after their page, such as `scp-001.ftml` or
+    /// `component:image-block.ftml`, as in the include directory.
+    fn page_slug(&self) -> Option<String> {
+        match self {
+            Input::Stdin => None,
+            Input::File(path) => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// Overrides for fields in the `PageInfo` of each input.
+#[derive(Debug, Default)]
+pub struct PageOverrides {
+    pub page: Option<String>,
+    pub category: Option<String>,
+    pub site: Option<String>,
+    pub title: Option<String>,
+    pub alt_title: Option<String>,
+    pub rating: Option<f64>,
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub output: OutputKind,
+    pub inputs: Vec<Input>,
+    pub settings: WikitextSettings,
+    pub page_overrides: PageOverrides,
+    pub include_directory: Option<PathBuf>,
+    pub output_directory: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn parse() -> Self {
+        let matches = command().get_matches();
+        let (name, submatches) = matches.subcommand().expect("No subcommand");
+
+        let output = match name {
+            "tokens" => OutputKind::Tokens,
+            "ast" => OutputKind::Ast,
+            "html" => OutputKind::Html {
+                body_only: submatches.is_present("body-only"),
+            },
+            "text" => OutputKind::Text,
+            "warnings" => OutputKind::Warnings,
+            _ => unreachable!("Unknown subcommand: {name}"),
+        };
+
+        let inputs = match submatches.values_of_os("files") {
+            Some(values) => values
+                .map(|value| {
+                    if value == "-" {
+                        Input::Stdin
+                    } else {
+                        Input::File(PathBuf::from(value))
+                    }
+                })
+                .collect(),
+            None => vec![Input::Stdin],
+        };
+
+        Options {
+            output,
+            inputs,
+            settings: parse_settings(submatches),
+            page_overrides: parse_page_overrides(submatches),
+            include_directory: submatches.value_of_os("include-dir").map(PathBuf::from),
+            output_directory: submatches.value_of_os("output-dir").map(PathBuf::from),
+        }
+    }
+
+    /// Builds the `PageInfo` for the given input.
+    ///
+    /// The page name defaults to the file name, and a category
+    /// prefix in it (such as `fragment:`) is used as the category.
+    pub fn page_info(&self, input: &Input) -> PageInfo<'static> {
+        let overrides = &self.page_overrides;
+        let slug = overrides
+            .page
+            .clone()
+            .or_else(|| input.page_slug())
+            .unwrap_or_else(|| str!("page"));
+
+        let (category, page) = match slug.split_once(':') {
+            Some((category, page)) => (Some(str!(category)), str!(page)),
+            None => (None, slug),
+        };
+
+        let category = overrides.category.clone().or(category);
+        let title = overrides.title.clone().unwrap_or_else(|| page.clone());
+
+        PageInfo {
+            page: Cow::Owned(page),
+            category: category.map(Cow::Owned),
+            site: Cow::Owned(overrides.site.clone().unwrap_or_else(|| str!("www"))),
+            title: Cow::Owned(title),
+            alt_title: overrides.alt_title.clone().map(Cow::Owned),
+            rating: overrides.rating.unwrap_or(0.0),
+            tags: overrides.tags.iter().cloned().map(Cow::Owned).collect(),
+            language: Cow::Owned(
+                overrides
+                    .language
+                    .clone()
+                    .unwrap_or_else(|| str!("default")),
+            ),
+        }
+    }
+
+    #[inline]
+    pub fn include_directory(&self) -> Option<&Path> {
+        self.include_directory.as_deref()
+    }
+}
+
+fn command() -> Command<'static> {
+    Command::new("ftml")
+        .version(ftml::info::PKG_VERSION)
+        .long_version(ftml::info::FULL_VERSION.as_str())
+        .about("Parse and render Wikidot text, from files or standard input.")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("mode")
+                .short('m')
+                .long("mode")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values([
+                    "page",
+                    "draft",
+                    "forum-post",
+                    "direct-message",
+                    "list",
+                ])
+                .default_value("page")
+                .global(true)
+                .help("What context the wikitext is processed in."),
+        )
+        .arg(
+            Arg::new("include-compatibility")
+                .long("include-compatibility")
+                .global(true)
+                .help("Treat [[include]] as [[include-messy]], as Wikidot does."),
+        )
+        .arg(
+            Arg::new("include-dir")
+                .short('I')
+                .long("include-dir")
+                .takes_value(true)
+                .value_name("DIRECTORY")
+                .allow_invalid_utf8(true)
+                .global(true)
+                .help("Directory of .ftml files to resolve includes from."),
+        )
+        .arg(
+            Arg::new("output-dir")
+                .short('o')
+                .long("output-dir")
+                .takes_value(true)
+                .value_name("DIRECTORY")
+                .allow_invalid_utf8(true)
+                .global(true)
+                .help("Write the output for each input to a file in this directory."),
+        )
+        .arg(
+            Arg::new("page")
+                .long("page")
+                .takes_value(true)
+                .value_name("SLUG")
+                .global(true)
+                .help("The page name. Defaults to the file name."),
+        )
+        .arg(
+            Arg::new("category")
+                .long("category")
+                .takes_value(true)
+                .value_name("NAME")
+                .global(true)
+                .help("The page category."),
+        )
+        .arg(
+            Arg::new("site")
+                .long("site")
+                .takes_value(true)
+                .value_name("SLUG")
+                .global(true)
+                .help("The site the page is on. Defaults to \"www\"."),
+        )
+        .arg(
+            Arg::new("title")
+                .long("title")
+                .takes_value(true)
+                .value_name("TEXT")
+                .global(true)
+                .help("The page title. Defaults to the page name."),
+        )
+        .arg(
+            Arg::new("alt-title")
+                .long("alt-title")
+                .takes_value(true)
+                .value_name("TEXT")
+                .global(true)
+                .help("The alternate title of the page."),
+        )
+        .arg(
+            Arg::new("rating")
+                .long("rating")
+                .takes_value(true)
+                .value_name("NUMBER")
+                .global(true)
+                .help("The page rating."),
+        )
+        .arg(
+            Arg::new("tag")
+                .short('t')
+                .long("tag")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .value_name("TAG")
+                .global(true)
+                .help("A tag on the page. May be given multiple times."),
+        )
+        .arg(
+            Arg::new("language")
+                .long("language")
+                .takes_value(true)
+                .value_name("LOCALE")
+                .global(true)
+                .help("The language of the page."),
+        )
+        .subcommand(with_files(
+            Command::new("tokens").about("Output the tokens from lexing, as JSON."),
+        ))
+        .subcommand(with_files(
+            Command::new("ast").about("Output the syntax tree, as JSON."),
+        ))
+        .subcommand(with_files(
+            Command::new("html")
+                .about("Output the rendered HTML body, styles, and meta tags, as JSON.")
+                .arg(
+                    Arg::new("body-only")
+                        .short('b')
+                        .long("body-only")
+                        .help("Only output the HTML body."),
+                ),
+        ))
+        .subcommand(with_files(
+            Command::new("text").about("Output the rendered text."),
+        ))
+        .subcommand(with_files(Command::new("warnings").about(
+            "Output the parser warnings, exiting unsuccessfully if there are any.",
+        )))
+}
+
+fn with_files(command: Command<'static>) -> Command<'static> {
+    command.arg(
+        Arg::new("files")
+            .takes_value(true)
+            .multiple_values(true)
+            .value_name("FILE")
+            .allow_invalid_utf8(true)
+            .help("Files to read wikitext from. Reads from standard input if none, or \"-\"."),
+    )
+}
+
+fn parse_settings(matches: &ArgMatches) -> WikitextSettings {
+    let mode = match matches.value_of("mode") {
+        Some("page") | None => WikitextMode::Page,
+        Some("draft") => WikitextMode::Draft,
+        Some("forum-post") => WikitextMode::ForumPost,
+        Some("direct-message") => WikitextMode::DirectMessage,
+        Some("list") => WikitextMode::List,
+        Some(mode) => unreachable!("Unknown mode: {mode}"),
+    };
+
+    let mut settings = WikitextSettings::from_mode(mode);
+    settings.use_include_compatibility = matches.is_present("include-compatibility");
+    settings
+}
+
+fn parse_page_overrides(matches: &ArgMatches) -> PageOverrides {
+    let get = |name| matches.value_of(name).map(String::from);
+
+    let rating = matches.value_of("rating").map(|value| match value.parse() {
+        Ok(rating) => rating,
+        Err(_) => {
+            eprintln!("Invalid rating: {value}");
+            process::exit(2);
+        }
+    });
+
+    PageOverrides {
+        page: get("page"),
+        category: get("category"),
+        site: get("site"),
+        title: get("title"),
+        alt_title: get("alt-title"),
+        rating,
+        tags: matches
+            .values_of("tag")
+            .map(|values| values.map(String::from).collect())
+            .unwrap_or_default(),
+        language: get("language"),
+    }
+}
+
+#[test]
+fn command_is_valid() {
+    command().debug_assert();
+}
+
+#[test]
+fn page_info() {
+    let options = Options {
+        output: OutputKind::Ast,
+        inputs: vec![],
+        settings: WikitextSettings::from_mode(WikitextMode::Page),
+        page_overrides: PageOverrides {
+            site: Some(str!("scp-wiki")),
+            tags: vec![str!("tale")],
+            ..PageOverrides::default()
+        },
+        include_directory: None,
+        output_directory: None,
+    };
+
+    let page_info =
+        options.page_info(&Input::File(PathBuf::from("pages/fragment:scp-001.ftml")));
+    assert_eq!(page_info.page, "scp-001");
+    assert_eq!(page_info.category.as_deref(), Some("fragment"));
+    assert_eq!(page_info.site, "scp-wiki");
+    assert_eq!(page_info.title, "scp-001");
+    assert_eq!(page_info.tags, vec!["tale"]);
+
+    let page_info = options.page_info(&Input::Stdin);
+    assert_eq!(page_info.page, "page");
+    assert_eq!(page_info.category, None);
+}
@@ -0,0 +1,113 @@
+/*
+ * includer.rs
+ *
+ * ftml-cli - Command-line tool for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::data::PageRef;
+use ftml::includes::{FetchedPage, IncludeRef, Includer};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::Path;
+use wikidot_normalize::normalize;
+
+/// Fetches included pages from a directory of `.ftml` files.
+///
+/// Each page is read from the file named after its normalized name,
+/// for instance `[[include-messy Component:Image Block]]` reads
+/// `component:image-block.ftml`. Pages on other sites are never found,
+/// nor is anything if no directory was given.
+#[derive(Debug, Copy, Clone)]
+pub struct DirectoryIncluder<'a> {
+    directory: Option<&'a Path>,
+    site: &'a str,
+}
+
+impl<'a> DirectoryIncluder<'a> {
+    #[inline]
+    pub fn new(directory: Option<&'a Path>, site: &'a str) -> Self {
+        DirectoryIncluder { directory, site }
+    }
+
+    fn read_page(&self, page_ref: &PageRef) -> io::Result<Option<String>> {
+        let directory = match self.directory {
+            Some(directory) => directory,
+            None => return Ok(None),
+        };
+
+        if let Some(site) = page_ref.site() {
+            if site != self.site {
+                return Ok(None);
+            }
+        }
+
+        let mut name = str!(page_ref.page());
+        normalize(&mut name);
+
+        // Don't permit escaping the include directory
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Ok(None);
+        }
+
+        match fs::read_to_string(directory.join(format!("{name}.ftml"))) {
+            Ok(content) => Ok(Some(content)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl<'t> Includer<'t> for DirectoryIncluder<'_> {
+    type Error = io::Error;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> io::Result<Vec<FetchedPage<'t>>> {
+        includes
+            .iter()
+            .map(|include| {
+                let page_ref = include.page_ref().clone();
+                let content = self.read_page(&page_ref)?.map(Cow::Owned);
+
+                Ok(FetchedPage { page_ref, content })
+            })
+            .collect()
+    }
+
+    fn no_such_include(&mut self, page_ref: &PageRef<'t>) -> io::Result<Cow<'t, str>> {
+        Ok(Cow::Owned(format!(
+            "[[div class=\"error-block\"]]\nNo such page to include: {page_ref}\n[[/div]]",
+        )))
+    }
+
+    fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> io::Result<Cow<'t, str>> {
+        Ok(Cow::Owned(format!(
+            "[[div class=\"error-block\"]]\nPage includes itself: {page_ref}\n[[/div]]",
+        )))
+    }
+
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> io::Result<Cow<'t, str>> {
+        Ok(Cow::Owned(format!(
+            "[[div class=\"error-block\"]]\nPage is nested too deeply to include: {page_ref}\n[[/div]]",
+        )))
+    }
+}
@@ -0,0 +1,95 @@
+/*
+ * main.rs
+ *
+ * ftml-cli - Command-line tool for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[macro_use]
+extern crate str_macro;
+
+mod args;
+mod includer;
+mod process;
+
+use self::args::{Input, Options, OutputKind};
+use self::process::{process, Output};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process as exit;
+
+fn main() {
+    let options = Options::parse();
+
+    if let Some(directory) = &options.output_directory {
+        if let Err(error) = fs::create_dir_all(directory) {
+            eprintln!("ftml: {}: {error}", directory.display());
+            exit::exit(2);
+        }
+    }
+
+    let mut failed = false;
+    let mut has_warnings = false;
+
+    for input in &options.inputs {
+        let result = process(&options, input).and_then(|output| {
+            has_warnings |= output.has_warnings;
+            write_output(&options, input, output)
+        });
+
+        if let Err(error) = result {
+            eprintln!("ftml: {}: {error}", input.name());
+            failed = true;
+        }
+    }
+
+    if failed {
+        exit::exit(2);
+    }
+
+    if has_warnings && options.output == OutputKind::Warnings {
+        exit::exit(1);
+    }
+}
+
+fn write_output(options: &Options, input: &Input, output: Output) -> io::Result<()> {
+    let mut contents = output.contents;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+
+    match &options.output_directory {
+        Some(directory) => {
+            fs::write(output_path(directory, input, options.output), contents)
+        }
+        None => io::stdout().lock().write_all(contents.as_bytes()),
+    }
+}
+
+/// Gets the path to write the output for this input to,
+/// which is its file name with the extension for the output.
+fn output_path(directory: &Path, input: &Input, output: OutputKind) -> PathBuf {
+    let stem = match input {
+        Input::Stdin => str!("stdin"),
+        Input::File(path) => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| str!("output")),
+    };
+
+    directory.join(format!("{stem}.{}", output.extension()))
+}
@@ -0,0 +1,148 @@
+/*
+ * process.rs
+ *
+ * ftml-cli - Command-line tool for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::args::{Input, Options, OutputKind};
+use crate::includer::DirectoryIncluder;
+use ftml::parsing::ParseWarning;
+use ftml::render::html::HtmlRender;
+use ftml::render::text::TextRender;
+use ftml::render::Render;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read};
+
+/// The result of processing one input.
+#[derive(Debug)]
+pub struct Output {
+    pub contents: String,
+    pub has_warnings: bool,
+}
+
+/// Runs the wikitext in this input through ftml, producing the requested output.
+///
+/// This is the same process as rendering a page: includes are substituted,
+/// the text is preprocessed, then it is tokenized, parsed, and rendered.
+/// Warning locations refer to the text after these first two steps.
+pub fn process(options: &Options, input: &Input) -> io::Result<Output> {
+    let text = read_input(input)?;
+    let page_info = options.page_info(input);
+    let settings = &options.settings;
+    let includer = DirectoryIncluder::new(options.include_directory(), &page_info.site);
+
+    let (mut text, _) = ftml::include(&text, &page_info, settings, includer, || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Includer returned invalid pages",
+        )
+    })?;
+
+    ftml::preprocess(&mut text);
+    let tokens = ftml::tokenize(&text);
+
+    if options.output == OutputKind::Tokens {
+        return Ok(Output {
+            contents: to_json(tokens.tokens())?,
+            has_warnings: false,
+        });
+    }
+
+    let (tree, warnings) =
+        ftml::parse_with_includer(&tokens, &page_info, settings, includer).into();
+
+    let contents = match options.output {
+        OutputKind::Tokens => unreachable!(),
+        OutputKind::Ast => to_json(&tree)?,
+        OutputKind::Html { body_only } => {
+            let html = HtmlRender::default().render(&tree, &page_info, settings);
+
+            if body_only {
+                html.body
+            } else {
+                to_json(&html)?
+            }
+        }
+        OutputKind::Text => TextRender::default().render(&tree, &page_info, settings),
+        OutputKind::Warnings => format_warnings(&input.name(), &text, &warnings),
+    };
+
+    Ok(Output {
+        contents,
+        has_warnings: !warnings.is_empty(),
+    })
+}
+
+fn read_input(input: &Input) -> io::Result<String> {
+    match input {
+        Input::Stdin => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+        Input::File(path) => fs::read_to_string(path),
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(io::Error::from)
+}
+
+/// Formats warnings one per line, as `name:line:column: kind (rule)`, in order of position.
+fn format_warnings(name: &str, text: &str, warnings: &[ParseWarning]) -> String {
+    let mut warnings: Vec<_> = warnings.iter().collect();
+    warnings.sort_by_key(|warning| warning.span().start);
+
+    let mut output = String::new();
+    for warning in warnings {
+        let (line, column) = line_column(text, warning.span().start);
+
+        writeln!(
+            output,
+            "{name}:{line}:{column}: {} (rule {})",
+            warning.kind().name(),
+            warning.rule(),
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+/// Gets the one-based line and column of a byte offset.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+#[test]
+fn line_columns() {
+    let text = "abc\nd☃f\n\nxyz";
+
+    assert_eq!(line_column(text, 0), (1, 1));
+    assert_eq!(line_column(text, 2), (1, 3));
+    assert_eq!(line_column(text, 4), (2, 1));
+    assert_eq!(line_column(text, 8), (2, 3));
+    assert_eq!(line_column(text, 11), (4, 1));
+    assert_eq!(line_column(text, 100), (4, 4));
+}
@@ -0,0 +1,167 @@
+/*
+ * cli.rs
+ *
+ * ftml-cli - Command-line tool for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const PAGES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/pages");
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ftml"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Unable to run ftml");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn page(name: &str) -> String {
+    Path::new(PAGES).join(name).to_string_lossy().into_owned()
+}
+
+#[test]
+fn tokens() {
+    let output = run(&["tokens"], "**bold**");
+    assert!(output.status.success());
+
+    let tokens: Value = serde_json::from_str(stdout(&output)).unwrap();
+    let names: Vec<_> = tokens
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|token| token["token"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(
+        names,
+        vec!["input-start", "bold", "identifier", "bold", "input-end"],
+    );
+}
+
+#[test]
+fn ast() {
+    let output = run(&["ast"], "//apple//");
+    assert!(output.status.success());
+
+    let tree: Value = serde_json::from_str(stdout(&output)).unwrap();
+    assert!(tree["elements"].is_array());
+}
+
+#[test]
+fn html() {
+    let output = run(&["html", "--body-only"], "**bold**");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "<wj-body class=\"wj-body\"><p><strong>bold</strong></p></wj-body>\n",
+    );
+
+    let output = run(&["html", "--title", "Apple", "--tag", "fruit"], "text");
+    assert!(output.status.success());
+
+    let html: Value = serde_json::from_str(stdout(&output)).unwrap();
+    assert!(html["body"].as_str().unwrap().contains("text"));
+    assert!(html["styles"].is_array());
+    assert!(html["meta"].is_array());
+}
+
+#[test]
+fn mode() {
+    // Page syntax such as modules is not permitted in forum posts
+    let input = "[[module Rate]]";
+
+    let output = run(&["warnings"], input);
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    let output = run(&["warnings", "--mode", "forum-post"], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).starts_with("stdin:1:1: "));
+}
+
+#[test]
+fn includes() {
+    let main = page("main.ftml");
+
+    let output = run(&["text", "--include-dir", PAGES, &main], "");
+    assert!(output.status.success());
+
+    let text = stdout(&output);
+    assert!(text.contains("Hello from an include"));
+    assert!(text.contains("No such page to include: missing-page"));
+
+    let output = run(&["text", &main], "");
+    assert!(stdout(&output).contains("No such page to include: notice"));
+
+    let output = run(&["html", "-b", "-I", PAGES, &page("cycle.ftml")], "");
+    assert!(stdout(&output).contains("Page includes itself: cycle"));
+}
+
+#[test]
+fn output_directory() {
+    let directory: PathBuf = [env!("CARGO_TARGET_TMPDIR"), "output"].iter().collect();
+    let _ = fs::remove_dir_all(&directory);
+
+    let output = run(
+        &[
+            "html",
+            "--body-only",
+            "--output-dir",
+            &directory.to_string_lossy(),
+            "-I",
+            PAGES,
+            &page("main.ftml"),
+            &page("notice.ftml"),
+            "-",
+        ],
+        "From stdin",
+    );
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let read = |name| fs::read_to_string(directory.join(name)).unwrap();
+    assert!(read("main.html").contains("Hello from an include"));
+    assert!(read("notice.html").contains("class=\"notice\""));
+    assert!(read("stdin.html").contains("From stdin"));
+}
+
+#[test]
+fn errors() {
+    let output = run(&["text", "does-not-exist.ftml"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("ftml: does-not-exist.ftml: "));
+}
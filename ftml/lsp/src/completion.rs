@@ -0,0 +1,314 @@
+/*
+ * completion.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Completion of block names, module names, and their arguments.
+//!
+//! This looks at the text of the current line before the cursor,
+//! since the block being typed is generally incomplete, and so
+//! would not be parsed as a block.
+
+use crate::document::Document;
+use crate::spec;
+use ftml::info::{ArgumentInfo, ArgumentType, ArgumentValue};
+use lsp_types::{CompletionItem, CompletionItemKind, Position};
+use regex::Regex;
+
+lazy_static! {
+    static ref ARGUMENT_VALUE_REGEX: Regex =
+        Regex::new(r#"([A-Za-z0-9_-]+)\s*=\s*"[^"]*$"#).unwrap();
+}
+
+/// Safe HTML attributes offered for blocks which accept them.
+const HTML_ATTRIBUTES: [&str; 3] = ["class", "id", "style"];
+
+/// What is being typed at the cursor.
+#[derive(Debug, PartialEq, Eq)]
+enum Context<'a> {
+    /// The name of a block, after `[[`.
+    BlockName,
+
+    /// The name of a block being ended, after `[[/`.
+    EndBlockName,
+
+    /// The name of a module, after `[[module`.
+    ModuleName,
+
+    /// An argument in the head of a block or module.
+    Argument {
+        block: &'a str,
+        module: Option<&'a str>,
+    },
+
+    /// The value of an argument, within quotes.
+    ArgumentValue {
+        block: &'a str,
+        module: Option<&'a str>,
+        argument: &'a str,
+    },
+}
+
+pub fn completion(document: &Document, position: Position) -> Vec<CompletionItem> {
+    let offset = document.offset(position);
+    let text = &document.text()[..offset];
+    let line = &text[text.rfind('\n').map(|i| i + 1).unwrap_or(0)..];
+
+    match get_context(line) {
+        Some(Context::BlockName) => block_names(false),
+        Some(Context::EndBlockName) => block_names(true),
+        Some(Context::ModuleName) => module_names(),
+        Some(Context::Argument { block, module }) => arguments(block, module),
+        Some(Context::ArgumentValue {
+            block,
+            module,
+            argument,
+        }) => argument_values(block, module, argument),
+        None => vec![],
+    }
+}
+
+fn get_context(line: &str) -> Option<Context<'_>> {
+    // Find the block the cursor is in, if it hasn't been closed
+    let start = line.rfind("[[")?;
+    let head = &line[start + 2..];
+
+    if head.contains("]]") {
+        return None;
+    }
+
+    if let Some(name) = head.strip_prefix('/') {
+        return if name.contains(char::is_whitespace) {
+            None
+        } else {
+            Some(Context::EndBlockName)
+        };
+    }
+
+    let head = head.trim_start_matches(['*', '_']).trim_start();
+    let (block, rest) = match head.split_once(char::is_whitespace) {
+        Some(split) => split,
+        None => return Some(Context::BlockName),
+    };
+
+    // Modules have their name as the first word after the block name
+    let (module, rest) = match spec::find_block(block) {
+        Some(spec) if spec.name == "module" => {
+            let rest = rest.trim_start();
+            match rest.split_once(char::is_whitespace) {
+                Some((module, rest)) => (Some(module), rest),
+                None => return Some(Context::ModuleName),
+            }
+        }
+        _ => (None, rest),
+    };
+
+    match ARGUMENT_VALUE_REGEX.captures(rest) {
+        Some(captures) => Some(Context::ArgumentValue {
+            block,
+            module,
+            argument: captures.get(1).unwrap().as_str(),
+        }),
+        None if rest.matches('"').count() % 2 == 0 => {
+            Some(Context::Argument { block, module })
+        }
+        None => None,
+    }
+}
+
+fn block_names(end: bool) -> Vec<CompletionItem> {
+    let mut items = Vec::new();
+
+    for spec in spec::blocks() {
+        if end && !spec::has_body(spec) {
+            continue;
+        }
+
+        let docs = spec::block_docs(&spec.name);
+        for name in spec.names() {
+            items.push(CompletionItem {
+                label: str!(name),
+                kind: Some(CompletionItemKind::KEYWORD),
+                detail: docs.map(|docs| docs.title.clone()),
+                deprecated: Some(spec.deprecated),
+                ..CompletionItem::default()
+            });
+        }
+    }
+
+    items
+}
+
+fn module_names() -> Vec<CompletionItem> {
+    spec::modules()
+        .iter()
+        .flat_map(|spec| {
+            spec.names().map(move |name| CompletionItem {
+                label: str!(name),
+                kind: Some(CompletionItemKind::MODULE),
+                detail: Some(format!("{} module", spec.name)),
+                deprecated: Some(spec.deprecated),
+                ..CompletionItem::default()
+            })
+        })
+        .collect()
+}
+
+fn arguments(block: &str, module: Option<&str>) -> Vec<CompletionItem> {
+    let mut items: Vec<_> = get_arguments(block, module)
+        .into_iter()
+        .flat_map(|arguments| arguments.iter())
+        .map(|spec| CompletionItem {
+            label: spec.name.clone(),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: Some(spec::describe_argument(spec)),
+            insert_text: Some(format!("{}=\"\"", spec.name)),
+            ..CompletionItem::default()
+        })
+        .collect();
+
+    let html_attributes = module.is_none()
+        && spec::find_block(block)
+            .map(|spec| spec::has_map_head(spec) && spec.html_attributes)
+            .unwrap_or(false);
+
+    if html_attributes {
+        items.extend(HTML_ATTRIBUTES.iter().map(|name| CompletionItem {
+            label: str!(name),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: Some(str!("HTML attribute")),
+            insert_text: Some(format!("{name}=\"\"")),
+            ..CompletionItem::default()
+        }));
+    }
+
+    items
+}
+
+fn argument_values(
+    block: &str,
+    module: Option<&str>,
+    argument: &str,
+) -> Vec<CompletionItem> {
+    let spec = get_arguments(block, module).and_then(|arguments| {
+        arguments
+            .iter()
+            .find(|spec| spec.name.eq_ignore_ascii_case(argument))
+    });
+
+    let values: Vec<String> = match spec {
+        Some(ArgumentInfo {
+            kind: ArgumentType::Bool,
+            ..
+        }) => {
+            vec![str!("true"), str!("false")]
+        }
+        Some(spec) => spec
+            .values
+            .iter()
+            .filter_map(|value| match value {
+                ArgumentValue::String(value) => Some(value.clone()),
+                _ => None,
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    values
+        .into_iter()
+        .map(|value| CompletionItem {
+            label: value,
+            kind: Some(CompletionItemKind::ENUM_MEMBER),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+fn get_arguments(block: &str, module: Option<&str>) -> Option<&'static [ArgumentInfo]> {
+    match module {
+        Some(module) => spec::find_module(module).map(|spec| spec.arguments.as_slice()),
+        None => spec::find_block(block)
+            .filter(|spec| spec::has_map_head(spec))
+            .map(|spec| spec.arguments.as_slice()),
+    }
+}
+
+#[test]
+fn contexts() {
+    assert_eq!(get_context("Some text"), None);
+    assert_eq!(get_context("[[div]] text"), None);
+    assert_eq!(get_context("[["), Some(Context::BlockName));
+    assert_eq!(get_context("text [[coll"), Some(Context::BlockName));
+    assert_eq!(get_context("[[*us"), Some(Context::BlockName));
+    assert_eq!(get_context("[[/di"), Some(Context::EndBlockName));
+    assert_eq!(get_context("[[module "), Some(Context::ModuleName));
+    assert_eq!(get_context("[[module List"), Some(Context::ModuleName));
+    assert_eq!(
+        get_context("[[collapsible show=\"+ Open\" "),
+        Some(Context::Argument {
+            block: "collapsible",
+            module: None,
+        }),
+    );
+    assert_eq!(
+        get_context("[[collapsible show=\"+ Op"),
+        Some(Context::ArgumentValue {
+            block: "collapsible",
+            module: None,
+            argument: "show",
+        }),
+    );
+    assert_eq!(
+        get_context("[[module Categories includeHidden=\""),
+        Some(Context::ArgumentValue {
+            block: "module",
+            module: Some("Categories"),
+            argument: "includeHidden",
+        }),
+    );
+}
+
+#[test]
+fn items() {
+    let labels = |items: Vec<CompletionItem>| -> Vec<String> {
+        items.into_iter().map(|item| item.label).collect()
+    };
+
+    let names = labels(block_names(false));
+    assert!(names.contains(&str!("collapsible")));
+    assert!(names.contains(&str!("ul")));
+    assert!(!names.contains(&str!("list-unordered")));
+
+    let names = labels(block_names(true));
+    assert!(names.contains(&str!("div")));
+    assert!(!names.contains(&str!("iframe")));
+
+    let names = labels(arguments("collapsible", None));
+    assert!(names.contains(&str!("hideLocation")));
+    assert!(names.contains(&str!("class")));
+
+    let names = labels(argument_values("collapsible", None, "hidelocation"));
+    assert_eq!(names, vec!["top", "both", "bottom", "neither"]);
+
+    let names = labels(argument_values(
+        "module",
+        Some("Categories"),
+        "includeHidden",
+    ));
+    assert_eq!(names, vec!["true", "false"]);
+}
@@ -0,0 +1,120 @@
+/*
+ * definition.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Jumping from references to what they refer to on the same page.
+//!
+//! This covers links to anchors (`[[# name]]`), footnotes
+//! (to the footnote block they are listed in), and equation references.
+
+use crate::document::{Analysis, Document};
+use crate::elements::{element_at, spanned_elements};
+use ftml::tree::{Element, LinkLocation};
+use lsp_types::{Location, Position, Url};
+use std::ops::Range;
+
+pub fn definition(
+    uri: &Url,
+    document: &Document,
+    analysis: &Analysis,
+    position: Position,
+) -> Option<Location> {
+    let offset = document.offset(position);
+    let (_, element) = element_at(&analysis.tree, offset)?;
+    let span = find_target(&analysis.tree, element)?;
+
+    Some(Location {
+        uri: uri.clone(),
+        range: document.range(span),
+    })
+}
+
+fn find_target(
+    tree: &ftml::tree::SyntaxTree,
+    reference: &Element,
+) -> Option<Range<usize>> {
+    let elements = spanned_elements(tree);
+    let find = |predicate: &dyn Fn(&Element) -> bool| {
+        elements
+            .iter()
+            .find(|(_, element)| predicate(element))
+            .map(|(span, _)| span.clone())
+    };
+
+    match reference {
+        Element::Link {
+            link: LinkLocation::Url(url),
+            ..
+        } => {
+            let name = url.strip_prefix('#')?;
+            find(
+                &|element| matches!(element, Element::AnchorName(anchor) if anchor == name),
+            )
+        }
+        Element::Footnote => {
+            find(&|element| matches!(element, Element::FootnoteBlock { .. }))
+        }
+        Element::EquationReference(name) => find(
+            &|element| matches!(element, Element::Math { name: Some(math), .. } if math == name),
+        ),
+        _ => None,
+    }
+}
+
+#[test]
+fn definitions() {
+    use lsp_types::Range as LspRange;
+
+    let uri = Url::parse("file:///page.ftml").unwrap();
+    let document = Document::new(str!(
+        "[[# top]]\n\
+         Text[[footnote]]Note[[/footnote]]\n\n\
+         [[math eq]]\nx = 1\n[[/math]]\n\n\
+         See [[eref eq]] or [#top go up].\n\n\
+         [[footnoteblock]]"
+    ));
+    let tokens = document.tokenize();
+    let analysis = Analysis::new(&tokens);
+
+    let get = |line, character| {
+        definition(&uri, &document, &analysis, Position::new(line, character))
+            .map(|location| location.range)
+    };
+
+    // Anchor
+    assert_eq!(
+        get(7, 22),
+        Some(LspRange::new(Position::new(0, 0), Position::new(0, 9))),
+    );
+
+    // Footnote
+    assert_eq!(
+        get(1, 8).map(|range| range.start),
+        Some(Position::new(9, 0))
+    );
+
+    // Equation
+    assert_eq!(
+        get(7, 8).map(|range| range.start),
+        Some(Position::new(3, 0))
+    );
+
+    // Not a reference
+    assert_eq!(get(1, 1), None);
+}
@@ -0,0 +1,114 @@
+/*
+ * diagnostics.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::document::{Analysis, Document};
+use ftml::lint::{self, Severity};
+use ftml::parsing::{ParseWarning, ParseWarningKind};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+const SOURCE: &str = "ftml";
+
+/// Produces diagnostics for the parser warnings and lints in a document.
+pub fn diagnostics(document: &Document, analysis: &Analysis) -> Vec<Diagnostic> {
+    let warnings = analysis
+        .warnings
+        .iter()
+        .filter(|warning| is_reported(warning.kind()))
+        .map(|warning| warning_diagnostic(document, warning));
+
+    let lints = lint::lint(analysis.tokens, &analysis.tree, analysis.settings())
+        .into_iter()
+        .filter_map(|diagnostic| lint_diagnostic(document, diagnostic));
+
+    warnings.chain(lints).collect()
+}
+
+/// Whether a warning is shown to the user.
+///
+/// Whenever a rule fails, the fallback rule also emits a warning
+/// for the same location, which would only duplicate the first.
+fn is_reported(kind: ParseWarningKind) -> bool {
+    !matches!(kind, ParseWarningKind::NoRulesMatch)
+}
+
+fn warning_diagnostic(document: &Document, warning: &ParseWarning) -> Diagnostic {
+    Diagnostic {
+        range: document.range(warning.span()),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(warning.kind().name().to_string())),
+        source: Some(str!(SOURCE)),
+        message: format!(
+            "{} (rule {})",
+            describe_kind(warning.kind().name()),
+            warning.rule(),
+        ),
+        ..Diagnostic::default()
+    }
+}
+
+fn lint_diagnostic(
+    document: &Document,
+    diagnostic: lint::Diagnostic,
+) -> Option<Diagnostic> {
+    let severity = match diagnostic.severity() {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Info => DiagnosticSeverity::INFORMATION,
+        Severity::Hint => DiagnosticSeverity::HINT,
+    };
+
+    Some(Diagnostic {
+        range: document.range(diagnostic.span()?),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostic.kind().name().to_string())),
+        source: Some(str!(SOURCE)),
+        message: format!(
+            "{} ({})",
+            diagnostic.message(),
+            diagnostic.fix().description
+        ),
+        ..Diagnostic::default()
+    })
+}
+
+/// Converts the name of a warning kind into a sentence.
+///
+/// For instance, `BlockMissingName` becomes "Block missing name".
+fn describe_kind(name: &str) -> String {
+    let mut description = String::new();
+
+    for ch in name.chars() {
+        if ch.is_ascii_uppercase() && !description.is_empty() {
+            description.push(' ');
+            description.push(ch.to_ascii_lowercase());
+        } else {
+            description.push(ch);
+        }
+    }
+
+    description
+}
+
+#[test]
+fn kind_descriptions() {
+    assert_eq!(describe_kind("BlockMissingName"), "Block missing name");
+    assert_eq!(describe_kind("InvalidUrl"), "Invalid url");
+    assert_eq!(describe_kind("EndOfInput"), "End of input");
+}
@@ -0,0 +1,169 @@
+/*
+ * document.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::data::PageInfo;
+use ftml::parsing::ParseWarning;
+use ftml::settings::{WikitextMode, WikitextSettings};
+use ftml::tree::SyntaxTree;
+use ftml::Tokenization;
+use lsp_types::{Position, Range as LspRange};
+use std::borrow::Cow;
+use std::ops::Range;
+
+lazy_static! {
+    static ref PAGE_INFO: PageInfo<'static> = PageInfo {
+        page: Cow::Borrowed("page"),
+        category: None,
+        site: Cow::Borrowed("www"),
+        title: Cow::Borrowed("Page"),
+        alt_title: None,
+        rating: 0.0,
+        tags: vec![],
+        language: Cow::Borrowed("default"),
+    };
+    static ref SETTINGS: WikitextSettings = WikitextSettings {
+        record_spans: true,
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    };
+}
+
+/// An open text document, with the information needed to convert positions.
+#[derive(Debug)]
+pub struct Document {
+    text: String,
+
+    /// The byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl Document {
+    pub fn new(text: String) -> Self {
+        let line_starts = [0]
+            .into_iter()
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Document { text, line_starts }
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Tokenizes this document, to be parsed with [`Analysis::new`].
+    ///
+    /// The preprocessor is not run, since it changes the text and
+    /// so spans in the result would not match the document.
+    #[inline]
+    pub fn tokenize(&self) -> Tokenization<'_> {
+        ftml::tokenize(&self.text)
+    }
+
+    /// Converts a byte offset into an LSP position, which counts UTF-16 code units.
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Converts an LSP position into a byte offset.
+    ///
+    /// Positions past the end of a line are clamped to it.
+    pub fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        let start = match self.line_starts.get(line) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+
+        let mut units = 0;
+        for (index, ch) in self.text[start..].char_indices() {
+            if ch == '\n' || units >= position.character as usize {
+                return start + index;
+            }
+
+            units += ch.len_utf16();
+        }
+
+        self.text.len()
+    }
+
+    #[inline]
+    pub fn range(&self, span: Range<usize>) -> LspRange {
+        LspRange::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+/// The result of parsing a document.
+#[derive(Debug)]
+pub struct Analysis<'t> {
+    pub tokens: &'t Tokenization<'t>,
+    pub tree: SyntaxTree<'t>,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<'t> Analysis<'t> {
+    pub fn new(tokens: &'t Tokenization<'t>) -> Self {
+        let (tree, warnings) = ftml::parse(tokens, &PAGE_INFO, &SETTINGS).into();
+
+        Analysis {
+            tokens,
+            tree,
+            warnings,
+        }
+    }
+
+    #[inline]
+    pub fn page_info(&self) -> &'static PageInfo<'static> {
+        &PAGE_INFO
+    }
+
+    #[inline]
+    pub fn settings(&self) -> &'static WikitextSettings {
+        &SETTINGS
+    }
+}
+
+#[test]
+fn positions() {
+    let document = Document::new(str!("abc\nCafé 𝄞 x\n\nend"));
+
+    assert_eq!(document.position(0), Position::new(0, 0));
+    assert_eq!(document.position(4), Position::new(1, 0));
+    assert_eq!(document.position(10), Position::new(1, 5));
+    assert_eq!(document.position(15), Position::new(1, 8));
+    assert_eq!(document.position(17), Position::new(2, 0));
+    assert_eq!(document.position(18), Position::new(3, 0));
+    assert_eq!(document.position(100), Position::new(3, 3));
+
+    assert_eq!(document.offset(Position::new(1, 5)), 10);
+    assert_eq!(document.offset(Position::new(1, 7)), 14);
+    assert_eq!(document.offset(Position::new(1, 8)), 15);
+    assert_eq!(document.offset(Position::new(1, 50)), 16);
+    assert_eq!(document.offset(Position::new(9, 0)), document.text().len());
+}
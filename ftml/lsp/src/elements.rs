@@ -0,0 +1,106 @@
+/*
+ * elements.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ftml::tree::{Element, ListItem, SyntaxTree};
+use std::ops::Range;
+
+/// Gets every element in the tree which has a source span, at any depth.
+///
+/// Elements are listed before those nested within them.
+pub fn spanned_elements<'a, 't>(
+    tree: &'a SyntaxTree<'t>,
+) -> Vec<(Range<usize>, &'a Element<'t>)> {
+    let mut output = Vec::new();
+    collect(&tree.elements, &mut output);
+
+    for footnote in &tree.footnotes {
+        collect(footnote, &mut output);
+    }
+
+    output
+}
+
+/// Gets the innermost element with a source span which contains the given offset.
+pub fn element_at<'a, 't>(
+    tree: &'a SyntaxTree<'t>,
+    offset: usize,
+) -> Option<(Range<usize>, &'a Element<'t>)> {
+    spanned_elements(tree)
+        .into_iter()
+        .filter(|(span, _)| span.start <= offset && offset < span.end)
+        .min_by_key(|(span, _)| span.len())
+}
+
+fn collect<'a, 't>(
+    elements: &'a [Element<'t>],
+    output: &mut Vec<(Range<usize>, &'a Element<'t>)>,
+) {
+    for element in elements {
+        collect_element(element, output);
+    }
+}
+
+fn collect_element<'a, 't>(
+    element: &'a Element<'t>,
+    output: &mut Vec<(Range<usize>, &'a Element<'t>)>,
+) {
+    match element {
+        Element::Spanned { span, element } => {
+            output.push((span.clone(), element));
+            collect_element(element, output);
+        }
+        Element::Container(container) => collect(container.elements(), output),
+        Element::Table(table) => {
+            for row in &table.rows {
+                for cell in &row.cells {
+                    collect(&cell.elements, output);
+                }
+            }
+        }
+        Element::TabView(tabs) => {
+            for tab in tabs {
+                collect(&tab.elements, output);
+            }
+        }
+        Element::List { items, .. } => {
+            for item in items {
+                match item {
+                    ListItem::Elements { elements, .. } => collect(elements, output),
+                    ListItem::SubList { element } => collect_element(element, output),
+                }
+            }
+        }
+        Element::DefinitionList(items) => {
+            for item in items {
+                collect(&item.key, output);
+                collect(&item.value, output);
+            }
+        }
+        Element::BibliographyBlock { entries, .. } => {
+            for entry in entries {
+                collect(&entry.contents, output);
+            }
+        }
+        Element::Anchor { elements, .. }
+        | Element::Collapsible { elements, .. }
+        | Element::Color { elements, .. } => collect(elements, output),
+        _ => (),
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * folding.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Folding ranges for blocks and comments spanning multiple lines.
+//!
+//! Blocks are matched by name using the tokens rather than the syntax tree,
+//! so that blocks which fail to parse can still be folded.
+
+use crate::document::{Analysis, Document};
+use ftml::parsing::Token;
+use lsp_types::{FoldingRange, FoldingRangeKind};
+
+pub fn folding_ranges(document: &Document, analysis: &Analysis) -> Vec<FoldingRange> {
+    let text = document.text();
+    let mut ranges = Vec::new();
+    let mut blocks: Vec<(String, u32)> = Vec::new();
+    let mut comment_start = None;
+
+    for extracted in analysis.tokens.tokens() {
+        let line = document.position(extracted.span.start).line;
+
+        match extracted.token {
+            Token::LeftBlock | Token::LeftBlockStar => {
+                let name = block_name(&text[extracted.span.end..]);
+                if !name.is_empty() {
+                    blocks.push((name, line));
+                }
+            }
+            Token::LeftBlockEnd => {
+                let name = block_name(&text[extracted.span.end..]);
+
+                // Pop to the matching block, dropping any left unclosed within it
+                if let Some(index) = blocks.iter().rposition(|(open, _)| *open == name) {
+                    let (_, start_line) = blocks[index];
+                    blocks.truncate(index);
+                    add_range(&mut ranges, start_line, line, None);
+                }
+            }
+            Token::LeftComment => comment_start = Some(line),
+            Token::RightComment => {
+                if let Some(start_line) = comment_start.take() {
+                    add_range(
+                        &mut ranges,
+                        start_line,
+                        line,
+                        Some(FoldingRangeKind::Comment),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    ranges.sort_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
+
+/// Gets the block name at the start of this text, normalized for comparison.
+fn block_name(text: &str) -> String {
+    text.trim_start()
+        .chars()
+        .take_while(|&ch| !ch.is_whitespace() && ch != ']')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn add_range(
+    ranges: &mut Vec<FoldingRange>,
+    start_line: u32,
+    end_line: u32,
+    kind: Option<FoldingRangeKind>,
+) {
+    if end_line > start_line {
+        ranges.push(FoldingRange {
+            start_line,
+            start_character: None,
+            end_line,
+            end_character: None,
+            kind,
+            collapsed_text: None,
+        });
+    }
+}
+
+#[test]
+fn ranges() {
+    let document = Document::new(str!(
+        "[[div]]\n[[span]]x[[/span]]\n[[*DIV]]\ninner\n[[/div]]\n[[/div]]\n\n[!--\ncomment\n--]\n\n[[collapsible]]\nunclosed"
+    ));
+    let tokens = document.tokenize();
+    let analysis = Analysis::new(&tokens);
+    let ranges: Vec<_> = folding_ranges(&document, &analysis)
+        .into_iter()
+        .map(|range| (range.start_line, range.end_line, range.kind))
+        .collect();
+
+    assert_eq!(
+        ranges,
+        vec![
+            (0, 5, None),
+            (2, 4, None),
+            (7, 9, Some(FoldingRangeKind::Comment)),
+        ],
+    );
+}
@@ -0,0 +1,187 @@
+/*
+ * hover.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Documentation for the block or module name under the cursor.
+
+use crate::document::{Analysis, Document};
+use crate::spec;
+use ftml::info::{BlockInfo, ModuleInfo};
+use ftml::parsing::{ExtractedToken, Token};
+use ftml::render::markdown::MarkdownRender;
+use ftml::render::Render;
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+use std::fmt::Write;
+
+pub fn hover(
+    document: &Document,
+    analysis: &Analysis,
+    position: Position,
+) -> Option<Hover> {
+    let offset = document.offset(position);
+    let tokens = analysis.tokens.tokens();
+    let index = tokens
+        .iter()
+        .position(|extracted| extracted.span.contains(&offset))?;
+
+    let name = &tokens[index];
+    if name.token != Token::Identifier {
+        return None;
+    }
+
+    // Find the name of the block this is in, if it's the first word in one
+    let previous = previous_word(tokens, index);
+    let block = match previous.map(|i| tokens[i].token) {
+        Some(token) if is_block_start(token) => name,
+        Some(Token::Identifier) => {
+            let previous = previous.unwrap();
+            match previous_word(tokens, previous).map(|i| tokens[i].token) {
+                Some(token) if is_block_start(token) => &tokens[previous],
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    let spec = spec::find_block(block.slice)?;
+    let contents = if block.span == name.span {
+        block_markdown(spec)
+    } else if spec.name == "module" {
+        module_markdown(spec::find_module(name.slice)?)
+    } else {
+        return None;
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: contents,
+        }),
+        range: Some(document.range(name.span.clone())),
+    })
+}
+
+/// Gets the index of the token before this one, skipping whitespace.
+fn previous_word(tokens: &[ExtractedToken], index: usize) -> Option<usize> {
+    tokens[..index]
+        .iter()
+        .rposition(|extracted| extracted.token != Token::Whitespace)
+}
+
+#[inline]
+fn is_block_start(token: Token) -> bool {
+    matches!(
+        token,
+        Token::LeftBlock | Token::LeftBlockStar | Token::LeftBlockEnd,
+    )
+}
+
+fn block_markdown(spec: &BlockInfo) -> String {
+    let mut output = String::new();
+
+    match spec::block_docs(&spec.name) {
+        Some(docs) => {
+            writeln!(output, "**{}**\n", docs.title).unwrap();
+            writeln!(output, "{}\n", wikitext_to_markdown(&docs.info)).unwrap();
+
+            if let Some(ref example) = docs.example {
+                writeln!(output, "```\n{}\n```\n", example.trim_end()).unwrap();
+            }
+        }
+        None => writeln!(output, "**{}**\n", spec.name).unwrap(),
+    }
+
+    let names: Vec<_> = spec.names().map(|name| format!("`{name}`")).collect();
+    writeln!(output, "Names: {}  ", names.join(", ")).unwrap();
+    writeln!(output, "Arguments: {}  ", spec.head.name()).unwrap();
+    writeln!(output, "Body: {}", spec.body.name()).unwrap();
+
+    for argument in &spec.arguments {
+        let description = spec::describe_argument(argument);
+        writeln!(output, "* `{}`: {description}", argument.name).unwrap();
+    }
+
+    output
+}
+
+fn module_markdown(spec: &ModuleInfo) -> String {
+    let mut output = format!("**{}** module\n\nBody: {}\n", spec.name, spec.body.name());
+
+    for argument in &spec.arguments {
+        let description = spec::describe_argument(argument);
+        writeln!(output, "* `{}`: {description}", argument.name).unwrap();
+    }
+
+    output
+}
+
+/// Renders the wikitext used in the block documentation as Markdown.
+fn wikitext_to_markdown(wikitext: &str) -> String {
+    let document = Document::new(str!(wikitext));
+    let tokens = document.tokenize();
+    let analysis = Analysis::new(&tokens);
+
+    MarkdownRender::default().render(
+        &analysis.tree,
+        analysis.page_info(),
+        analysis.settings(),
+    )
+}
+
+#[test]
+fn hovers() {
+    let document = Document::new(str!(
+        "[[collapsible show=\"x\"]]\n[[/collapsible]]\n\n[[module Rate]]"
+    ));
+    let tokens = document.tokenize();
+    let analysis = Analysis::new(&tokens);
+
+    let get = |line, character| {
+        hover(&document, &analysis, Position::new(line, character)).map(|hover| {
+            match hover.contents {
+                HoverContents::Markup(content) => content.value,
+                _ => panic!("Hover was not Markdown"),
+            }
+        })
+    };
+
+    let contents = get(0, 4).expect("No hover for block name");
+    assert!(contents.starts_with("**Collapsible**"));
+    assert!(contents.contains("`hideLocation`"));
+
+    let contents = get(1, 5).expect("No hover for end block name");
+    assert!(contents.starts_with("**Collapsible**"));
+
+    let contents = get(3, 10).expect("No hover for module name");
+    assert!(contents.starts_with("**Rate** module"));
+
+    assert_eq!(get(0, 15), None);
+    assert_eq!(
+        get(3, 4).map(|contents| contents.contains("Names")),
+        Some(true)
+    );
+}
+
+#[test]
+fn docs_markdown() {
+    assert_eq!(
+        wikitext_to_markdown("Uses the {{[[div]]}} block."),
+        "Uses the <code>\\[\\[div\\]\\]</code> block.",
+    );
+}
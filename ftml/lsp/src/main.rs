@@ -0,0 +1,64 @@
+/*
+ * main.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use]
+extern crate serde;
+
+#[macro_use]
+extern crate str_macro;
+
+mod completion;
+mod definition;
+mod diagnostics;
+mod document;
+mod elements;
+mod folding;
+mod hover;
+mod outline;
+mod server;
+mod spec;
+
+use lsp_server::Connection;
+use std::{env, process};
+
+fn main() {
+    if env::args()
+        .skip(1)
+        .any(|arg| arg == "--version" || arg == "-V")
+    {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
+    // Communicate over standard input and output,
+    // so logging must not go to stdout.
+    let (connection, io_threads) = Connection::stdio();
+
+    if let Err(error) = server::run(&connection) {
+        eprintln!("ftml-lsp: {error}");
+        process::exit(1);
+    }
+
+    drop(connection);
+    io_threads.join().expect("Unable to join I/O threads");
+}
@@ -0,0 +1,158 @@
+/*
+ * outline.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The document outline, built from its headings.
+
+use crate::document::{Analysis, Document};
+use ftml::parsing::Token;
+use lsp_types::{DocumentSymbol, Range as LspRange, SymbolKind};
+use std::ops::Range;
+
+#[derive(Debug)]
+struct Heading {
+    level: usize,
+    name: String,
+    span: Range<usize>,
+}
+
+pub fn outline(document: &Document, analysis: &Analysis) -> Vec<DocumentSymbol> {
+    let headings = find_headings(document, analysis);
+    let mut symbols = Vec::new();
+    let mut index = 0;
+
+    while index < headings.len() {
+        symbols.push(build_symbol(document, &headings, &mut index));
+    }
+
+    symbols
+}
+
+/// Gets all the headings in the document.
+///
+/// Only heading tokens at the start of a line are headings,
+/// others are regular text.
+fn find_headings(document: &Document, analysis: &Analysis) -> Vec<Heading> {
+    let text = document.text();
+    let tokens = analysis.tokens.tokens();
+    let mut headings = Vec::new();
+
+    for (index, extracted) in tokens.iter().enumerate() {
+        if extracted.token != Token::Heading {
+            continue;
+        }
+
+        let at_line_start = index == 0
+            || matches!(
+                tokens[index - 1].token,
+                Token::InputStart | Token::LineBreak | Token::ParagraphBreak,
+            );
+
+        if !at_line_start {
+            continue;
+        }
+
+        let start = extracted.span.start;
+        let end = text[start..]
+            .find('\n')
+            .map(|index| start + index)
+            .unwrap_or(text.len());
+
+        let level = extracted.slice.matches('+').count();
+        let name = text[extracted.span.end..end].trim();
+
+        headings.push(Heading {
+            level,
+            name: str!(name),
+            span: start..end,
+        });
+    }
+
+    headings
+}
+
+/// Builds the symbol for the heading at this index, with the ones within its section as children.
+fn build_symbol(
+    document: &Document,
+    headings: &[Heading],
+    index: &mut usize,
+) -> DocumentSymbol {
+    let heading = &headings[*index];
+    *index += 1;
+
+    let mut children = Vec::new();
+    while *index < headings.len() && headings[*index].level > heading.level {
+        children.push(build_symbol(document, headings, index));
+    }
+
+    // The section continues until the next heading which isn't within it
+    let section_end = match headings.get(*index) {
+        Some(next) => document.position(next.span.start),
+        None => document.position(document.text().len()),
+    };
+
+    let selection_range = document.range(heading.span.clone());
+    let range = LspRange::new(selection_range.start, section_end);
+
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: if heading.name.is_empty() {
+            str!("(untitled)")
+        } else {
+            heading.name.clone()
+        },
+        detail: Some(format!("h{}", heading.level)),
+        kind: SymbolKind::STRING,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+#[test]
+fn headings() {
+    let document = Document::new(str!(
+        "+ Title\n\nIntro\n\n++ First\n\n+++ Detail\n\nA + B\n\n++* Second\n\n+ Appendix"
+    ));
+    let tokens = document.tokenize();
+    let analysis = Analysis::new(&tokens);
+    let symbols = outline(&document, &analysis);
+
+    let names = |symbols: &[DocumentSymbol]| -> Vec<String> {
+        symbols.iter().map(|symbol| symbol.name.clone()).collect()
+    };
+
+    assert_eq!(names(&symbols), vec!["Title", "Appendix"]);
+
+    let children = symbols[0].children.as_ref().expect("No subsections");
+    assert_eq!(names(children), vec!["First", "Second"]);
+    assert_eq!(children[0].detail.as_deref(), Some("h2"));
+    assert_eq!(children[0].range.end, children[1].range.start);
+
+    let grandchildren = children[0].children.as_ref().expect("No subsections");
+    assert_eq!(names(grandchildren), vec!["Detail"]);
+    assert!(children[1].children.is_none());
+    assert_eq!(symbols[0].range.end.line, 12);
+}
@@ -0,0 +1,239 @@
+/*
+ * server.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The main loop of the server, dispatching requests and notifications.
+//!
+//! Documents are small enough that each request re-parses the document
+//! rather than caching the syntax tree, which borrows from the text.
+
+use crate::completion::completion;
+use crate::definition::definition;
+use crate::diagnostics::diagnostics;
+use crate::document::{Analysis, Document};
+use crate::folding::folding_ranges;
+use crate::hover::hover;
+use crate::outline::outline;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, HoverRequest,
+    Request as _,
+};
+use lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DocumentSymbolParams,
+    DocumentSymbolResponse, FoldingRangeParams, FoldingRangeProviderCapability,
+    GotoDefinitionParams, GotoDefinitionResponse, HoverParams, HoverProviderCapability,
+    OneOf, PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::FULL,
+        )),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(
+                ["[", "/", " ", "\""].iter().map(|s| str!(s)).collect(),
+            ),
+            ..CompletionOptions::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// Runs the server over this connection until it is shut down.
+pub fn run(connection: &Connection) -> Result<()> {
+    let capabilities = serde_json::to_value(capabilities())?;
+    connection.initialize(capabilities)?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+
+                server.handle_request(request)?;
+            }
+            Message::Notification(notification) => {
+                server.handle_notification(notification)?;
+            }
+            Message::Response(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+struct Server<'c> {
+    connection: &'c Connection,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server<'_> {
+    fn handle_request(&mut self, request: Request) -> Result<()> {
+        let id = request.id.clone();
+        let response = match request.method.as_str() {
+            Completion::METHOD => {
+                let params: CompletionParams = parse_params(request)?;
+                let position = params.text_document_position;
+                self.respond(&position.text_document.uri, |document| {
+                    let items = completion(document, position.position);
+                    Some(CompletionResponse::Array(items))
+                })
+            }
+            HoverRequest::METHOD => {
+                let params: HoverParams = parse_params(request)?;
+                let position = params.text_document_position_params;
+                self.respond(&position.text_document.uri, |document| {
+                    let tokens = document.tokenize();
+                    hover(document, &Analysis::new(&tokens), position.position)
+                })
+            }
+            DocumentSymbolRequest::METHOD => {
+                let params: DocumentSymbolParams = parse_params(request)?;
+                self.respond(&params.text_document.uri, |document| {
+                    let tokens = document.tokenize();
+                    let symbols = outline(document, &Analysis::new(&tokens));
+                    Some(DocumentSymbolResponse::Nested(symbols))
+                })
+            }
+            GotoDefinition::METHOD => {
+                let params: GotoDefinitionParams = parse_params(request)?;
+                let position = params.text_document_position_params;
+                let uri = &position.text_document.uri;
+                self.respond(uri, |document| {
+                    let tokens = document.tokenize();
+                    let analysis = Analysis::new(&tokens);
+                    definition(uri, document, &analysis, position.position)
+                        .map(GotoDefinitionResponse::Scalar)
+                })
+            }
+            FoldingRangeRequest::METHOD => {
+                let params: FoldingRangeParams = parse_params(request)?;
+                self.respond(&params.text_document.uri, |document| {
+                    let tokens = document.tokenize();
+                    Some(folding_ranges(document, &Analysis::new(&tokens)))
+                })
+            }
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("Unsupported method: {method}"),
+            )),
+        };
+
+        let response = match response {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        };
+
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    /// Runs a request handler against an open document.
+    fn respond<F, T>(
+        &self,
+        uri: &Url,
+        f: F,
+    ) -> std::result::Result<serde_json::Value, (ErrorCode, String)>
+    where
+        F: FnOnce(&Document) -> Option<T>,
+        T: Serialize,
+    {
+        let document = self.documents.get(uri).ok_or_else(|| {
+            (
+                ErrorCode::InvalidParams,
+                format!("Document not open: {uri}"),
+            )
+        })?;
+
+        serde_json::to_value(f(document))
+            .map_err(|error| (ErrorCode::InternalError, error.to_string()))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let document = params.text_document;
+                self.update(document.uri, document.text)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+
+                // With full sync, the last change contains the whole document
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.update(params.text_document.uri, change.text)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.publish(uri, vec![])?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self, uri: Url, text: String) -> Result<()> {
+        let document = Document::new(text);
+        let tokens = document.tokenize();
+        let diagnostics = diagnostics(&document, &Analysis::new(&tokens));
+        self.documents.insert(uri.clone(), document);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let notification = Notification::new(str!(PublishDiagnostics::METHOD), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(request: Request) -> Result<T> {
+    Ok(serde_json::from_value(request.params)?)
+}
@@ -0,0 +1,140 @@
+/*
+ * spec.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The block and module specifications, and their documentation.
+//!
+//! Specifications come from `ftml::info`, and documentation is embedded
+//! from the CodeMirror hover-tips in `locales/cmftml/en.yaml`, so that
+//! the server always matches the version of ftml it is built with.
+
+use ftml::info::{ArgumentInfo, ArgumentValue, BlockBody, BlockHead, BlockInfo};
+use std::collections::BTreeMap;
+
+pub use ftml::info::{blocks, find_block, find_module, modules};
+
+const BLOCK_DOCS_YAML: &str = include_str!("../../../locales/cmftml/en.yaml");
+
+lazy_static! {
+    static ref BLOCK_DOCS: BlockDocsFile = serde_yaml::from_str(BLOCK_DOCS_YAML)
+        .expect("Unable to parse block documentation");
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockDocsFile {
+    blocks: BTreeMap<String, BlockDocs>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlockDocs {
+    #[serde(rename = "TITLE")]
+    pub title: String,
+
+    /// A description of the block, written in wikitext.
+    #[serde(rename = "INFO")]
+    pub info: String,
+
+    #[serde(rename = "EXAMPLE")]
+    pub example: Option<String>,
+}
+
+/// Gets the documentation for the block with the given name.
+#[inline]
+pub fn block_docs(name: &str) -> Option<&'static BlockDocs> {
+    BLOCK_DOCS.blocks.get(name)
+}
+
+/// Whether this block takes a map of arguments in its head.
+#[inline]
+pub fn has_map_head(block: &BlockInfo) -> bool {
+    matches!(block.head, BlockHead::Map | BlockHead::ValueMap)
+}
+
+/// Whether this block has a body, and so an end block.
+#[inline]
+pub fn has_body(block: &BlockInfo) -> bool {
+    block.body != BlockBody::None
+}
+
+/// A short description of this argument, such as `bool, default true`.
+pub fn describe_argument(argument: &ArgumentInfo) -> String {
+    let mut description = str!(argument.kind.name());
+
+    if !argument.values.is_empty() {
+        let values: Vec<_> = argument.values.iter().map(value_to_string).collect();
+        description.push_str(&format!(", one of {}", values.join(" / ")));
+    }
+
+    match (argument.min_value, argument.max_value) {
+        (Some(min), Some(max)) => description.push_str(&format!(", {min} to {max}")),
+        (Some(min), None) => description.push_str(&format!(", at least {min}")),
+        (None, Some(max)) => description.push_str(&format!(", at most {max}")),
+        (None, None) => (),
+    }
+
+    if let Some(ref default) = argument.default {
+        description.push_str(&format!(", default {}", value_to_string(default)));
+    }
+
+    description
+}
+
+fn value_to_string(value: &ArgumentValue) -> String {
+    match value {
+        ArgumentValue::Bool(value) => value.to_string(),
+        ArgumentValue::Int(value) => value.to_string(),
+        ArgumentValue::Float(value) => value.to_string(),
+        ArgumentValue::String(value) => value.clone(),
+    }
+}
+
+#[test]
+fn specs() {
+    for name in BLOCK_DOCS.blocks.keys() {
+        assert!(
+            blocks().iter().any(|block| &block.name == name),
+            "Documented block {name} does not exist"
+        );
+    }
+
+    let block = find_block("B").expect("Bold block not found");
+    assert_eq!(block.name, "bold");
+    assert!(has_body(block));
+
+    let argument = find_block("collapsible")
+        .unwrap()
+        .arguments
+        .iter()
+        .find(|argument| argument.name == "hideLocation")
+        .unwrap();
+
+    assert_eq!(
+        describe_argument(argument),
+        "string, one of top / both / bottom / neither, default top",
+    );
+
+    let argument = find_module("ListPages")
+        .unwrap()
+        .arguments
+        .iter()
+        .find(|argument| argument.name == "perPage")
+        .unwrap();
+
+    assert_eq!(describe_argument(argument), "int, 1 to 250, default 20",);
+}
@@ -0,0 +1,249 @@
+/*
+ * stdio.rs
+ *
+ * ftml-lsp - Language server for Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Runs the server binary and talks to it over stdio, as an editor would.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const URI: &str = "file:///test/page.ftml";
+
+const TEXT: &str = "\
+[[# top]]
++ Title
+
+[[div class=\"box\"]]
+Some text[[footnote]]Note[[/footnote]].
+[[/div]]
+
+++ Section
+
+[[collapsible hideLocation=\"both\"]]
+[[iframe]]
+[[/collapsible]]
+
+[#top Back up]
+";
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_ftml-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Unable to start server");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = None;
+
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = Some(value.parse::<usize>().unwrap());
+            }
+        }
+
+        let mut body = vec![0; length.expect("No Content-Length header")];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }));
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }));
+
+        loop {
+            let message = self.receive();
+            if message["id"] == json!(id) {
+                assert!(
+                    message.get("error").is_none(),
+                    "Request {method} failed: {message}",
+                );
+
+                return message["result"].clone();
+            }
+        }
+    }
+
+    fn position_request(&mut self, method: &str, line: u32, character: u32) -> Value {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+}
+
+#[test]
+fn session() {
+    let mut client = Client::spawn();
+
+    // Setup
+    let result = client.request(
+        "initialize",
+        json!({ "processId": null, "rootUri": null, "capabilities": {} }),
+    );
+    let capabilities = &result["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], json!(true));
+    assert_eq!(capabilities["foldingRangeProvider"], json!(true));
+    client.notify("initialized", json!({}));
+
+    // Diagnostics
+    client.notify(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": {
+                "uri": URI,
+                "languageId": "ftml",
+                "version": 1,
+                "text": TEXT,
+            },
+        }),
+    );
+
+    let message = client.receive();
+    assert_eq!(message["method"], "textDocument/publishDiagnostics");
+    assert_eq!(message["params"]["uri"], URI);
+    let diagnostics = message["params"]["diagnostics"].as_array().unwrap();
+    assert!(
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic["range"]["start"]["line"] == 10),
+        "No diagnostic for the invalid iframe: {diagnostics:?}",
+    );
+
+    // Completion
+    let result = client.position_request("textDocument/completion", 3, 4);
+    let labels: Vec<_> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert!(labels.contains(&"div"));
+    assert!(labels.contains(&"collapsible"));
+
+    let result = client.position_request("textDocument/completion", 9, 28);
+    let labels: Vec<_> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, vec!["top", "both", "bottom", "neither"]);
+
+    // Hover
+    let result = client.position_request("textDocument/hover", 9, 5);
+    let contents = result["contents"]["value"].as_str().unwrap();
+    assert!(contents.starts_with("**Collapsible**"));
+
+    // Outline
+    let result = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    assert_eq!(result[0]["name"], "Title");
+    assert_eq!(result[0]["children"][0]["name"], "Section");
+
+    // Definition
+    let result = client.position_request("textDocument/definition", 13, 3);
+    assert_eq!(result["uri"], URI);
+    assert_eq!(
+        result["range"]["start"],
+        json!({ "line": 0, "character": 0 })
+    );
+
+    // Folding
+    let result = client.request(
+        "textDocument/foldingRange",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let ranges: Vec<_> = result
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|range| (range["startLine"].clone(), range["endLine"].clone()))
+        .collect();
+    assert_eq!(ranges, vec![(json!(3), json!(5)), (json!(9), json!(11))]);
+
+    // Closing clears diagnostics
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let message = client.receive();
+    assert_eq!(message["params"]["diagnostics"], json!([]));
+
+    // Shutdown
+    assert_eq!(client.request("shutdown", Value::Null), Value::Null);
+    client.notify("exit", Value::Null);
+
+    let status = client.child.wait().unwrap();
+    assert!(status.success());
+}
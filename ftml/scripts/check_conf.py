@@ -122,7 +122,7 @@ def load_block_data(root_dir):
     for name, block in blocks.items():
         # Aliases
         # We use sets so alias order doesn't matter
-        aliases = block.get("aliases", [])
+        aliases = block.get("aliases", []) + block.get("deprecated-aliases", [])
 
         if not block.get("exclude-name", False):
             aliases.append(name)
@@ -0,0 +1,78 @@
+/*
+ * css/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Sanitization of CSS written by users.
+//!
+//! Stylesheets from `[[css]]` blocks and `[[module CSS]]`, as well as `style`
+//! attributes, are parsed and checked against the policy in [`CssSettings`].
+//! Rules and declarations which are not permitted are removed, such as
+//! `@import`, `expression()`, or URLs with a `javascript:` scheme, while
+//! the rest of the CSS is kept as it was written. Comments are removed.
+//!
+//! Each removal is reported as a [`CssIssue`], which the parser turns
+//! into a warning.
+//!
+//! [`CssSettings`]: crate::settings::CssSettings
+
+mod sanitizer;
+mod tokenizer;
+
+use self::sanitizer::{sanitize, Context};
+use crate::parsing::ParseWarningKind;
+use crate::settings::CssSettings;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The result of sanitizing some CSS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedCss<'a> {
+    /// The CSS with anything not permitted removed.
+    ///
+    /// This is borrowed if nothing needed to be changed.
+    pub css: Cow<'a, str>,
+
+    /// What was removed, and why.
+    pub issues: Vec<CssIssue>,
+}
+
+/// Something which was removed while sanitizing CSS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CssIssue {
+    /// Why this was removed.
+    pub kind: ParseWarningKind,
+
+    /// The byte range in the original CSS which was removed.
+    pub span: Range<usize>,
+}
+
+/// Sanitizes a stylesheet, such as the contents of a `[[css]]` block.
+///
+/// If `scope_class` is set in the settings, then selectors
+/// are also scoped to within that class.
+#[inline]
+pub fn sanitize_stylesheet<'a>(css: &'a str, settings: &CssSettings) -> SanitizedCss<'a> {
+    sanitize(css, settings, Context::Stylesheet)
+}
+
+/// Sanitizes a list of declarations, such as the value of a `style` attribute.
+#[inline]
+pub fn sanitize_style<'a>(css: &'a str, settings: &CssSettings) -> SanitizedCss<'a> {
+    sanitize(css, settings, Context::Declarations)
+}
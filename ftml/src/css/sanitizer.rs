@@ -0,0 +1,702 @@
+/*
+ * css/sanitizer.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::tokenizer::{tokenize, CssToken, TokenKind};
+use super::{CssIssue, SanitizedCss};
+use crate::parsing::ParseWarningKind;
+use crate::settings::CssSettings;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Properties which run scripts or load bindings in some browsers.
+const DISALLOWED_PROPERTIES: [&str; 3] = ["behavior", "-ms-behavior", "-moz-binding"];
+
+/// Functions which run scripts in some browsers.
+const DISALLOWED_FUNCTIONS: [&str; 1] = ["expression"];
+
+/// Functions whose string arguments are URLs.
+const URL_FUNCTIONS: [&str; 4] = ["url", "src", "image-set", "-webkit-image-set"];
+
+/// At-rules which contain rules in the same context as themselves.
+const GROUPING_AT_RULES: [&str; 8] = [
+    "container",
+    "document",
+    "layer",
+    "media",
+    "scope",
+    "starting-style",
+    "supports",
+    "-moz-document",
+];
+
+/// What the contents of a list of rules or declarations are.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Context {
+    /// The top level of a stylesheet, or a grouping rule within it.
+    ///
+    /// Selectors here are scoped, if enabled.
+    Stylesheet,
+
+    /// Within `@keyframes`, where selectors are percentages.
+    Keyframes,
+
+    /// Within a style rule or other at-rule.
+    ///
+    /// This contains declarations, and possibly nested rules.
+    Block,
+
+    /// A `style` attribute, which only contains declarations.
+    Declarations,
+}
+
+/// A token, or a block of tokens between matching brackets.
+#[derive(Debug)]
+enum Component {
+    Token(usize),
+    Block {
+        open: usize,
+        close: Option<usize>,
+        children: Vec<Component>,
+    },
+}
+
+impl Component {
+    #[inline]
+    fn first_token(&self) -> usize {
+        match *self {
+            Component::Token(index) => index,
+            Component::Block { open, .. } => open,
+        }
+    }
+}
+
+pub fn sanitize<'a>(
+    source: &'a str,
+    settings: &CssSettings,
+    context: Context,
+) -> SanitizedCss<'a> {
+    let tokens = tokenize(source);
+    let mut sanitizer = Sanitizer {
+        source,
+        tokens: &tokens,
+        settings,
+        output: String::with_capacity(source.len()),
+        last_emitted: None,
+        issues: Vec::new(),
+    };
+
+    let (components, _) = sanitizer.build_components(&mut 0, None);
+    sanitizer.sanitize_list(&components, context);
+
+    let Sanitizer {
+        mut output, issues, ..
+    } = sanitizer;
+
+    // Nothing emitted can form an end tag, since strings have '<' escaped
+    // and other '<' tokens are checked. But this output is placed within
+    // a <style> element, so this ensures it cannot be closed regardless.
+    if output.contains("</") {
+        output = output.replace("</", "< /");
+    }
+
+    let css = if output == source {
+        Cow::Borrowed(source)
+    } else {
+        Cow::Owned(output)
+    };
+
+    SanitizedCss { css, issues }
+}
+
+#[derive(Debug)]
+struct Sanitizer<'a, 's> {
+    source: &'a str,
+    tokens: &'s [CssToken<'a>],
+    settings: &'s CssSettings,
+    output: String,
+    last_emitted: Option<usize>,
+    issues: Vec<CssIssue>,
+}
+
+impl<'a, 's> Sanitizer<'a, 's> {
+    // Component tree
+
+    /// Groups tokens into components, until the given closing token.
+    ///
+    /// Returns the components, and the index of the closing token if it was found.
+    /// Comments are skipped, as are closing tokens which do not match an open block.
+    fn build_components(
+        &mut self,
+        position: &mut usize,
+        closer: Option<TokenKind>,
+    ) -> (Vec<Component>, Option<usize>) {
+        let mut components = Vec::new();
+
+        while let Some(token) = self.tokens.get(*position) {
+            let index = *position;
+            *position += 1;
+
+            match token.kind {
+                TokenKind::Comment => (),
+                kind if Some(kind) == closer => return (components, Some(index)),
+                kind if kind.is_closer() => {
+                    self.issue(ParseWarningKind::CssMalformed, token.span.clone());
+                }
+                kind => match kind.closer() {
+                    Some(child_closer) => {
+                        let (children, close) =
+                            self.build_components(position, Some(child_closer));
+
+                        if close.is_none() {
+                            self.issue(
+                                ParseWarningKind::CssMalformed,
+                                token.span.clone(),
+                            );
+                        }
+
+                        components.push(Component::Block {
+                            open: index,
+                            close,
+                            children,
+                        });
+                    }
+                    None => components.push(Component::Token(index)),
+                },
+            }
+        }
+
+        (components, None)
+    }
+
+    // Rules and declarations
+
+    fn sanitize_list(&mut self, components: &[Component], context: Context) {
+        let mut index = 0;
+
+        while index < components.len() {
+            let component = &components[index];
+
+            // Whitespace and empty statements
+            if let Component::Token(token) = *component {
+                match self.tokens[token].kind {
+                    TokenKind::Whitespace | TokenKind::Semicolon => {
+                        self.emit_token(token);
+                        index += 1;
+                        continue;
+                    }
+                    TokenKind::Cdo | TokenKind::Cdc => {
+                        self.issue(
+                            ParseWarningKind::CssMalformed,
+                            self.tokens[token].span.clone(),
+                        );
+                        index += 1;
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+
+            // Find the end of this statement, which is either
+            // a semicolon or a block.
+            let start = index;
+            let mut has_block = false;
+
+            while index < components.len() {
+                let component = &components[index];
+                index += 1;
+
+                match *component {
+                    Component::Token(token)
+                        if self.tokens[token].kind == TokenKind::Semicolon =>
+                    {
+                        break;
+                    }
+                    Component::Block { open, .. }
+                        if self.tokens[open].kind == TokenKind::LeftBrace =>
+                    {
+                        has_block = true;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+
+            let statement = &components[start..index];
+            let first_kind = self.tokens[statement[0].first_token()].kind;
+
+            if first_kind == TokenKind::AtKeyword {
+                self.sanitize_at_rule(statement, context);
+            } else if has_block {
+                self.sanitize_qualified_rule(statement, context);
+            } else {
+                self.sanitize_declaration(statement, context);
+            }
+        }
+    }
+
+    fn sanitize_at_rule(&mut self, statement: &[Component], context: Context) {
+        let at_keyword = statement[0].first_token();
+        let name = self.tokens[at_keyword].value.to_ascii_lowercase();
+
+        if context == Context::Declarations {
+            return self.drop(statement, ParseWarningKind::CssMalformed);
+        }
+
+        if !self.settings.allows_at_rule(&name) {
+            debug!("Removing disallowed at-rule '@{name}'");
+            return self.drop(statement, ParseWarningKind::CssDisallowedAtRule);
+        }
+
+        let (prelude, block) = split_block(self.tokens, &statement[1..]);
+        if let Some(kind) = self.check_components(prelude, name == "import") {
+            return self.drop(statement, kind);
+        }
+
+        self.emit_token(at_keyword);
+        self.emit_components(prelude);
+
+        if let Some(block) = block {
+            let child_context = if name.ends_with("keyframes") {
+                Context::Keyframes
+            } else if GROUPING_AT_RULES.contains(&name.as_str()) {
+                match context {
+                    Context::Stylesheet => Context::Stylesheet,
+                    _ => Context::Block,
+                }
+            } else {
+                Context::Block
+            };
+
+            self.emit_block(block, child_context);
+        }
+    }
+
+    fn sanitize_qualified_rule(&mut self, statement: &[Component], context: Context) {
+        if context == Context::Declarations {
+            return self.drop(statement, ParseWarningKind::CssMalformed);
+        }
+
+        let (prelude, block) = split_block(self.tokens, statement);
+        let block = block.expect("Qualified rule has no block");
+
+        if let Some(kind) = self.check_components(prelude, false) {
+            return self.drop(statement, kind);
+        }
+
+        match (context, &self.settings.scope_class) {
+            (Context::Stylesheet, Some(scope_class)) => {
+                self.emit_scoped_selectors(prelude, scope_class)
+            }
+            _ => self.emit_components(prelude),
+        }
+
+        self.emit_block(block, Context::Block);
+    }
+
+    fn sanitize_declaration(&mut self, statement: &[Component], context: Context) {
+        if matches!(context, Context::Stylesheet | Context::Keyframes) {
+            return self.drop(statement, ParseWarningKind::CssMalformed);
+        }
+
+        // Find the property name, and the colon after it
+        let mut tokens = statement
+            .iter()
+            .filter(|component| !self.is_whitespace(component));
+
+        let name = match (tokens.next(), tokens.next()) {
+            (Some(&Component::Token(name)), Some(&Component::Token(colon)))
+                if self.tokens[name].kind == TokenKind::Ident
+                    && self.tokens[colon].kind == TokenKind::Colon =>
+            {
+                &self.tokens[name].value
+            }
+            _ => return self.drop(statement, ParseWarningKind::CssMalformed),
+        };
+
+        if DISALLOWED_PROPERTIES
+            .iter()
+            .any(|property| name.eq_ignore_ascii_case(property))
+        {
+            debug!("Removing disallowed property '{name}'");
+            return self.drop(statement, ParseWarningKind::CssDisallowedProperty);
+        }
+
+        if let Some(kind) = self.check_components(statement, false) {
+            return self.drop(statement, kind);
+        }
+
+        self.emit_components(statement);
+    }
+
+    /// Checks these components for anything which is not permitted.
+    ///
+    /// If `in_url` is set, then strings are URLs, as in `url("...")`.
+    fn check_components(
+        &self,
+        components: &[Component],
+        in_url: bool,
+    ) -> Option<ParseWarningKind> {
+        for (index, component) in components.iter().enumerate() {
+            match *component {
+                Component::Token(token) => {
+                    let CssToken { kind, value, .. } = &self.tokens[token];
+
+                    match kind {
+                        TokenKind::BadString
+                        | TokenKind::BadUrl
+                        | TokenKind::Cdo
+                        | TokenKind::Cdc
+                        | TokenKind::Delim('\\') => {
+                            return Some(ParseWarningKind::CssMalformed)
+                        }
+                        TokenKind::Url if !self.is_url_allowed(value) => {
+                            return Some(ParseWarningKind::CssDisallowedUrl);
+                        }
+                        TokenKind::String if in_url && !self.is_url_allowed(value) => {
+                            return Some(ParseWarningKind::CssDisallowedUrl);
+                        }
+                        TokenKind::Delim('<') => {
+                            // Prevent anything which looks like an end tag, "</"
+                            if let Some(&Component::Token(next)) =
+                                components.get(index + 1)
+                            {
+                                if self.tokens[next].kind == TokenKind::Delim('/') {
+                                    return Some(ParseWarningKind::CssMalformed);
+                                }
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                Component::Block {
+                    open, ref children, ..
+                } => {
+                    let CssToken { kind, value, .. } = &self.tokens[open];
+                    let mut child_in_url = false;
+
+                    if *kind == TokenKind::Function {
+                        let name = value.to_ascii_lowercase();
+
+                        if DISALLOWED_FUNCTIONS.contains(&name.as_str()) {
+                            debug!("Removing disallowed function '{name}()'");
+                            return Some(ParseWarningKind::CssDisallowedFunction);
+                        }
+
+                        child_in_url = URL_FUNCTIONS.contains(&name.as_str());
+                    }
+
+                    if let Some(kind) = self.check_components(children, child_in_url) {
+                        return Some(kind);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Determines if this URL has a permitted scheme, or is relative.
+    fn is_url_allowed(&self, url: &str) -> bool {
+        // Browsers ignore whitespace and control characters in URLs,
+        // and treat backslashes as forward slashes.
+        let url: String = url
+            .chars()
+            .filter(|ch| !ch.is_whitespace() && !ch.is_control())
+            .map(|ch| if ch == '\\' { '/' } else { ch })
+            .collect();
+
+        if url.starts_with("//") {
+            return self.settings.allows_url_scheme("http")
+                || self.settings.allows_url_scheme("https");
+        }
+
+        let scheme = match url.split_once(':') {
+            Some((scheme, _)) => scheme,
+            None => return true,
+        };
+
+        let is_scheme = scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'));
+
+        // Otherwise the colon is part of a relative path, such as "a/b:c"
+        !is_scheme || self.settings.allows_url_scheme(scheme)
+    }
+
+    /// Removes this statement from the output, adding a warning.
+    fn drop(&mut self, statement: &[Component], kind: ParseWarningKind) {
+        let start = self.tokens[statement[0].first_token()].span.start;
+        let end = self.span_end(&statement[statement.len() - 1]);
+        self.issue(kind, start..end);
+    }
+
+    fn issue(&mut self, kind: ParseWarningKind, span: Range<usize>) {
+        self.issues.push(CssIssue { kind, span });
+    }
+
+    // Output
+
+    fn emit_block(&mut self, block: &Component, context: Context) {
+        if let Component::Block {
+            open,
+            close,
+            ref children,
+        } = *block
+        {
+            self.emit_token(open);
+            self.sanitize_list(children, context);
+            self.emit_close(close, self.tokens[open].kind);
+        }
+    }
+
+    fn emit_components(&mut self, components: &[Component]) {
+        for component in components {
+            match *component {
+                Component::Token(token) => self.emit_token(token),
+                Component::Block {
+                    open,
+                    close,
+                    ref children,
+                } => {
+                    self.emit_token(open);
+                    self.emit_components(children);
+                    self.emit_close(close, self.tokens[open].kind);
+                }
+            }
+        }
+    }
+
+    /// Emits the closing token of a block, adding it if it is missing.
+    fn emit_close(&mut self, close: Option<usize>, open_kind: TokenKind) {
+        match close {
+            Some(close) => self.emit_token(close),
+            None => self.output.push(match open_kind.closer() {
+                Some(TokenKind::RightBrace) => '}',
+                Some(TokenKind::RightBracket) => ']',
+                _ => ')',
+            }),
+        }
+    }
+
+    /// Emits a selector list, with each selector scoped under the given class.
+    ///
+    /// Selectors for the root element, such as `body`, are replaced with the class.
+    fn emit_scoped_selectors(&mut self, prelude: &[Component], scope_class: &str) {
+        let selectors = prelude.split_inclusive(|component| {
+            matches!(
+                *component,
+                Component::Token(token) if self.tokens[token].kind == TokenKind::Comma,
+            )
+        });
+
+        for selector in selectors {
+            let start = selector
+                .iter()
+                .position(|component| !self.is_whitespace(component))
+                .unwrap_or(selector.len());
+
+            let (leading, selector) = selector.split_at(start);
+            self.emit_components(leading);
+
+            match selector.first() {
+                None => continue,
+                Some(&Component::Token(comma))
+                    if self.tokens[comma].kind == TokenKind::Comma =>
+                {
+                    self.emit_components(selector);
+                    continue;
+                }
+                _ => (),
+            }
+
+            match self.root_selector_length(selector) {
+                Some(length) => {
+                    self.output.push('.');
+                    self.output.push_str(scope_class);
+                    self.last_emitted = Some(selector[length - 1].first_token());
+                    self.emit_components(&selector[length..]);
+                }
+                None => {
+                    self.output.push('.');
+                    self.output.push_str(scope_class);
+                    self.output.push(' ');
+                    self.emit_components(selector);
+                }
+            }
+        }
+    }
+
+    /// If this selector begins with `html`, `body`, or `:root`, how many components that is.
+    fn root_selector_length(&self, selector: &[Component]) -> Option<usize> {
+        let token = |index: usize| match selector.get(index) {
+            Some(&Component::Token(token)) => Some(&self.tokens[token]),
+            _ => None,
+        };
+
+        match token(0) {
+            Some(CssToken {
+                kind: TokenKind::Ident,
+                value,
+                ..
+            }) if value.eq_ignore_ascii_case("html")
+                || value.eq_ignore_ascii_case("body") =>
+            {
+                Some(1)
+            }
+            Some(CssToken {
+                kind: TokenKind::Colon,
+                ..
+            }) => match token(1) {
+                Some(CssToken {
+                    kind: TokenKind::Ident,
+                    value,
+                    ..
+                }) if value.eq_ignore_ascii_case("root") => Some(2),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn emit_token(&mut self, index: usize) {
+        let token = &self.tokens[index];
+        let text = &self.source[token.span.clone()];
+
+        // If anything was removed before this token, such as a comment,
+        // then the tokens on either side may now combine. For instance,
+        // "expression/**/(" must not become "expression(".
+        if index > 0 && self.last_emitted != Some(index - 1) {
+            if let (Some(previous), Some(next)) =
+                (self.output.chars().next_back(), text.chars().next())
+            {
+                if needs_separator(previous, next) {
+                    self.output.push(' ');
+                }
+            }
+        }
+
+        match token.kind {
+            // Re-serialize strings and URLs which contain '<', or which reached
+            // the end of input. Closing them by appending a quote or parenthesis
+            // is not enough, since a trailing backslash would escape it.
+            TokenKind::String | TokenKind::Url
+                if text.contains('<') || !token.terminated =>
+            {
+                if token.kind == TokenKind::Url {
+                    self.output.push_str("url(");
+                }
+
+                serialize_string(&mut self.output, &token.value);
+
+                if token.kind == TokenKind::Url {
+                    self.output.push(')');
+                }
+            }
+            _ if text.contains("\\<") => {
+                // Escaped in a name, as in "a\<"
+                self.output.push_str(&text.replace("\\<", "\\3c "));
+            }
+            _ => self.output.push_str(text),
+        }
+
+        self.last_emitted = Some(index);
+    }
+
+    // Helpers
+
+    fn is_whitespace(&self, component: &Component) -> bool {
+        match *component {
+            Component::Token(token) => self.tokens[token].kind == TokenKind::Whitespace,
+            Component::Block { .. } => false,
+        }
+    }
+
+    fn span_end(&self, component: &Component) -> usize {
+        match *component {
+            Component::Token(token) => self.tokens[token].span.end,
+            Component::Block {
+                open,
+                close,
+                ref children,
+            } => match close {
+                Some(close) => self.tokens[close].span.end,
+                None => match children.last() {
+                    Some(child) => self.span_end(child),
+                    None => self.tokens[open].span.end,
+                },
+            },
+        }
+    }
+}
+
+/// Splits a statement into its prelude and block, if it ends with one.
+fn split_block<'c>(
+    tokens: &[CssToken],
+    statement: &'c [Component],
+) -> (&'c [Component], Option<&'c Component>) {
+    match statement.split_last() {
+        Some((block @ Component::Block { open, .. }, prelude))
+            if tokens[*open].kind == TokenKind::LeftBrace =>
+        {
+            (prelude, Some(block))
+        }
+        _ => (statement, None),
+    }
+}
+
+/// Whether two characters would form a different token if placed together.
+fn needs_separator(previous: char, next: char) -> bool {
+    fn is_name(ch: char) -> bool {
+        ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '\\') || !ch.is_ascii()
+    }
+
+    if next.is_whitespace() || previous.is_whitespace() {
+        return false;
+    }
+
+    match previous {
+        '#' | '@' | '<' | '/' | '\\' | '+' => true,
+        '.' => next.is_ascii_digit(),
+        _ if previous.is_ascii_digit() => is_name(next) || matches!(next, '.' | '%'),
+        _ if is_name(previous) => is_name(next) || next == '(',
+        _ => false,
+    }
+}
+
+/// Writes a string token, escaping characters which could end the `<style>` element.
+fn serialize_string(output: &mut String, value: &str) {
+    output.push('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' | '\\' => {
+                output.push('\\');
+                output.push(ch);
+            }
+            '<' | '\n' | '\r' | '\x0c' => {
+                str_write!(output, "\\{:x} ", ch as u32);
+            }
+            _ => output.push(ch),
+        }
+    }
+
+    output.push('"');
+}
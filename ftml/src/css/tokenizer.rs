@@ -0,0 +1,562 @@
+/*
+ * css/tokenizer.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tokenizer for CSS, following the [CSS Syntax Module].
+//!
+//! This does not interpret numbers or distinguish dimensions,
+//! since the sanitizer only needs to know where each token is
+//! and what names and strings it contains.
+//!
+//! [CSS Syntax Module]: https://www.w3.org/TR/css-syntax-3/#tokenization
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    Ident,
+    Function,
+    AtKeyword,
+    Hash,
+    String,
+    BadString,
+    Url,
+    BadUrl,
+    Number,
+    Delim(char),
+    Colon,
+    Semicolon,
+    Comma,
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Cdo,
+    Cdc,
+}
+
+impl TokenKind {
+    /// If this token opens a block, the token which closes it.
+    pub fn closer(self) -> Option<TokenKind> {
+        match self {
+            TokenKind::LeftBrace => Some(TokenKind::RightBrace),
+            TokenKind::LeftParen | TokenKind::Function => Some(TokenKind::RightParen),
+            TokenKind::LeftBracket => Some(TokenKind::RightBracket),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_closer(self) -> bool {
+        matches!(
+            self,
+            TokenKind::RightBrace | TokenKind::RightParen | TokenKind::RightBracket,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssToken<'a> {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+
+    /// The name of an identifier, function, at-keyword, or hash,
+    /// or the contents of a string or URL, with escapes decoded.
+    ///
+    /// This is empty for other tokens.
+    pub value: Cow<'a, str>,
+
+    /// Whether a string or URL was closed before the end of input.
+    pub terminated: bool,
+}
+
+pub fn tokenize(source: &str) -> Vec<CssToken<'_>> {
+    let mut tokenizer = Tokenizer {
+        source,
+        position: 0,
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(token) = tokenizer.next_token() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+#[derive(Debug)]
+struct Tokenizer<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.source[self.position..].chars().nth(offset)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek(0)?;
+        self.position += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn next_token(&mut self) -> Option<CssToken<'a>> {
+        let start = self.position;
+        let ch = self.bump()?;
+
+        let mut value = Cow::Borrowed("");
+        let mut terminated = true;
+
+        let kind = match ch {
+            '/' if self.peek(0) == Some('*') => {
+                self.position = match self.source[start + 2..].find("*/") {
+                    Some(index) => start + 2 + index + 2,
+                    None => self.source.len(),
+                };
+
+                TokenKind::Comment
+            }
+            _ if is_whitespace(ch) => {
+                while self.peek(0).map(is_whitespace).unwrap_or(false) {
+                    self.bump();
+                }
+
+                TokenKind::Whitespace
+            }
+            '"' | '\'' => {
+                let (kind, contents, closed) = self.consume_string(ch);
+                value = Cow::Owned(contents);
+                terminated = closed;
+                kind
+            }
+            '#' if is_name(self.peek(0)) || is_escape(self.peek(0), self.peek(1)) => {
+                value = self.consume_name();
+                TokenKind::Hash
+            }
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            '[' => TokenKind::LeftBracket,
+            ']' => TokenKind::RightBracket,
+            '{' => TokenKind::LeftBrace,
+            '}' => TokenKind::RightBrace,
+            ',' => TokenKind::Comma,
+            ':' => TokenKind::Colon,
+            ';' => TokenKind::Semicolon,
+            _ if starts_number(Some(ch), self.peek(0), self.peek(1)) => {
+                self.position = start;
+                self.consume_numeric();
+                TokenKind::Number
+            }
+            '-' if self.peek(0) == Some('-') && self.peek(1) == Some('>') => {
+                self.bump();
+                self.bump();
+                TokenKind::Cdc
+            }
+            '-' | '\\' if starts_ident(Some(ch), self.peek(0), self.peek(1)) => {
+                self.position = start;
+                self.consume_ident_like(&mut value, &mut terminated)
+            }
+            '<' if self.source[self.position..].starts_with("!--") => {
+                self.position += 3;
+                TokenKind::Cdo
+            }
+            '@' if starts_ident(self.peek(0), self.peek(1), self.peek(2)) => {
+                value = self.consume_name();
+                TokenKind::AtKeyword
+            }
+            _ if is_name_start(ch) => {
+                self.position = start;
+                self.consume_ident_like(&mut value, &mut terminated)
+            }
+            _ => TokenKind::Delim(ch),
+        };
+
+        Some(CssToken {
+            kind,
+            span: start..self.position,
+            value,
+            terminated,
+        })
+    }
+
+    /// Consumes a name, returning it with any escapes decoded.
+    fn consume_name(&mut self) -> Cow<'a, str> {
+        let start = self.position;
+        let mut decoded: Option<String> = None;
+
+        loop {
+            let ch = self.peek(0);
+
+            if is_name(ch) {
+                self.bump();
+
+                if let Some(ref mut decoded) = decoded {
+                    decoded.push(ch.unwrap());
+                }
+            } else if is_escape(ch, self.peek(1)) {
+                let before = self.position;
+                self.bump();
+
+                let escaped = self.consume_escape();
+                decoded
+                    .get_or_insert_with(|| str!(&self.source[start..before]))
+                    .push(escaped);
+            } else {
+                break;
+            }
+        }
+
+        match decoded {
+            Some(decoded) => Cow::Owned(decoded),
+            None => Cow::Borrowed(&self.source[start..self.position]),
+        }
+    }
+
+    /// Consumes an escape, after the backslash.
+    fn consume_escape(&mut self) -> char {
+        let hex_length = self.source[self.position..]
+            .chars()
+            .take(6)
+            .take_while(char::is_ascii_hexdigit)
+            .count();
+
+        if hex_length > 0 {
+            let digits = &self.source[self.position..self.position + hex_length];
+            self.position += hex_length;
+
+            match self.peek(0) {
+                Some('\r') if self.peek(1) == Some('\n') => self.position += 2,
+                Some(ch) if is_whitespace(ch) => self.position += 1,
+                _ => (),
+            }
+
+            return u32::from_str_radix(digits, 16)
+                .ok()
+                .filter(|&value| value != 0)
+                .and_then(char::from_u32)
+                .unwrap_or('\u{fffd}');
+        }
+
+        self.bump().unwrap_or('\u{fffd}')
+    }
+
+    fn consume_string(&mut self, quote: char) -> (TokenKind, String, bool) {
+        let mut contents = String::new();
+
+        loop {
+            match self.bump() {
+                None => return (TokenKind::String, contents, false),
+                Some(ch) if ch == quote => return (TokenKind::String, contents, true),
+                Some(ch) if is_newline(ch) => {
+                    // Unescaped newlines end the string, but are not part of it
+                    self.position -= ch.len_utf8();
+                    return (TokenKind::BadString, contents, false);
+                }
+                Some('\\') => match self.peek(0) {
+                    None => (),
+                    Some('\r') if self.peek(1) == Some('\n') => self.position += 2,
+                    Some(ch) if is_newline(ch) => self.position += 1,
+                    Some(_) => contents.push(self.consume_escape()),
+                },
+                Some(ch) => contents.push(ch),
+            }
+        }
+    }
+
+    fn consume_numeric(&mut self) {
+        if matches!(self.peek(0), Some('+' | '-')) {
+            self.bump();
+        }
+
+        self.consume_digits();
+
+        if self.peek(0) == Some('.') && is_digit(self.peek(1)) {
+            self.bump();
+            self.consume_digits();
+        }
+
+        if matches!(self.peek(0), Some('e' | 'E')) {
+            let digits_offset = match self.peek(1) {
+                Some('+' | '-') => 2,
+                _ => 1,
+            };
+
+            if is_digit(self.peek(digits_offset)) {
+                self.position += digits_offset;
+                self.consume_digits();
+            }
+        }
+
+        // Units or percentages
+        if starts_ident(self.peek(0), self.peek(1), self.peek(2)) {
+            self.consume_name();
+        } else if self.peek(0) == Some('%') {
+            self.bump();
+        }
+    }
+
+    fn consume_digits(&mut self) {
+        while is_digit(self.peek(0)) {
+            self.bump();
+        }
+    }
+
+    fn consume_ident_like(
+        &mut self,
+        value: &mut Cow<'a, str>,
+        terminated: &mut bool,
+    ) -> TokenKind {
+        *value = self.consume_name();
+
+        if self.peek(0) != Some('(') {
+            return TokenKind::Ident;
+        }
+
+        self.bump();
+
+        if !value.eq_ignore_ascii_case("url") {
+            return TokenKind::Function;
+        }
+
+        // If the URL is quoted, then it's a regular function taking a string.
+        let quoted = self.source[self.position..]
+            .chars()
+            .find(|&ch| !is_whitespace(ch))
+            .map(|ch| ch == '"' || ch == '\'')
+            .unwrap_or(false);
+
+        if quoted {
+            return TokenKind::Function;
+        }
+
+        let (kind, url, closed) = self.consume_url();
+        *value = Cow::Owned(url);
+        *terminated = closed;
+        kind
+    }
+
+    fn consume_url(&mut self) -> (TokenKind, String, bool) {
+        let mut url = String::new();
+
+        while self.peek(0).map(is_whitespace).unwrap_or(false) {
+            self.bump();
+        }
+
+        loop {
+            match self.bump() {
+                None => return (TokenKind::Url, url, false),
+                Some(')') => return (TokenKind::Url, url, true),
+                Some(ch) if is_whitespace(ch) => {
+                    while self.peek(0).map(is_whitespace).unwrap_or(false) {
+                        self.bump();
+                    }
+
+                    return match self.bump() {
+                        None => (TokenKind::Url, url, false),
+                        Some(')') => (TokenKind::Url, url, true),
+                        Some(_) => self.consume_bad_url(url),
+                    };
+                }
+                Some('"' | '\'' | '(') => return self.consume_bad_url(url),
+                Some(ch) if is_non_printable(ch) => return self.consume_bad_url(url),
+                Some('\\') => {
+                    if is_escape(Some('\\'), self.peek(0)) {
+                        url.push(self.consume_escape());
+                    } else {
+                        return self.consume_bad_url(url);
+                    }
+                }
+                Some(ch) => url.push(ch),
+            }
+        }
+    }
+
+    /// Consumes the rest of a malformed URL, so parsing can continue after it.
+    fn consume_bad_url(&mut self, url: String) -> (TokenKind, String, bool) {
+        loop {
+            match self.bump() {
+                None => return (TokenKind::BadUrl, url, false),
+                Some(')') => return (TokenKind::BadUrl, url, true),
+                Some('\\') if is_escape(Some('\\'), self.peek(0)) => {
+                    self.consume_escape();
+                }
+                Some(_) => (),
+            }
+        }
+    }
+}
+
+// Character classes
+
+#[inline]
+fn is_newline(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\x0c')
+}
+
+#[inline]
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t') || is_newline(ch)
+}
+
+#[inline]
+fn is_digit(ch: Option<char>) -> bool {
+    matches!(ch, Some('0'..='9'))
+}
+
+#[inline]
+fn is_non_printable(ch: char) -> bool {
+    matches!(ch, '\0'..='\x08' | '\x0b' | '\x0e'..='\x1f' | '\x7f')
+}
+
+#[inline]
+fn is_name_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii()
+}
+
+#[inline]
+fn is_name(ch: Option<char>) -> bool {
+    match ch {
+        Some(ch) => is_name_start(ch) || ch.is_ascii_digit() || ch == '-',
+        None => false,
+    }
+}
+
+/// Whether these two characters are a valid escape.
+#[inline]
+fn is_escape(first: Option<char>, second: Option<char>) -> bool {
+    match (first, second) {
+        (Some('\\'), Some(ch)) => !is_newline(ch),
+        _ => false,
+    }
+}
+
+/// Whether these three characters would start an identifier.
+fn starts_ident(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('-') => match second {
+            Some(ch) if is_name_start(ch) || ch == '-' => true,
+            _ => is_escape(second, third),
+        },
+        Some('\\') => is_escape(first, second),
+        Some(ch) => is_name_start(ch),
+        None => false,
+    }
+}
+
+/// Whether these three characters would start a number.
+fn starts_number(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('+' | '-') => is_digit(second) || (second == Some('.') && is_digit(third)),
+        Some('.') => is_digit(second),
+        ch => is_digit(ch),
+    }
+}
+
+#[test]
+fn tokens() {
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let tokens = tokenize($input);
+            let actual: Vec<(TokenKind, &str)> = tokens
+                .iter()
+                .map(|token| (token.kind, token.value.as_ref()))
+                .collect();
+
+            assert_eq!(actual, $expected, "Tokens for {:?} don't match", $input);
+        }};
+    }
+
+    use TokenKind::*;
+
+    check!(
+        "a{color:red}",
+        [
+            (Ident, "a"),
+            (LeftBrace, ""),
+            (Ident, "color"),
+            (Colon, ""),
+            (Ident, "red"),
+            (RightBrace, ""),
+        ],
+    );
+    check!(
+        "@media (max-width: 10.5em) /* x */",
+        [
+            (AtKeyword, "media"),
+            (Whitespace, ""),
+            (LeftParen, ""),
+            (Ident, "max-width"),
+            (Colon, ""),
+            (Whitespace, ""),
+            (Number, ""),
+            (RightParen, ""),
+            (Whitespace, ""),
+            (Comment, ""),
+        ],
+    );
+    check!(
+        r"expr\65 ssion(1)",
+        [(Function, "expression"), (Number, ""), (RightParen, "")],
+    );
+    check!(
+        "url( javascript:alert\\(1\\) )",
+        [(Url, "javascript:alert(1)")],
+    );
+    check!(
+        "url('a.png') url(a b)",
+        [
+            (Function, "url"),
+            (String, "a.png"),
+            (RightParen, ""),
+            (Whitespace, ""),
+            (BadUrl, "a"),
+        ],
+    );
+    check!(
+        "\"a\\\"b\" 'c\nd",
+        [
+            (String, "a\"b"),
+            (Whitespace, ""),
+            (BadString, "c"),
+            (Whitespace, ""),
+            (Ident, "d"),
+        ],
+    );
+    check!(
+        "<!-- #a1 -1px --> </",
+        [
+            (Cdo, ""),
+            (Whitespace, ""),
+            (Hash, "a1"),
+            (Whitespace, ""),
+            (Number, ""),
+            (Whitespace, ""),
+            (Cdc, ""),
+            (Whitespace, ""),
+            (Delim('<'), ""),
+            (Delim('/'), ""),
+        ],
+    );
+}
@@ -25,6 +25,7 @@ use std::borrow::Cow;
 #[serde(rename_all = "kebab-case")]
 pub struct Backlinks<'a> {
     pub included_pages: Vec<PageRef<'a>>,
+    pub included_elements: Vec<PageRef<'a>>,
     pub internal_links: Vec<PageRef<'a>>,
     pub external_links: Vec<Cow<'a, str>>,
 }
@@ -22,12 +22,17 @@
 
 mod backlinks;
 mod karma;
+mod module;
 mod page_info;
 mod page_ref;
 mod user_info;
 
 pub use self::backlinks::Backlinks;
 pub use self::karma::KarmaLevel;
+pub use self::module::{
+    CategoryListing, JoinState, ListPagesEntry, ListPagesResult, PageListing,
+    PageTreeNode,
+};
 pub use self::page_info::PageInfo;
 pub use self::page_ref::{PageRef, PageRefParseError};
 pub use self::user_info::UserInfo;
@@ -0,0 +1,133 @@
+/*
+ * data/module.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Data requested by renderers when rendering modules.
+//!
+//! See [`Handle`](crate::render::Handle) for how this information is retrieved.
+
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+
+/// A page, as listed in the output of a module.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PageListing<'a> {
+    /// The slug of the page, used to link to it.
+    pub slug: Cow<'a, str>,
+
+    /// The title of the page.
+    pub title: Cow<'a, str>,
+}
+
+/// A category on the site, along with the pages it contains.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CategoryListing<'a> {
+    /// The name of the category.
+    pub name: Cow<'a, str>,
+
+    /// The pages within this category.
+    pub pages: Vec<PageListing<'a>>,
+}
+
+/// A page within a hierarchy of parent relationships.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PageTreeNode<'a> {
+    /// The page this node represents.
+    pub page: PageListing<'a>,
+
+    /// Pages which have this page as their parent.
+    pub children: Vec<PageTreeNode<'a>>,
+}
+
+/// Whether the current user is able to join the site.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JoinState {
+    /// The user may join the site immediately.
+    Open,
+
+    /// The user may apply to join the site.
+    Application,
+
+    /// The user is already a member of the site.
+    Member,
+
+    /// The site is not accepting new members.
+    Closed,
+}
+
+impl JoinState {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            JoinState::Open => "open",
+            JoinState::Application => "application",
+            JoinState::Member => "member",
+            JoinState::Closed => "closed",
+        }
+    }
+}
+
+/// A page matched by `[[module ListPages]]`.
+///
+/// The fields here are substituted into the module's template.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListPagesEntry<'a> {
+    /// The full slug of the page, including any category.
+    pub slug: Cow<'a, str>,
+
+    /// The title of the page.
+    pub title: Cow<'a, str>,
+
+    /// The current rating of the page.
+    pub rating: f64,
+
+    /// The tags the page has.
+    pub tags: Vec<Cow<'a, str>>,
+
+    /// When the page was created, as a UNIX timestamp.
+    pub created_at: i64,
+
+    /// The name of the user who created the page.
+    pub created_by: Cow<'a, str>,
+
+    /// When the page was last updated, as a UNIX timestamp.
+    pub updated_at: i64,
+
+    /// The wikitext source of the page.
+    pub content: Cow<'a, str>,
+}
+
+/// The pages matched by `[[module ListPages]]`, on the current pagination page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListPagesResult<'a> {
+    /// The matching pages to list on this pagination page.
+    pub entries: Vec<ListPagesEntry<'a>>,
+
+    /// The total number of matching pages, across all pagination pages.
+    pub total: usize,
+
+    /// Which pagination page is being shown, starting from one.
+    pub current_page: NonZeroU32,
+}
@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::PageRef;
 use std::borrow::Cow;
 
 /// Metadata information on the article being rendered.
@@ -62,6 +63,20 @@ pub struct PageInfo<'a> {
 }
 
 impl PageInfo<'_> {
+    /// Gets a reference to this page, including its site.
+    ///
+    /// The page name is prefixed with its category, unless it is in `_default`.
+    pub fn page_ref(&self) -> PageRef<'static> {
+        let page = match self.category.as_deref() {
+            Some(category) if category != "_default" => {
+                format!("{}:{}", category, self.page)
+            }
+            _ => str!(self.page),
+        };
+
+        PageRef::page_and_site(str!(self.site), page)
+    }
+
     /// Generate a dummy PageInfo instance for tests.
     #[cfg(test)]
     pub fn dummy() -> Self {
@@ -0,0 +1,782 @@
+/*
+ * import/markdown.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::render::wikitext::escape::{escape, push_raw};
+use crate::url::is_url;
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, CowStr, Event, LinkType, Options, Parser, Tag,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Converts a CommonMark document into equivalent wikitext.
+///
+/// The [GitHub Flavored Markdown] extensions for tables, strikethrough,
+/// task list items, and footnotes are also supported.
+///
+/// Constructs are mapped to their wikitext equivalents:
+///
+/// * Headings become `+` headings.
+/// * Emphasis, strong emphasis, and strikethrough become
+///   `//italics//`, `**bold**`, and `--strikethrough--`.
+/// * Inline code becomes `{{monospace}}`, and code blocks become
+///   `[[code]]` blocks, with the language of fenced code as its `type`.
+/// * Links become single-bracket links if they are URLs or absolute paths,
+///   or triple-bracket links otherwise. Their labels are reduced to plain text.
+/// * Images become `[[image]]` blocks, with any surrounding link as its `link`.
+/// * Lists become native `*` and `#` lists, unless they contain blocks such as
+///   code or quotes, in which case `[[ul]]` and `[[ol]]` blocks are used.
+/// * Task list items become `[[checkbox]]` elements.
+/// * Tables become `||` tables, with headers as title cells.
+/// * Footnotes become `[[footnote]]` blocks at the point they are referenced.
+/// * Block quotes become `[[blockquote]]` blocks.
+/// * HTML blocks become `[[html]]` blocks, and HTML comments become `[!-- --]` comments.
+///   Inline HTML has no equivalent, and so is kept as raw text.
+///
+/// Any text which would otherwise be interpreted as wikitext is escaped.
+///
+/// [GitHub Flavored Markdown]: https://github.github.com/gfm/
+pub fn markdown_to_wikitext(markdown: &str) -> String {
+    info!("Converting Markdown to wikitext ({} bytes)", markdown.len());
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    let parser = Parser::new_ext(markdown, options).into_offset_iter();
+    let (events, definitions) = split_footnotes(coalesce(parser));
+
+    // Footnotes are placed where they are referenced,
+    // so their contents need to be converted first.
+    let footnotes = {
+        let no_footnotes = HashMap::new();
+        let mut footnotes = HashMap::new();
+
+        for (label, events) in definitions {
+            debug!("Converting footnote definition '{label}'");
+
+            let contents = Converter::new(&no_footnotes).convert(&events);
+            footnotes.insert(label.into_string(), contents);
+        }
+
+        footnotes
+    };
+
+    Converter::new(&footnotes).convert(&events)
+}
+
+/// Merges adjacent text events, and HTML events from the same block.
+///
+/// The parser splits text at any character which could have been syntax,
+/// so this is needed to find sequences which must be escaped in wikitext.
+fn coalesce<'a, I>(events: I) -> Vec<Event<'a>>
+where
+    I: Iterator<Item = (Event<'a>, Range<usize>)>,
+{
+    let mut merged: Vec<Event> = Vec::new();
+    let mut last_end = 0;
+
+    for (event, range) in events {
+        match (merged.last_mut(), event) {
+            (Some(Event::Text(previous)), Event::Text(text)) => {
+                *previous = CowStr::from(format!("{previous}{text}"));
+            }
+            (Some(Event::Html(previous)), Event::Html(html))
+                if range.start == last_end =>
+            {
+                *previous = CowStr::from(format!("{previous}{html}"));
+            }
+            (_, event) => merged.push(event),
+        }
+
+        last_end = range.end;
+    }
+
+    merged
+}
+
+type FootnoteDefinitions<'a> = Vec<(CowStr<'a>, Vec<Event<'a>>)>;
+
+/// Separates footnote definitions from the rest of the document.
+fn split_footnotes(events: Vec<Event>) -> (Vec<Event>, FootnoteDefinitions) {
+    let mut body = Vec::new();
+    let mut definitions = Vec::new();
+    let mut current: Option<(CowStr, Vec<Event>)> = None;
+
+    for event in events {
+        match (event, &mut current) {
+            (Event::Start(Tag::FootnoteDefinition(label)), _) => {
+                current = Some((label, Vec::new()));
+            }
+            (Event::End(Tag::FootnoteDefinition(_)), _) => {
+                definitions.extend(current.take());
+            }
+            (event, Some((_, events))) => events.push(event),
+            (event, None) => body.push(event),
+        }
+    }
+
+    (body, definitions)
+}
+
+/// What kind of structure text is currently being written into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Container {
+    BlockQuote,
+    Heading,
+    TableCell,
+    ListItem { native: bool },
+}
+
+#[derive(Debug, Copy, Clone)]
+struct List {
+    ordered: bool,
+    native: bool,
+}
+
+#[derive(Debug)]
+struct Link<'a> {
+    ltype: LinkType,
+    url: CowStr<'a>,
+    images: Vec<String>,
+}
+
+/// Text which is captured without formatting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Plain {
+    LinkLabel,
+    ImageDescription,
+}
+
+#[derive(Debug)]
+struct Converter<'a> {
+    /// The finished output, plus any buffers for text being captured.
+    ///
+    /// The last buffer is the one currently being written to.
+    buffers: Vec<String>,
+    footnotes: &'a HashMap<String, String>,
+    containers: Vec<Container>,
+    lists: Vec<List>,
+    links: Vec<Link<'a>>,
+
+    /// Which link labels or image descriptions we are in.
+    ///
+    /// Their contents are written as plain text, without any formatting.
+    plain: Vec<Plain>,
+
+    /// Whether the current text is part of a paragraph.
+    in_paragraph: bool,
+
+    /// Whether the current text is part of a code block.
+    in_code: bool,
+
+    /// Column alignments for the current table, and the current column.
+    table_alignments: Vec<Alignment>,
+    table_column: usize,
+    table_head: bool,
+}
+
+impl<'a> Converter<'a> {
+    fn new(footnotes: &'a HashMap<String, String>) -> Self {
+        Converter {
+            buffers: vec![String::new()],
+            footnotes,
+            containers: Vec::new(),
+            lists: Vec::new(),
+            links: Vec::new(),
+            plain: Vec::new(),
+            in_paragraph: false,
+            in_code: false,
+            table_alignments: Vec::new(),
+            table_column: 0,
+            table_head: false,
+        }
+    }
+
+    fn convert(mut self, events: &[Event<'a>]) -> String {
+        for (index, event) in events.iter().enumerate() {
+            match event {
+                Event::Start(tag) => self.start_tag(tag, &events[index + 1..]),
+                Event::End(tag) => self.end_tag(tag),
+                Event::Text(text) => self.push_text(text),
+                Event::Code(code) => self.push_code(code),
+                Event::Html(html) => self.push_html(html),
+                Event::FootnoteReference(label) => self.push_footnote(label),
+                Event::SoftBreak => self.push_str(" "),
+                Event::HardBreak => self.push_line_break(),
+                Event::Rule => {
+                    self.end_line();
+                    self.push_str("----");
+                    self.end_block();
+                }
+                Event::TaskListMarker(checked) => {
+                    self.push_str(if *checked {
+                        "[[*checkbox]] "
+                    } else {
+                        "[[checkbox]] "
+                    });
+                }
+            }
+        }
+
+        debug_assert_eq!(self.buffers.len(), 1, "Captured buffers not finished");
+        let mut output = self.buffers.pop().unwrap();
+        output.truncate(output.trim_end().len());
+        output
+    }
+
+    fn start_tag(&mut self, tag: &Tag<'a>, rest: &[Event<'a>]) {
+        match tag {
+            Tag::Link(ltype, url, _) => {
+                self.links.push(Link {
+                    ltype: *ltype,
+                    url: url.clone(),
+                    images: Vec::new(),
+                });
+
+                self.plain.push(Plain::LinkLabel);
+                self.buffers.push(String::new());
+            }
+            Tag::Image(..) => {
+                self.plain.push(Plain::ImageDescription);
+                self.buffers.push(String::new());
+            }
+
+            // Only the text of link labels and image descriptions is kept
+            _ if !self.plain.is_empty() => (),
+
+            Tag::Paragraph => self.in_paragraph = true,
+            Tag::Heading(level, _, _) => {
+                self.end_line();
+
+                for _ in 0..*level as usize {
+                    self.push_str("+");
+                }
+
+                self.push_str(" ");
+                self.containers.push(Container::Heading);
+            }
+            Tag::BlockQuote => {
+                self.end_line();
+                self.push_str("[[blockquote]]\n");
+                self.containers.push(Container::BlockQuote);
+            }
+            Tag::CodeBlock(kind) => {
+                self.end_line();
+
+                match kind {
+                    CodeBlockKind::Fenced(info) if !info.trim().is_empty() => {
+                        // Only the language is kept, other information is dropped
+                        let language = info.split_whitespace().next().unwrap_or("");
+                        let language = language.split(',').next().unwrap_or("");
+
+                        self.push_str("[[code type=");
+                        self.push_string_argument(language);
+                        self.push_str("]]\n");
+                    }
+                    _ => self.push_str("[[code]]\n"),
+                }
+
+                self.in_code = true;
+            }
+            Tag::List(start) => {
+                // Native lists cannot contain blocks, and cannot be nested in block lists
+                let native = match self.lists.last() {
+                    Some(parent) => parent.native,
+                    None => !contains_blocks(rest),
+                };
+
+                let ordered = start.is_some();
+                self.end_item_line();
+                self.lists.push(List { ordered, native });
+
+                if !native {
+                    self.push_str(if ordered { "[[ol]]\n" } else { "[[ul]]\n" });
+                }
+            }
+            Tag::Item => {
+                let list = *self.lists.last().expect("List item outside of list");
+                self.end_item_line();
+
+                if list.native {
+                    for _ in 1..self.lists.len() {
+                        self.push_str(" ");
+                    }
+
+                    self.push_str(if list.ordered { "# " } else { "* " });
+                } else {
+                    self.push_str("[[li]]");
+                }
+
+                self.containers.push(Container::ListItem {
+                    native: list.native,
+                });
+            }
+            Tag::FootnoteDefinition(_) => {
+                unreachable!("Footnote definitions should have been separated")
+            }
+            Tag::Table(alignments) => {
+                self.end_line();
+                self.table_alignments = alignments.clone();
+            }
+            Tag::TableHead => {
+                self.table_head = true;
+                self.table_column = 0;
+            }
+            Tag::TableRow => self.table_column = 0,
+            Tag::TableCell => {
+                self.containers.push(Container::TableCell);
+                self.buffers.push(String::new());
+            }
+            Tag::Emphasis => self.push_str("//"),
+            Tag::Strong => self.push_str("**"),
+            Tag::Strikethrough => self.push_str("--"),
+        }
+    }
+
+    fn end_tag(&mut self, tag: &Tag<'a>) {
+        match tag {
+            Tag::Link(_, url, title) => {
+                self.plain.pop();
+
+                let label = self.buffers.pop().unwrap();
+                let link = self.links.pop().expect("Link stack empty");
+                let label = label.trim();
+
+                if !self.plain.is_empty() {
+                    self.push_str(label);
+                    return;
+                }
+
+                // Images are placed in their own blocks, linking to the URL instead
+                for image in &link.images {
+                    self.push_str(image);
+                }
+
+                if !label.is_empty() || link.images.is_empty() {
+                    let link = build_link(link.ltype, url, label, title);
+                    self.push_str(&link);
+                }
+            }
+            Tag::Image(_, url, title) => {
+                self.plain.pop();
+
+                let alt = self.buffers.pop().unwrap();
+
+                match self.plain.last() {
+                    None => {
+                        let image = build_image(url, &alt, title, None);
+                        self.push_str(&image);
+                    }
+                    Some(Plain::LinkLabel) => {
+                        let link = self.links.last_mut().expect("Link stack empty");
+                        let image = build_image(url, &alt, title, Some(&link.url));
+                        link.images.push(image);
+                    }
+                    Some(Plain::ImageDescription) => self.push_str(&alt),
+                }
+            }
+
+            // Still within a link label or image description
+            _ if !self.plain.is_empty() => (),
+
+            Tag::Paragraph => {
+                self.in_paragraph = false;
+
+                match self.containers.last() {
+                    Some(Container::ListItem { native: true }) => self.push_str(" _\n"),
+                    _ => self.end_block(),
+                }
+            }
+            Tag::Heading(..) => {
+                self.containers.pop();
+                self.end_block();
+            }
+            Tag::BlockQuote => {
+                self.containers.pop();
+                self.trim_end();
+                self.push_str("\n[[/blockquote]]");
+                self.end_block();
+            }
+            Tag::CodeBlock(_) => {
+                self.in_code = false;
+                self.end_line();
+                self.push_str("[[/code]]");
+                self.end_block();
+            }
+            Tag::List(_) => {
+                let list = self.lists.pop().expect("List stack empty");
+
+                if !list.native {
+                    self.end_line();
+                    self.push_str(if list.ordered { "[[/ol]]" } else { "[[/ul]]" });
+                }
+
+                if self.lists.is_empty() {
+                    self.end_block();
+                }
+            }
+            Tag::Item => {
+                let native = matches!(
+                    self.containers.pop(),
+                    Some(Container::ListItem { native: true }),
+                );
+
+                self.trim_end();
+
+                if native {
+                    self.trim_end_matches(" _");
+                    self.push_str("\n");
+                } else {
+                    self.push_str("[[/li]]\n");
+                }
+            }
+            Tag::FootnoteDefinition(_) => {
+                unreachable!("Footnote definitions should have been separated")
+            }
+            Tag::Table(_) => {
+                self.table_alignments.clear();
+                self.end_block();
+            }
+            Tag::TableHead => {
+                self.table_head = false;
+                self.push_str("||\n");
+            }
+            Tag::TableRow => self.push_str("||\n"),
+            Tag::TableCell => {
+                self.containers.pop();
+
+                let contents = self.buffers.pop().unwrap();
+                let marker = if self.table_head {
+                    "||~ "
+                } else {
+                    match self.table_alignments.get(self.table_column) {
+                        Some(Alignment::Left) => "||< ",
+                        Some(Alignment::Center) => "||= ",
+                        Some(Alignment::Right) => "||> ",
+                        Some(Alignment::None) | None => "|| ",
+                    }
+                };
+
+                self.push_str(marker);
+                self.push_str(contents.trim());
+                self.push_str(" ");
+                self.table_column += 1;
+            }
+            Tag::Emphasis => self.push_str("//"),
+            Tag::Strong => self.push_str("**"),
+            Tag::Strikethrough => self.push_str("--"),
+        }
+    }
+
+    // Text
+
+    fn push_text(&mut self, text: &str) {
+        if self.in_code || !self.plain.is_empty() {
+            self.push_str(text);
+        } else if self.in_single_line() {
+            let line_start = self.at_line_start();
+            let buffer = self.buffers.last_mut().unwrap();
+            escape(buffer, &text.replace('\n', " "), line_start);
+        } else {
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.push_str("\n");
+                }
+
+                let line_start = self.at_line_start();
+                let buffer = self.buffers.last_mut().unwrap();
+                escape(buffer, line, line_start);
+            }
+        }
+    }
+
+    fn push_code(&mut self, code: &str) {
+        if !self.plain.is_empty() {
+            self.push_str(code);
+            return;
+        }
+
+        self.push_str("{{");
+
+        if code.chars().all(is_plain_character) {
+            self.push_str(code);
+        } else {
+            let buffer = self.buffers.last_mut().unwrap();
+            push_raw(buffer, code);
+        }
+
+        self.push_str("}}");
+    }
+
+    fn push_html(&mut self, html: &str) {
+        if !self.plain.is_empty() {
+            return;
+        }
+
+        if self.in_inline() {
+            // Markdown renderers pass inline HTML through,
+            // but wikitext has no equivalent, so display it instead.
+            self.push_text(html);
+        } else {
+            self.push_html_block(html);
+        }
+    }
+
+    fn push_html_block(&mut self, html: &str) {
+        let trimmed = html.trim();
+        self.end_line();
+
+        match trimmed
+            .strip_prefix("<!--")
+            .and_then(|comment| comment.strip_suffix("-->"))
+        {
+            Some(comment) if !comment.contains("--]") => {
+                self.push_str("[!--");
+                self.push_str(comment);
+                self.push_str("--]");
+            }
+            _ => {
+                self.push_str("[[html]]\n");
+                self.push_str(trimmed);
+                self.push_str("\n[[/html]]");
+            }
+        }
+
+        self.end_block();
+    }
+
+    fn push_footnote(&mut self, label: &str) {
+        if !self.plain.is_empty() {
+            return;
+        }
+
+        match self.footnotes.get(label) {
+            Some(contents) => {
+                self.push_str("[[footnote]]");
+                self.push_str(contents);
+                self.push_str("[[/footnote]]");
+            }
+            None => {
+                warn!("No definition for footnote '{label}'");
+                self.push_text(&format!("[^{label}]"));
+            }
+        }
+    }
+
+    fn push_line_break(&mut self) {
+        if !self.plain.is_empty() || self.in_single_line() {
+            self.push_str(" ");
+            return;
+        }
+
+        match self.containers.last() {
+            Some(Container::ListItem { native: true }) => self.push_str(" _\n"),
+            _ => self.push_str("\n"),
+        }
+    }
+
+    fn push_string_argument(&mut self, value: &str) {
+        let value = build_string_argument(value);
+        self.push_str(&value);
+    }
+
+    // Buffer management
+
+    #[inline]
+    fn push_str(&mut self, s: &str) {
+        self.buffers.last_mut().unwrap().push_str(s);
+    }
+
+    fn trim_end(&mut self) {
+        let buffer = self.buffers.last_mut().unwrap();
+        buffer.truncate(buffer.trim_end().len());
+    }
+
+    fn trim_end_matches(&mut self, pattern: &str) {
+        let buffer = self.buffers.last_mut().unwrap();
+        buffer.truncate(buffer.trim_end_matches(pattern).len());
+    }
+
+    /// Ends the current line, if there is anything on it.
+    fn end_line(&mut self) {
+        let buffer = self.buffers.last_mut().unwrap();
+
+        if !buffer.is_empty() && !buffer.ends_with('\n') {
+            buffer.push('\n');
+        }
+    }
+
+    /// Ends the current line, if there is anything on it.
+    ///
+    /// Within native list items, any trailing line break is removed,
+    /// since starting a new item or list already ends the line.
+    fn end_item_line(&mut self) {
+        if let Some(Container::ListItem { native: true }) = self.containers.last() {
+            self.trim_end();
+            self.trim_end_matches(" _");
+        }
+
+        self.end_line();
+    }
+
+    /// Ensures that the next text written starts a new block.
+    ///
+    /// Blocks are separated by a blank line, except within native lists,
+    /// where a blank line would end the list.
+    fn end_block(&mut self) {
+        self.trim_end();
+
+        let native_list = matches!(self.lists.last(), Some(List { native: true, .. }));
+        let buffer = self.buffers.last_mut().unwrap();
+        if !buffer.is_empty() {
+            buffer.push_str(if native_list { "\n" } else { "\n\n" });
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        let buffer = self.buffers.last().unwrap();
+
+        match buffer.chars().next_back() {
+            Some(ch) => ch == '\n',
+            None => self.buffers.len() == 1,
+        }
+    }
+
+    /// Whether the current container cannot span multiple lines.
+    fn in_single_line(&self) -> bool {
+        matches!(
+            self.containers.last(),
+            Some(Container::Heading | Container::TableCell),
+        )
+    }
+
+    /// Whether the current position is within a paragraph or other inline context.
+    fn in_inline(&self) -> bool {
+        self.in_paragraph
+            || !self.at_line_start()
+            || !self.plain.is_empty()
+            || matches!(
+                self.containers.last(),
+                Some(
+                    Container::Heading
+                        | Container::TableCell
+                        | Container::ListItem { native: true }
+                ),
+            )
+    }
+}
+
+/// Determines if a list contains any blocks, which native lists cannot hold.
+///
+/// This only checks up until the end of the list.
+fn contains_blocks(events: &[Event]) -> bool {
+    let mut depth = 0;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::List(_)) => depth += 1,
+            Event::End(Tag::List(_)) if depth == 0 => return false,
+            Event::End(Tag::List(_)) => depth -= 1,
+            Event::Start(
+                Tag::BlockQuote | Tag::CodeBlock(_) | Tag::Heading(..) | Tag::Table(_),
+            )
+            | Event::Rule => return true,
+            _ => (),
+        }
+    }
+
+    false
+}
+
+/// Characters which can be in monospace text without needing to be escaped.
+fn is_plain_character(ch: char) -> bool {
+    ch.is_alphanumeric() || " .;!?()'\"".contains(ch)
+}
+
+fn build_link(ltype: LinkType, url: &str, label: &str, title: &str) -> String {
+    match ltype {
+        // Both URLs and emails are automatically linked in wikitext
+        LinkType::Autolink | LinkType::Email if label == url => {
+            let mut buffer = String::new();
+            escape(&mut buffer, url, false);
+            return buffer;
+        }
+        _ => (),
+    }
+
+    if !title.is_empty() {
+        debug!("Dropping link title '{title}', it has no wikitext equivalent");
+    }
+
+    let url = url.replace(' ', "%20");
+    let label = if label.is_empty() { &url } else { label };
+
+    if (url.starts_with('/') || url.starts_with('#') || is_url(&url))
+        && !label.contains(']')
+    {
+        format!("[{url} {label}]")
+    } else {
+        format!("[[[{url}|{label}]]]")
+    }
+}
+
+fn build_image(url: &str, alt: &str, title: &str, link: Option<&str>) -> String {
+    let mut image = format!("[[image {}", url.replace(' ', "%20"));
+
+    let mut add_argument = |key: &str, value: &str| {
+        if !value.is_empty() {
+            image.push(' ');
+            image.push_str(key);
+            image.push('=');
+            image.push_str(&build_string_argument(value));
+        }
+    };
+
+    add_argument("alt", alt.trim());
+    add_argument("title", title);
+
+    if let Some(link) = link {
+        add_argument("link", link);
+    }
+
+    image.push_str("]]");
+    image
+}
+
+/// Builds a double-quoted string, for use as a block argument.
+fn build_string_argument(value: &str) -> String {
+    let mut string = String::with_capacity(value.len() + 2);
+    string.push('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\t' => string.push_str("\\t"),
+            _ => string.push(ch),
+        }
+    }
+
+    string.push('"');
+    string
+}
@@ -0,0 +1,51 @@
+/*
+ * import/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Conversion of documents in other markup languages into wikitext.
+//!
+//! The output is ordinary wikitext, which can then be edited further,
+//! or passed through the usual preprocess, tokenize, and parse steps.
+//! For convenience, [`markdown_to_tree`] performs all of these at once.
+
+mod markdown;
+
+pub use self::markdown::markdown_to_wikitext;
+
+use crate::data::PageInfo;
+use crate::parsing::ParseOutcome;
+use crate::settings::WikitextSettings;
+use crate::tree::SyntaxTree;
+
+/// Converts a CommonMark document into a syntax tree.
+///
+/// This is the same as parsing the output of [`markdown_to_wikitext`].
+pub fn markdown_to_tree(
+    markdown: &str,
+    page_info: &PageInfo,
+    settings: &WikitextSettings,
+) -> ParseOutcome<SyntaxTree<'static>> {
+    let mut wikitext = markdown_to_wikitext(markdown);
+    crate::preprocess(&mut wikitext);
+
+    let tokens = crate::tokenize(&wikitext);
+    let (tree, warnings) = crate::parse(&tokens, page_info, settings).into();
+
+    ParseOutcome::new(tree.to_owned(), warnings)
+}
@@ -0,0 +1,121 @@
+/*
+ * includes/ancestors.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::{PageInfo, PageRef};
+use std::borrow::Cow;
+use wikidot_normalize::normalize;
+
+/// The chain of pages being included to reach the current one.
+///
+/// This begins with the page being rendered, so that a page including itself,
+/// directly or through other pages, is detected at the first recurrence.
+#[derive(Debug, Clone)]
+pub struct IncludeAncestors<'t> {
+    /// The site of the page being rendered.
+    site: Cow<'t, str>,
+
+    /// The pages being expanded, outermost first.
+    ///
+    /// The first is always the page being rendered.
+    pages: Vec<PageRef<'t>>,
+}
+
+impl IncludeAncestors<'static> {
+    pub fn new(page_info: &PageInfo) -> Self {
+        IncludeAncestors {
+            site: Cow::Owned(str!(page_info.site)),
+            pages: vec![page_info.page_ref()],
+        }
+    }
+}
+
+impl<'t> IncludeAncestors<'t> {
+    /// The number of included pages being expanded.
+    ///
+    /// This does not count the page being rendered.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.pages.len() - 1
+    }
+
+    /// The included page whose includes are being expanded.
+    ///
+    /// This is `None` if it is the page being rendered.
+    #[inline]
+    pub fn parent(&self) -> Option<&PageRef<'t>> {
+        self.pages[1..].last()
+    }
+
+    #[inline]
+    pub fn push(&mut self, page_ref: PageRef<'t>) {
+        self.pages.push(page_ref);
+    }
+
+    #[inline]
+    pub fn pop(&mut self) {
+        debug_assert!(self.pages.len() > 1, "Popped page being rendered");
+        self.pages.pop();
+    }
+
+    /// Gives a page reference found within the given page the site of that page.
+    ///
+    /// References without a site are relative to the page they are in, so those
+    /// within a page from another site refer to that site rather than this one.
+    pub fn qualify<'p>(&self, within: &PageRef, page_ref: PageRef<'p>) -> PageRef<'p> {
+        match (page_ref.site(), self.other_site(within)) {
+            (None, Some(site)) => PageRef {
+                site: Some(Cow::Owned(str!(site))),
+                page: page_ref.page,
+            },
+            _ => page_ref,
+        }
+    }
+
+    /// Determines if including this page would cause an include cycle.
+    pub fn is_cycle(&self, page_ref: &PageRef) -> bool {
+        self.pages
+            .iter()
+            .any(|ancestor| self.same_page(ancestor, page_ref))
+    }
+
+    /// Determines if two page references refer to the same page.
+    ///
+    /// Page slugs are compared after normalization, so `Some-Page` and `some-page`
+    /// are considered the same. References without a site are on the site
+    /// being rendered.
+    fn same_page(&self, first: &PageRef, second: &PageRef) -> bool {
+        fn normalized(page: &str) -> String {
+            let mut page = str!(page);
+            normalize(&mut page);
+            page
+        }
+
+        self.other_site(first) == self.other_site(second)
+            && normalized(first.page()) == normalized(second.page())
+    }
+
+    /// Gets the site of this page, or `None` if it is on the site being rendered.
+    fn other_site<'a>(&self, page_ref: &'a PageRef) -> Option<&'a str> {
+        match page_ref.site() {
+            Some(site) if !site.eq_ignore_ascii_case(&self.site) => Some(site),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * includes/include_async.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Asynchronous version of [`include`](super::include).
+
+use super::state::{IncludeState, NestedPage, Replacement};
+use super::{find_includes, substitute_ranges, AsyncIncluder, IncludeRef, IncludedPage};
+use crate::data::PageInfo;
+use crate::settings::WikitextSettings;
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+
+type SubstituteFuture<'a, E> =
+    Pin<Box<dyn Future<Output = Result<String, E>> + Send + 'a>>;
+
+/// Substitutes all `[[include-messy]]` blocks with the wikitext of the included pages.
+///
+/// This is the same as [`include`](super::include), except pages are fetched
+/// using an [`AsyncIncluder`], so that the includer need not block.
+pub async fn include_async<'t, I, E, F>(
+    input: &'t str,
+    page_info: &PageInfo<'_>,
+    settings: &WikitextSettings,
+    includer: I,
+    invalid_return: F,
+) -> Result<(String, Vec<IncludedPage<'t>>), E>
+where
+    I: AsyncIncluder<'t, Error = E> + Send,
+    F: FnOnce() -> E + Send,
+{
+    if !settings.enable_page_syntax {
+        info!("Includes are disabled for this input, skipping");
+
+        let output = str!(input);
+        let pages = vec![];
+        return Ok((output, pages));
+    }
+
+    info!("Finding and replacing all instances of include blocks in text (async)");
+
+    let mut context = AsyncIncludeContext {
+        state: IncludeState::new(settings, page_info, invalid_return),
+        includer,
+    };
+
+    let (ranges, includes) = find_includes(input, settings);
+    let output = context.substitute(input, ranges, includes, 1).await?;
+
+    Ok((output, context.state.into_pages()))
+}
+
+#[derive(Debug)]
+struct AsyncIncludeContext<'s, 't, I, F> {
+    state: IncludeState<'s, 't, F>,
+    includer: I,
+}
+
+impl<'s, 't, I, E, F> AsyncIncludeContext<'s, 't, I, F>
+where
+    I: AsyncIncluder<'t, Error = E> + Send,
+    F: FnOnce() -> E + Send,
+{
+    /// Replaces the given include blocks in the input text.
+    ///
+    /// Since this recurses into included pages, the future is boxed.
+    /// See `IncludeContext::substitute()` for more information.
+    fn substitute<'a>(
+        &'a mut self,
+        input: &'a str,
+        ranges: Vec<Range<usize>>,
+        includes: Vec<IncludeRef<'t>>,
+        depth: u32,
+    ) -> SubstituteFuture<'a, E> {
+        Box::pin(async move {
+            if includes.is_empty() {
+                return Ok(str!(input));
+            }
+
+            // Replace blocks which are nested too deeply, without fetching them
+            if self.state.depth_exceeded(depth) {
+                let mut replacements = Vec::with_capacity(includes.len());
+
+                for (range, include) in ranges.into_iter().zip(includes) {
+                    let replace_with = self
+                        .includer
+                        .include_depth_exceeded(include.page_ref())
+                        .await?
+                        .into_owned();
+
+                    replacements.push((range, replace_with));
+                }
+
+                return Ok(substitute_ranges(input, replacements));
+            }
+
+            // Retrieve included pages
+            let fetched_pages = self.includer.include_pages(&includes).await?;
+            self.state.check_fetched(&includes, &fetched_pages)?;
+
+            // Get the content to replace each include block with
+            let mut replacements = Vec::with_capacity(includes.len());
+
+            for ((range, include), fetched) in
+                ranges.into_iter().zip(includes).zip(fetched_pages)
+            {
+                let replacement = self.state.resolve(include, fetched, depth)?;
+                let replace_with = match replacement {
+                    Replacement::Cycle(page_ref) => {
+                        self.includer.include_cycle(&page_ref).await?.into_owned()
+                    }
+                    Replacement::Missing(page_ref) => {
+                        self.includer.no_such_include(&page_ref).await?.into_owned()
+                    }
+                    Replacement::Page(NestedPage {
+                        page_ref,
+                        content,
+                        ranges,
+                        includes,
+                    }) => {
+                        self.state.enter(page_ref);
+                        let result =
+                            self.substitute(&content, ranges, includes, depth + 1).await;
+                        self.state.leave();
+                        result?
+                    }
+                };
+
+                replacements.push((range, replace_with));
+            }
+
+            Ok(substitute_ranges(input, replacements))
+        })
+    }
+}
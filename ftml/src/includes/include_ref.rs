@@ -20,6 +20,7 @@
 
 use crate::data::PageRef;
 use crate::tree::VariableMap;
+use std::borrow::Cow;
 
 /// Represents an include block.
 ///
@@ -55,6 +56,24 @@ impl<'t> IncludeRef<'t> {
     pub fn variables(&self) -> &VariableMap<'t> {
         &self.variables
     }
+
+    pub fn to_owned(&self) -> IncludeRef<'static> {
+        let variables = self
+            .variables
+            .iter()
+            .map(|(key, value)| {
+                let key = Cow::Owned(str!(key));
+                let value = Cow::Owned(str!(value));
+
+                (key, value)
+            })
+            .collect();
+
+        IncludeRef {
+            page_ref: self.page_ref.to_owned(),
+            variables,
+        }
+    }
 }
 
 impl<'t> From<IncludeRef<'t>> for (PageRef<'t>, VariableMap<'t>) {
@@ -0,0 +1,52 @@
+/*
+ * includes/included_page.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::data::PageRef;
+
+/// A page which was included by a `[[include-messy]]` block.
+///
+/// Together, a list of these describes the full include graph of a page.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct IncludedPage<'t> {
+    /// The page which was included.
+    pub page_ref: PageRef<'t>,
+
+    /// How deeply nested this include is.
+    ///
+    /// Pages included directly by the original wikitext have a depth of one,
+    /// pages included by those have a depth of two, and so on.
+    pub depth: u32,
+
+    /// The page whose wikitext contained this include.
+    ///
+    /// This is `None` for pages included directly by the original wikitext.
+    pub parent: Option<PageRef<'t>>,
+}
+
+impl IncludedPage<'_> {
+    pub fn to_owned(&self) -> IncludedPage<'static> {
+        IncludedPage {
+            page_ref: self.page_ref.to_owned(),
+            depth: self.depth,
+            parent: self.parent.as_ref().map(PageRef::to_owned),
+        }
+    }
+}
@@ -69,6 +69,58 @@ impl<'t> Includer<'t> for DebugIncluder {
     fn no_such_include(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
         Ok(Cow::Owned(format!("<MISSING-PAGE {page_ref}>")))
     }
+
+    #[inline]
+    fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!("<CYCLIC-PAGE {page_ref}>")))
+    }
+
+    #[inline]
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!("<DEEP-PAGE {page_ref}>")))
+    }
+}
+
+/// An asynchronous version of [`DebugIncluder`], producing the same output.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncDebugIncluder;
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+impl<'t> AsyncIncluder<'t> for AsyncDebugIncluder {
+    type Error = Void;
+
+    async fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>, Void> {
+        DebugIncluder.include_pages(includes)
+    }
+
+    async fn no_such_include(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        DebugIncluder.no_such_include(page_ref)
+    }
+
+    async fn include_cycle(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        DebugIncluder.include_cycle(page_ref)
+    }
+
+    async fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        DebugIncluder.include_depth_exceeded(page_ref)
+    }
 }
 
 /// Rendering a `HashMap` as a string, sorted alphabetically.
@@ -26,6 +26,9 @@ mod prelude {
     pub use crate::includes::{FetchedPage, IncludeRef, Includer};
     pub use std::borrow::Cow;
     pub use std::collections::HashMap;
+
+    #[cfg(feature = "async")]
+    pub use crate::includes::AsyncIncluder;
 }
 
 use crate::includes::{IncludeRef, PageRef};
@@ -34,6 +37,9 @@ use std::borrow::Cow;
 pub use self::debug::DebugIncluder;
 pub use self::null::NullIncluder;
 
+#[cfg(feature = "async")]
+pub use self::debug::AsyncDebugIncluder;
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct FetchedPage<'t> {
@@ -53,4 +59,61 @@ pub trait Includer<'t> {
         &mut self,
         page_ref: &PageRef<'t>,
     ) -> Result<Cow<'t, str>, Self::Error>;
+
+    /// Produces the wikitext to substitute when an include cycle is found.
+    ///
+    /// That is, when the given page includes itself, directly or
+    /// through other pages.
+    fn include_cycle(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Self::Error>;
+
+    /// Produces the wikitext to substitute when an include is nested too deeply.
+    ///
+    /// That is, when the given page would be included beyond the depth in
+    /// [`WikitextSettings::max_include_depth`]. The page is not fetched.
+    ///
+    /// [`WikitextSettings::max_include_depth`]: crate::settings::WikitextSettings::max_include_depth
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Self::Error>;
+}
+
+/// An asynchronous counterpart of [`Includer`].
+///
+/// This is for includers which need to perform I/O to fetch pages,
+/// such as making database queries, without blocking the executor.
+/// It is used by [`include_async`](crate::includes::include_async).
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncIncluder<'t> {
+    type Error;
+
+    async fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>, Self::Error>;
+
+    async fn no_such_include(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Self::Error>;
+
+    /// Produces the wikitext to substitute when an include cycle is found.
+    ///
+    /// See [`Includer::include_cycle`].
+    async fn include_cycle(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Self::Error>;
+
+    /// Produces the wikitext to substitute when an include is nested too deeply.
+    ///
+    /// See [`Includer::include_depth_exceeded`].
+    async fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Self::Error>;
 }
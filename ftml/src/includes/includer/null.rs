@@ -39,4 +39,17 @@ impl<'t> Includer<'t> for NullIncluder {
     fn no_such_include(&mut self, _page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
         Ok(Cow::Borrowed(""))
     }
+
+    #[inline]
+    fn include_cycle(&mut self, _page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Borrowed(""))
+    }
+
+    #[inline]
+    fn include_depth_exceeded(
+        &mut self,
+        _page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Borrowed(""))
+    }
 }
@@ -27,18 +27,34 @@
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "async")]
+mod include_async;
+
+mod ancestors;
 mod include_ref;
+mod included_page;
 mod includer;
 mod parse;
+mod state;
 
+pub(crate) use self::ancestors::IncludeAncestors;
 pub use self::include_ref::IncludeRef;
+pub use self::included_page::IncludedPage;
 pub use self::includer::{DebugIncluder, FetchedPage, Includer, NullIncluder};
 
+#[cfg(feature = "async")]
+pub use self::include_async::include_async;
+
+#[cfg(feature = "async")]
+pub use self::includer::{AsyncDebugIncluder, AsyncIncluder};
+
 use self::parse::parse_include_block;
-use crate::data::PageRef;
+use self::state::{IncludeState, NestedPage, Replacement};
+use crate::data::{PageInfo, PageRef};
 use crate::settings::WikitextSettings;
 use crate::tree::VariableMap;
 use regex::{Regex, RegexBuilder};
+use std::ops::Range;
 
 lazy_static! {
     static ref INCLUDE_REGEX: Regex = {
@@ -53,12 +69,25 @@ lazy_static! {
         Regex::new(r"\{\$(?P<name>[a-zA-Z0-9_\-]+)\}").unwrap();
 }
 
+/// Substitutes all `[[include-messy]]` blocks with the wikitext of the included pages.
+///
+/// Includes are expanded recursively, so any includes within the fetched pages
+/// are themselves substituted, up to the depth in
+/// [`WikitextSettings::max_include_depth`]. Blocks beyond that depth are not expanded,
+/// and [`Includer::include_depth_exceeded`] is substituted instead.
+/// If a page would include itself, directly or through other pages, then
+/// [`Includer::include_cycle`] is substituted instead. This includes the page
+/// being rendered, as described by `page_info`.
+///
+/// Returns the substituted wikitext, along with every page which was included
+/// at any depth, in the order they appear.
 pub fn include<'t, I, E, F>(
     input: &'t str,
+    page_info: &PageInfo<'_>,
     settings: &WikitextSettings,
-    mut includer: I,
+    includer: I,
     invalid_return: F,
-) -> Result<(String, Vec<PageRef<'t>>), E>
+) -> Result<(String, Vec<IncludedPage<'t>>), E>
 where
     I: Includer<'t, Error = E>,
     F: FnOnce() -> E,
@@ -73,6 +102,24 @@ where
 
     info!("Finding and replacing all instances of include blocks in text");
 
+    let mut context = IncludeContext {
+        state: IncludeState::new(settings, page_info, invalid_return),
+        includer,
+    };
+
+    let (ranges, includes) = find_includes(input, settings);
+    let output = context.substitute(input, ranges, includes, 1)?;
+
+    Ok((output, context.state.into_pages()))
+}
+
+/// Finds all include blocks in the given text.
+///
+/// Returns the range each block occupies, alongside the parsed block.
+fn find_includes<'a>(
+    input: &'a str,
+    settings: &WikitextSettings,
+) -> (Vec<Range<usize>>, Vec<IncludeRef<'a>>) {
     let mut ranges = Vec::new();
     let mut includes = Vec::new();
 
@@ -95,67 +142,105 @@ where
         }
     }
 
-    // Retrieve included pages
-    let fetched_pages = includer.include_pages(&includes)?;
-
-    // Ensure it matches up with the request
-    if includes.len() != fetched_pages.len() {
-        return Err(invalid_return());
-    }
+    (ranges, includes)
+}
 
-    // Substitute inclusions
-    //
-    // We must iterate backwards for all the indices to be valid
+#[derive(Debug)]
+struct IncludeContext<'s, 't, I, F> {
+    state: IncludeState<'s, 't, F>,
+    includer: I,
+}
 
-    let ranges_iter = ranges.into_iter();
-    let includes_iter = includes.into_iter();
-    let fetched_iter = fetched_pages.into_iter();
+impl<'s, 't, I, E, F> IncludeContext<'s, 't, I, F>
+where
+    I: Includer<'t, Error = E>,
+    F: FnOnce() -> E,
+{
+    /// Replaces the given include blocks in the input text.
+    ///
+    /// The depth is that of the pages being included by these blocks,
+    /// where includes in the original wikitext have a depth of one.
+    fn substitute(
+        &mut self,
+        input: &str,
+        ranges: Vec<Range<usize>>,
+        includes: Vec<IncludeRef<'t>>,
+        depth: u32,
+    ) -> Result<String, E> {
+        if includes.is_empty() {
+            return Ok(str!(input));
+        }
 
-    let joined_iter = ranges_iter.zip(includes_iter).zip(fetched_iter).rev();
+        // Replace blocks which are nested too deeply, without fetching them
+        if self.state.depth_exceeded(depth) {
+            let mut replacements = Vec::with_capacity(includes.len());
 
-    // Borrowing from the original text and doing in-place insertions
-    // will not work here. We are trying to both return the page names
-    // (slices from the input string), and replace it with new content.
-    let mut output = String::from(input);
-    let mut pages = Vec::new();
+            for (range, include) in ranges.into_iter().zip(includes) {
+                let replace_with = self
+                    .includer
+                    .include_depth_exceeded(include.page_ref())?
+                    .into_owned();
 
-    for ((range, include), fetched) in joined_iter {
-        let (page_ref, variables) = include.into();
+                replacements.push((range, replace_with));
+            }
 
-        info!(
-            "Replacing range for included page ({}..{})",
-            range.start, range.end,
-        );
+            return Ok(substitute_ranges(input, replacements));
+        }
 
-        // Ensure the returned page reference matches
-        if page_ref != fetched.page_ref {
-            return Err(invalid_return());
+        // Retrieve included pages
+        let fetched_pages = self.includer.include_pages(&includes)?;
+        self.state.check_fetched(&includes, &fetched_pages)?;
+
+        // Get the content to replace each include block with
+        let mut replacements = Vec::with_capacity(includes.len());
+
+        for ((range, include), fetched) in
+            ranges.into_iter().zip(includes).zip(fetched_pages)
+        {
+            let replace_with = match self.state.resolve(include, fetched, depth)? {
+                Replacement::Cycle(page_ref) => {
+                    self.includer.include_cycle(&page_ref)?.into_owned()
+                }
+                Replacement::Missing(page_ref) => {
+                    self.includer.no_such_include(&page_ref)?.into_owned()
+                }
+                Replacement::Page(NestedPage {
+                    page_ref,
+                    content,
+                    ranges,
+                    includes,
+                }) => {
+                    self.state.enter(page_ref);
+                    let result = self.substitute(&content, ranges, includes, depth + 1);
+                    self.state.leave();
+                    result?
+                }
+            };
+
+            replacements.push((range, replace_with));
         }
 
-        // Get replaced content, or error message
-        let replace_with = match fetched.content {
-            // Take fetched content, replace variables
-            Some(mut content) => {
-                replace_variables(content.to_mut(), &variables);
-                content
-            }
+        Ok(substitute_ranges(input, replacements))
+    }
+}
 
-            // Include not found, return premade template
-            None => includer.no_such_include(&page_ref)?,
-        };
+/// Replaces each range in the input with its corresponding content.
+///
+/// The ranges must be in order and not overlap.
+fn substitute_ranges(input: &str, replacements: Vec<(Range<usize>, String)>) -> String {
+    // We must iterate backwards for all the indices to be valid
+    let mut output = str!(input);
 
-        // Append page to final list
-        pages.push(page_ref);
+    for (range, replace_with) in replacements.into_iter().rev() {
+        info!(
+            "Replacing range for included page ({}..{})",
+            range.start, range.end,
+        );
 
-        // Perform the substitution
         output.replace_range(range, &replace_with);
     }
 
-    // Since we iterate in reverse order, the pages are reversed.
-    pages.reverse();
-
-    // Return
-    Ok((output, pages))
+    output
 }
 
 fn replace_variables(content: &mut String, variables: &VariableMap) {
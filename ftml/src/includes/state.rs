@@ -0,0 +1,192 @@
+/*
+ * includes/state.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! State for expanding includes, apart from fetching pages.
+//!
+//! Both [`include`](super::include) and `include_async` use this,
+//! differing only in how they call the includer.
+
+use super::{
+    find_includes, replace_variables, FetchedPage, IncludeAncestors, IncludeRef,
+    IncludedPage,
+};
+use crate::data::{PageInfo, PageRef};
+use crate::settings::WikitextSettings;
+use std::ops::Range;
+
+#[derive(Debug)]
+pub struct IncludeState<'s, 't, F> {
+    settings: &'s WikitextSettings,
+    invalid_return: Option<F>,
+
+    /// The pages currently being expanded, starting with the page being rendered.
+    ancestors: IncludeAncestors<'t>,
+
+    /// All pages included so far, in order.
+    pages: Vec<IncludedPage<'t>>,
+}
+
+/// What an include block is to be replaced with, once its page has been fetched.
+#[derive(Debug)]
+pub enum Replacement<'t> {
+    /// The page is already being expanded, so including it would be a cycle.
+    Cycle(PageRef<'t>),
+
+    /// The page does not exist.
+    Missing(PageRef<'t>),
+
+    /// The page's wikitext, whose own includes must be expanded.
+    Page(NestedPage<'t>),
+}
+
+/// The wikitext of an included page, with variables substituted.
+#[derive(Debug)]
+pub struct NestedPage<'t> {
+    pub page_ref: PageRef<'t>,
+    pub content: String,
+    pub ranges: Vec<Range<usize>>,
+    pub includes: Vec<IncludeRef<'t>>,
+}
+
+impl<'s, 't, E, F> IncludeState<'s, 't, F>
+where
+    F: FnOnce() -> E,
+{
+    pub fn new(
+        settings: &'s WikitextSettings,
+        page_info: &PageInfo,
+        invalid_return: F,
+    ) -> Self {
+        IncludeState {
+            settings,
+            invalid_return: Some(invalid_return),
+            ancestors: IncludeAncestors::new(page_info),
+            pages: Vec::new(),
+        }
+    }
+
+    /// Consumes the state, returning all pages which were included.
+    #[inline]
+    pub fn into_pages(self) -> Vec<IncludedPage<'t>> {
+        self.pages
+    }
+
+    fn invalid(&mut self) -> E {
+        let invalid_return = self
+            .invalid_return
+            .take()
+            .expect("Invalid return function already used");
+
+        invalid_return()
+    }
+
+    /// Determines if include blocks at this depth are nested too deeply.
+    ///
+    /// The depth is that of the pages being included by these blocks,
+    /// where includes in the original wikitext have a depth of one.
+    /// Such blocks are not expanded, and their pages are not fetched.
+    pub fn depth_exceeded(&self, depth: u32) -> bool {
+        if depth > self.settings.max_include_depth {
+            warn!("Maximum include depth reached, not expanding includes");
+            return true;
+        }
+
+        false
+    }
+
+    /// Ensures the includer returned a page for each include block.
+    pub fn check_fetched(
+        &mut self,
+        includes: &[IncludeRef],
+        fetched_pages: &[FetchedPage],
+    ) -> Result<(), E> {
+        if includes.len() != fetched_pages.len() {
+            return Err(self.invalid());
+        }
+
+        Ok(())
+    }
+
+    /// Records an included page, and determines what to replace its block with.
+    pub fn resolve(
+        &mut self,
+        include: IncludeRef<'t>,
+        fetched: FetchedPage<'t>,
+        depth: u32,
+    ) -> Result<Replacement<'t>, E> {
+        let (page_ref, variables) = include.into();
+
+        // Ensure the returned page reference matches
+        if page_ref != fetched.page_ref {
+            return Err(self.invalid());
+        }
+
+        // Append page to final list
+        self.pages.push(IncludedPage {
+            page_ref: page_ref.clone(),
+            depth,
+            parent: self.ancestors.parent().cloned(),
+        });
+
+        if self.ancestors.is_cycle(&page_ref) {
+            warn!("Include cycle detected for page {page_ref}");
+            return Ok(Replacement::Cycle(page_ref));
+        }
+
+        let mut content = match fetched.content {
+            Some(content) => content.into_owned(),
+            None => return Ok(Replacement::Missing(page_ref)),
+        };
+
+        // Replace variables, then find the includes within it.
+        //
+        // The include blocks borrow from this page's content,
+        // so they must be made owned to be passed to the includer.
+        // If this page is on another site, so are pages it includes without a site.
+        replace_variables(&mut content, &variables);
+        let (ranges, includes) = find_includes(&content, self.settings);
+        let includes = includes
+            .iter()
+            .map(|include| {
+                let (nested_ref, variables) = include.to_owned().into();
+                let nested_ref = self.ancestors.qualify(&page_ref, nested_ref);
+                IncludeRef::new(nested_ref, variables)
+            })
+            .collect();
+
+        Ok(Replacement::Page(NestedPage {
+            page_ref,
+            content,
+            ranges,
+            includes,
+        }))
+    }
+
+    /// Marks this page as being expanded, until the matching `leave()`.
+    #[inline]
+    pub fn enter(&mut self, page_ref: PageRef<'t>) {
+        self.ancestors.push(page_ref);
+    }
+
+    #[inline]
+    pub fn leave(&mut self) {
+        self.ancestors.pop();
+    }
+}
@@ -18,18 +18,36 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{include, DebugIncluder, PageRef};
+use super::{
+    include, DebugIncluder, FetchedPage, IncludeRef, IncludedPage, Includer, PageRef,
+};
+use crate::data::PageInfo;
 use crate::settings::{WikitextMode, WikitextSettings};
+use std::borrow::Cow;
+use void::{ResultVoidExt, Void};
 
 #[test]
 fn includes() {
+    let page_info = PageInfo::dummy();
     let settings = WikitextSettings::from_mode(WikitextMode::Page);
 
     macro_rules! test {
         ($text:expr, $expected:expr $(,)?) => {{
             let mut text = str!($text);
-            let result = include(&mut text, &settings, DebugIncluder, || panic!());
-            let (output, actual) = result.expect("Fetching pages failed");
+            let result =
+                include(&mut text, &page_info, &settings, DebugIncluder, || panic!());
+            let (output, pages) = result.expect("Fetching pages failed");
+            let actual: Vec<PageRef> = pages
+                .into_iter()
+                .map(
+                    |IncludedPage {
+                         page_ref, depth, ..
+                     }| {
+                        assert_eq!(depth, 1, "Debug includer produced nested include");
+                        page_ref
+                    },
+                )
+                .collect();
             let expected = $expected;
 
             println!("Input:  '{}'", $text);
@@ -270,3 +288,373 @@ fn includes() {
         vec![],
     );
 }
+
+/// Includer whose pages themselves contain includes.
+#[derive(Debug)]
+struct NestedIncluder;
+
+impl<'t> Includer<'t> for NestedIncluder {
+    type Error = Void;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>, Void> {
+        let pages = includes
+            .iter()
+            .map(|include| {
+                let page_ref = include.page_ref().clone();
+                let content = match page_ref.page() {
+                    "outer" => Some("outer\n[[include-messy middle | name=nested]]"),
+                    "middle" => Some("middle {$name}\n[[include-messy inner]]"),
+                    "inner" => Some("inner"),
+                    "loop-a" => Some("a\n[[include-messy loop-b]]"),
+                    "loop-b" => Some("b\n[[include-messy LOOP-A]]"),
+                    "page-a" => Some("a\n[[include-messy page-b]]"),
+                    "page-b" => Some("b\n[[include-messy page-a]]"),
+                    "self" => Some("self\n[[include-messy self]]"),
+                    "variable" => Some("variable\n[[include-messy {$target}]]"),
+                    "deep" => Some("deep\n[[include-messy deep-1]]"),
+                    "deep-1" => Some("1\n[[include-messy deep-2]]"),
+                    "deep-2" => Some("2\n[[include-messy deep-3]]"),
+                    "deep-3" => Some("3\n[[include-messy deep-4]]"),
+                    "deep-4" => Some("4\n[[include-messy deep-5]]"),
+                    "deep-5" => Some("5\n[[include-messy deep-6]]"),
+                    _ => None,
+                };
+
+                FetchedPage {
+                    page_ref,
+                    content: content.map(Cow::Borrowed),
+                }
+            })
+            .collect();
+
+        Ok(pages)
+    }
+
+    fn no_such_include(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!("<MISSING {page_ref}>")))
+    }
+
+    fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!("<CYCLE {page_ref}>")))
+    }
+
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!("<DEPTH {page_ref}>")))
+    }
+}
+
+#[test]
+fn nested_includes() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($text:expr, $expected_output:expr, $expected_pages:expr $(,)?) => {{
+            let (output, pages) =
+                include($text, &page_info, &settings, NestedIncluder, || panic!())
+                    .void_unwrap();
+
+            let actual_pages: Vec<(&str, u32, Option<&str>)> = pages
+                .iter()
+                .map(|page| {
+                    let parent = page.parent.as_ref().map(|parent| parent.page());
+                    (page.page_ref.page(), page.depth, parent)
+                })
+                .collect();
+
+            println!("Input:  {:?}", $text);
+            println!("Output: {:?}", output);
+            println!("Pages:  {:?}", actual_pages);
+            println!();
+
+            assert_eq!(
+                output, $expected_output,
+                "Actual output doesn't match expected",
+            );
+            assert_eq!(
+                actual_pages, $expected_pages,
+                "Actual pages to include doesn't match expected",
+            );
+        }};
+    }
+
+    test!("[[include-messy inner]]", "inner", vec![("inner", 1, None)]);
+    test!(
+        "A\n[[include-messy outer]]\nB",
+        "A\nouter\nmiddle nested\ninner\nB",
+        vec![
+            ("outer", 1, None),
+            ("middle", 2, Some("outer")),
+            ("inner", 3, Some("middle")),
+        ],
+    );
+    test!(
+        "[[include-messy outer]]\n[[include-messy inner]]",
+        "outer\nmiddle nested\ninner\ninner",
+        vec![
+            ("outer", 1, None),
+            ("middle", 2, Some("outer")),
+            ("inner", 3, Some("middle")),
+            ("inner", 1, None),
+        ],
+    );
+    test!(
+        "[[include-messy variable | target=inner]]",
+        "variable\ninner",
+        vec![("variable", 1, None), ("inner", 2, Some("variable"))],
+    );
+    test!(
+        "[[include-messy variable | target=nowhere]]",
+        "variable\n<MISSING nowhere>",
+        vec![("variable", 1, None), ("nowhere", 2, Some("variable"))],
+    );
+
+    // Cycles
+    test!(
+        "[[include-messy self]]",
+        "self\n<CYCLE self>",
+        vec![("self", 1, None), ("self", 2, Some("self"))],
+    );
+    test!(
+        "[[include-messy loop-a]]",
+        "a\nb\n<CYCLE LOOP-A>",
+        vec![
+            ("loop-a", 1, None),
+            ("loop-b", 2, Some("loop-a")),
+            ("LOOP-A", 3, Some("loop-b")),
+        ],
+    );
+
+    // Depth limit
+    test!(
+        "[[include-messy deep]]",
+        "deep\n1\n2\n3\n4\n<DEPTH deep-5>",
+        vec![
+            ("deep", 1, None),
+            ("deep-1", 2, Some("deep")),
+            ("deep-2", 3, Some("deep-1")),
+            ("deep-3", 4, Some("deep-2")),
+            ("deep-4", 5, Some("deep-3")),
+        ],
+    );
+}
+
+#[test]
+fn root_page_cycles() {
+    let mut page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($page:expr, $category:expr, $text:expr, $expected_output:expr, $expected_pages:expr $(,)?) => {{
+            page_info.page = Cow::Borrowed($page);
+            page_info.category = $category.map(Cow::Borrowed);
+
+            let (output, pages) =
+                include($text, &page_info, &settings, NestedIncluder, || panic!())
+                    .void_unwrap();
+
+            let actual_pages: Vec<(&str, u32)> = pages
+                .iter()
+                .map(|page| (page.page_ref.page(), page.depth))
+                .collect();
+
+            assert_eq!(
+                output, $expected_output,
+                "Actual output doesn't match expected",
+            );
+            assert_eq!(
+                actual_pages, $expected_pages,
+                "Actual pages to include doesn't match expected",
+            );
+        }};
+    }
+
+    // Page A, which includes B, which includes A
+    test!(
+        "page-a",
+        None,
+        "a\n[[include-messy page-b]]",
+        "a\nb\n<CYCLE page-a>",
+        vec![("page-b", 1), ("page-a", 2)],
+    );
+
+    // Pages including themselves
+    test!(
+        "page-a",
+        None,
+        "[[include-messy Page-A]]",
+        "<CYCLE Page-A>",
+        vec![("Page-A", 1)],
+    );
+    test!(
+        "page-a",
+        None,
+        "[[include-messy :sandbox:page-a]]",
+        "<CYCLE :sandbox:page-a>",
+        vec![("page-a", 1)],
+    );
+    test!(
+        "page-a",
+        Some("_default"),
+        "[[include-messy _default:page-a]]",
+        "<CYCLE _default:page-a>",
+        vec![("_default:page-a", 1)],
+    );
+    test!(
+        "page-a",
+        Some("fragment"),
+        "[[include-messy fragment:page-a]]",
+        "<CYCLE fragment:page-a>",
+        vec![("fragment:page-a", 1)],
+    );
+
+    // Other sites are different pages
+    test!(
+        "page-a",
+        None,
+        "[[include-messy :other:page-a]]",
+        "a\nb\n<CYCLE :other:page-a>",
+        vec![("page-a", 1), ("page-b", 2), ("page-a", 3)],
+    );
+}
+
+#[test]
+fn other_site_includes() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($text:expr, $expected_output:expr, $expected_pages:expr $(,)?) => {{
+            let (output, pages) =
+                include($text, &page_info, &settings, NestedIncluder, || panic!())
+                    .void_unwrap();
+
+            let actual_pages: Vec<(Option<&str>, &str, Option<String>)> = pages
+                .iter()
+                .map(|page| {
+                    let parent = page.parent.as_ref().map(PageRef::to_string);
+                    (page.page_ref.site(), page.page_ref.page(), parent)
+                })
+                .collect();
+
+            assert_eq!(
+                output, $expected_output,
+                "Actual output doesn't match expected",
+            );
+            assert_eq!(
+                actual_pages, $expected_pages,
+                "Actual pages to include doesn't match expected",
+            );
+        }};
+    }
+
+    // Includes without a site are on the same site as the page containing them
+    test!(
+        "[[include-messy :other:outer]]",
+        "outer\nmiddle nested\ninner",
+        vec![
+            (Some("other"), "outer", None),
+            (Some("other"), "middle", Some(str!(":other:outer"))),
+            (Some("other"), "inner", Some(str!(":other:middle"))),
+        ],
+    );
+
+    // Including the page being rendered from another site is still a cycle
+    test!(
+        "[[include-messy :other:variable | target=:sandbox:some-page]]",
+        "variable\n<CYCLE :sandbox:some-page>",
+        vec![
+            (Some("other"), "variable", None),
+            (Some("sandbox"), "some-page", Some(str!(":other:variable"))),
+        ],
+    );
+
+    // Includes on this site, whether qualified or not, are unchanged
+    test!(
+        "[[include-messy :sandbox:outer]]",
+        "outer\nmiddle nested\ninner",
+        vec![
+            (Some("sandbox"), "outer", None),
+            (None, "middle", Some(str!(":sandbox:outer"))),
+            (None, "inner", Some(str!("middle"))),
+        ],
+    );
+}
+
+#[test]
+fn include_depth_setting() {
+    let page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($max_depth:expr, $expected_output:expr, $expected_pages:expr $(,)?) => {{
+            settings.max_include_depth = $max_depth;
+
+            let (output, pages) = include(
+                "[[include-messy outer]]",
+                &page_info,
+                &settings,
+                NestedIncluder,
+                || panic!(),
+            )
+            .void_unwrap();
+
+            assert_eq!(
+                output, $expected_output,
+                "Actual output doesn't match expected",
+            );
+            assert_eq!(
+                pages.len(),
+                $expected_pages,
+                "Actual number of included pages doesn't match expected",
+            );
+        }};
+    }
+
+    test!(0, "<DEPTH outer>", 0);
+    test!(1, "outer\n<DEPTH middle>", 1);
+    test!(2, "outer\nmiddle nested\n<DEPTH inner>", 2);
+    test!(3, "outer\nmiddle nested\ninner", 3);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn includes_async() {
+    use super::{include_async, AsyncDebugIncluder};
+    use futures_executor::block_on;
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! test {
+        ($text:expr $(,)?) => {{
+            let expected =
+                include($text, &page_info, &settings, DebugIncluder, || panic!());
+            let actual = block_on(include_async(
+                $text,
+                &page_info,
+                &settings,
+                AsyncDebugIncluder,
+                || panic!(),
+            ));
+
+            assert_eq!(
+                actual, expected,
+                "Async include output doesn't match synchronous version",
+            );
+        }};
+    }
+
+    test!("");
+    test!("[[include-messy page]]");
+    test!("[[include-messy apple a=1|b=2]]");
+    test!("A\n[[include-messy page-1]]\nB\n[[include-messy page-2 | x=y]]");
+    test!("[[include-messy :other-site:page]]");
+    test!("[[include-messy ::invalid-page]]");
+}
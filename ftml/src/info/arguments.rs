@@ -0,0 +1,75 @@
+/*
+ * info/arguments.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use strum_macros::IntoStaticStr;
+
+/// A named argument accepted by a block or module.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArgumentInfo {
+    /// The name of the argument, as written in the block head.
+    pub name: String,
+
+    /// What kind of value the argument takes.
+    #[serde(rename = "type")]
+    pub kind: ArgumentType,
+
+    /// The values which are permitted, if limited to a fixed set.
+    #[serde(default)]
+    pub values: Vec<ArgumentValue>,
+
+    /// The lowest permitted value, for numeric arguments.
+    pub min_value: Option<f64>,
+
+    /// The highest permitted value, for numeric arguments.
+    pub max_value: Option<f64>,
+
+    /// The value used if the argument is not specified.
+    pub default: Option<ArgumentValue>,
+}
+
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ArgumentType {
+    String,
+    Int,
+    Float,
+    Bool,
+}
+
+impl ArgumentType {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+/// A value for an argument, such as its default.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
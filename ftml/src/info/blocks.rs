@@ -0,0 +1,250 @@
+/*
+ * info/blocks.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::ArgumentInfo;
+use strum_macros::IntoStaticStr;
+
+const BLOCKS_JSON: &str = include_str!(concat!(env!("OUT_DIR"), "/blocks.json"));
+
+lazy_static! {
+    static ref BLOCKS: Vec<BlockInfo> =
+        serde_json::from_str(BLOCKS_JSON).expect("Unable to parse block information");
+}
+
+/// Information about a block, from `conf/blocks.toml`.
+///
+/// This is checked against the block rules in the parser,
+/// so the names and flags here are those it accepts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockInfo {
+    /// The canonical name of this block.
+    pub name: String,
+
+    /// Whether this block is deprecated, and should not be recommended to users.
+    #[serde(default)]
+    pub deprecated: bool,
+
+    /// Other names this block can be invoked with.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+
+    /// Other names this block can be invoked with, which should be replaced by its name.
+    #[serde(default)]
+    pub deprecated_aliases: Vec<String>,
+
+    /// Whether the canonical name cannot be used to invoke this block.
+    ///
+    /// This is the case when the name is only descriptive, for instance
+    /// `list-unordered`, which is invoked as `[[ul]]`.
+    #[serde(default)]
+    pub exclude_name: bool,
+
+    /// Whether this block accepts the star flag, as in `[[*user]]`.
+    #[serde(default)]
+    pub accepts_star: bool,
+
+    /// Whether this block accepts the score flag, as in `[[div_]]`.
+    #[serde(default)]
+    pub accepts_score: bool,
+
+    /// Whether the head and tail of this block can be on separate lines from its body.
+    #[serde(default)]
+    pub accepts_newlines: bool,
+
+    /// What the head of the block takes.
+    pub head: BlockHead,
+
+    /// What the body of the block contains.
+    pub body: BlockBody,
+
+    /// Whether this block accepts safe HTML attributes, such as `class` or `style`.
+    #[serde(default)]
+    pub html_attributes: bool,
+
+    /// What this block produces when rendered as HTML.
+    pub html_output: BlockOutput,
+
+    /// Whether this block has behavior which needs special handling.
+    pub special: Option<BlockSpecial>,
+
+    /// The named arguments this block accepts, if it takes a map.
+    #[serde(default)]
+    pub arguments: Vec<ArgumentInfo>,
+}
+
+impl BlockInfo {
+    /// All the names this block can be invoked with.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let name = if self.exclude_name {
+            None
+        } else {
+            Some(self.name.as_str())
+        };
+
+        name.into_iter()
+            .chain(self.aliases.iter().map(String::as_str))
+            .chain(self.deprecated_aliases.iter().map(String::as_str))
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum BlockHead {
+    /// Nothing besides the name, as in `[[footnoteblock]]`.
+    None,
+
+    /// A single value, as in `[[user aismallard]]`.
+    Value,
+
+    /// A map of named arguments, as in `[[div class="box"]]`.
+    Map,
+
+    /// A value followed by named arguments, as in `[[image source.png width="100"]]`.
+    #[serde(rename = "value+map")]
+    #[strum(serialize = "value+map")]
+    ValueMap,
+}
+
+impl BlockHead {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum BlockBody {
+    /// The block has no body or end block.
+    None,
+
+    /// The body is taken as text, without being parsed.
+    Raw,
+
+    /// The body is parsed as elements.
+    Elements,
+
+    /// The body has its own special handling, such as a list of tabs.
+    Other,
+}
+
+impl BlockBody {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum BlockOutput {
+    /// Nothing is produced.
+    None,
+
+    /// An HTML element with the given tag, and possibly a class.
+    Html { tag: String, class: Option<String> },
+
+    /// A stylesheet, as opposed to an element.
+    Css,
+
+    /// Some other output, depending on the block.
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockSpecial {
+    /// Invokes a module, which is named by the head of the block.
+    Module,
+
+    /// Includes another page, as elements.
+    IncludeElements,
+
+    /// Includes another page, as text substituted before parsing.
+    IncludeMessy,
+}
+
+/// Gets information about all blocks, sorted by name.
+#[inline]
+pub fn blocks() -> &'static [BlockInfo] {
+    &BLOCKS
+}
+
+/// Finds the block which can be invoked with the given name.
+///
+/// As with the parser, this is case-insensitive and permits the score flag.
+pub fn find_block(name: &str) -> Option<&'static BlockInfo> {
+    let name = name.strip_suffix('_').unwrap_or(name);
+
+    blocks().iter().find(|block| {
+        block
+            .names()
+            .any(|block_name| block_name.eq_ignore_ascii_case(name))
+    })
+}
+
+#[test]
+fn block_info() {
+    use super::ArgumentValue;
+
+    let div = find_block("DIV_").expect("No div block");
+    assert_eq!(div.name, "div");
+    assert_eq!(div.head, BlockHead::Map);
+    assert_eq!(div.body, BlockBody::Elements);
+    assert_eq!(div.body.name(), "elements");
+    assert!(div.accepts_score);
+    assert_eq!(
+        div.html_output,
+        BlockOutput::Html {
+            tag: str!("div"),
+            class: None,
+        },
+    );
+
+    let image = find_block("image").expect("No image block");
+    assert_eq!(image.head.name(), "value+map");
+
+    let list = find_block("ul").expect("No unordered list block");
+    assert_eq!(list.name, "list-unordered");
+    assert!(find_block("list-unordered").is_none());
+
+    let collapsible = find_block("collapsible").expect("No collapsible block");
+    let argument = collapsible
+        .arguments
+        .iter()
+        .find(|argument| argument.name == "hideLocation")
+        .expect("No hideLocation argument");
+
+    assert_eq!(argument.values.len(), 4);
+    assert_eq!(argument.default, Some(ArgumentValue::String(str!("top"))));
+
+    let module = find_block("module").expect("No module block");
+    assert_eq!(module.special, Some(BlockSpecial::Module));
+    assert_eq!(find_block("MODULE654"), Some(module));
+
+    assert!(find_block("not-a-block").is_none());
+}
@@ -1,5 +1,5 @@
 /*
- * info.rs
+ * info/mod.rs
  *
  * ftml - Library to parse Wikidot text
  * Copyright (C) 2019-2022 Wikijump Team
@@ -18,11 +18,23 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+//! Information about this build of ftml, and the syntax it supports.
+
 #[allow(unused)]
 mod build {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+mod arguments;
+mod blocks;
+mod modules;
+
+pub use self::arguments::{ArgumentInfo, ArgumentType, ArgumentValue};
+pub use self::blocks::{
+    blocks, find_block, BlockBody, BlockHead, BlockInfo, BlockOutput, BlockSpecial,
+};
+pub use self::modules::{find_module, modules, ModuleInfo};
+
 pub use self::build::{
     BUILT_TIME_UTC, CFG_ENV, CFG_OS, CFG_TARGET_ARCH, CI_PLATFORM, DEBUG,
     GIT_COMMIT_HASH, NUM_JOBS, PKG_LICENSE, PKG_NAME, PKG_REPOSITORY, PKG_VERSION,
@@ -0,0 +1,102 @@
+/*
+ * info/modules.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{ArgumentInfo, BlockBody};
+
+const MODULES_JSON: &str = include_str!(concat!(env!("OUT_DIR"), "/modules.json"));
+
+lazy_static! {
+    static ref MODULES: Vec<ModuleInfo> =
+        serde_json::from_str(MODULES_JSON).expect("Unable to parse module information");
+}
+
+/// Information about a module, from `conf/modules.toml`.
+///
+/// Modules are invoked through `[[module]]`, as in `[[module Rate]]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ModuleInfo {
+    /// The canonical name of this module.
+    pub name: String,
+
+    /// Whether this module is deprecated, and should not be recommended to users.
+    #[serde(default)]
+    pub deprecated: bool,
+
+    /// Other names this module can be invoked with.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+
+    /// What the body of the module contains.
+    pub body: BlockBody,
+
+    /// Whether this module accepts safe HTML attributes, such as `class` or `style`.
+    #[serde(default)]
+    pub html_attributes: bool,
+
+    /// The named arguments this module accepts.
+    #[serde(default)]
+    pub arguments: Vec<ArgumentInfo>,
+}
+
+impl ModuleInfo {
+    /// All the names this module can be invoked with.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        Some(self.name.as_str())
+            .into_iter()
+            .chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+/// Gets information about all modules, sorted by name.
+#[inline]
+pub fn modules() -> &'static [ModuleInfo] {
+    &MODULES
+}
+
+/// Finds the module which can be invoked with the given name. Case-insensitive.
+pub fn find_module(name: &str) -> Option<&'static ModuleInfo> {
+    modules().iter().find(|module| {
+        module
+            .names()
+            .any(|module_name| module_name.eq_ignore_ascii_case(name))
+    })
+}
+
+#[test]
+fn module_info() {
+    use super::ArgumentType;
+
+    let list_pages = find_module("listpages").expect("No ListPages module");
+    assert_eq!(list_pages.name, "ListPages");
+    assert_eq!(list_pages.body, BlockBody::Raw);
+
+    let per_page = list_pages
+        .arguments
+        .iter()
+        .find(|argument| argument.name == "perPage")
+        .expect("No perPage argument");
+
+    assert_eq!(per_page.kind, ArgumentType::Int);
+    assert_eq!(per_page.min_value, Some(1.0));
+    assert_eq!(per_page.max_value, Some(250.0));
+
+    assert!(find_module("not-a-module").is_none());
+}
@@ -31,6 +31,7 @@
 //!
 //! The overall flow is the following:
 //!
+//! * Apply live template (if any)
 //! * Run messy includer
 //! * Run preprocessor
 //! * Run tokenizer
@@ -50,12 +51,19 @@
 //! plain text and full HTML respectively.
 //!
 //! # Features
-//! This crate has one feature of note:
+//! This crate has three features of note:
 //!
 //! The `mathml` feature pulls in the `latex2mathml` library,
 //! which renders LaTeX blocks using MathML. It is enabled
 //! by default.
 //!
+//! The `async` feature adds [`AsyncIncluder`] and [`include_async`],
+//! for embedders which need to fetch included pages without blocking.
+//! It is disabled by default.
+//!
+//! The `markdown-import` feature adds the [`import`] module, which
+//! converts CommonMark documents into wikitext. It is disabled by default.
+//!
 //! # Targets
 //! The library supports being compiled into WebAssembly.
 //! (target `wasm32-unknown-unknown`, see [`wasm-pack`] for more information)
@@ -72,6 +80,9 @@
 //! [`Render`]: ./render/trait.Render.html
 //! [`TextRender`]: ./render/html/struct.HtmlRender.html
 //! [`HtmlRender`]: ./render/text/struct.TextRender.html
+//! [`AsyncIncluder`]: ./includes/trait.AsyncIncluder.html
+//! [`include_async`]: ./includes/fn.include_async.html
+//! [`import`]: ./import/index.html
 //! [`serde`]: https://docs.rs/serde
 //! [`wasm-pack`]: https://rustwasm.github.io/docs/wasm-pack/
 
@@ -124,31 +135,42 @@ mod utf16;
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
+pub mod css;
 pub mod data;
+#[cfg(feature = "markdown-import")]
+pub mod import;
 pub mod includes;
 pub mod info;
+pub mod lint;
 pub mod parsing;
 pub mod render;
 pub mod settings;
+pub mod template;
 pub mod tokenizer;
 pub mod tree;
 
 pub use self::includes::include;
-pub use self::parsing::parse;
+pub use self::lint::lint;
+pub use self::parsing::{
+    parse, parse_with_blocks, parse_with_includer, parse_with_includer_and_blocks,
+    IncrementalParse,
+};
 pub use self::preproc::preprocess;
-pub use self::tokenizer::{tokenize, Tokenization};
+pub use self::template::apply_template;
+pub use self::tokenizer::{retokenize, tokenize, TextEdit, Tokenization};
 pub use self::utf16::Utf16IndexMap;
 
 pub mod prelude {
     pub use super::data::PageInfo;
     pub use super::includes::{include, Includer};
-    pub use super::parsing::{parse, ParseResult, ParseWarning};
+    pub use super::parsing::{parse, parse_with_includer, ParseResult, ParseWarning};
     pub use super::preprocess;
     pub use super::render::Render;
     pub use super::settings::{
         InterwikiSettings, WikitextMode, WikitextSettings, DEFAULT_INTERWIKI,
         EMPTY_INTERWIKI,
     };
+    pub use super::template::apply_template;
     pub use super::tokenizer::{tokenize, Tokenization};
     pub use super::tree::{Element, SyntaxTree};
 }
@@ -0,0 +1,204 @@
+/*
+ * lint/diagnostic.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::utf16::Utf16IndexMap;
+use std::ops::Range;
+use strum_macros::IntoStaticStr;
+
+/// A problem found by the linter.
+///
+/// Unlike a `ParseWarning`, the wikitext here was parsed successfully,
+/// but is likely to not do what the author intended, or to behave
+/// differently than it would on Wikidot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    severity: Severity,
+    span: Option<Range<usize>>,
+    message: String,
+    fix: SuggestedFix,
+}
+
+impl Diagnostic {
+    #[inline]
+    pub fn new(
+        kind: DiagnosticKind,
+        span: Option<Range<usize>>,
+        message: String,
+        fix: SuggestedFix,
+    ) -> Self {
+        Diagnostic {
+            kind,
+            severity: kind.severity(),
+            span,
+            message,
+            fix,
+        }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// The byte range in the source this diagnostic refers to.
+    ///
+    /// This is absent for diagnostics found in the syntax tree
+    /// if it was not parsed with source spans recorded.
+    #[inline]
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    pub fn fix(&self) -> &SuggestedFix {
+        &self.fix
+    }
+
+    #[must_use]
+    pub fn to_utf16_indices(&self, map: &Utf16IndexMap) -> Self {
+        let convert = |span: &Range<usize>| {
+            let start = map.get_index(span.start);
+            let end = map.get_index(span.end);
+            start..end
+        };
+
+        let mut diagnostic = self.clone();
+        diagnostic.span = self.span.as_ref().map(convert);
+
+        if let Some(ref mut edit) = diagnostic.fix.edit {
+            edit.span = convert(&edit.span);
+        }
+
+        diagnostic
+    }
+}
+
+/// How to address a diagnostic.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SuggestedFix {
+    /// A description of the fix, suitable to show to the user.
+    pub description: String,
+
+    /// An edit to the source which applies the fix, if it can be done automatically.
+    pub edit: Option<Replacement>,
+}
+
+impl SuggestedFix {
+    #[inline]
+    pub fn manual<S: Into<String>>(description: S) -> Self {
+        SuggestedFix {
+            description: description.into(),
+            edit: None,
+        }
+    }
+
+    #[inline]
+    pub fn replace<S: Into<String>>(
+        description: S,
+        span: Option<Range<usize>>,
+        text: String,
+    ) -> Self {
+        SuggestedFix {
+            description: description.into(),
+            edit: span.map(|span| Replacement { span, text }),
+        }
+    }
+}
+
+/// Replaces a range of the source with new text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Replacement {
+    pub span: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// This block is invoked using an alias which is kept only for compatibility.
+    DeprecatedAlias,
+
+    /// A literal `[[include]]` is used, but include compatibility is disabled.
+    IncludeWithoutCompatibility,
+
+    /// This heading is more than one level deeper than the one before it.
+    HeadingSkipsLevel,
+
+    /// This image has no alternative text.
+    ImageMissingAlt,
+
+    /// This container has nothing in it.
+    EmptyContainer,
+
+    /// This formatting marker was not matched, and appears as literal text.
+    UnclosedFormatting,
+
+    /// No equation has the name being referenced.
+    DanglingEquationReference,
+
+    /// An anchor with this name already exists on the page.
+    DuplicateAnchorName,
+}
+
+impl DiagnosticKind {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+
+    /// The severity given to diagnostics of this kind.
+    pub fn severity(self) -> Severity {
+        match self {
+            DiagnosticKind::IncludeWithoutCompatibility
+            | DiagnosticKind::DanglingEquationReference => Severity::Error,
+            DiagnosticKind::HeadingSkipsLevel
+            | DiagnosticKind::ImageMissingAlt
+            | DiagnosticKind::UnclosedFormatting
+            | DiagnosticKind::DuplicateAnchorName => Severity::Warning,
+            DiagnosticKind::DeprecatedAlias => Severity::Info,
+            DiagnosticKind::EmptyContainer => Severity::Hint,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+/*
+ * lint/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Reports higher-level problems with wikitext, for use in editors.
+//!
+//! Parsing never fails, and `ParseWarning`s only describe where syntax could
+//! not be matched. The linter looks at the result of a successful parse for
+//! things which are likely mistakes, such as headings which skip levels or
+//! references to equations which do not exist.
+//!
+//! Each [`Diagnostic`] has a severity and a suggested fix. If the syntax tree
+//! was parsed with [`WikitextSettings::record_spans`] enabled, then all of
+//! them have locations, and many fixes can be applied automatically.
+//!
+//! [`WikitextSettings::record_spans`]: crate::settings::WikitextSettings::record_spans
+
+#[cfg(test)]
+mod test;
+
+mod diagnostic;
+mod tokens;
+mod tree;
+
+pub use self::diagnostic::{
+    Diagnostic, DiagnosticKind, Replacement, Severity, SuggestedFix,
+};
+
+use self::tokens::lint_tokens;
+use self::tree::lint_tree;
+use crate::settings::WikitextSettings;
+use crate::tokenizer::Tokenization;
+use crate::tree::SyntaxTree;
+
+/// Checks the given wikitext for problems.
+///
+/// The syntax tree must be the one produced by parsing these tokens.
+/// Diagnostics are returned in order of where they occur.
+pub fn lint(
+    tokenization: &Tokenization,
+    tree: &SyntaxTree,
+    settings: &WikitextSettings,
+) -> Vec<Diagnostic> {
+    info!("Linting wikitext");
+
+    let mut diagnostics = Vec::new();
+    lint_tokens(tokenization.tokens(), settings, &mut diagnostics);
+    lint_tree(tokenization.full_text().inner(), tree, &mut diagnostics);
+
+    // Diagnostics without a location go last
+    diagnostics.sort_by_key(|diagnostic| {
+        diagnostic
+            .span()
+            .map(|span| span.start)
+            .unwrap_or(usize::MAX)
+    });
+
+    diagnostics
+}
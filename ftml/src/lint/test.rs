@@ -0,0 +1,133 @@
+/*
+ * lint/test.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{lint, Diagnostic, DiagnosticKind, Severity};
+use crate::data::PageInfo;
+use crate::settings::{WikitextMode, WikitextSettings};
+use std::ops::Range;
+
+fn run(text: &str, record_spans: bool) -> Vec<Diagnostic> {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings {
+        record_spans,
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    };
+
+    let tokens = crate::tokenize(text);
+    let (tree, _) = crate::parse(&tokens, &page_info, &settings).into();
+    lint(&tokens, &tree, &settings)
+}
+
+#[test]
+fn lint_kinds() {
+    macro_rules! check {
+        ($text:expr, $kinds:expr $(,)?) => {{
+            let kinds: Vec<DiagnosticKind> = run($text, true)
+                .iter()
+                .map(|diagnostic| diagnostic.kind())
+                .collect();
+
+            assert_eq!(kinds, $kinds, "Diagnostics for {:?} did not match", $text,);
+        }};
+    }
+
+    check!("Some **plain** text\n\n+ Heading\n\n++ Subheading", vec![]);
+    check!("[[image a.png alt=\"A cat\"]]", vec![]);
+    check!("[[div class=\"clear\"]]\n[[/div]]", vec![]);
+    check!(
+        "[[eref pythagoras]]\n[[math pythagoras]]\na^2 + b^2 = c^2\n[[/math]]",
+        vec![]
+    );
+
+    check!(
+        "[[include some-page]]",
+        vec![DiagnosticKind::IncludeWithoutCompatibility]
+    );
+    check!("[[module654 Rate]]", vec![DiagnosticKind::DeprecatedAlias]);
+    check!(
+        "+ Title\n\n+++ Section",
+        vec![DiagnosticKind::HeadingSkipsLevel]
+    );
+    check!("[[image a.png]]", vec![DiagnosticKind::ImageMissingAlt]);
+    check!(
+        "[[span]][[/span]] and [[b]] [[/b]]",
+        vec![
+            DiagnosticKind::EmptyContainer,
+            DiagnosticKind::EmptyContainer,
+        ]
+    );
+    check!("Some **bold text", vec![DiagnosticKind::UnclosedFormatting]);
+    check!(
+        "See [[eref missing]].",
+        vec![DiagnosticKind::DanglingEquationReference]
+    );
+    check!(
+        "[[# top]] and [[# top]]",
+        vec![DiagnosticKind::DuplicateAnchorName]
+    );
+    check!(
+        "[[# top]]\n\n[[image a.png]]\n\n+ A\n\n+++ B",
+        vec![
+            DiagnosticKind::ImageMissingAlt,
+            DiagnosticKind::HeadingSkipsLevel,
+        ]
+    );
+}
+
+#[test]
+fn lint_fixes() {
+    fn check(text: &str, expected_span: Range<usize>, expected_fix: &str) {
+        let diagnostics = run(text, true);
+        assert_eq!(diagnostics.len(), 1, "Expected one diagnostic for {text:?}");
+
+        let edit = diagnostics[0]
+            .fix()
+            .edit
+            .as_ref()
+            .expect("Diagnostic has no automatic fix");
+
+        let mut fixed = str!(text);
+        fixed.replace_range(edit.span.clone(), &edit.text);
+
+        assert_eq!(diagnostics[0].span(), Some(expected_span));
+        assert_eq!(fixed, expected_fix, "Fixed text for {text:?} did not match");
+    }
+
+    check("[[include some-page]]", 2..9, "[[include-messy some-page]]");
+    check("[[module654 Rate]]", 2..11, "[[module Rate]]");
+    check("+ Title\n\n+++ Section", 9..20, "+ Title\n\n++ Section");
+    check("Some **bold text", 5..7, "Some @@**@@bold text");
+    check(
+        "[[# top]] and [[# top]]",
+        14..23,
+        "[[# top]] and [[# top-2]]",
+    );
+}
+
+#[test]
+fn lint_without_spans() {
+    let diagnostics = run("Text\n\n[[image a.png]]", false);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind(), DiagnosticKind::ImageMissingAlt);
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    assert_eq!(diagnostics[0].span(), None);
+    assert_eq!(diagnostics[0].fix().edit, None);
+}
@@ -0,0 +1,118 @@
+/*
+ * lint/tokens.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Checks which only need the tokens, such as block names as they were written.
+
+use super::{Diagnostic, DiagnosticKind, SuggestedFix};
+use crate::info::blocks;
+use crate::parsing::{ExtractedToken, Token};
+use crate::settings::WikitextSettings;
+
+pub fn lint_tokens(
+    tokens: &[ExtractedToken],
+    settings: &WikitextSettings,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (index, extracted) in tokens.iter().enumerate() {
+        if !matches!(extracted.token, Token::LeftBlock | Token::LeftBlockStar) {
+            continue;
+        }
+
+        if let Some(name) = block_name(&tokens[index + 1..]) {
+            lint_block_name(name, settings, diagnostics);
+        }
+    }
+}
+
+/// Gets the name token of a block, given the tokens after its opening brackets.
+///
+/// Names made up of several tokens, such as `include-messy`, are skipped,
+/// since none of the names checked here have such a form.
+fn block_name<'r, 't>(
+    tokens: &'r [ExtractedToken<'t>],
+) -> Option<&'r ExtractedToken<'t>> {
+    let mut tokens = tokens
+        .iter()
+        .skip_while(|extracted| extracted.token == Token::Whitespace);
+
+    let name = tokens
+        .next()
+        .filter(|name| name.token == Token::Identifier)?;
+
+    match tokens.next()?.token {
+        Token::Whitespace
+        | Token::LineBreak
+        | Token::ParagraphBreak
+        | Token::RightBlock
+        | Token::InputEnd => Some(name),
+        _ => None,
+    }
+}
+
+fn lint_block_name(
+    name: &ExtractedToken,
+    settings: &WikitextSettings,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let span = Some(name.span.clone());
+
+    if name.slice.eq_ignore_ascii_case("include") && !settings.use_include_compatibility {
+        diagnostics.push(Diagnostic::new(
+            DiagnosticKind::IncludeWithoutCompatibility,
+            span,
+            str!("[[include]] is only supported in Wikidot compatibility mode"),
+            SuggestedFix::replace(
+                "Use [[include-messy]] instead",
+                Some(name.span.clone()),
+                str!("include-messy"),
+            ),
+        ));
+        return;
+    }
+
+    for block in blocks() {
+        let alias = match block
+            .deprecated_aliases
+            .iter()
+            .find(|alias| name.slice.eq_ignore_ascii_case(alias))
+        {
+            Some(alias) => alias,
+            None => continue,
+        };
+
+        // The name to suggest instead, which is the first one not deprecated
+        let replacement = match block.names().next() {
+            Some(replacement) if replacement != alias => replacement,
+            _ => continue,
+        };
+
+        diagnostics.push(Diagnostic::new(
+            DiagnosticKind::DeprecatedAlias,
+            span,
+            format!("[[{alias}]] is a deprecated name for [[{replacement}]]"),
+            SuggestedFix::replace(
+                format!("Use [[{replacement}]] instead"),
+                Some(name.span.clone()),
+                str!(replacement),
+            ),
+        ));
+        return;
+    }
+}
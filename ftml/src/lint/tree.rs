@@ -0,0 +1,344 @@
+/*
+ * lint/tree.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Checks which are run on the syntax tree.
+//!
+//! Elements are only given locations if the tree has source spans.
+//! Those without one use the span of the nearest element containing them.
+
+use super::{Diagnostic, DiagnosticKind, SuggestedFix};
+use crate::tree::{
+    Container, ContainerType, Element, HeadingLevel, ListItem, SyntaxTree,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Formatting markers which only appear as text if they were not closed.
+const FORMATTING_MARKERS: [&str; 8] = ["**", "//", "__", "^^", ",,", "{{", "}}", "##"];
+
+pub fn lint_tree(text: &str, tree: &SyntaxTree, diagnostics: &mut Vec<Diagnostic>) {
+    let mut linter = TreeLinter {
+        text,
+        diagnostics,
+        last_heading: None,
+        anchor_names: HashMap::new(),
+        equation_names: HashSet::new(),
+        equation_references: Vec::new(),
+    };
+
+    linter.lint_elements(&tree.elements, None, true);
+
+    for footnote in &tree.footnotes {
+        linter.lint_elements(footnote, None, true);
+    }
+
+    linter.finish();
+}
+
+#[derive(Debug)]
+struct TreeLinter<'a, 'e> {
+    text: &'a str,
+    diagnostics: &'a mut Vec<Diagnostic>,
+    last_heading: Option<HeadingLevel>,
+    anchor_names: HashMap<&'e str, usize>,
+    equation_names: HashSet<&'e str>,
+    equation_references: Vec<(&'e str, Option<Range<usize>>)>,
+}
+
+impl<'e> TreeLinter<'_, 'e> {
+    /// Checks each of the given elements, and those nested within them.
+    ///
+    /// Elements in included pages are not part of this page's source,
+    /// so they are not reported. But they are still visited if `report`
+    /// is false, since they may define names referenced here.
+    fn lint_elements(
+        &mut self,
+        elements: &'e [Element],
+        span: Option<&Range<usize>>,
+        report: bool,
+    ) {
+        for element in elements {
+            self.lint_element(element, span, report);
+        }
+    }
+
+    fn lint_element(
+        &mut self,
+        element: &'e Element,
+        span: Option<&Range<usize>>,
+        report: bool,
+    ) {
+        match element {
+            Element::Spanned { span, element } => {
+                self.lint_element(element, Some(span), report);
+            }
+            Element::Container(container) => {
+                if report {
+                    self.lint_container(container, span);
+                }
+
+                self.lint_elements(container.elements(), span, report);
+            }
+            Element::Text(text)
+                if report && FORMATTING_MARKERS.contains(&text.as_ref()) =>
+            {
+                self.diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::UnclosedFormatting,
+                    span.cloned(),
+                    format!("The formatting marker {text} is never closed"),
+                    SuggestedFix::replace(
+                        "Escape the marker, if it is meant to be literal text",
+                        span.cloned(),
+                        format!("@@{text}@@"),
+                    ),
+                ));
+            }
+            Element::Image { attributes, .. } => {
+                let has_alt = attributes
+                    .get()
+                    .get("alt")
+                    .map(|alt| !alt.trim().is_empty())
+                    .unwrap_or(false);
+
+                if report && !has_alt {
+                    self.diagnostics.push(Diagnostic::new(
+                        DiagnosticKind::ImageMissingAlt,
+                        span.cloned(),
+                        str!("This image has no alternative text"),
+                        SuggestedFix::manual("Add an alt attribute describing the image"),
+                    ));
+                }
+            }
+            Element::AnchorName(name) => {
+                let count = self.anchor_names.entry(name).or_insert(0);
+                *count += 1;
+
+                if report && *count > 1 {
+                    let renamed = format!("{name}-{count}");
+                    let edit_span = span.and_then(|span| self.find_in(span, name));
+
+                    self.diagnostics.push(Diagnostic::new(
+                        DiagnosticKind::DuplicateAnchorName,
+                        span.cloned(),
+                        format!(
+                            "The anchor name \"{name}\" is already used on this page"
+                        ),
+                        SuggestedFix::replace(
+                            format!("Rename this anchor to \"{renamed}\""),
+                            edit_span,
+                            renamed,
+                        ),
+                    ));
+                }
+            }
+            Element::Math {
+                name: Some(name), ..
+            } => {
+                self.equation_names.insert(name);
+            }
+            Element::EquationReference(name) if report => {
+                self.equation_references.push((name, span.cloned()));
+            }
+            Element::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        self.lint_elements(&cell.elements, span, report);
+                    }
+                }
+            }
+            Element::TabView(tabs) => {
+                for tab in tabs {
+                    self.lint_elements(&tab.elements, span, report);
+                }
+            }
+            Element::List { items, .. } => {
+                for item in items {
+                    match item {
+                        ListItem::Elements { elements, .. } => {
+                            self.lint_elements(elements, span, report);
+                        }
+                        ListItem::SubList { element } => {
+                            self.lint_element(element, span, report);
+                        }
+                    }
+                }
+            }
+            Element::DefinitionList(items) => {
+                for item in items {
+                    self.lint_elements(&item.key, span, report);
+                    self.lint_elements(&item.value, span, report);
+                }
+            }
+            Element::BibliographyBlock { entries, .. } => {
+                for entry in entries {
+                    self.lint_elements(&entry.contents, span, report);
+                }
+            }
+            Element::Include { elements, .. } => {
+                self.lint_elements(elements, span, false);
+            }
+            Element::Anchor { elements, .. }
+            | Element::Collapsible { elements, .. }
+            | Element::Color { elements, .. } => {
+                self.lint_elements(elements, span, report);
+            }
+            _ => (),
+        }
+    }
+
+    fn lint_container(&mut self, container: &Container, span: Option<&Range<usize>>) {
+        match container.ctype() {
+            ContainerType::Header(heading) => {
+                let level = heading.level;
+
+                if let Some(last) = self.last_heading {
+                    if level.value() > last.value() + 1 {
+                        let span = self.heading_span(container).or_else(|| span.cloned());
+                        self.lint_heading_level(level, last.value() + 1, span.as_ref());
+                    }
+                }
+
+                self.last_heading = Some(level);
+            }
+            ctype if is_empty_container(container, ctype) => {
+                let name = ctype.name().to_ascii_lowercase();
+
+                self.diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::EmptyContainer,
+                    span.cloned(),
+                    format!("This {name} container is empty"),
+                    SuggestedFix::replace("Remove it", span.cloned(), String::new()),
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    fn lint_heading_level(
+        &mut self,
+        level: HeadingLevel,
+        expected: u8,
+        span: Option<&Range<usize>>,
+    ) {
+        let level = level.value();
+
+        // Only offer an edit if the heading begins with its + markers
+        let edit_span = span
+            .map(|span| span.start..span.start + usize::from(level))
+            .filter(|span| {
+                self.text
+                    .get(span.clone())
+                    .map(|markers| markers.bytes().all(|b| b == b'+'))
+                    .unwrap_or(false)
+            });
+
+        self.diagnostics.push(Diagnostic::new(
+            DiagnosticKind::HeadingSkipsLevel,
+            span.cloned(),
+            format!("This level {level} heading skips over level {expected}"),
+            SuggestedFix::replace(
+                format!("Make this a level {expected} heading"),
+                edit_span,
+                "+".repeat(usize::from(expected)),
+            ),
+        ));
+    }
+
+    /// Gets the source of a heading, which is the line its contents are on.
+    ///
+    /// Headings are not given spans of their own, since the rule
+    /// consumes consecutive headings together.
+    fn heading_span(&self, container: &Container) -> Option<Range<usize>> {
+        let first = container
+            .elements()
+            .iter()
+            .find_map(|element| match element {
+                Element::Spanned { span, .. } => Some(span.start),
+                _ => None,
+            })?;
+
+        let start = self
+            .text
+            .get(..first)?
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let end = self
+            .text
+            .get(first..)?
+            .find('\n')
+            .map(|i| first + i)
+            .unwrap_or(self.text.len());
+
+        Some(start..end)
+    }
+
+    /// Finds where the given name occurs in the source of an element.
+    fn find_in(&self, span: &Range<usize>, name: &str) -> Option<Range<usize>> {
+        let source = self.text.get(span.clone())?;
+        let offset = span.start + source.find(name)?;
+        Some(offset..offset + name.len())
+    }
+
+    /// Performs checks which need the whole tree to have been visited.
+    fn finish(self) {
+        for (name, span) in self.equation_references {
+            if !self.equation_names.contains(name) {
+                self.diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::DanglingEquationReference,
+                    span,
+                    format!("No equation is named \"{name}\""),
+                    SuggestedFix::manual(format!(
+                        "Add a [[math {name}]] block, or correct the name"
+                    )),
+                ));
+            }
+        }
+    }
+}
+
+/// Determines if a container is empty and serves no purpose.
+///
+/// Empty blocks with attributes, such as a `[[div class="clear"]]`,
+/// are commonly used for styling, so they are permitted.
+fn is_empty_container(container: &Container, ctype: ContainerType) -> bool {
+    let purposeless = match ctype {
+        ContainerType::Bold
+        | ContainerType::Italics
+        | ContainerType::Underline
+        | ContainerType::Superscript
+        | ContainerType::Subscript
+        | ContainerType::Strikethrough
+        | ContainerType::Monospace
+        | ContainerType::Mark
+        | ContainerType::Insertion
+        | ContainerType::Deletion => true,
+        ContainerType::Span | ContainerType::Div | ContainerType::Blockquote => {
+            container.attributes().get().is_empty()
+        }
+        _ => false,
+    };
+
+    purposeless
+        && container
+            .elements()
+            .iter()
+            .all(|element| element.is_whitespace())
+}
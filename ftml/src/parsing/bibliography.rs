@@ -0,0 +1,109 @@
+/*
+ * parsing/bibliography.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::ParseWarning;
+use crate::tree::{offset_spans, BibliographyEntry, BibliographyList};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// The bibliography entries and citations found while parsing.
+///
+/// Citations usually come before the `[[bibliography]]` block they refer to,
+/// so they can only be checked and numbered once parsing is finished.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct PendingBibliography<'t> {
+    entries: Vec<BibliographyEntry<'t>>,
+
+    /// Each cited label, along with the warning to produce if no entry has it.
+    cites: Vec<(Cow<'t, str>, ParseWarning)>,
+}
+
+impl<'t> PendingBibliography<'t> {
+    #[inline]
+    pub fn push_entries(&mut self, entries: &[BibliographyEntry<'t>]) {
+        self.entries.extend_from_slice(entries);
+    }
+
+    #[inline]
+    pub fn push_cite(&mut self, label: Cow<'t, str>, warning: ParseWarning) {
+        self.cites.push((label, warning));
+    }
+
+    #[inline]
+    pub fn append(&mut self, other: &mut Self) {
+        self.entries.append(&mut other.entries);
+        self.cites.append(&mut other.cites);
+    }
+
+    /// Replaces the warning for each citation.
+    ///
+    /// This is used for included pages, whose warnings refer to their own source.
+    pub fn replace_warnings(&mut self, warning: &ParseWarning) {
+        for (_, cite_warning) in &mut self.cites {
+            *cite_warning = warning.clone();
+        }
+    }
+
+    /// Moves the spans of all entries and warnings by the given number of bytes.
+    pub fn offset_spans(&mut self, offset: isize) {
+        for entry in &mut self.entries {
+            offset_spans(&mut entry.contents, offset);
+        }
+
+        for (_, warning) in &mut self.cites {
+            warning.offset_span(offset);
+        }
+    }
+
+    pub fn to_owned(&self) -> PendingBibliography<'static> {
+        PendingBibliography {
+            entries: self
+                .entries
+                .iter()
+                .map(BibliographyEntry::to_owned)
+                .collect(),
+            cites: self
+                .cites
+                .iter()
+                .map(|(label, warning)| (Cow::Owned(label.to_string()), warning.clone()))
+                .collect(),
+        }
+    }
+
+    /// Numbers the entries, adding a warning for each citation of a missing label.
+    pub fn resolve(self, warnings: &mut Vec<ParseWarning>) -> BibliographyList<'t> {
+        let PendingBibliography { entries, cites } = self;
+        let bibliography =
+            BibliographyList::new(entries, cites.iter().map(|(label, _)| label.as_ref()));
+
+        // A citation may have been parsed more than once, if the
+        // rule containing it failed and fell back to another.
+        let mut seen = HashSet::new();
+
+        for (label, warning) in cites {
+            if bibliography.get(&label).is_none() && seen.insert(warning.span()) {
+                warn!("No bibliography entry with label '{label}'");
+                warnings.push(warning);
+            }
+        }
+
+        bibliography
+    }
+}
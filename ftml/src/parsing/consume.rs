@@ -51,13 +51,14 @@ pub fn consume<'p, 'r, 't>(
     debug!("Looking for valid rules");
     let mut all_exceptions = Vec::new();
     let current = parser.current();
+    let start = current.span.start;
 
-    for &rule in get_rules_for_token(current) {
+    for &rule in get_rules_for_token(parser) {
         debug!("Trying rule consumption for tokens (rule {})", rule.name());
 
         let old_remaining = parser.remaining();
         match rule.try_consume(parser) {
-            Ok(output) => {
+            Ok(mut output) => {
                 info!("Rule {} matched, returning generated result", rule.name());
 
                 // If the pointer hasn't moved, we step one token.
@@ -65,6 +66,8 @@ pub fn consume<'p, 'r, 't>(
                     parser.step()?;
                 }
 
+                output.item = add_span(parser, start, output.item);
+
                 // Explicitly drop exceptions
                 //
                 // We're returning the successful consumption
@@ -90,6 +93,7 @@ pub fn consume<'p, 'r, 't>(
     warn!("All rules exhausted, using generic text fallback");
     let element = text!(current.slice);
     parser.step()?;
+    let element = add_span(parser, start, element.into());
 
     // We should only carry styles over from *successful* consumptions
     debug!("Removing non-warnings from exceptions list");
@@ -115,3 +119,33 @@ pub fn consume<'p, 'r, 't>(
 
     ok!(element, all_exceptions)
 }
+
+/// Wraps a consumed element in `Element::Spanned`, if spans are being recorded.
+///
+/// The span runs from the given start to the current token, which is
+/// the first one not consumed. Partials are left as-is since they are
+/// unwrapped by their parent rule, as are consumptions which produced
+/// several elements, since they do not have a single span.
+fn add_span<'t>(
+    parser: &Parser<'_, 't>,
+    start: usize,
+    elements: Elements<'t>,
+) -> Elements<'t> {
+    if !parser.record_spans() {
+        return elements;
+    }
+
+    match elements {
+        Elements::Single(element)
+            if !matches!(element, Element::Partial(_) | Element::Spanned { .. }) =>
+        {
+            let span = start..parser.current().span.start;
+
+            Elements::Single(Element::Spanned {
+                span,
+                element: Box::new(element),
+            })
+        }
+        _ => elements,
+    }
+}
@@ -19,6 +19,7 @@
  */
 
 use super::{rule::Rule, ExtractedToken, Token};
+use crate::tokenizer::offset_index;
 use crate::utf16::Utf16IndexMap;
 use std::borrow::Cow;
 use std::ops::Range;
@@ -91,6 +92,19 @@ impl ParseWarning {
         self.kind
     }
 
+    /// Moves the span of this warning by the given number of bytes.
+    #[inline]
+    pub(crate) fn offset_span(&mut self, offset: isize) {
+        self.span.start = offset_index(self.span.start, offset);
+        self.span.end = offset_index(self.span.end, offset);
+    }
+
+    /// Replaces the span of this warning.
+    #[inline]
+    pub(crate) fn set_span(&mut self, span: Range<usize>) {
+        self.span = span;
+    }
+
     #[must_use]
     pub fn to_utf16_indices(&self, map: &Utf16IndexMap) -> Self {
         // Copy fields
@@ -173,9 +187,15 @@ pub enum ParseWarningKind {
     /// There is a tab outside of a tabview.
     TabOutsideTabView,
 
+    /// This gallery has lines other than `: file` items in it.
+    GalleryContainsNonItem,
+
     /// Footnotes are not permitted from inside footnotes.
     FootnotesNested,
 
+    /// This bibliography has elements other than `: label : citation` entries in it.
+    BibliographyContainsNonEntry,
+
     /// This native blockquote tries to nest too deeply.
     BlockquoteDepthExceeded,
 
@@ -221,11 +241,38 @@ pub enum ParseWarningKind {
     /// The given page to be included does not exist.
     NoSuchPage,
 
+    /// This include is nested within too many other included pages.
+    IncludeDepthExceeded,
+
+    /// The includer failed while fetching the page to be included.
+    IncludeFailed,
+
     /// The given variable was not found, and thus not substituted.
     NoSuchVariable,
 
+    /// No bibliography entry has the label being cited.
+    NoSuchBibliographyEntry,
+
+    /// This expression could not be evaluated.
+    InvalidExpression,
+
     /// The URL passed here was invalid.
     InvalidUrl,
+
+    /// This CSS at-rule is not permitted, and was removed.
+    CssDisallowedAtRule,
+
+    /// This CSS contains a URL with a scheme which is not permitted, and was removed.
+    CssDisallowedUrl,
+
+    /// This CSS property is not permitted, and was removed.
+    CssDisallowedProperty,
+
+    /// This CSS function is not permitted, and was removed.
+    CssDisallowedFunction,
+
+    /// This CSS is malformed, such as unclosed brackets or HTML markup, and was removed.
+    CssMalformed,
 }
 
 impl ParseWarningKind {
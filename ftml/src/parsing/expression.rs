@@ -0,0 +1,516 @@
+/*
+ * parsing/expression.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A small evaluator for the expressions in `[[#expr]]` and `[[#ifexpr]]`.
+//!
+//! This supports arithmetic, comparisons, and boolean logic on numbers.
+//! Booleans are represented as `1` and `0`, like in Wikidot.
+//! There are no loops, assignments, or user-defined functions,
+//! and nesting is limited, so evaluation always terminates quickly.
+
+use std::fmt::{self, Display};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// How deeply parentheses and unary operators may be nested.
+const MAX_EXPRESSION_DEPTH: usize = 64;
+
+/// Evaluates the given expression, returning its numeric result.
+pub fn evaluate(expression: &str) -> Result<f64, ExpressionError> {
+    let tokens = tokenize(expression)?;
+    let mut parser = ExpressionParser {
+        tokens: &tokens,
+        index: 0,
+        depth: 0,
+    };
+
+    let value = parser.parse_or()?;
+    if parser.index < tokens.len() {
+        return Err(ExpressionError::UnexpectedToken);
+    }
+
+    if !value.is_finite() {
+        return Err(ExpressionError::NotFinite);
+    }
+
+    Ok(value)
+}
+
+/// Formats the result of an expression for display.
+///
+/// Integers are shown without a fractional part.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        (value as i64).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// An error which occurred while evaluating an expression.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExpressionError {
+    /// The expression contains a character which is not valid in any token.
+    UnexpectedCharacter,
+
+    /// A token appeared where it is not permitted.
+    UnexpectedToken,
+
+    /// The expression ended where more was expected.
+    UnexpectedEnd,
+
+    /// The name used is not a known function or constant.
+    UnknownName,
+
+    /// The expression divides by zero.
+    DivisionByZero,
+
+    /// The result is infinite or not a number.
+    NotFinite,
+
+    /// Parentheses or operators are nested too deeply.
+    DepthExceeded,
+}
+
+impl Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ExpressionError::UnexpectedCharacter => "unexpected character",
+            ExpressionError::UnexpectedToken => "unexpected token",
+            ExpressionError::UnexpectedEnd => "unexpected end of expression",
+            ExpressionError::UnknownName => "unknown function or constant",
+            ExpressionError::DivisionByZero => "division by zero",
+            ExpressionError::NotFinite => "result is not a finite number",
+            ExpressionError::DepthExceeded => "expression is nested too deeply",
+        };
+
+        f.write_str(message)
+    }
+}
+
+// Tokenizing
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExpressionToken {
+    Number(f64),
+    Name(String),
+    LeftParen,
+    RightParen,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    Not,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<ExpressionToken>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        let token = match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' | '.' => tokenize_number(&mut chars)?,
+            _ if ch.is_ascii_alphabetic() => {
+                let mut name = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !ch.is_ascii_alphanumeric() && ch != '_' {
+                        break;
+                    }
+
+                    name.push(ch.to_ascii_lowercase());
+                    chars.next();
+                }
+
+                match name.as_str() {
+                    "mod" => ExpressionToken::Modulo,
+                    "and" => ExpressionToken::And,
+                    "or" => ExpressionToken::Or,
+                    "not" => ExpressionToken::Not,
+                    _ => ExpressionToken::Name(name),
+                }
+            }
+            _ => {
+                chars.next();
+
+                // Operators which may be followed by a second character
+                let mut next_is = |expected: char| {
+                    let matches = chars.peek() == Some(&expected);
+                    if matches {
+                        chars.next();
+                    }
+                    matches
+                };
+
+                match ch {
+                    '(' => ExpressionToken::LeftParen,
+                    ')' => ExpressionToken::RightParen,
+                    '+' => ExpressionToken::Plus,
+                    '-' => ExpressionToken::Minus,
+                    '*' => ExpressionToken::Multiply,
+                    '/' => ExpressionToken::Divide,
+                    '%' => ExpressionToken::Modulo,
+                    '^' => ExpressionToken::Power,
+                    '=' => {
+                        next_is('=');
+                        ExpressionToken::Equal
+                    }
+                    '!' if next_is('=') => ExpressionToken::NotEqual,
+                    '!' => ExpressionToken::Not,
+                    '<' if next_is('=') => ExpressionToken::LessEqual,
+                    '<' if next_is('>') => ExpressionToken::NotEqual,
+                    '<' => ExpressionToken::Less,
+                    '>' if next_is('=') => ExpressionToken::GreaterEqual,
+                    '>' => ExpressionToken::Greater,
+                    '&' if next_is('&') => ExpressionToken::And,
+                    '|' if next_is('|') => ExpressionToken::Or,
+                    _ => return Err(ExpressionError::UnexpectedCharacter),
+                }
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn tokenize_number(
+    chars: &mut Peekable<Chars>,
+) -> Result<ExpressionToken, ExpressionError> {
+    let mut number = String::new();
+
+    while let Some(&ch) = chars.peek() {
+        if !ch.is_ascii_digit() && ch != '.' {
+            break;
+        }
+
+        number.push(ch);
+        chars.next();
+    }
+
+    // Exponent, such as in "1.5e3"
+    if matches!(chars.peek(), Some('e' | 'E')) {
+        let mut lookahead = chars.clone();
+        lookahead.next();
+
+        let mut exponent = String::from("e");
+        if let Some(&sign @ ('+' | '-')) = lookahead.peek() {
+            exponent.push(sign);
+            lookahead.next();
+        }
+
+        if matches!(lookahead.peek(), Some(ch) if ch.is_ascii_digit()) {
+            while let Some(&ch) = lookahead.peek() {
+                if !ch.is_ascii_digit() {
+                    break;
+                }
+
+                exponent.push(ch);
+                lookahead.next();
+            }
+
+            number.push_str(&exponent);
+            *chars = lookahead;
+        }
+    }
+
+    number
+        .parse()
+        .map(ExpressionToken::Number)
+        .map_err(|_| ExpressionError::UnexpectedToken)
+}
+
+// Parsing and evaluation
+
+struct ExpressionParser<'a> {
+    tokens: &'a [ExpressionToken],
+    index: usize,
+    depth: usize,
+}
+
+impl ExpressionParser<'_> {
+    fn peek(&self) -> Option<&ExpressionToken> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<&ExpressionToken> {
+        let token = self.tokens.get(self.index);
+        self.index += 1;
+        token
+    }
+
+    fn next_if(&mut self, expected: &ExpressionToken) -> bool {
+        let matches = self.peek() == Some(expected);
+        if matches {
+            self.index += 1;
+        }
+        matches
+    }
+
+    fn nested<F>(&mut self, f: F) -> Result<f64, ExpressionError>
+    where
+        F: FnOnce(&mut Self) -> Result<f64, ExpressionError>,
+    {
+        if self.depth >= MAX_EXPRESSION_DEPTH {
+            return Err(ExpressionError::DepthExceeded);
+        }
+
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.parse_and()?;
+
+        while self.next_if(&ExpressionToken::Or) {
+            let right = self.parse_and()?;
+            value = from_bool(is_true(value) || is_true(right));
+        }
+
+        Ok(value)
+    }
+
+    fn parse_and(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.parse_not()?;
+
+        while self.next_if(&ExpressionToken::And) {
+            let right = self.parse_not()?;
+            value = from_bool(is_true(value) && is_true(right));
+        }
+
+        Ok(value)
+    }
+
+    fn parse_not(&mut self) -> Result<f64, ExpressionError> {
+        if self.next_if(&ExpressionToken::Not) {
+            return self.nested(|parser| Ok(from_bool(!is_true(parser.parse_not()?))));
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.parse_additive()?;
+
+        loop {
+            let compare: fn(f64, f64) -> bool = match self.peek() {
+                Some(ExpressionToken::Equal) => |a, b| a == b,
+                Some(ExpressionToken::NotEqual) => |a, b| a != b,
+                Some(ExpressionToken::Less) => |a, b| a < b,
+                Some(ExpressionToken::LessEqual) => |a, b| a <= b,
+                Some(ExpressionToken::Greater) => |a, b| a > b,
+                Some(ExpressionToken::GreaterEqual) => |a, b| a >= b,
+                _ => return Ok(value),
+            };
+
+            self.index += 1;
+            let right = self.parse_additive()?;
+            value = from_bool(compare(value, right));
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.parse_multiplicative()?;
+
+        loop {
+            match self.peek() {
+                Some(ExpressionToken::Plus) => {
+                    self.index += 1;
+                    value += self.parse_multiplicative()?;
+                }
+                Some(ExpressionToken::Minus) => {
+                    self.index += 1;
+                    value -= self.parse_multiplicative()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.parse_power()?;
+
+        loop {
+            let token = match self.peek() {
+                Some(
+                    token @ (ExpressionToken::Multiply
+                    | ExpressionToken::Divide
+                    | ExpressionToken::Modulo),
+                ) => token.clone(),
+                _ => return Ok(value),
+            };
+
+            self.index += 1;
+            let right = self.parse_power()?;
+
+            value = match token {
+                ExpressionToken::Multiply => value * right,
+                _ if right == 0.0 => return Err(ExpressionError::DivisionByZero),
+                ExpressionToken::Divide => value / right,
+                _ => value % right,
+            };
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<f64, ExpressionError> {
+        let base = self.parse_unary()?;
+
+        // Exponentiation is right-associative
+        if self.next_if(&ExpressionToken::Power) {
+            let exponent = self.nested(Self::parse_power)?;
+            return Ok(base.powf(exponent));
+        }
+
+        Ok(base)
+    }
+
+    fn parse_unary(&mut self) -> Result<f64, ExpressionError> {
+        if self.next_if(&ExpressionToken::Minus) {
+            return self.nested(|parser| Ok(-parser.parse_unary()?));
+        }
+
+        if self.next_if(&ExpressionToken::Plus) {
+            return self.nested(Self::parse_unary);
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<f64, ExpressionError> {
+        match self.next().cloned() {
+            Some(ExpressionToken::Number(value)) => Ok(value),
+            Some(ExpressionToken::LeftParen) => self.parse_parenthesized(),
+            Some(ExpressionToken::Name(name)) => {
+                let function: fn(f64) -> f64 = match name.as_str() {
+                    "true" => return Ok(1.0),
+                    "false" => return Ok(0.0),
+                    "pi" => return Ok(std::f64::consts::PI),
+                    "e" => return Ok(std::f64::consts::E),
+                    "abs" => f64::abs,
+                    "floor" => f64::floor,
+                    "ceil" => f64::ceil,
+                    "round" => f64::round,
+                    "trunc" => f64::trunc,
+                    "sqrt" => f64::sqrt,
+                    _ => return Err(ExpressionError::UnknownName),
+                };
+
+                if !self.next_if(&ExpressionToken::LeftParen) {
+                    return Err(ExpressionError::UnexpectedToken);
+                }
+
+                Ok(function(self.parse_parenthesized()?))
+            }
+            Some(_) => Err(ExpressionError::UnexpectedToken),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+
+    /// Parses the rest of a parenthesized expression, after the `(`.
+    fn parse_parenthesized(&mut self) -> Result<f64, ExpressionError> {
+        let value = self.nested(Self::parse_or)?;
+
+        match self.next() {
+            Some(ExpressionToken::RightParen) => Ok(value),
+            Some(_) => Err(ExpressionError::UnexpectedToken),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+}
+
+#[inline]
+fn is_true(value: f64) -> bool {
+    value != 0.0
+}
+
+#[inline]
+fn from_bool(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[test]
+fn expressions() {
+    macro_rules! check {
+        ($expression:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                evaluate($expression).map(format_number),
+                Ok(str!($expected)),
+                "Expression {:?} evaluated incorrectly",
+                $expression,
+            );
+        };
+    }
+
+    macro_rules! check_err {
+        ($expression:expr, $error:expr $(,)?) => {
+            assert_eq!(
+                evaluate($expression),
+                Err($error),
+                "Expression {:?} did not fail as expected",
+                $expression,
+            );
+        };
+    }
+
+    check!("1 + 2 * 3", "7");
+    check!("(1 + 2) * 3", "9");
+    check!("2 ^ 3 ^ 2", "512");
+    check!("-2 ^ 2", "4");
+    check!("7 mod 3", "1");
+    check!("7 / 2", "3.5");
+    check!("1.5e2", "150");
+    check!("round(2.5) + floor(-1.5)", "1");
+    check!("3 > 2 and 2 >= 2", "1");
+    check!("1 = 2 or not 0", "1");
+    check!("1 <> 1 || !(2 != 2)", "1");
+    check!("true && false", "0");
+
+    check_err!("", ExpressionError::UnexpectedEnd);
+    check_err!("1 +", ExpressionError::UnexpectedEnd);
+    check_err!("(1", ExpressionError::UnexpectedEnd);
+    check_err!("1 2", ExpressionError::UnexpectedToken);
+    check_err!("1 / 0", ExpressionError::DivisionByZero);
+    check_err!("5 mod 0", ExpressionError::DivisionByZero);
+    check_err!("sqrt(0 - 1)", ExpressionError::NotFinite);
+    check_err!("foo(1)", ExpressionError::UnknownName);
+    check_err!("{$x} + 1", ExpressionError::UnexpectedCharacter);
+    check_err!(&"(".repeat(1000), ExpressionError::DepthExceeded);
+    check_err!(&"-".repeat(1000), ExpressionError::DepthExceeded);
+}
@@ -0,0 +1,110 @@
+/*
+ * parsing/includer.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Adapter allowing the parser to fetch pages for `[[include-elements]]`.
+//!
+//! The parser is cloned freely and has no knowledge of the includer's
+//! type or error, so the [`Includer`] is wrapped in an object-safe trait
+//! which can be shared between parser instances.
+
+use crate::data::PageRef;
+use crate::includes::{IncludeRef, Includer};
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+pub trait ElementIncluder {
+    /// Fetches the wikitext of the given page.
+    ///
+    /// If the page does not exist, the includer's "no such include"
+    /// wikitext is returned instead. Returns `None` if the includer failed,
+    /// which is reported as [`ParseWarningKind::IncludeFailed`].
+    ///
+    /// [`ParseWarningKind::IncludeFailed`]: crate::parsing::ParseWarningKind::IncludeFailed
+    fn fetch_page(&self, page_ref: &PageRef) -> Option<String>;
+
+    /// Gets the wikitext to use if the given page would include itself.
+    ///
+    /// Returns `None` if the includer failed.
+    fn include_cycle(&self, page_ref: &PageRef) -> Option<String>;
+}
+
+impl Debug for dyn ElementIncluder + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("dyn ElementIncluder")
+    }
+}
+
+/// Wraps an [`Includer`] so it can be shared by parser instances.
+pub struct SharedIncluder<'t, I> {
+    includer: RefCell<I>,
+    _marker: PhantomData<&'t ()>,
+}
+
+impl<'t, I> SharedIncluder<'t, I> {
+    #[inline]
+    pub fn new(includer: I) -> Self {
+        SharedIncluder {
+            includer: RefCell::new(includer),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'t, I> ElementIncluder for SharedIncluder<'t, I>
+where
+    I: Includer<'t>,
+{
+    fn fetch_page(&self, page_ref: &PageRef) -> Option<String> {
+        let mut includer = self.includer.borrow_mut();
+        let page_ref = page_ref.to_owned();
+        let includes = [IncludeRef::page_only(page_ref.clone())];
+
+        let content = match includer.include_pages(&includes) {
+            Ok(mut pages) => match pages.pop() {
+                Some(fetched) if fetched.page_ref == page_ref => fetched.content,
+                _ => {
+                    warn!("Includer returned mismatched pages for {page_ref}");
+                    return None;
+                }
+            },
+            Err(_) => {
+                warn!("Includer returned an error fetching page {page_ref}");
+                return None;
+            }
+        };
+
+        match content {
+            Some(content) => Some(content.into_owned()),
+            None => includer
+                .no_such_include(&page_ref)
+                .ok()
+                .map(|content| content.into_owned()),
+        }
+    }
+
+    fn include_cycle(&self, page_ref: &PageRef) -> Option<String> {
+        self.includer
+            .borrow_mut()
+            .include_cycle(&page_ref.to_owned())
+            .ok()
+            .map(|content| content.into_owned())
+    }
+}
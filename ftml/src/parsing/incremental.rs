@@ -0,0 +1,434 @@
+/*
+ * parsing/incremental.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Re-parsing of edited wikitext, reusing the unaffected parts of the previous parse.
+//!
+//! At the top level, a page is a series of blocks separated by paragraph breaks,
+//! and the parser starts over with fresh state at each one. So the output of a block
+//! depends only on the tokens from its start up to the furthest one the parser looked at.
+//! If none of those tokens were changed by an edit, the block's output can be reused,
+//! moving any source positions as needed.
+
+use super::paragraph::gather_block;
+use super::parser::Parser;
+use super::rule::impls::RULE_PAGE;
+use super::{build_fallback_tree, build_tree, extract_exceptions, PendingBibliography};
+use super::{ParseOutcome, ParseSuccess, ParseWarning, Token};
+use crate::data::PageInfo;
+use crate::settings::WikitextSettings;
+use crate::tokenizer::{offset_index, TextEdit, Tokenization};
+use crate::tree::{offset_spans, Element, SyntaxTree};
+use std::borrow::Cow;
+use std::mem;
+use std::ops::Range;
+
+/// The state of a parse, which can be updated after edits to the wikitext.
+///
+/// After each edit, only the top-level blocks which could be affected by
+/// it are parsed again. The resulting syntax tree and warnings are the same
+/// as those from running [`parse()`](crate::parse) on the full text.
+///
+/// Pair this with [`retokenize()`](crate::tokenizer::retokenize) to avoid
+/// lexing the full text again.
+#[derive(Debug, Clone)]
+pub struct IncrementalParse {
+    tokens: Vec<(Token, Range<usize>)>,
+    blocks: Vec<Block>,
+    outcome: ParseOutcome<SyntaxTree<'static>>,
+}
+
+impl IncrementalParse {
+    /// Parses the given tokens in full.
+    pub fn new<'r, 't>(
+        tokenization: &'r Tokenization<'t>,
+        page_info: &'r PageInfo<'t>,
+        settings: &'r WikitextSettings,
+    ) -> Self
+    where
+        'r: 't,
+    {
+        info!("Running incremental parser on full input");
+
+        let mut blocks = Vec::new();
+        parse_blocks(
+            tokenization,
+            page_info,
+            settings,
+            &mut blocks,
+            Reused::none(),
+        );
+
+        IncrementalParse {
+            tokens: extract_tokens(tokenization),
+            outcome: assemble(tokenization, &blocks),
+            blocks,
+        }
+    }
+
+    /// Updates the parse after the given edit.
+    ///
+    /// The tokenization is for the text after the edit, and the page information
+    /// and settings must be the same as when this parse was created.
+    ///
+    /// # Panics
+    /// Panics if the length of the edited text does not match the edit having
+    /// been applied to the previous text.
+    pub fn update<'r, 't>(
+        &mut self,
+        tokenization: &'r Tokenization<'t>,
+        edit: &TextEdit,
+        page_info: &'r PageInfo<'t>,
+        settings: &'r WikitextSettings,
+    ) where
+        'r: 't,
+    {
+        info!(
+            "Running incremental parser on edited input (range {}..{}, replacement length {})",
+            edit.range.start,
+            edit.range.end,
+            edit.replacement.len(),
+        );
+
+        let old_tokens = &self.tokens;
+        let new_tokens = tokenization.tokens();
+        let old_length = old_tokens.last().map(|(_, span)| span.end).unwrap_or(0);
+        assert_eq!(
+            old_length - edit.range.len() + edit.replacement.len(),
+            tokenization.full_text().inner().len(),
+            "Edited text length does not match the edit",
+        );
+
+        // Find how many tokens at the start and end are unchanged by the edit
+        let prefix = old_tokens
+            .iter()
+            .zip(new_tokens)
+            .take_while(|((old_token, old_span), new)| {
+                *old_token == new.token
+                    && *old_span == new.span
+                    && old_span.end <= edit.range.start
+            })
+            .count();
+
+        let suffix = old_tokens
+            .iter()
+            .rev()
+            .zip(new_tokens.iter().rev())
+            .take(old_tokens.len().min(new_tokens.len()) - prefix)
+            .take_while(|((old_token, old_span), new)| {
+                *old_token == new.token
+                    && old_span.start >= edit.range.end
+                    && offset_index(old_span.start, edit.offset()) == new.span.start
+                    && offset_index(old_span.end, edit.offset()) == new.span.end
+            })
+            .count();
+
+        debug!(
+            "Tokens unchanged by edit: {prefix} at start, {suffix} at end (of {})",
+            new_tokens.len(),
+        );
+
+        // Keep the leading blocks which only examined unchanged tokens
+        let mut old_blocks = mem::take(&mut self.blocks).into_iter().peekable();
+        let mut blocks = Vec::new();
+
+        while let Some(block) = old_blocks.next_if(|block| block.examined < prefix) {
+            blocks.push(block);
+        }
+
+        // Re-parse until reaching a block that starts within the unchanged tokens
+        // at the end, then move the remaining blocks into position
+        let reused = Reused {
+            blocks: old_blocks.collect(),
+            index_offset: new_tokens.len() as isize - old_tokens.len() as isize,
+            byte_offset: edit.offset(),
+            first_index: new_tokens.len() - suffix,
+        };
+
+        parse_blocks(tokenization, page_info, settings, &mut blocks, reused);
+
+        self.tokens = extract_tokens(tokenization);
+        self.outcome = assemble(tokenization, &blocks);
+        self.blocks = blocks;
+    }
+
+    /// Gets the syntax tree and warnings for the current text.
+    #[inline]
+    pub fn outcome(&self) -> &ParseOutcome<SyntaxTree<'static>> {
+        &self.outcome
+    }
+
+    #[inline]
+    pub fn into_outcome(self) -> ParseOutcome<SyntaxTree<'static>> {
+        self.outcome
+    }
+}
+
+/// The result of parsing one top-level block.
+#[derive(Debug, Clone)]
+struct Block {
+    /// The index of the first token in this block.
+    start: usize,
+
+    /// The index of the paragraph break (or input end) token which ended this block.
+    end: usize,
+
+    /// The index of the furthest token examined while parsing this block.
+    examined: usize,
+
+    result: Result<BlockOutput, ParseWarning>,
+}
+
+impl Block {
+    fn offset(&mut self, index_offset: isize, byte_offset: isize) {
+        self.start = offset_index(self.start, index_offset);
+        self.end = offset_index(self.end, index_offset);
+        self.examined = offset_index(self.examined, index_offset);
+
+        match self.result {
+            Ok(ref mut output) => {
+                for warning in &mut output.warnings {
+                    warning.offset_span(byte_offset);
+                }
+
+                offset_spans(&mut output.elements, byte_offset);
+
+                for footnote in &mut output.footnotes {
+                    offset_spans(footnote, byte_offset);
+                }
+
+                output.bibliography.offset_spans(byte_offset);
+            }
+            Err(ref mut warning) => warning.offset_span(byte_offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BlockOutput {
+    elements: Vec<Element<'static>>,
+    warnings: Vec<ParseWarning>,
+    styles: Vec<Cow<'static, str>>,
+    table_of_contents_depths: Vec<(usize, String)>,
+    footnotes: Vec<Vec<Element<'static>>>,
+    has_footnote_block: bool,
+    bibliography: PendingBibliography<'static>,
+}
+
+/// The blocks from the previous parse which may be reused after the edit.
+#[derive(Debug, Default)]
+struct Reused {
+    /// Previous blocks, with indices from before the edit.
+    blocks: Vec<Block>,
+
+    /// How far tokens after the edit have moved.
+    index_offset: isize,
+
+    /// How far text after the edit has moved, in bytes.
+    byte_offset: isize,
+
+    /// The index of the first token which is unchanged after the edit.
+    first_index: usize,
+}
+
+impl Reused {
+    /// For a new parse, where there are no previous blocks.
+    #[inline]
+    fn none() -> Self {
+        Reused {
+            first_index: usize::MAX,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses blocks until the end of input, appending them to `blocks`.
+///
+/// Parsing starts after the last block in the list, or at the start of input.
+fn parse_blocks<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    blocks: &mut Vec<Block>,
+    reused: Reused,
+) where
+    'r: 't,
+{
+    let Reused {
+        blocks: old_blocks,
+        index_offset,
+        byte_offset,
+        first_index,
+    } = reused;
+
+    let token_count = tokenization.tokens().len();
+    let is_finished =
+        |block: &Block| block.result.is_err() || block.end + 1 >= token_count;
+
+    if blocks.last().map(is_finished).unwrap_or(false) {
+        return;
+    }
+
+    let mut old_blocks = old_blocks.into_iter().peekable();
+    let mut next = blocks.last().map(|block| block.end + 1).unwrap_or(0);
+
+    loop {
+        // If this block starts in the unchanged tokens, and so did one
+        // in the previous parse, then it and all following are the same.
+        if next >= first_index {
+            let old_start = offset_index(next, -index_offset);
+            while old_blocks
+                .next_if(|block| block.start < old_start)
+                .is_some()
+            {}
+
+            if old_blocks.peek().map(|block| block.start) == Some(old_start) {
+                debug!("Reusing blocks from previous parse, starting at token {next}");
+
+                blocks.extend(old_blocks.map(|mut block| {
+                    block.offset(index_offset, byte_offset);
+                    block
+                }));
+
+                return;
+            }
+        }
+
+        debug!("Parsing block starting at token {next}");
+        let block = parse_block(tokenization, page_info, settings, next);
+        let finished = is_finished(&block);
+        next = block.end + 1;
+        blocks.push(block);
+
+        if finished {
+            return;
+        }
+    }
+}
+
+fn parse_block<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    start: usize,
+) -> Block
+where
+    'r: 't,
+{
+    let mut parser = Parser::new_at(tokenization, page_info, settings, start);
+    let result = gather_block(&mut parser, RULE_PAGE);
+    let last = tokenization.tokens().len() - 1;
+    let examined = last - parser.fewest_remaining();
+
+    match result {
+        Ok(ParseSuccess {
+            item: elements,
+            exceptions,
+            ..
+        }) => {
+            let (warnings, styles) = extract_exceptions(exceptions);
+            let output = BlockOutput {
+                elements: elements.iter().map(Element::to_owned).collect(),
+                warnings,
+                styles: styles
+                    .into_iter()
+                    .map(|style| Cow::Owned(style.into_owned()))
+                    .collect(),
+                table_of_contents_depths: parser.remove_table_of_contents(),
+                footnotes: parser
+                    .remove_footnotes()
+                    .iter()
+                    .map(|footnote| footnote.iter().map(Element::to_owned).collect())
+                    .collect(),
+                has_footnote_block: parser.has_footnote_block(),
+                bibliography: parser.remove_bibliography().to_owned(),
+            };
+
+            Block {
+                start,
+                end: last - parser.remaining().len(),
+                examined,
+                result: Ok(output),
+            }
+        }
+
+        // Parsing cannot continue, so this block covers the rest of the input.
+        Err(warning) => Block {
+            start,
+            end: last,
+            examined: last,
+            result: Err(warning),
+        },
+    }
+}
+
+/// Combines the blocks into a syntax tree, the same way a full parse does.
+fn assemble(
+    tokenization: &Tokenization,
+    blocks: &[Block],
+) -> ParseOutcome<SyntaxTree<'static>> {
+    let mut elements = Vec::new();
+    let mut warnings = Vec::new();
+    let mut styles = Vec::new();
+    let mut table_of_contents_depths = Vec::new();
+    let mut footnotes = Vec::new();
+    let mut has_footnote_block = false;
+    let mut bibliography = PendingBibliography::default();
+
+    for block in blocks {
+        let output = match block.result {
+            Ok(ref output) => output,
+            Err(ref warning) => {
+                let (tree, warnings) = build_fallback_tree(
+                    tokenization.full_text().inner(),
+                    warning.clone(),
+                )
+                .into();
+
+                return ParseOutcome::new(tree.to_owned(), warnings);
+            }
+        };
+
+        elements.extend_from_slice(&output.elements);
+        warnings.extend_from_slice(&output.warnings);
+        styles.extend_from_slice(&output.styles);
+        table_of_contents_depths.extend_from_slice(&output.table_of_contents_depths);
+        footnotes.extend_from_slice(&output.footnotes);
+        has_footnote_block |= output.has_footnote_block;
+        bibliography.append(&mut output.bibliography.clone());
+    }
+
+    build_tree(
+        elements,
+        warnings,
+        styles,
+        table_of_contents_depths,
+        footnotes,
+        has_footnote_block,
+        bibliography,
+    )
+}
+
+fn extract_tokens(tokenization: &Tokenization) -> Vec<(Token, Range<usize>)> {
+    tokenization
+        .tokens()
+        .iter()
+        .map(|token| (token.token, token.span.clone()))
+        .collect()
+}
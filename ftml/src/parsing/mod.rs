@@ -21,6 +21,7 @@
 #[macro_use]
 mod macros;
 
+mod bibliography;
 mod boolean;
 mod check_step;
 mod collect;
@@ -29,6 +30,9 @@ mod consume;
 mod depth;
 mod element_condition;
 mod exception;
+mod expression;
+mod includer;
+mod incremental;
 mod outcome;
 mod paragraph;
 mod parser;
@@ -51,6 +55,7 @@ mod prelude {
 
 use self::depth::{process_depths, DepthItem, DepthList};
 use self::element_condition::{ElementCondition, ElementConditionType};
+use self::includer::SharedIncluder;
 use self::paragraph::{gather_paragraphs, NO_CLOSE_CONDITION};
 use self::parser::Parser;
 use self::parser_wrap::ParserWrap;
@@ -58,29 +63,115 @@ use self::rule::impls::RULE_PAGE;
 use self::string::parse_string;
 use self::strip::{strip_newlines, strip_whitespace};
 use crate::data::PageInfo;
+use crate::includes::Includer;
 use crate::next_index::{NextIndex, TableOfContentsIndex};
 use crate::settings::WikitextSettings;
 use crate::tokenizer::Tokenization;
 use crate::tree::{
-    AttributeMap, Element, LinkLabel, LinkLocation, LinkType, ListItem, ListType,
-    SyntaxTree,
+    AttributeMap, BibliographyList, Element, LinkLabel, LinkLocation, LinkType, ListItem,
+    ListType, SyntaxTree,
 };
 use std::borrow::Cow;
+use std::rc::Rc;
 
+pub use self::bibliography::PendingBibliography;
 pub use self::boolean::{parse_boolean, NonBooleanValue};
 pub use self::exception::{ParseException, ParseWarning, ParseWarningKind};
+pub use self::incremental::IncrementalParse;
 pub use self::outcome::ParseOutcome;
 pub use self::result::{ParseResult, ParseSuccess};
+pub use self::rule::impls::{
+    Arguments, CustomBlock, CustomBlockBody, CustomBlockFn, CustomBlockInput,
+    CustomBlocks,
+};
 pub use self::token::{ExtractedToken, Token};
 
 /// Parse through the given tokens and produce an AST.
 ///
 /// This takes a list of `ExtractedToken` items produced by `tokenize()`.
+///
+/// Since no includer is available, any `[[include-elements]]` blocks
+/// are not substituted. See [`parse_with_includer`].
 pub fn parse<'r, 't>(
     tokenization: &'r Tokenization<'t>,
     page_info: &'r PageInfo<'t>,
     settings: &'r WikitextSettings,
 ) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+{
+    let parser = Parser::new(tokenization, page_info, settings);
+    parse_tree(tokenization, parser)
+}
+
+/// Parse through the given tokens and produce an AST, including pages as needed.
+///
+/// This is the same as [`parse`], except the given includer is used to fetch
+/// pages for `[[include-elements]]` blocks. Each such page is parsed separately,
+/// with its elements placed in this tree. Nested includes are followed up to
+/// the depth in [`WikitextSettings::max_include_depth`].
+///
+/// Any failures from the includer result in a warning for that block.
+pub fn parse_with_includer<'r, 't, 'i, I>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    includer: I,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+    'i: 'r,
+    I: Includer<'i> + 'r,
+{
+    let mut parser = Parser::new(tokenization, page_info, settings);
+    parser.set_includer(Rc::new(SharedIncluder::new(includer)));
+    parse_tree(tokenization, parser)
+}
+
+/// Parse through the given tokens and produce an AST, with additional blocks.
+///
+/// This is the same as [`parse`], except the given custom blocks
+/// can be used in addition to the built-in ones. See [`CustomBlock`].
+pub fn parse_with_blocks<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    custom_blocks: &'r CustomBlocks,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+{
+    let mut parser = Parser::new(tokenization, page_info, settings);
+    parser.set_custom_blocks(custom_blocks);
+    parse_tree(tokenization, parser)
+}
+
+/// Parse through the given tokens and produce an AST, including pages and with additional blocks.
+///
+/// This combines [`parse_with_includer`] and [`parse_with_blocks`].
+/// Custom blocks can also be used in included pages.
+pub fn parse_with_includer_and_blocks<'r, 't, 'i, I>(
+    tokenization: &'r Tokenization<'t>,
+    page_info: &'r PageInfo<'t>,
+    settings: &'r WikitextSettings,
+    includer: I,
+    custom_blocks: &'r CustomBlocks,
+) -> ParseOutcome<SyntaxTree<'t>>
+where
+    'r: 't,
+    'i: 'r,
+    I: Includer<'i> + 'r,
+{
+    let mut parser = Parser::new(tokenization, page_info, settings);
+    parser.set_includer(Rc::new(SharedIncluder::new(includer)));
+    parser.set_custom_blocks(custom_blocks);
+    parse_tree(tokenization, parser)
+}
+
+fn parse_tree<'r, 't>(
+    tokenization: &'r Tokenization<'t>,
+    parser: Parser<'r, 't>,
+) -> ParseOutcome<SyntaxTree<'t>>
 where
     'r: 't,
 {
@@ -90,81 +181,102 @@ where
         table_of_contents_depths,
         footnotes,
         has_footnote_block,
-    } = parse_internal(page_info, settings, tokenization);
-
-    // For producing table of contents indexes
-    let mut incrementer = Incrementer(0);
+        bibliography,
+    } = run_parser(parser);
 
     info!("Finished paragraph gathering, matching on consumption");
     match result {
         Ok(ParseSuccess {
-            item: mut elements,
+            item: elements,
             exceptions,
             ..
         }) => {
             let (warnings, styles) = extract_exceptions(exceptions);
 
-            info!(
-                "Finished parsing, producing final syntax tree ({} warnings, {} styles)",
-                warnings.len(),
-                styles.len(),
-            );
-
-            // process_depths() wants a "list type", so we map in a () for each.
-            let table_of_contents_depths = table_of_contents_depths
-                .into_iter()
-                .map(|(depth, contents)| (depth, (), contents));
-
-            // Convert TOC depth lists
-            let table_of_contents = process_depths((), table_of_contents_depths)
-                .into_iter()
-                .map(|(_, items)| build_toc_list_element(&mut incrementer, items))
-                .collect::<Vec<_>>();
-
-            // Add a footnote block at the end,
-            // if the user doesn't have one already
-            if !has_footnote_block {
-                info!("No footnote block in elements, appending one");
-
-                elements.push(Element::FootnoteBlock {
-                    title: None,
-                    hide: false,
-                });
-            }
-
-            SyntaxTree::from_element_result(
+            build_tree(
                 elements,
                 warnings,
                 styles,
-                table_of_contents,
-                footnotes,
-            )
-        }
-        Err(warning) => {
-            // This path is only reachable if a very bad error occurs.
-            //
-            // If this happens, then just return the input source as the output
-            // and the warning.
-
-            error!("Fatal error occurred at highest-level parsing: {warning:#?}");
-            let wikitext = tokenization.full_text().inner();
-            let elements = vec![text!(wikitext)];
-            let warnings = vec![warning];
-            let styles = vec![];
-            let table_of_contents = vec![];
-            let footnotes = vec![];
-
-            SyntaxTree::from_element_result(
-                elements,
-                warnings,
-                styles,
-                table_of_contents,
+                table_of_contents_depths,
                 footnotes,
+                has_footnote_block,
+                bibliography,
             )
         }
+        Err(warning) => build_fallback_tree(tokenization.full_text().inner(), warning),
     }
 }
 
+/// Assembles the final syntax tree from the results of parsing.
+fn build_tree<'t>(
+    mut elements: Vec<Element<'t>>,
+    mut warnings: Vec<ParseWarning>,
+    styles: Vec<Cow<'t, str>>,
+    table_of_contents_depths: Vec<(usize, String)>,
+    footnotes: Vec<Vec<Element<'t>>>,
+    has_footnote_block: bool,
+    bibliography: PendingBibliography<'t>,
+) -> ParseOutcome<SyntaxTree<'t>> {
+    info!(
+        "Finished parsing, producing final syntax tree ({} warnings, {} styles)",
+        warnings.len(),
+        styles.len(),
+    );
+
+    let table_of_contents = build_table_of_contents(table_of_contents_depths);
+
+    // Add a footnote block at the end,
+    // if the user doesn't have one already
+    if !has_footnote_block {
+        info!("No footnote block in elements, appending one");
+
+        elements.push(Element::FootnoteBlock {
+            title: None,
+            hide: false,
+        });
+    }
+
+    // Now that all entries are known, number them and check citations
+    let bibliography = bibliography.resolve(&mut warnings);
+
+    SyntaxTree::from_element_result(
+        elements,
+        warnings,
+        styles,
+        table_of_contents,
+        footnotes,
+        bibliography,
+    )
+}
+
+/// Produces the syntax tree for when parsing fails entirely.
+fn build_fallback_tree(
+    wikitext: &str,
+    warning: ParseWarning,
+) -> ParseOutcome<SyntaxTree<'_>> {
+    // This path is only reachable if a very bad error occurs.
+    //
+    // If this happens, then just return the input source as the output
+    // and the warning.
+
+    error!("Fatal error occurred at highest-level parsing: {warning:#?}");
+    let elements = vec![text!(wikitext)];
+    let warnings = vec![warning];
+    let styles = vec![];
+    let table_of_contents = vec![];
+    let footnotes = vec![];
+    let bibliography = BibliographyList::default();
+
+    SyntaxTree::from_element_result(
+        elements,
+        warnings,
+        styles,
+        table_of_contents,
+        footnotes,
+        bibliography,
+    )
+}
+
 /// Runs the parser, but returns the raw internal results prior to conversion.
 pub fn parse_internal<'r, 't>(
     page_info: &'r PageInfo<'t>,
@@ -174,8 +286,15 @@ pub fn parse_internal<'r, 't>(
 where
     'r: 't,
 {
-    let mut parser = Parser::new(tokenization, page_info, settings);
+    let parser = Parser::new(tokenization, page_info, settings);
+    run_parser(parser)
+}
 
+/// Runs the given parser over its entire input, gathering the raw results.
+fn run_parser<'r, 't>(mut parser: Parser<'r, 't>) -> UnstructuredParseResult<'r, 't>
+where
+    'r: 't,
+{
     // At the top level, we gather elements into paragraphs
     info!("Running parser on tokens");
     let result = gather_paragraphs(&mut parser, RULE_PAGE, NO_CLOSE_CONDITION);
@@ -184,12 +303,14 @@ where
     let table_of_contents_depths = parser.remove_table_of_contents();
     let footnotes = parser.remove_footnotes();
     let has_footnote_block = parser.has_footnote_block();
+    let bibliography = parser.remove_bibliography();
 
     UnstructuredParseResult {
         result,
         table_of_contents_depths,
         footnotes,
         has_footnote_block,
+        bibliography,
     }
 }
 
@@ -211,6 +332,25 @@ fn extract_exceptions(
     (warnings, styles)
 }
 
+/// Builds the table of contents from the depth and name of each heading.
+pub(crate) fn build_table_of_contents(
+    table_of_contents_depths: Vec<(usize, String)>,
+) -> Vec<Element<'static>> {
+    // For producing table of contents indexes
+    let mut incrementer = Incrementer(0);
+
+    // process_depths() wants a "list type", so we map in a () for each.
+    let table_of_contents_depths = table_of_contents_depths
+        .into_iter()
+        .map(|(depth, contents)| (depth, (), contents));
+
+    // Convert TOC depth lists
+    process_depths((), table_of_contents_depths)
+        .into_iter()
+        .map(|(_, items)| build_toc_list_element(&mut incrementer, items))
+        .collect()
+}
+
 fn build_toc_list_element(
     incr: &mut Incrementer,
     list: DepthList<(), String>,
@@ -278,4 +418,7 @@ pub struct UnstructuredParseResult<'r, 't> {
 
     /// Whether a footnote block was placed during parsing.
     pub has_footnote_block: bool,
+
+    /// The bibliography entries and citations, before they are numbered.
+    pub bibliography: PendingBibliography<'t>,
 }
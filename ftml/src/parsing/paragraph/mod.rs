@@ -51,14 +51,41 @@ type CloseConditionFn = fn(&mut Parser) -> Result<bool, ParseWarning>;
 pub fn gather_paragraphs<'r, 't, F>(
     parser: &mut Parser<'r, 't>,
     rule: Rule,
-    mut close_condition_fn: Option<F>,
+    close_condition_fn: Option<F>,
 ) -> ParseResult<'r, 't, Vec<Element<'t>>>
 where
     'r: 't,
     F: FnMut(&mut Parser<'r, 't>) -> Result<bool, ParseWarning>,
 {
     info!("Gathering paragraphs until ending");
+    gather_paragraphs_impl(parser, rule, close_condition_fn, false)
+}
+
+/// Like `gather_paragraphs()`, but stops at the first paragraph break.
+///
+/// The parser is left pointing at the paragraph break (or end of input).
+/// This produces one top-level block for incremental parsing.
+pub fn gather_block<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    rule: Rule,
+) -> ParseResult<'r, 't, Vec<Element<'t>>>
+where
+    'r: 't,
+{
+    info!("Gathering paragraphs until the next paragraph break");
+    gather_paragraphs_impl(parser, rule, NO_CLOSE_CONDITION, true)
+}
 
+fn gather_paragraphs_impl<'r, 't, F>(
+    parser: &mut Parser<'r, 't>,
+    rule: Rule,
+    mut close_condition_fn: Option<F>,
+    stop_at_paragraph_break: bool,
+) -> ParseResult<'r, 't, Vec<Element<'t>>>
+where
+    'r: 't,
+    F: FnMut(&mut Parser<'r, 't>) -> Result<bool, ParseWarning>,
+{
     // Update parser rule
     parser.set_rule(rule);
 
@@ -86,6 +113,10 @@ where
             }
 
             // If we've hit a paragraph break, then finish the current paragraph
+            Token::ParagraphBreak if stop_at_paragraph_break => {
+                info!("Hit a paragraph break, terminating token iteration");
+                break;
+            }
             Token::ParagraphBreak => {
                 info!("Hit a paragraph break, creating a new paragraph container");
 
@@ -136,7 +167,7 @@ fn push_elements<'t>(
 
     for element in elements {
         // Don't add a line break if the paragraph is otherwise empty
-        if stack.current_empty() && element == Element::LineBreak {
+        if stack.current_empty() && element.unspanned() == &Element::LineBreak {
             continue;
         }
 
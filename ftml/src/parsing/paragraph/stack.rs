@@ -21,6 +21,7 @@
 use crate::parsing::prelude::*;
 use crate::tree::{AttributeMap, Container, ContainerType};
 use std::mem;
+use std::ops::Range;
 
 #[derive(Debug, Default)]
 pub struct ParagraphStack<'t> {
@@ -86,7 +87,7 @@ impl<'t> ParagraphStack<'t> {
     pub fn pop_line_break(&mut self) {
         debug!("Popping last element if Element::LineBreak");
 
-        if let Some(Element::LineBreak) = self.current.last() {
+        if let Some(Element::LineBreak) = self.current.last().map(Element::unspanned) {
             self.current.pop();
         }
     }
@@ -105,10 +106,19 @@ impl<'t> ParagraphStack<'t> {
 
         // Pull out gathered elements, then make a new paragraph container
         let elements = mem::take(&mut self.current);
+        let span = elements_span(&elements);
         let container =
             Container::new(ContainerType::Paragraph, elements, AttributeMap::new());
         let element = Element::Container(container);
-        Some(element)
+
+        // If its contents have spans, then so does the paragraph
+        match span {
+            Some(span) => Some(Element::Spanned {
+                span,
+                element: Box::new(element),
+            }),
+            None => Some(element),
+        }
     }
 
     pub fn end_paragraph(&mut self) {
@@ -169,3 +179,19 @@ impl<'t> ParagraphStack<'t> {
         self.finished
     }
 }
+
+/// Gets the span covering all the spanned elements in the list.
+///
+/// This is `None` unless spans are being recorded, see `Element::Spanned`.
+fn elements_span(elements: &[Element]) -> Option<Range<usize>> {
+    fn span<'a>(element: &'a Element) -> Option<&'a Range<usize>> {
+        match element {
+            Element::Spanned { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    let start = elements.iter().find_map(span)?.start;
+    let end = elements.iter().rev().find_map(span)?.end;
+    Some(start..end)
+}
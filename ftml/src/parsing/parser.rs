@@ -18,15 +18,23 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::bibliography::PendingBibliography;
 use super::condition::ParseCondition;
+use super::includer::ElementIncluder;
 use super::prelude::*;
+use super::rule::impls::CustomBlocks;
 use super::rule::Rule;
 use super::RULE_PAGE;
-use crate::data::PageInfo;
+use crate::css::{self, CssIssue, SanitizedCss};
+use crate::data::{PageInfo, PageRef};
+use crate::includes::IncludeAncestors;
 use crate::render::text::TextRender;
 use crate::tokenizer::Tokenization;
-use crate::tree::{AcceptsPartial, HeadingLevel};
-use std::cell::RefCell;
+use crate::tree::{
+    AcceptsPartial, BibliographyEntry, HeadingLevel, VariableMap, VariableScopes,
+};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::{mem, ptr};
 
@@ -62,11 +70,50 @@ pub struct Parser<'r, 't> {
     // Schema: Vec<List of elements in a footnote>
     footnotes: Rc<RefCell<Vec<Vec<Element<'t>>>>>,
 
+    // Bibliography
+    //
+    // Entries from [[bibliography]] blocks, and the labels cited with ((bibcite)).
+    bibliography: Rc<RefCell<PendingBibliography<'t>>>,
+
+    // Includes
+    //
+    // The includer used to fetch pages for [[include-elements]], if any,
+    // and the chain of pages being included to reach this one, starting with
+    // the page being rendered.
+    includer: Option<Rc<dyn ElementIncluder + 'r>>,
+    include_ancestors: Rc<IncludeAncestors<'static>>,
+
+    // Custom blocks
+    //
+    // Blocks registered by the embedder, which are available
+    // in addition to the built-in ones, if any.
+    custom_blocks: Option<&'r CustomBlocks>,
+
+    // Variables
+    //
+    // The variables passed by each [[include-elements]] this parser is within,
+    // used when evaluating expression blocks such as [[#if]].
+    variables: Rc<VariableScopes>,
+
+    // Lookahead
+    //
+    // The fewest remaining tokens this parser, or any of its clones,
+    // has stepped or looked ahead to. That is, how far into the input
+    // was examined, which is needed for incremental parsing.
+    fewest_remaining: Rc<Cell<usize>>,
+
+    // Pending warnings
+    //
+    // Warnings from sanitizing style attributes, which are added
+    // to the block they are in once it has been parsed successfully.
+    pending_warnings: Rc<RefCell<Vec<ParseWarning>>>,
+
     // Flags
     accepts_partial: AcceptsPartial,
     in_footnote: bool, // Whether we're currently inside [[footnote]] ... [[/footnote]].
     has_footnote_block: bool, // Whether a [[footnoteblock]] was created.
     start_of_line: bool,
+    record_spans: bool, // Whether to wrap elements in Element::Spanned.
 }
 
 impl<'r, 't> Parser<'r, 't> {
@@ -95,13 +142,77 @@ impl<'r, 't> Parser<'r, 't> {
             depth: 0,
             table_of_contents: make_shared_vec(),
             footnotes: make_shared_vec(),
+            bibliography: Rc::new(RefCell::new(PendingBibliography::default())),
+            includer: None,
+            include_ancestors: Rc::new(IncludeAncestors::new(page_info)),
+            custom_blocks: None,
+            variables: Rc::new(VariableScopes::new()),
+            fewest_remaining: Rc::new(Cell::new(remaining.len())),
+            pending_warnings: make_shared_vec(),
             accepts_partial: AcceptsPartial::None,
             in_footnote: false,
             has_footnote_block: false,
             start_of_line: true,
+            record_spans: settings.record_spans,
         }
     }
 
+    /// Creates a parser for the wikitext of a page included via `[[include-elements]]`.
+    ///
+    /// The new parser has its own state, but shares the settings, includer,
+    /// custom blocks, and recursion depth of this one. The included page is appended
+    /// to the list of include ancestors, and the variables passed to it
+    /// are added as a new scope.
+    ///
+    /// Source spans are not recorded, since they would refer to
+    /// the included page's wikitext rather than this one's.
+    pub fn new_included<'r2, 't2>(
+        &self,
+        tokenization: &'r2 Tokenization<'t2>,
+        page_ref: PageRef<'static>,
+        variables: &VariableMap,
+    ) -> Parser<'r2, 't2>
+    where
+        'r: 'r2,
+        't: 't2,
+    {
+        let mut include_ancestors = IncludeAncestors::clone(&self.include_ancestors);
+        include_ancestors.push(page_ref);
+
+        let mut variable_scopes = VariableScopes::clone(&self.variables);
+        variable_scopes.push_scope(variables);
+
+        let mut parser = Parser::new(tokenization, self.page_info, self.settings);
+        parser.depth = self.depth;
+        parser.includer = self.includer.clone();
+        parser.include_ancestors = Rc::new(include_ancestors);
+        parser.custom_blocks = self.custom_blocks;
+        parser.variables = Rc::new(variable_scopes);
+        parser.record_spans = false;
+        parser
+    }
+
+    /// Creates a parser starting at the given token, as if after a paragraph break.
+    ///
+    /// This is used by incremental parsing, which parses each
+    /// top-level block separately.
+    pub(crate) fn new_at(
+        tokenization: &'r Tokenization<'t>,
+        page_info: &'r PageInfo<'t>,
+        settings: &'r WikitextSettings,
+        index: usize,
+    ) -> Self {
+        let (current, remaining) = tokenization.tokens()[index..]
+            .split_first()
+            .expect("Token index for parser start is out of range");
+
+        let mut parser = Parser::new(tokenization, page_info, settings);
+        parser.current = current;
+        parser.remaining = remaining;
+        parser.fewest_remaining = Rc::new(Cell::new(remaining.len()));
+        parser
+    }
+
     // Getters
     #[inline]
     pub fn page_info(&self) -> &PageInfo<'t> {
@@ -138,23 +249,97 @@ impl<'r, 't> Parser<'r, 't> {
         self.has_footnote_block
     }
 
+    #[inline]
+    pub fn record_spans(&self) -> bool {
+        self.record_spans
+    }
+
     #[inline]
     pub fn start_of_line(&self) -> bool {
         self.start_of_line
     }
 
+    #[inline]
+    pub fn includer(&self) -> Option<&dyn ElementIncluder> {
+        self.includer.as_deref()
+    }
+
+    #[inline]
+    pub fn custom_blocks(&self) -> Option<&'r CustomBlocks> {
+        self.custom_blocks
+    }
+
+    /// The number of `[[include-elements]]` this parser is nested within.
+    #[inline]
+    pub fn include_depth(&self) -> usize {
+        self.include_ancestors.depth()
+    }
+
+    /// The variables in scope from any `[[include-elements]]` blocks.
+    #[inline]
+    pub fn variables(&self) -> &VariableScopes {
+        &self.variables
+    }
+
+    /// Gives a page reference the site of the included page this parser is within.
+    ///
+    /// See [`IncludeAncestors::qualify`].
+    pub fn qualify_page_ref<'p>(&self, page_ref: PageRef<'p>) -> PageRef<'p> {
+        match self.include_ancestors.parent() {
+            Some(parent) => self.include_ancestors.qualify(parent, page_ref),
+            None => page_ref,
+        }
+    }
+
+    /// Determines if including this page would cause an include cycle.
+    pub fn is_include_cycle(&self, page_ref: &PageRef) -> bool {
+        self.include_ancestors.is_cycle(page_ref)
+    }
+
     // Setters
     #[inline]
     pub fn set_rule(&mut self, rule: Rule) {
         self.rule = rule;
     }
 
+    #[inline]
+    pub fn set_includer(&mut self, includer: Rc<dyn ElementIncluder + 'r>) {
+        self.includer = Some(includer);
+    }
+
+    #[inline]
+    pub fn set_custom_blocks(&mut self, custom_blocks: &'r CustomBlocks) {
+        self.custom_blocks = Some(custom_blocks);
+    }
+
     pub fn clone_with_rule(&self, rule: Rule) -> Self {
         let mut clone = self.clone();
         clone.set_rule(rule);
         clone
     }
 
+    /// Runs the given parse without adding to the table of contents, footnotes, or bibliography.
+    ///
+    /// This is for wikitext which is parsed but then discarded, such as the
+    /// branch of an `[[#if]]` which is not taken. Afterwards, only the position
+    /// in the input is kept from the parser used.
+    pub fn parse_detached<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut Parser<'r, 't>) -> T,
+    {
+        let mut parser = self.clone();
+        parser.table_of_contents = make_shared_vec();
+        parser.footnotes = make_shared_vec();
+        parser.bibliography = Rc::new(RefCell::new(PendingBibliography::default()));
+        parser.pending_warnings = make_shared_vec();
+
+        let result = f(&mut parser);
+        self.current = parser.current;
+        self.remaining = parser.remaining;
+        self.start_of_line = parser.start_of_line;
+        result
+    }
+
     pub fn depth_increment(&mut self) -> Result<(), ParseWarning> {
         self.depth += 1;
         debug!("Incrementing recursion depth to {}", self.depth);
@@ -206,8 +391,11 @@ impl<'r, 't> Parser<'r, 't> {
         let level = usize::from(heading.value()) - 1;
 
         // Render name as text, so it lacks formatting
-        let name =
-            TextRender.render_partial(name_elements, self.page_info, self.settings);
+        let name = TextRender::default().render_partial(
+            name_elements,
+            self.page_info,
+            self.settings,
+        );
 
         self.table_of_contents.borrow_mut().push((level, name));
     }
@@ -227,6 +415,81 @@ impl<'r, 't> Parser<'r, 't> {
         mem::take(&mut self.footnotes.borrow_mut())
     }
 
+    // Bibliography
+    pub fn push_bibliography_entries(&mut self, entries: &[BibliographyEntry<'t>]) {
+        self.bibliography.borrow_mut().push_entries(entries);
+    }
+
+    pub fn push_bibliography_cite(&mut self, label: Cow<'t, str>) {
+        let warning = self.make_warn(ParseWarningKind::NoSuchBibliographyEntry);
+        self.bibliography.borrow_mut().push_cite(label, warning);
+    }
+
+    #[cold]
+    pub fn remove_bibliography(&mut self) -> PendingBibliography<'t> {
+        mem::take(&mut self.bibliography.borrow_mut())
+    }
+
+    // CSS
+
+    /// Sanitizes a stylesheet from this page, such as the body of a `[[css]]` block.
+    ///
+    /// The offset is where the stylesheet begins in the wikitext, so that
+    /// warnings for anything removed refer to its location there.
+    /// The sanitized stylesheet is returned as the last exception.
+    pub fn sanitize_stylesheet(
+        &self,
+        css: &'t str,
+        offset: usize,
+    ) -> Vec<ParseException<'t>> {
+        let SanitizedCss { css, issues } =
+            css::sanitize_stylesheet(css, &self.settings.css);
+
+        let mut exceptions: Vec<_> = issues
+            .into_iter()
+            .map(|CssIssue { kind, span }| {
+                let mut warning = self.make_warn(kind);
+                warning.set_span(offset + span.start..offset + span.end);
+                ParseException::Warning(warning)
+            })
+            .collect();
+
+        exceptions.push(ParseException::Style(css));
+        exceptions
+    }
+
+    /// Sanitizes the value of a `style` attribute.
+    ///
+    /// Warnings for anything removed are added to the
+    /// current block, once it has been parsed successfully.
+    pub fn sanitize_style(&self, style: Cow<'t, str>) -> Cow<'t, str> {
+        let SanitizedCss { css, issues } =
+            css::sanitize_style(&style, &self.settings.css);
+        let css = match css {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(css) => Some(css),
+        };
+
+        self.pending_warnings
+            .borrow_mut()
+            .extend(issues.into_iter().map(|issue| self.make_warn(issue.kind)));
+
+        match css {
+            Some(css) => Cow::Owned(css),
+            None => style,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn pending_warning_count(&self) -> usize {
+        self.pending_warnings.borrow().len()
+    }
+
+    /// Removes the pending warnings added since there were the given number.
+    pub(crate) fn take_pending_warnings(&self, since: usize) -> Vec<ParseWarning> {
+        self.pending_warnings.borrow_mut().drain(since..).collect()
+    }
+
     // Special for [[include]], appending a SyntaxTree
     pub fn append_toc_and_footnotes(
         &mut self,
@@ -240,6 +503,10 @@ impl<'r, 't> Parser<'r, 't> {
         self.footnotes.borrow_mut().append(footnotes);
     }
 
+    pub fn append_bibliography(&mut self, bibliography: &mut PendingBibliography<'t>) {
+        self.bibliography.borrow_mut().append(bibliography);
+    }
+
     // State evaluation
     pub fn evaluate(&self, condition: ParseCondition) -> bool {
         info!(
@@ -333,6 +600,19 @@ impl<'r, 't> Parser<'r, 't> {
         self.remaining
     }
 
+    /// Gets the fewest number of tokens remaining after any token examined so far.
+    #[inline]
+    pub fn fewest_remaining(&self) -> usize {
+        self.fewest_remaining.get()
+    }
+
+    #[inline]
+    fn mark_examined(&self, remaining: usize) {
+        if remaining < self.fewest_remaining.get() {
+            self.fewest_remaining.set(remaining);
+        }
+    }
+
     #[inline]
     pub fn update(&mut self, parser: &Parser<'r, 't>) {
         // Flags
@@ -367,6 +647,7 @@ impl<'r, 't> Parser<'r, 't> {
             Some((current, remaining)) => {
                 self.current = current;
                 self.remaining = remaining;
+                self.mark_examined(remaining.len());
                 Ok(current)
             }
             None => {
@@ -394,6 +675,7 @@ impl<'r, 't> Parser<'r, 't> {
     #[inline]
     pub fn look_ahead(&self, offset: usize) -> Option<&'r ExtractedToken<'t>> {
         debug!("Looking ahead to a token (offset {offset})");
+        self.mark_examined(self.remaining.len().saturating_sub(offset + 1));
         self.remaining.get(offset)
     }
 
@@ -0,0 +1,53 @@
+/*
+ * parsing/rule/impls/bibcite.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+
+pub const RULE_BIBLIOGRAPHY_CITE: Rule = Rule {
+    name: "bibliography-cite",
+    position: LineRequirement::Any,
+    try_consume_fn,
+};
+
+fn try_consume_fn<'p, 'r, 't>(
+    parser: &'p mut Parser<'r, 't>,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Consuming token as bibliography citation");
+
+    let ExtractedToken { slice, .. } = parser.current();
+
+    // The token is "((bibcite", then the label surrounded by spaces or tabs, then "))".
+    // Only those two characters are separators, as in the lexer grammar,
+    // so other whitespace is part of the label.
+    let label = match slice
+        .strip_prefix("((bibcite")
+        .and_then(|slice| slice.strip_suffix("))"))
+    {
+        Some(label) => label.trim_matches([' ', '\t']),
+        None => return Err(parser.make_warn(ParseWarningKind::RuleFailed)),
+    };
+
+    if label.is_empty() {
+        return Err(parser.make_warn(ParseWarningKind::RuleFailed));
+    }
+
+    parser.push_bibliography_cite(cow!(label));
+    ok!(Element::BibliographyCite { label: cow!(label) })
+}
@@ -19,7 +19,6 @@
  */
 
 use crate::parsing::{parse_boolean, ParseWarning, ParseWarningKind, Parser};
-use crate::settings::WikitextSettings;
 use crate::tree::AttributeMap;
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -109,11 +108,16 @@ impl<'t> Arguments<'t> {
     /// Similar to `to_hash_map()`, but creates an `AttributeMap` instead.
     ///
     /// Because all fields are passed from the user, this does ID isolation
-    /// if that is enabled, and so needs `WikitextSettings` to be passed in.
-    #[inline]
-    pub fn to_attribute_map(&self, settings: &WikitextSettings) -> AttributeMap<'t> {
+    /// if that is enabled, and sanitizes the `style` attribute,
+    /// and so needs the `Parser` to be passed in.
+    pub fn to_attribute_map(&self, parser: &Parser<'_, 't>) -> AttributeMap<'t> {
         let mut map = AttributeMap::from_arguments(&self.inner);
-        map.isolate_id(settings);
+        map.isolate_id(parser.settings());
+
+        if let Some(style) = map.remove("style") {
+            map.insert("style", parser.sanitize_style(style));
+        }
+
         map
     }
 }
@@ -0,0 +1,132 @@
+/*
+ * parsing/rule/impls/block/blocks/bibliography.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::render::text::TextRender;
+use crate::tree::{BibliographyEntry, DefinitionListItem};
+use std::borrow::Cow;
+
+pub const BLOCK_BIBLIOGRAPHY: BlockRule = BlockRule {
+    name: "block-bibliography",
+    accepts_names: &["bibliography"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn: parse_bibliography,
+};
+
+pub const BLOCK_BIBCITE: BlockRule = BlockRule {
+    name: "block-bibcite",
+    accepts_names: &["bibcite"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_bibcite,
+};
+
+fn parse_bibliography<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing bibliography block (in-head {in_head})");
+    assert!(!flag_star, "Bibliography doesn't allow star flag");
+    assert!(!flag_score, "Bibliography doesn't allow score flag");
+    assert_block_name(&BLOCK_BIBLIOGRAPHY, name);
+
+    let mut arguments = parser.get_head_map(&BLOCK_BIBLIOGRAPHY, in_head)?;
+    let title = arguments.get("title");
+
+    if !arguments.is_empty() {
+        warn!("Invalid argument keys found");
+        return Err(parser.make_warn(ParseWarningKind::BlockMalformedArguments));
+    }
+
+    // The entries are written as a definition list,
+    // with each key being the label for that citation.
+    let (elements, exceptions, _) =
+        parser.get_body_elements(&BLOCK_BIBLIOGRAPHY, false)?.into();
+
+    let mut entries: Vec<BibliographyEntry> = Vec::new();
+
+    for element in elements {
+        match element.unspanned() {
+            Element::DefinitionList(items) => {
+                for DefinitionListItem { key, value } in items {
+                    let label = TextRender::default().render_partial(
+                        key,
+                        parser.page_info(),
+                        parser.settings(),
+                    );
+                    let label = label.trim();
+
+                    if entries.iter().any(|entry| entry.label == label) {
+                        warn!("Duplicate bibliography label '{label}', ignoring");
+                        continue;
+                    }
+
+                    entries.push(BibliographyEntry {
+                        label: Cow::Owned(str!(label)),
+                        contents: value.clone(),
+                    });
+                }
+            }
+
+            // Ignore internal whitespace.
+            element if element.is_whitespace() => (),
+
+            // Return a warning for anything else.
+            _ => {
+                return Err(
+                    parser.make_warn(ParseWarningKind::BibliographyContainsNonEntry)
+                )
+            }
+        }
+    }
+
+    parser.push_bibliography_entries(&entries);
+
+    let element = Element::BibliographyBlock { title, entries };
+    ok!(false; element, exceptions)
+}
+
+fn parse_bibcite<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing bibcite block (in-head {in_head})");
+    assert!(!flag_star, "Bibcite doesn't allow star flag");
+    assert!(!flag_score, "Bibcite doesn't allow score flag");
+    assert_block_name(&BLOCK_BIBCITE, name);
+
+    let label =
+        parser.get_head_value(&BLOCK_BIBCITE, in_head, |parser, value| match value {
+            Some(label) => Ok(label.trim()),
+            None => Err(parser.make_warn(ParseWarningKind::BlockMissingArguments)),
+        })?;
+
+    parser.push_bibliography_cite(cow!(label));
+    ok!(Element::BibliographyCite { label: cow!(label) })
+}
@@ -51,7 +51,7 @@ fn parse_fn<'r, 't>(
     let element = Element::Container(Container::new(
         ContainerType::Blockquote,
         elements,
-        arguments.to_attribute_map(parser.settings()),
+        arguments.to_attribute_map(parser),
     ));
 
     ok!(element, exceptions)
@@ -50,7 +50,7 @@ fn parse_fn<'r, 't>(
     let element = Element::Container(Container::new(
         ContainerType::Bold,
         elements,
-        arguments.to_attribute_map(parser.settings()),
+        arguments.to_attribute_map(parser),
     ));
 
     ok!(paragraph_safe; element, exceptions)
@@ -43,7 +43,7 @@ fn parse_fn<'r, 't>(
 
     parser.get_head_none(&BLOCK_CSS, in_head)?;
 
-    let css = parser.get_body_text(&BLOCK_CSS)?;
-    let exceptions = vec![ParseException::Style(cow!(css))];
+    let (css, offset) = parser.get_body_text_with_offset(&BLOCK_CSS)?;
+    let exceptions = parser.sanitize_stylesheet(css, offset);
     ok!(Elements::None, exceptions)
 }
@@ -51,7 +51,7 @@ fn parse_fn<'r, 't>(
     let element = Element::Container(Container::new(
         ContainerType::Deletion,
         elements,
-        arguments.to_attribute_map(parser.settings()),
+        arguments.to_attribute_map(parser),
     ));
 
     ok!(paragraph_safe; element, exceptions)
@@ -0,0 +1,270 @@
+/*
+ * parsing/rule/impls/block/blocks/expression.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Block rules for Wikidot's expression blocks, in the style of ParserFunctions.
+//!
+//! * `[[#expr expression]]` shows the result of evaluating the expression.
+//! * `[[#if value | then | else]]` shows one of its branches depending on the value.
+//! * `[[#ifexpr expression | then | else]]` is the same, but evaluates the expression.
+//!
+//! Variables from any `[[include-elements]]` blocks are substituted into
+//! the value or expression before it is checked. The branches are parsed
+//! as regular wikitext, so variables within them are rendered as usual.
+
+use super::prelude::*;
+use crate::parsing::expression::{evaluate, format_number};
+use crate::parsing::{parse_boolean, strip_whitespace};
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+lazy_static! {
+    static ref VARIABLE_REGEX: Regex =
+        Regex::new(r"\{\$(?P<name>[a-zA-Z0-9_\-]+)\}").unwrap();
+}
+
+pub const BLOCK_EXPR: BlockRule = BlockRule {
+    name: "block-expr",
+    accepts_names: &["#expr"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_expr,
+};
+
+pub const BLOCK_IF: BlockRule = BlockRule {
+    name: "block-if",
+    accepts_names: &["#if"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_if,
+};
+
+pub const BLOCK_IFEXPR: BlockRule = BlockRule {
+    name: "block-ifexpr",
+    accepts_names: &["#ifexpr"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: false,
+    parse_fn: parse_ifexpr,
+};
+
+fn parse_expr<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing expr block (in-head {in_head})");
+    assert!(!flag_star, "Expr doesn't allow star flag");
+    assert!(!flag_score, "Expr doesn't allow score flag");
+    assert_block_name(&BLOCK_EXPR, name);
+
+    let mut exceptions = Vec::new();
+    let (expression, _) = get_value(parser, &BLOCK_EXPR, in_head, &mut exceptions)?;
+    let value = evaluate_expression(parser, &expression)?;
+    let element = Element::Text(Cow::Owned(format_number(value)));
+
+    ok!(element, exceptions)
+}
+
+fn parse_if<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing if block (in-head {in_head})");
+    assert!(!flag_star, "If doesn't allow star flag");
+    assert!(!flag_score, "If doesn't allow score flag");
+    assert_block_name(&BLOCK_IF, name);
+
+    let mut exceptions = Vec::new();
+    let (value, has_branches) = get_value(parser, &BLOCK_IF, in_head, &mut exceptions)?;
+
+    // Like Wikidot, a value is true unless it's empty or a false boolean
+    // such as "false" or "0".
+    let value = value.trim();
+    let condition = !value.is_empty() && !matches!(parse_boolean(value), Ok(false));
+
+    get_branches(parser, condition, has_branches, exceptions)
+}
+
+fn parse_ifexpr<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing ifexpr block (in-head {in_head})");
+    assert!(!flag_star, "Ifexpr doesn't allow star flag");
+    assert!(!flag_score, "Ifexpr doesn't allow score flag");
+    assert_block_name(&BLOCK_IFEXPR, name);
+
+    let mut exceptions = Vec::new();
+    let (expression, has_branches) =
+        get_value(parser, &BLOCK_IFEXPR, in_head, &mut exceptions)?;
+    let condition = evaluate_expression(parser, &expression)? != 0.0;
+
+    get_branches(parser, condition, has_branches, exceptions)
+}
+
+/// Gets the value or expression in the head of the block,
+/// with any variables in scope substituted.
+///
+/// Also returns whether there are branches following it,
+/// that is, whether the value ended with `|` rather than `]]`.
+fn get_value<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    block_rule: &BlockRule,
+    in_head: bool,
+    exceptions: &mut Vec<ParseException<'t>>,
+) -> Result<(Cow<'t, str>, bool), ParseWarning>
+where
+    'r: 't,
+{
+    if !in_head {
+        warn!("Block has no value or expression");
+        return Err(parser.make_warn(ParseWarningKind::BlockMissingArguments));
+    }
+
+    // Expressions don't have branches, so they may contain '|'
+    let close_conditions: &[ParseCondition] = if block_rule.name == BLOCK_EXPR.name {
+        &[ParseCondition::current(Token::RightBlock)]
+    } else {
+        &[
+            ParseCondition::current(Token::Pipe),
+            ParseCondition::current(Token::RightBlock),
+        ]
+    };
+
+    let (value, last) = collect_text_keep(
+        parser,
+        parser.rule(),
+        close_conditions,
+        &[ParseCondition::current(Token::ParagraphBreak)],
+        Some(ParseWarningKind::BlockMissingArguments),
+    )?;
+
+    let has_branches = last.token == Token::Pipe;
+    let value = substitute_variables(parser, value, exceptions);
+    Ok((value, has_branches))
+}
+
+/// Replaces each `{$variable}` with its value.
+///
+/// Variables which are not in scope are left as-is, with a warning.
+fn substitute_variables<'t>(
+    parser: &Parser<'_, 't>,
+    value: &'t str,
+    exceptions: &mut Vec<ParseException<'t>>,
+) -> Cow<'t, str> {
+    let mut missing = false;
+    let value = VARIABLE_REGEX.replace_all(value, |captures: &Captures| {
+        match parser.variables().get(&captures["name"]) {
+            Some(value) => str!(value),
+            None => {
+                missing = true;
+                str!(&captures[0])
+            }
+        }
+    });
+
+    if missing {
+        warn!("Variable in block value is not in scope, leaving as-is");
+        exceptions.push(ParseException::Warning(
+            parser.make_warn(ParseWarningKind::NoSuchVariable),
+        ));
+    }
+
+    value
+}
+
+fn evaluate_expression(parser: &Parser, expression: &str) -> Result<f64, ParseWarning> {
+    match evaluate(expression) {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            warn!("Unable to evaluate expression '{expression}': {error}");
+            Err(parser.make_warn(ParseWarningKind::InvalidExpression))
+        }
+    }
+}
+
+/// Parses the `| then | else` branches, keeping the one chosen by the condition.
+///
+/// Either branch may be omitted, in which case nothing is shown.
+/// The branch which is not taken is parsed detached, so that footnotes,
+/// headings, or citations within it are not added to the page.
+fn get_branches<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    condition: bool,
+    has_branches: bool,
+    mut exceptions: Vec<ParseException<'t>>,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    let index = if condition { 0 } else { 1 };
+    let mut branches = Vec::new();
+    let mut has_next = has_branches;
+
+    while has_next {
+        // The second branch is the last, so it may contain '|'
+        let close_conditions: &[ParseCondition] = if branches.is_empty() {
+            &[
+                ParseCondition::current(Token::Pipe),
+                ParseCondition::current(Token::RightBlock),
+            ]
+        } else {
+            &[ParseCondition::current(Token::RightBlock)]
+        };
+
+        let collect = |parser: &mut Parser<'r, 't>| {
+            collect_consume_keep(
+                parser,
+                parser.rule(),
+                close_conditions,
+                &[ParseCondition::current(Token::ParagraphBreak)],
+                None,
+            )
+        };
+
+        let result = if branches.len() == index {
+            collect(parser)
+        } else {
+            parser.parse_detached(collect)
+        };
+
+        let ((mut elements, last), branch_exceptions, paragraph_safe) = result?.into();
+
+        strip_whitespace(&mut elements);
+        branches.push((elements, branch_exceptions, paragraph_safe));
+        has_next = last.token == Token::Pipe;
+    }
+
+    let (elements, branch_exceptions, paragraph_safe) = if index < branches.len() {
+        branches.swap_remove(index)
+    } else {
+        (Vec::new(), Vec::new(), true)
+    };
+
+    exceptions.extend(branch_exceptions);
+    ok!(paragraph_safe; elements, exceptions)
+}
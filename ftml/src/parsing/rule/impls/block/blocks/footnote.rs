@@ -74,17 +74,18 @@ fn parse_footnote_ref<'r, 't>(
         parser.get_body_elements(&BLOCK_FOOTNOTE, true)?.into();
 
     if elements.len() == 1 {
-        match elements.pop().unwrap() {
+        let element = elements.pop().unwrap();
+
+        match element.unspanned() {
             // Unwrap the paragraph and get its contents.
             Element::Container(container)
                 if container.ctype() == ContainerType::Paragraph =>
             {
-                let mut new_elements: Vec<Element> = container.into();
-                elements.append(&mut new_elements);
+                elements.extend_from_slice(container.elements());
             }
 
             // Other element, keep as-is.
-            element => elements.push(element),
+            _ => elements.push(element),
         };
     }
 
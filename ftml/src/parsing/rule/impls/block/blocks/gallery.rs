@@ -0,0 +1,118 @@
+/*
+ * parsing/rule/impls/block/blocks/gallery.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::tree::{GalleryItem, GalleryOrder, GallerySize, ImageSource, LinkLocation};
+use regex::Regex;
+
+lazy_static! {
+    static ref GALLERY_ITEM_REGEX: Regex =
+        Regex::new(r#"^:\s*([^\s"]+)((?:\s+[\w\-]+\s*=\s*"[^"]*")*)\s*$"#).unwrap();
+    static ref GALLERY_ITEM_ARGUMENT_REGEX: Regex =
+        Regex::new(r#"([\w\-]+)\s*=\s*"([^"]*)""#).unwrap();
+}
+
+pub const BLOCK_GALLERY: BlockRule = BlockRule {
+    name: "block-gallery",
+    accepts_names: &["gallery"],
+    accepts_star: false,
+    accepts_score: false,
+    accepts_newlines: true,
+    parse_fn,
+};
+
+fn parse_fn<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing gallery block (in-head {in_head})");
+    assert!(!flag_star, "Gallery doesn't allow star flag");
+    assert!(!flag_score, "Gallery doesn't allow score flag");
+    assert_block_name(&BLOCK_GALLERY, name);
+
+    let mut arguments = parser.get_head_map(&BLOCK_GALLERY, in_head)?;
+    let size: GallerySize = arguments.get_value(parser, "size")?.unwrap_or_default();
+    let order: GalleryOrder = arguments.get_value(parser, "order")?.unwrap_or_default();
+    let viewer = arguments.get_bool(parser, "viewer")?.unwrap_or(true);
+
+    if !arguments.is_empty() {
+        warn!("Invalid argument keys found");
+        return Err(parser.make_warn(ParseWarningKind::BlockMalformedArguments));
+    }
+
+    // Each line of the body is one file, in the form
+    // `: source [link="..."] [title="..."]`.
+    let body = parser.get_body_text(&BLOCK_GALLERY)?;
+    let mut items = Vec::new();
+
+    for line in body.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match parse_item(line) {
+            Some(item) => items.push(item),
+            None => {
+                warn!("Invalid gallery item '{line}'");
+                return Err(parser.make_warn(ParseWarningKind::GalleryContainsNonItem));
+            }
+        }
+    }
+
+    order.sort(&mut items);
+
+    ok!(Element::Gallery {
+        items,
+        size,
+        viewer,
+    })
+}
+
+fn parse_item(line: &str) -> Option<GalleryItem<'_>> {
+    let captures = GALLERY_ITEM_REGEX.captures(line)?;
+    let source = ImageSource::parse(captures.get(1)?.as_str())?;
+    let arguments = captures.get(2).map_or("", |arguments| arguments.as_str());
+    let mut link = None;
+    let mut title = None;
+
+    for captures in GALLERY_ITEM_ARGUMENT_REGEX.captures_iter(arguments) {
+        let key = captures.get(1)?.as_str();
+        let value = captures.get(2)?.as_str();
+
+        if key.eq_ignore_ascii_case("link") {
+            link = Some(LinkLocation::parse(cow!(value)));
+        } else if key.eq_ignore_ascii_case("title") {
+            title = Some(cow!(value));
+        } else {
+            warn!("Unknown gallery item argument '{key}'");
+            return None;
+        }
+    }
+
+    Some(GalleryItem {
+        source,
+        link,
+        title,
+    })
+}
@@ -57,7 +57,7 @@ fn parse_fn<'r, 't>(
         source,
         link,
         alignment,
-        attributes: arguments.to_attribute_map(parser.settings()),
+        attributes: arguments.to_attribute_map(parser),
     };
 
     ok!(element)
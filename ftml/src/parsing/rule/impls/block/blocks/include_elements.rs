@@ -20,12 +20,17 @@
 
 use super::prelude::*;
 use crate::data::PageRef;
-use crate::parsing::UnstructuredParseResult;
+use crate::parsing::{run_parser, UnstructuredParseResult};
+use crate::tree::VariableMap;
+use std::borrow::Cow;
 
 /// Block rule for include (elements).
 ///
 /// This takes the resultant `SyntaxTree` from another page and
 /// inserts them into this page being built.
+///
+/// The page is fetched during parsing using the includer passed to
+/// [`parse_with_includer`](crate::parsing::parse_with_includer).
 pub const BLOCK_INCLUDE_ELEMENTS: BlockRule = BlockRule {
     name: "block-include-elements",
     accepts_names: &["include-elements"],
@@ -42,7 +47,7 @@ fn parse_fn<'r, 't>(
     flag_score: bool,
     in_head: bool,
 ) -> ParseResult<'r, 't, Elements<'t>> {
-    info!("Found invalid include-elements block");
+    info!("Found include-elements block");
     parser.check_page_syntax()?;
     assert!(!flag_star, "Include (elements) doesn't allow star flag");
     assert!(!flag_score, "Include (elements) doesn't allow score flag");
@@ -53,17 +58,19 @@ fn parse_fn<'r, 't>(
         parser.get_head_name_map(&BLOCK_INCLUDE_ELEMENTS, in_head)?;
 
     let page_ref = match PageRef::parse(page_name) {
-        Ok(page_ref) => page_ref,
+        Ok(page_ref) => parser.qualify_page_ref(page_ref),
         Err(_) => return Err(parser.make_warn(ParseWarningKind::BlockMalformedArguments)),
     };
 
     // Get page to be included
+    let variables = variables.to_hash_map();
     let UnstructuredParseResult {
         result,
         mut table_of_contents_depths,
         mut footnotes,
         has_footnote_block,
-    } = include_page(parser, &page_ref)?;
+        mut bibliography,
+    } = include_page(parser, &page_ref, &variables)?;
 
     if has_footnote_block {
         parser.set_footnote_block();
@@ -80,7 +87,12 @@ fn parse_fn<'r, 't>(
     // Update parser state, build, and return
     parser.append_toc_and_footnotes(&mut table_of_contents_depths, &mut footnotes);
 
-    let variables = variables.to_hash_map();
+    // Citations in the included page are checked along with this one's,
+    // but any warnings refer to this block instead.
+    bibliography
+        .replace_warnings(&parser.make_warn(ParseWarningKind::NoSuchBibliographyEntry));
+    parser.append_bibliography(&mut bibliography);
+
     let element = Element::Include {
         paragraph_safe,
         variables,
@@ -93,22 +105,89 @@ fn parse_fn<'r, 't>(
 
 fn include_page<'r, 't>(
     parser: &Parser<'r, 't>,
-    _page: &PageRef,
+    page_ref: &PageRef,
+    variables: &VariableMap,
 ) -> Result<UnstructuredParseResult<'r, 't>, ParseWarning> {
-    // TODO stubbed
+    let includer = match parser.includer() {
+        Some(includer) => includer,
+        None => {
+            warn!("No includer available, cannot include page {page_ref}");
+            return Err(parser.make_warn(ParseWarningKind::NoSuchPage));
+        }
+    };
 
-    if false {
-        return Err(parser.make_warn(ParseWarningKind::NoSuchPage));
+    if parser.include_depth() >= parser.settings().max_include_depth as usize {
+        warn!("Maximum include depth reached, not including page {page_ref}");
+        return Err(parser.make_warn(ParseWarningKind::IncludeDepthExceeded));
     }
 
+    // Fetch the page's wikitext
+    let page_ref = page_ref.to_owned();
+    let wikitext = if parser.is_include_cycle(&page_ref) {
+        warn!("Include cycle detected for page {page_ref}");
+        includer.include_cycle(&page_ref)
+    } else {
+        includer.fetch_page(&page_ref)
+    };
+
+    let mut wikitext = match wikitext {
+        Some(wikitext) => wikitext,
+        None => {
+            warn!("Includer failed, cannot include page {page_ref}");
+            return Err(parser.make_warn(ParseWarningKind::IncludeFailed));
+        }
+    };
+
+    // Parse the page separately, so that markup within it
+    // cannot affect the page doing the including.
+    crate::preprocess(&mut wikitext);
+    let tokenization = crate::tokenize(&wikitext);
+    let UnstructuredParseResult {
+        result,
+        table_of_contents_depths,
+        footnotes,
+        has_footnote_block,
+        bibliography,
+    } = run_parser(parser.new_included(&tokenization, page_ref, variables));
+
+    // Since the parsed page borrows from the fetched wikitext,
+    // its elements must be made owned.
+    //
+    // Warnings within the included page refer to its own source,
+    // so only styles are kept.
+    let result = match result {
+        Ok(ParseSuccess {
+            item: elements,
+            exceptions,
+            paragraph_safe,
+            ..
+        }) => {
+            let elements = elements.iter().map(Element::to_owned).collect();
+            let exceptions = exceptions
+                .into_iter()
+                .filter_map(|exception| match exception {
+                    ParseException::Style(style) => {
+                        Some(ParseException::Style(Cow::Owned(style.into_owned())))
+                    }
+                    ParseException::Warning(_) => None,
+                })
+                .collect();
+
+            Ok(ParseSuccess::new(elements, exceptions, paragraph_safe))
+        }
+        Err(warning) => Err(parser.make_warn(warning.kind())),
+    };
+
+    let footnotes = footnotes
+        .iter()
+        .map(|footnote| footnote.iter().map(Element::to_owned).collect())
+        .collect();
+
     Ok(UnstructuredParseResult {
-        result: Ok(ParseSuccess::new(
-            vec![text!("<INCLUDED PAGE (ELEMENTS)>")],
-            vec![],
-            false,
-        )),
-        table_of_contents_depths: vec![],
-        footnotes: vec![],
-        has_footnote_block: false,
+        result,
+        table_of_contents_depths,
+        footnotes,
+        has_footnote_block,
+        bibliography: bibliography.to_owned(),
     })
 }
@@ -115,7 +115,7 @@ fn parse_list_block<'r, 't>(
 
     // Get attributes
     let arguments = parser.get_head_map(block_rule, in_head)?;
-    let attributes = arguments.to_attribute_map(parser.settings());
+    let attributes = arguments.to_attribute_map(parser);
 
     // Get body and convert into list form.
     let (mut elements, exceptions, _) =
@@ -143,17 +143,8 @@ fn parse_list_block<'r, 't>(
                 }
 
                 // Or sub-lists.
-                Element::List {
-                    ltype,
-                    attributes,
-                    items: sub_items,
-                } => {
-                    let element = Box::new(Element::List {
-                        ltype,
-                        attributes,
-                        items: sub_items,
-                    });
-
+                element if matches!(element.unspanned(), Element::List { .. }) => {
+                    let element = Box::new(element);
                     items.push(ListItem::SubList { element });
                 }
 
@@ -199,7 +190,7 @@ fn parse_list_item<'r, 't>(
 
     // Get attributes
     let arguments = parser.get_head_map(&BLOCK_LI, in_head)?;
-    let attributes = arguments.to_attribute_map(parser.settings());
+    let attributes = arguments.to_attribute_map(parser);
 
     // Get body elements
     let (mut elements, exceptions, _) =
@@ -62,6 +62,7 @@ mod align_justify;
 mod align_left;
 mod align_right;
 mod anchor;
+mod bibliography;
 mod blockquote;
 mod bold;
 mod char;
@@ -74,7 +75,9 @@ mod del;
 mod div;
 mod embed;
 mod equation_ref;
+mod expression;
 mod footnote;
+mod gallery;
 mod hidden;
 mod html;
 mod ifcategory;
@@ -113,6 +116,7 @@ pub use self::align_justify::BLOCK_ALIGN_JUSTIFY;
 pub use self::align_left::BLOCK_ALIGN_LEFT;
 pub use self::align_right::BLOCK_ALIGN_RIGHT;
 pub use self::anchor::BLOCK_ANCHOR;
+pub use self::bibliography::{BLOCK_BIBCITE, BLOCK_BIBLIOGRAPHY};
 pub use self::blockquote::BLOCK_BLOCKQUOTE;
 pub use self::bold::BLOCK_BOLD;
 pub use self::char::BLOCK_CHAR;
@@ -125,7 +129,9 @@ pub use self::del::BLOCK_DEL;
 pub use self::div::BLOCK_DIV;
 pub use self::embed::BLOCK_EMBED;
 pub use self::equation_ref::BLOCK_EQUATION_REF;
+pub use self::expression::{BLOCK_EXPR, BLOCK_IF, BLOCK_IFEXPR};
 pub use self::footnote::{BLOCK_FOOTNOTE, BLOCK_FOOTNOTE_BLOCK};
+pub use self::gallery::BLOCK_GALLERY;
 pub use self::hidden::BLOCK_HIDDEN;
 pub use self::html::BLOCK_HTML;
 pub use self::ifcategory::BLOCK_IFCATEGORY;
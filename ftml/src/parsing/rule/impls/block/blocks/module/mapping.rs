@@ -22,11 +22,12 @@ use super::{modules::*, ModuleRule};
 use std::collections::HashMap;
 use unicase::UniCase;
 
-pub const MODULE_RULES: [ModuleRule; 6] = [
+pub const MODULE_RULES: [ModuleRule; 7] = [
     MODULE_BACKLINKS,
     MODULE_CATEGORIES,
     MODULE_CSS,
     MODULE_JOIN,
+    MODULE_LIST_PAGES,
     MODULE_PAGE_TREE,
     MODULE_RATE,
 ];
@@ -76,3 +77,38 @@ fn build_module_rule_map(module_rules: &'static [ModuleRule]) -> ModuleRuleMap {
 fn module_rule_map() {
     let _ = &*MODULE_RULE_MAP;
 }
+
+#[test]
+fn module_rules_match_info() {
+    use crate::info::{find_module, modules};
+    use std::collections::BTreeSet;
+
+    for module_rule in &MODULE_RULES {
+        let module = find_module(module_rule.accepts_names[0]).unwrap_or_else(|| {
+            panic!("No module information for rule '{}'", module_rule.name)
+        });
+
+        let rule_names: BTreeSet<_> = module_rule
+            .accepts_names
+            .iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        let info_names: BTreeSet<_> = module
+            .names()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        assert_eq!(
+            rule_names, info_names,
+            "Names for rule '{}' do not match module '{}'",
+            module_rule.name, module.name,
+        );
+    }
+
+    assert_eq!(
+        MODULE_RULES.len(),
+        modules().len(),
+        "Module information has extra entries",
+    );
+}
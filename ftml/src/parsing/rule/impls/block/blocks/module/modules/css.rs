@@ -34,7 +34,7 @@ fn parse_fn<'r, 't>(
     info!("Parsing categories module");
     assert_module_name(&MODULE_CSS, name);
 
-    let css = parser.get_body_text(&BLOCK_MODULE)?;
-    let exceptions = vec![ParseException::Style(cow!(css))];
+    let (css, offset) = parser.get_body_text_with_offset(&BLOCK_MODULE)?;
+    let exceptions = parser.sanitize_stylesheet(css, offset);
     ok!(true; None, exceptions)
 }
@@ -35,7 +35,7 @@ fn parse_fn<'r, 't>(
     assert_module_name(&MODULE_JOIN, name);
 
     let button_text = arguments.get("button");
-    let attributes = arguments.to_attribute_map(parser.settings());
+    let attributes = arguments.to_attribute_map(parser);
 
     ok!(false; Some(Module::Join {
         button_text,
@@ -0,0 +1,171 @@
+/*
+ * parsing/rule/impls/block/blocks/module/modules/list_pages.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::settings::WikitextMode;
+use crate::tree::{ListPagesQuery, LIST_PAGES_MAX_PER_PAGE};
+use std::borrow::Cow;
+
+/// The template used if the module's body is empty.
+const DEFAULT_TEMPLATE: &str = "* %%title_linked%%";
+
+pub const MODULE_LIST_PAGES: ModuleRule = ModuleRule {
+    name: "module-list-pages",
+    accepts_names: &["ListPages"],
+    parse_fn,
+};
+
+fn parse_fn<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    mut arguments: Arguments<'t>,
+) -> ParseResult<'r, 't, Option<Module<'t>>> {
+    info!("Parsing ListPages module");
+    assert_module_name(&MODULE_LIST_PAGES, name);
+
+    // Pages listed by this module are themselves rendered in list mode,
+    // so we don't permit nesting to avoid unbounded recursion.
+    if parser.settings().mode == WikitextMode::List {
+        warn!("Attempted to use ListPages module while in list mode");
+        return Err(parser.make_warn(ParseWarningKind::NotSupportedMode));
+    }
+
+    let mut query = ListPagesQuery::default();
+
+    if let Some(value) = arguments.get("category") {
+        parse_categories(parser, value, &mut query);
+    }
+
+    if let Some(value) = arguments.get("tags") {
+        parse_tags(parser, value, &mut query);
+    }
+
+    query.created_by = arguments.get("created_by");
+    query.rating = arguments.get_value(parser, "rating")?;
+
+    if let Some(order) = arguments.get_value(parser, "order")? {
+        query.order = order;
+    }
+
+    if let Some(offset) = arguments.get_value(parser, "offset")? {
+        query.offset = offset;
+    }
+
+    query.limit = arguments.get_value(parser, "limit")?;
+
+    if let Some(per_page) = arguments.get_value(parser, "perPage")? {
+        query.per_page = per_page;
+    }
+
+    if query.per_page.get() > LIST_PAGES_MAX_PER_PAGE {
+        warn!(
+            "ListPages perPage value too large ({} > {})",
+            query.per_page, LIST_PAGES_MAX_PER_PAGE,
+        );
+
+        return Err(parser.make_warn(ParseWarningKind::BlockMalformedArguments));
+    }
+
+    if let Some(separate) = arguments.get_bool(parser, "separate")? {
+        query.separate = separate;
+    }
+
+    // Get template, using the default if there's none
+    let body = parser.get_body_text(&BLOCK_MODULE)?;
+    let template = if body.trim().is_empty() {
+        cow!(DEFAULT_TEMPLATE)
+    } else {
+        cow!(body)
+    };
+
+    ok!(false; Some(Module::ListPages { query, template }))
+}
+
+/// Parses the `category` argument.
+///
+/// This is a list of category names, where `*` means all categories,
+/// `.` means the current page's category, and a leading `-` excludes
+/// that category.
+fn parse_categories<'t>(
+    parser: &Parser<'_, 't>,
+    value: Cow<'t, str>,
+    query: &mut ListPagesQuery<'t>,
+) {
+    let mut categories = Vec::new();
+    let mut all = false;
+
+    for category in split_list(&value) {
+        match category {
+            "*" => all = true,
+            "." => categories.push(current_category(parser)),
+            _ => match category.strip_prefix('-') {
+                Some(".") => query.excluded_categories.push(current_category(parser)),
+                Some(category) => query.excluded_categories.push(owned(category)),
+                None => categories.push(owned(category)),
+            },
+        }
+    }
+
+    if !all && !categories.is_empty() {
+        query.categories = Some(categories);
+    }
+}
+
+/// Parses the `tags` argument.
+///
+/// This is a list of tags, where a leading `+` means the tag is required,
+/// and a leading `-` means it is forbidden. Otherwise pages must have
+/// at least one of the listed tags. The special value `=` means the
+/// tags of the current page.
+fn parse_tags<'t>(
+    parser: &Parser<'_, 't>,
+    value: Cow<'t, str>,
+    query: &mut ListPagesQuery<'t>,
+) {
+    for tag in split_list(&value) {
+        if tag == "=" {
+            query.tags.extend(parser.page_info().tags.iter().cloned());
+        } else if let Some(tag) = tag.strip_prefix('+') {
+            query.required_tags.push(owned(tag));
+        } else if let Some(tag) = tag.strip_prefix('-') {
+            query.excluded_tags.push(owned(tag));
+        } else {
+            query.tags.push(owned(tag));
+        }
+    }
+}
+
+fn current_category<'t>(parser: &Parser<'_, 't>) -> Cow<'t, str> {
+    match parser.page_info().category {
+        Some(ref category) => category.clone(),
+        None => cow!("_default"),
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+}
+
+#[inline]
+fn owned(value: &str) -> Cow<'static, str> {
+    Cow::Owned(str!(value))
+}
@@ -44,13 +44,13 @@ fn parse_fn<'r, 't>(
 
     // Gather paragraphs
     let arguments = parser.get_head_map(&BLOCK_PARAGRAPH, in_head)?;
-    let attributes = arguments.to_attribute_map(parser.settings());
+    let attributes = arguments.to_attribute_map(parser);
     let (mut elements, exceptions, _) =
         parser.get_body_elements(&BLOCK_PARAGRAPH, true)?.into();
 
     // Apply attributes to each paragraph
     for element in &mut elements {
-        if let Element::Container(ref mut container) = element {
+        if let Element::Container(ref mut container) = element.unspanned_mut() {
             if container.ctype() == ContainerType::Paragraph {
                 container.attributes_mut().clone_from(&attributes);
             }
@@ -48,7 +48,7 @@ fn parse_fn<'r, 't>(
     let element = Element::RadioButton {
         name: cow!(name),
         checked: flag_star,
-        attributes: arguments.to_attribute_map(parser.settings()),
+        attributes: arguments.to_attribute_map(parser),
     };
 
     ok!(element)
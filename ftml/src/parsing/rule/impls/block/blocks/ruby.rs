@@ -113,7 +113,7 @@ fn parse_block<'r, 't>(
     let element = Element::Container(Container::new(
         ContainerType::Ruby,
         elements,
-        arguments.to_attribute_map(parser.settings()),
+        arguments.to_attribute_map(parser),
     ));
 
     ok!(paragraph_safe; element, exceptions)
@@ -143,7 +143,7 @@ fn parse_text<'r, 't>(
 
     let element = Element::Partial(PartialElement::RubyText(RubyText {
         elements,
-        attributes: arguments.to_attribute_map(parser.settings()),
+        attributes: arguments.to_attribute_map(parser),
     }));
 
     ok!(paragraph_safe; element, exceptions)
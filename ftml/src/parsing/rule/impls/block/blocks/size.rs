@@ -55,7 +55,7 @@ fn parse_fn<'r, 't>(
 
     let attributes = {
         let mut map = AttributeMap::new();
-        map.insert("style", Cow::Owned(size));
+        map.insert("style", parser.sanitize_style(Cow::Owned(size)));
         map
     };
 
@@ -44,7 +44,7 @@ fn parse_fn<'r, 't>(
     assert_block_name(&BLOCK_TABLE_OF_CONTENTS, name);
 
     let arguments = parser.get_head_map(&BLOCK_TABLE_OF_CONTENTS, in_head)?;
-    let attributes = arguments.to_attribute_map(parser.settings());
+    let attributes = arguments.to_attribute_map(parser);
     let align = FloatAlignment::parse(name).map(|float| float.align);
     let element = Element::TableOfContents { align, attributes };
     ok!(false; element)
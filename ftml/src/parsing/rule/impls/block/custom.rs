@@ -0,0 +1,342 @@
+/*
+ * parsing/rule/impls/block/custom.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Blocks defined by the embedder at runtime, rather than built into ftml.
+//!
+//! Each [`CustomBlock`] describes what its head and body contain,
+//! which are parsed the same way as for built-in blocks, and
+//! has a function which produces elements from them. These are
+//! collected in [`CustomBlocks`], which is passed to the parser.
+//!
+//! [`CustomBlocks`]: crate::parsing::CustomBlocks
+
+use super::mapping::get_block_rule_with_name;
+use super::{Arguments, BlockRule};
+use crate::info::{BlockBody, BlockHead};
+use crate::parsing::parser::Parser;
+use crate::parsing::{ParseResult, ParseWarningKind};
+use crate::tree::{AttributeMap, Element, Elements};
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use unicase::UniCase;
+
+/// Function type to produce the elements for a custom block.
+///
+/// If this returns a warning, the block fails to parse, like a built-in
+/// block would with invalid arguments. Usually this is
+/// [`ParseWarningKind::BlockMalformedArguments`].
+pub type CustomBlockFn =
+    dyn for<'t> Fn(CustomBlockInput<'t>) -> Result<Elements<'t>, ParseWarningKind>;
+
+/// Define a block which can be used in addition to those built into ftml.
+pub struct CustomBlock {
+    /// The block rule used to parse this block.
+    ///
+    /// All custom blocks share the rule name `block-custom`,
+    /// since rule names must be static.
+    rule: BlockRule,
+
+    /// What the head of the block takes.
+    head: BlockHead,
+
+    /// What the body of the block contains.
+    body: BlockBody,
+
+    /// Function which produces the elements for this block.
+    build_fn: Box<CustomBlockFn>,
+}
+
+impl CustomBlock {
+    /// Creates a custom block, which can be invoked by any of the given names.
+    ///
+    /// The block accepts newlines separating its head and tail from its body,
+    /// like `[[div]]` does, but not the score flag. See [`accepts_newlines`]
+    /// and [`accepts_score`] to change this.
+    ///
+    /// # Panics
+    /// If there are no names, or the body is [`BlockBody::Other`], since
+    /// that describes special handling which custom blocks cannot have.
+    ///
+    /// [`accepts_newlines`]: CustomBlock::accepts_newlines
+    /// [`accepts_score`]: CustomBlock::accepts_score
+    pub fn new<F>(
+        names: &'static [&'static str],
+        head: BlockHead,
+        body: BlockBody,
+        build_fn: F,
+    ) -> Self
+    where
+        F: for<'t> Fn(CustomBlockInput<'t>) -> Result<Elements<'t>, ParseWarningKind>
+            + 'static,
+    {
+        assert!(!names.is_empty(), "Custom block has no names");
+        assert_ne!(
+            body,
+            BlockBody::Other,
+            "Custom block cannot have a special body",
+        );
+
+        CustomBlock {
+            rule: BlockRule {
+                name: "block-custom",
+                accepts_names: names,
+                accepts_star: false,
+                accepts_score: false,
+                accepts_newlines: true,
+                parse_fn,
+            },
+            head,
+            body,
+            build_fn: Box::new(build_fn),
+        }
+    }
+
+    /// Sets whether this block accepts the score flag (`_`), as in `[[div_]]`.
+    ///
+    /// If the body contains elements, then it is not wrapped in
+    /// paragraphs when this flag is given. The build function
+    /// receives whether it was given, to change its output otherwise.
+    #[must_use]
+    pub fn accepts_score(mut self, value: bool) -> Self {
+        self.rule.accepts_score = value;
+        self
+    }
+
+    /// Sets whether the head and tail of this block can be separated from its body by newlines.
+    ///
+    /// If so, these newlines are not line breaks, and if the body contains
+    /// elements it is wrapped in paragraphs. This should be disabled for inline
+    /// blocks, such as those producing a `<span>`.
+    #[must_use]
+    pub fn accepts_newlines(mut self, value: bool) -> Self {
+        self.rule.accepts_newlines = value;
+        self
+    }
+
+    #[inline]
+    pub fn names(&self) -> &'static [&'static str] {
+        self.rule.accepts_names
+    }
+
+    #[inline]
+    pub fn head(&self) -> BlockHead {
+        self.head
+    }
+
+    #[inline]
+    pub fn body(&self) -> BlockBody {
+        self.body
+    }
+}
+
+impl Debug for CustomBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomBlock")
+            .field("names", &self.rule.accepts_names)
+            .field("accepts_score", &self.rule.accepts_score)
+            .field("accepts_newlines", &self.rule.accepts_newlines)
+            .field("head", &self.head)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An invocation of a custom block, with its head and body parsed.
+#[derive(Debug)]
+pub struct CustomBlockInput<'t> {
+    /// The name the block was invoked with, without any score flag.
+    pub name: &'t str,
+
+    /// Whether the block was invoked with the score flag (`_`).
+    pub flag_score: bool,
+
+    /// The value in the head, if it takes one.
+    pub value: Option<&'t str>,
+
+    /// The arguments in the head, if it takes a map.
+    pub arguments: Arguments<'t>,
+
+    /// The safe HTML attributes in the arguments, such as `class` or `style`.
+    ///
+    /// These are processed as they are for built-in blocks,
+    /// for instance prefixing IDs if enabled in the settings.
+    pub attributes: AttributeMap<'t>,
+
+    /// The contents of the body.
+    pub body: CustomBlockBody<'t>,
+}
+
+/// The contents of a custom block's body, per its [`BlockBody`].
+#[derive(Debug)]
+pub enum CustomBlockBody<'t> {
+    None,
+    Raw(&'t str),
+    Elements(Vec<Element<'t>>),
+}
+
+/// A collection of custom blocks to use when parsing.
+#[derive(Debug, Default)]
+pub struct CustomBlocks {
+    blocks: Vec<CustomBlock>,
+    names: HashMap<UniCase<&'static str>, usize>,
+}
+
+impl CustomBlocks {
+    #[inline]
+    pub fn new() -> Self {
+        CustomBlocks::default()
+    }
+
+    /// Adds a custom block to this collection.
+    ///
+    /// # Panics
+    /// If any of the block's names are already used, either
+    /// by a built-in block or one which was added previously.
+    pub fn register(&mut self, block: CustomBlock) {
+        let index = self.blocks.len();
+
+        for &name in block.names() {
+            assert!(
+                get_block_rule_with_name(name).is_none(),
+                "Custom block name '{name}' is used by a built-in block",
+            );
+
+            let previous = self.names.insert(UniCase::ascii(name), index);
+            assert!(
+                previous.is_none(),
+                "Custom block name '{name}' is used by another custom block",
+            );
+        }
+
+        self.blocks.push(block);
+    }
+
+    /// Gets the custom block with the given name.
+    ///
+    /// Like built-in blocks, this is case-insensitive
+    /// and ignores the score flag.
+    pub fn get(&self, name: &str) -> Option<&CustomBlock> {
+        let name = name.strip_suffix('_').unwrap_or(name); // score flag
+        let name = UniCase::ascii(name); // case-insensitive
+
+        self.names.get(&name).map(|&index| &self.blocks[index])
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &CustomBlock> {
+        self.blocks.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// Gets the custom block rule with the given name, if the parser has one.
+pub fn get_custom_block_rule<'r>(
+    parser: &Parser<'r, '_>,
+    name: &str,
+) -> Option<&'r BlockRule> {
+    parser
+        .custom_blocks()
+        .and_then(|blocks| blocks.get(name))
+        .map(|block| &block.rule)
+}
+
+fn parse_fn<'r, 't>(
+    parser: &mut Parser<'r, 't>,
+    name: &'t str,
+    flag_star: bool,
+    flag_score: bool,
+    in_head: bool,
+) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Parsing custom block (name '{name}', in-head {in_head}, score {flag_score})");
+    assert!(!flag_star, "Custom blocks don't allow star flag");
+
+    let block = parser
+        .custom_blocks()
+        .and_then(|blocks| blocks.get(name))
+        .expect("Parsing custom block which is not registered");
+
+    let rule = &block.rule;
+
+    // Parse the head, per the block's definition
+    let (value, arguments) = match block.head {
+        BlockHead::None => {
+            parser.get_head_none(rule, in_head)?;
+            (None, Arguments::new())
+        }
+        BlockHead::Value => {
+            let value =
+                parser.get_head_value(rule, in_head, |parser, value| match value {
+                    Some(value) => Ok(value.trim()),
+                    None => {
+                        Err(parser.make_warn(ParseWarningKind::BlockMissingArguments))
+                    }
+                })?;
+
+            (Some(value), Arguments::new())
+        }
+        BlockHead::Map => (None, parser.get_head_map(rule, in_head)?),
+        BlockHead::ValueMap => {
+            let (value, arguments) = parser.get_head_name_map(rule, in_head)?;
+            (Some(value), arguments)
+        }
+    };
+
+    let attributes = arguments.to_attribute_map(parser);
+
+    // Parse the body, likewise
+    let (body, exceptions) = match block.body {
+        BlockBody::None => (CustomBlockBody::None, Vec::new()),
+        BlockBody::Raw => {
+            let text = parser.get_body_text(rule)?;
+            (CustomBlockBody::Raw(text), Vec::new())
+        }
+        BlockBody::Elements => {
+            let wrap_paragraphs = rule.accepts_newlines && !flag_score;
+            let (elements, exceptions, _) =
+                parser.get_body_elements(rule, wrap_paragraphs)?.into();
+
+            (CustomBlockBody::Elements(elements), exceptions)
+        }
+        BlockBody::Other => unreachable!("Custom block has special body"),
+    };
+
+    let input = CustomBlockInput {
+        name,
+        flag_score,
+        value,
+        arguments,
+        attributes,
+        body,
+    };
+
+    match (block.build_fn)(input) {
+        Ok(elements) => ok!(elements, exceptions),
+        Err(kind) => Err(parser.make_warn(kind)),
+    }
+}
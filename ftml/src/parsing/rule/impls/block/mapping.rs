@@ -22,12 +22,14 @@ use super::{blocks::*, BlockRule};
 use std::collections::HashMap;
 use unicase::UniCase;
 
-pub const BLOCK_RULES: [BlockRule; 59] = [
+pub const BLOCK_RULES: [BlockRule; 65] = [
     BLOCK_ALIGN_CENTER,
     BLOCK_ALIGN_JUSTIFY,
     BLOCK_ALIGN_LEFT,
     BLOCK_ALIGN_RIGHT,
     BLOCK_ANCHOR,
+    BLOCK_BIBCITE,
+    BLOCK_BIBLIOGRAPHY,
     BLOCK_BLOCKQUOTE,
     BLOCK_BOLD,
     BLOCK_CHAR,
@@ -40,11 +42,15 @@ pub const BLOCK_RULES: [BlockRule; 59] = [
     BLOCK_DIV,
     BLOCK_EMBED,
     BLOCK_EQUATION_REF,
+    BLOCK_EXPR,
     BLOCK_FOOTNOTE,
     BLOCK_FOOTNOTE_BLOCK,
+    BLOCK_GALLERY,
     BLOCK_HIDDEN,
     BLOCK_HTML,
+    BLOCK_IF,
     BLOCK_IFCATEGORY,
+    BLOCK_IFEXPR,
     BLOCK_IFRAME,
     BLOCK_IFTAGS,
     BLOCK_IMAGE,
@@ -130,3 +136,59 @@ fn build_block_rule_map(block_rules: &'static [BlockRule]) -> BlockRuleMap {
 fn block_rule_map() {
     let _ = &*BLOCK_RULE_MAP;
 }
+
+#[test]
+fn block_rules_match_info() {
+    use crate::info::{blocks, find_block};
+    use std::collections::BTreeSet;
+
+    // These rules are not documented as blocks:
+    // [[later]] is unimplemented, and the alignment blocks are written as [[<]], etc.
+    let block_rules = BLOCK_RULES.iter().filter(|block_rule| {
+        block_rule.name != "block-later" && !block_rule.name.starts_with("block-align-")
+    });
+
+    let mut count = 0;
+    for block_rule in block_rules {
+        let block = find_block(block_rule.accepts_names[0]).unwrap_or_else(|| {
+            panic!("No block information for rule '{}'", block_rule.name)
+        });
+
+        let rule_names: BTreeSet<_> = block_rule
+            .accepts_names
+            .iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        let info_names: BTreeSet<_> = block
+            .names()
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+
+        assert_eq!(
+            rule_names, info_names,
+            "Names for rule '{}' do not match block '{}'",
+            block_rule.name, block.name,
+        );
+
+        assert_eq!(
+            (
+                block_rule.accepts_star,
+                block_rule.accepts_score,
+                block_rule.accepts_newlines,
+            ),
+            (
+                block.accepts_star,
+                block.accepts_score,
+                block.accepts_newlines
+            ),
+            "Flags for rule '{}' do not match block '{}'",
+            block_rule.name,
+            block.name,
+        );
+
+        count += 1;
+    }
+
+    assert_eq!(count, blocks().len(), "Block information has extra entries");
+}
@@ -31,6 +31,7 @@ use crate::tree::Elements;
 use std::fmt::{self, Debug};
 
 mod arguments;
+mod custom;
 mod mapping;
 mod parser;
 mod rule;
@@ -38,7 +39,13 @@ mod rule;
 pub mod blocks;
 
 pub use self::arguments::Arguments;
-pub use self::rule::{RULE_BLOCK, RULE_BLOCK_SKIP_NEWLINE, RULE_BLOCK_STAR};
+pub use self::custom::{
+    CustomBlock, CustomBlockBody, CustomBlockFn, CustomBlockInput, CustomBlocks,
+};
+pub use self::rule::{
+    is_hash_block_name, RULE_BLOCK, RULE_BLOCK_HASH, RULE_BLOCK_SKIP_NEWLINE,
+    RULE_BLOCK_STAR,
+};
 
 /// Define a rule for how to parse a block.
 #[derive(Clone)]
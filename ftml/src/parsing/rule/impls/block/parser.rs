@@ -56,6 +56,40 @@ where
         self.get_block_name_internal(ParseWarningKind::BlockMissingName)
     }
 
+    /// Like `get_block_name()`, but for blocks beginning with `[[#`, such as `[[#if]]`.
+    ///
+    /// The name returned includes the leading `#`. Unlike other blocks,
+    /// there cannot be a space before the name, since `[[# name]]` is an anchor.
+    pub fn get_hash_block_name(&mut self) -> Result<(&'t str, bool), ParseWarning> {
+        info!("Looking for identifier after '[[#'");
+
+        let start = self.current();
+        self.get_token(Token::LeftBlockAnchor, ParseWarningKind::RuleFailed)?;
+
+        if self.current().token == Token::Whitespace {
+            return Err(self.make_warn(ParseWarningKind::BlockMissingName));
+        }
+
+        let (_, last) = collect_text_keep(
+            self,
+            self.rule(),
+            &[
+                ParseCondition::current(Token::Whitespace),
+                ParseCondition::current(Token::LineBreak),
+                ParseCondition::current(Token::ParagraphBreak),
+                ParseCondition::current(Token::RightBlock),
+            ],
+            &[],
+            Some(ParseWarningKind::BlockMissingName),
+        )?;
+
+        // Slice from the '#' in "[[#" to the end of the name
+        let name = self.full_text().slice_partial(start, last);
+        let name = name.strip_prefix("[[").unwrap_or(name);
+        let in_head = last.token != Token::RightBlock;
+        Ok((name, in_head))
+    }
+
     fn get_block_name_internal(
         &mut self,
         kind: ParseWarningKind,
@@ -193,12 +227,21 @@ where
         &mut self,
         block_rule: &BlockRule,
     ) -> Result<&'t str, ParseWarning> {
+        let (slice, _) = self.get_body_text_with_offset(block_rule)?;
+        Ok(slice)
+    }
+
+    /// Like `get_body_text()`, but also returns where the body begins in the wikitext.
+    pub fn get_body_text_with_offset(
+        &mut self,
+        block_rule: &BlockRule,
+    ) -> Result<(&'t str, usize), ParseWarning> {
         info!("Getting block body as text (rule {})", block_rule.name);
 
         // State variables for collecting span
         let (start, end) = self.get_body_generic(block_rule, |_| Ok(()))?;
         let slice = self.full_text().slice_partial(start, end);
-        Ok(slice)
+        Ok((slice, start.span.start))
     }
 
     #[inline]
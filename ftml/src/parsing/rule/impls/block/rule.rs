@@ -19,7 +19,9 @@
  */
 
 use super::super::prelude::*;
+use super::custom::get_custom_block_rule;
 use super::mapping::get_block_rule_with_name;
+use super::BlockRule;
 
 pub const RULE_BLOCK: Rule = Rule {
     name: "block",
@@ -33,6 +35,12 @@ pub const RULE_BLOCK_STAR: Rule = Rule {
     try_consume_fn: block_star,
 };
 
+pub const RULE_BLOCK_HASH: Rule = Rule {
+    name: "block-hash",
+    position: LineRequirement::Any,
+    try_consume_fn: block_hash,
+};
+
 pub const RULE_BLOCK_SKIP_NEWLINE: Rule = Rule {
     name: "block-skip",
     position: LineRequirement::Any, // this rule happens *on* a newline, not after one
@@ -45,12 +53,24 @@ fn block_regular<'r, 't>(
     parser: &mut Parser<'r, 't>,
 ) -> ParseResult<'r, 't, Elements<'t>> {
     info!("Trying to process a block");
-    parse_block(parser, false)
+    parse_block(parser, false, false)
 }
 
 fn block_star<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elements<'t>> {
     info!("Trying to process a block (with star flag)");
-    parse_block(parser, true)
+    parse_block(parser, true, false)
+}
+
+fn block_hash<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elements<'t>> {
+    info!("Trying to process a block (with hash name)");
+    parse_block(parser, false, true)
+}
+
+/// Determines if this is the name of a hash block, such as `if` in `[[#if`.
+///
+/// Other names after `[[#` are anchors, which the block rule should not try.
+pub fn is_hash_block_name(name: &str) -> bool {
+    get_block_rule_with_name(&format!("#{name}")).is_some()
 }
 
 fn block_skip<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elements<'t>> {
@@ -68,7 +88,7 @@ fn block_skip<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elemen
         let (name, _) = parser.get_block_name(false)?;
 
         // Get the block rule: if it accepts newlines, then we consume here
-        match get_block_rule_with_name(name) {
+        match get_block_rule(parser, name) {
             Some(block_rule) => Ok(block_rule.accepts_newlines),
             None => Ok(false),
         }
@@ -87,23 +107,33 @@ fn block_skip<'r, 't>(parser: &mut Parser<'r, 't>) -> ParseResult<'r, 't, Elemen
 fn parse_block<'r, 't>(
     parser: &mut Parser<'r, 't>,
     flag_star: bool,
+    flag_hash: bool,
 ) -> ParseResult<'r, 't, Elements<'t>>
 where
     'r: 't,
 {
-    info!("Trying to process a block (star {flag_star})");
+    info!("Trying to process a block (star {flag_star}, hash {flag_hash})");
+    let start = parser.current().span.start;
 
-    // Set general rule based on presence of star flag
+    // Set general rule based on presence of star or hash flag
     parser.set_rule(if flag_star {
         RULE_BLOCK_STAR
+    } else if flag_hash {
+        RULE_BLOCK_HASH
     } else {
         RULE_BLOCK
     });
 
     // Get block name
-    parser.get_optional_space()?;
-
-    let (name, in_head) = parser.get_block_name(flag_star)?;
+    //
+    // Hash blocks such as "[[#if" have their name
+    // directly after the "[[#" token.
+    let (name, in_head) = if flag_hash {
+        parser.get_hash_block_name()?
+    } else {
+        parser.get_optional_space()?;
+        parser.get_block_name(flag_star)?
+    };
     debug!("Got block name '{name}' (in head {in_head})");
 
     let (name, flag_score) = match name.strip_suffix('_') {
@@ -112,7 +142,7 @@ where
     };
 
     // Get the block rule for this name
-    let block = match get_block_rule_with_name(name) {
+    let block = match get_block_rule(parser, name) {
         Some(block) => block,
         None => return Err(parser.make_warn(ParseWarningKind::NoSuchBlock)),
     };
@@ -137,5 +167,26 @@ where
     // This is responsible for parsing any arguments,
     // and terminating the block (the ']]' token),
     // then processing the body (if any) and tail block.
-    (block.parse_fn)(parser, name, flag_star, flag_score, in_head)
+    let pending_warnings = parser.pending_warning_count();
+    let result = (block.parse_fn)(parser, name, flag_star, flag_score, in_head);
+
+    // Add any warnings from sanitizing this block's style attribute,
+    // spanning the whole block. Blocks within this one have already taken theirs.
+    let span = start..parser.current().span.start;
+    let warnings = parser.take_pending_warnings(pending_warnings);
+    result.map(|mut success| {
+        success
+            .exceptions
+            .extend(warnings.into_iter().map(|mut warning| {
+                warning.set_span(span.clone());
+                ParseException::Warning(warning)
+            }));
+
+        success
+    })
+}
+
+/// Gets the block rule with the given name, either built-in or custom.
+fn get_block_rule<'r>(parser: &Parser<'r, '_>, name: &str) -> Option<&'r BlockRule> {
+    get_block_rule_with_name(name).or_else(|| get_custom_block_rule(parser, name))
 }
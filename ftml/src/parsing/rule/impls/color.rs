@@ -19,6 +19,7 @@
  */
 
 use super::prelude::*;
+use crate::css::{sanitize_style, SanitizedCss};
 
 pub const RULE_COLOR: Rule = Rule {
     name: "color",
@@ -49,6 +50,13 @@ fn try_consume_fn<'p, 'r, 't>(
 
     debug!("Retrieved color descriptor, now building container ('{color}')");
 
+    // The color is used in a style attribute, so it must be safe there too
+    let style = format!("color: {color};");
+    let SanitizedCss { issues, .. } = sanitize_style(&style, &parser.settings().css);
+    if let Some(issue) = issues.first() {
+        return Err(parser.make_warn(issue.kind));
+    }
+
     // Build color container
     let (elements, exceptions, paragraph_safe) = collect_consume(
         parser,
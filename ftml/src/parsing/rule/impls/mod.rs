@@ -33,6 +33,7 @@ mod prelude {
 }
 
 mod anchor;
+mod bibcite;
 mod block;
 mod blockquote;
 mod bold;
@@ -69,7 +70,12 @@ mod url;
 mod variable;
 
 pub use self::anchor::RULE_ANCHOR;
-pub use self::block::{RULE_BLOCK, RULE_BLOCK_SKIP_NEWLINE, RULE_BLOCK_STAR};
+pub use self::bibcite::RULE_BIBLIOGRAPHY_CITE;
+pub use self::block::{
+    is_hash_block_name, Arguments, CustomBlock, CustomBlockBody, CustomBlockFn,
+    CustomBlockInput, CustomBlocks, RULE_BLOCK, RULE_BLOCK_HASH, RULE_BLOCK_SKIP_NEWLINE,
+    RULE_BLOCK_STAR,
+};
 pub use self::blockquote::RULE_BLOCKQUOTE;
 pub use self::bold::RULE_BOLD;
 pub use self::center::RULE_CENTER;
@@ -19,7 +19,8 @@
  */
 
 use super::{impls::*, Rule};
-use crate::parsing::token::{ExtractedToken, Token};
+use crate::parsing::token::Token;
+use crate::parsing::Parser;
 use enum_map::EnumMap;
 
 lazy_static! {
@@ -40,7 +41,7 @@ lazy_static! {
             Token::RightBracket => vec![RULE_TEXT],
             Token::LeftBlock => vec![RULE_BLOCK],
             Token::LeftBlockEnd => vec![],
-            Token::LeftBlockAnchor => vec![RULE_ANCHOR],
+            Token::LeftBlockAnchor => vec![RULE_ANCHOR, RULE_BLOCK_HASH],
             Token::LeftBlockStar => vec![RULE_BLOCK_STAR],
             Token::RightBlock => vec![],
             Token::LeftMath => vec![RULE_MATH],
@@ -93,6 +94,7 @@ lazy_static! {
             Token::Email => vec![RULE_EMAIL],
             Token::Url => vec![RULE_URL],
             Token::Variable => vec![RULE_VARIABLE, RULE_TEXT],
+            Token::BibliographyCite => vec![RULE_BIBLIOGRAPHY_CITE, RULE_TEXT],
             Token::String => vec![RULE_TEXT],
 
             // Input boundaries
@@ -111,7 +113,25 @@ lazy_static! {
     };
 }
 
-#[inline]
-pub fn get_rules_for_token(current: &ExtractedToken) -> &'static [Rule] {
+/// Gets the rules which could start at the parser's current token.
+///
+/// `[[#` begins both anchors and hash blocks such as `[[#if`,
+/// so the latter is only tried if the token after it is one of their names.
+pub fn get_rules_for_token(parser: &Parser) -> &'static [Rule] {
+    let current = parser.current();
+
+    if current.token == Token::LeftBlockAnchor {
+        let hash_block = match parser.look_ahead(0) {
+            Some(next) => {
+                next.token == Token::Identifier && is_hash_block_name(next.slice)
+            }
+            None => false,
+        };
+
+        if !hash_block {
+            return &[RULE_ANCHOR];
+        }
+    }
+
     &RULE_MAP[current.token]
 }
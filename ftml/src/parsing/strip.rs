@@ -23,7 +23,10 @@ use crate::tree::Element;
 pub fn strip_newlines(elements: &mut Vec<Element>) {
     // Remove leading line breaks
     while let Some(element) = elements.first() {
-        if !matches!(element, Element::LineBreak | Element::LineBreaks(_)) {
+        if !matches!(
+            element.unspanned(),
+            Element::LineBreak | Element::LineBreaks(_),
+        ) {
             break;
         }
 
@@ -32,7 +35,10 @@ pub fn strip_newlines(elements: &mut Vec<Element>) {
 
     // Remove trailing line breaks
     while let Some(element) = elements.last() {
-        if !matches!(element, Element::LineBreak | Element::LineBreaks(_)) {
+        if !matches!(
+            element.unspanned(),
+            Element::LineBreak | Element::LineBreaks(_),
+        ) {
             break;
         }
 
@@ -144,6 +144,7 @@ pub enum Token {
     Email,
     Url,
     Variable,
+    BibliographyCite,
     String,
 
     //
@@ -279,6 +280,7 @@ impl Token {
             Rule::email => Token::Email,
             Rule::url => Token::Url,
             Rule::variable => Token::Variable,
+            Rule::bibcite => Token::BibliographyCite,
             Rule::string => Token::String,
 
             // Other
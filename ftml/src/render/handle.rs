@@ -18,49 +18,182 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::data::{PageInfo, UserInfo};
+use super::messages::english_message;
+use crate::data::{
+    CategoryListing, JoinState, ListPagesResult, PageInfo, PageListing, PageTreeNode,
+    UserInfo,
+};
 use crate::settings::WikitextSettings;
-use crate::tree::{ImageSource, LinkLabel, LinkLocation, Module};
-use crate::url::BuildSiteUrl;
+use crate::tree::{ImageSource, LinkLabel, LinkLocation, ListPagesQuery};
 use std::borrow::Cow;
-use std::num::NonZeroUsize;
-use strum_macros::IntoStaticStr;
-use wikidot_normalize::normalize;
+use std::fmt::Debug;
+use std::num::{NonZeroU32, NonZeroUsize};
 
-#[derive(Debug)]
-pub struct Handle;
+/// Callbacks used by renderers to fetch external data.
+///
+/// Renderers need information which is not present in the syntax tree,
+/// such as the titles of linked pages, information about users, or where
+/// uploaded files are hosted. Embedders implement this trait to supply
+/// that information, and pass it to the renderer (for instance, via
+/// [`HtmlRender::new`]).
+///
+/// If no such data is available, [`DefaultHandle`] can be used,
+/// which returns placeholder values.
+///
+/// [`HtmlRender::new`]: crate::render::html::HtmlRender::new
+pub trait Handle: Debug {
+    /// Gets the title of the page being linked to.
+    ///
+    /// This is used for links which have no explicit label,
+    /// such as `[[[page|]]]`.
+    fn get_page_title(&self, link: &LinkLocation) -> String;
 
-impl Handle {
-    pub fn render_module(
+    /// Looks up information about the user with the given name.
+    ///
+    /// Returns `None` if the user does not exist.
+    fn get_user_info<'a>(&self, name: &'a str) -> Option<UserInfo<'a>>;
+
+    /// Gets the URL that the given image source should be loaded from.
+    ///
+    /// Returns `None` if the source cannot be resolved or is not permitted.
+    fn get_image_link<'a>(
         &self,
-        buffer: &mut String,
-        module: &Module,
-        mode: ModuleRenderMode,
-    ) {
-        info!(
-            "Rendering module '{}' (mode '{}')",
-            module.name(),
-            mode.name(),
-        );
-
-        match mode {
-            ModuleRenderMode::Html => {
-                str_write!(buffer, "<p>TODO: module {}</p>", module.name());
-            }
-            ModuleRenderMode::Text => {
-                str_write!(buffer, "TODO: module {}", module.name());
+        source: &ImageSource<'a>,
+        info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> Option<Cow<'a, str>>;
+
+    /// Gets the message string for the given key and language.
+    ///
+    /// The language is the one specified in [`PageInfo`] for the page being rendered.
+    /// Implementations should fall back to English if no translation is available,
+    /// see [`english_message`] for the default text of each key in [`MESSAGE_KEYS`].
+    ///
+    /// [`english_message`]: crate::render::messages::english_message
+    /// [`MESSAGE_KEYS`]: crate::render::messages::MESSAGE_KEYS
+    fn get_message(&self, language: &str, message: &str) -> Cow<'static, str>;
+
+    /// Submits the given HTML to be hosted separately.
+    ///
+    /// Returns the URL which the snippet can be embedded from,
+    /// as used by `[[html]]` blocks.
+    fn post_html(&self, info: &PageInfo, html: &str) -> String;
+
+    /// Submits the contents of a code block.
+    ///
+    /// The index is the one-based position of this code block in the page.
+    fn post_code(&self, index: NonZeroUsize, code: &str);
+
+    // Module data
+    //
+    // These have default implementations returning `None`,
+    // in which case the module is rendered without that data.
+
+    /// Gets the list of pages which link to the given page.
+    ///
+    /// Used by `[[module Backlinks]]`.
+    fn get_backlinks(&self, info: &PageInfo, page: &str) -> Option<Vec<PageListing<'_>>> {
+        let _ = (info, page);
+        None
+    }
+
+    /// Gets the list of categories on the site, with the pages in each.
+    ///
+    /// Categories beginning with `_` should only be included if `include_hidden` is set.
+    /// Used by `[[module Categories]]`.
+    fn get_categories(
+        &self,
+        info: &PageInfo,
+        include_hidden: bool,
+    ) -> Option<Vec<CategoryListing<'_>>> {
+        let _ = (info, include_hidden);
+        None
+    }
+
+    /// Gets the hierarchy of child pages starting from the given root page.
+    ///
+    /// If a depth is specified, then children beyond that many levels
+    /// need not be included. Used by `[[module PageTree]]`.
+    fn get_page_tree(
+        &self,
+        info: &PageInfo,
+        root: &str,
+        depth: Option<NonZeroU32>,
+    ) -> Option<PageTreeNode<'_>> {
+        let _ = (info, root, depth);
+        None
+    }
+
+    /// Gets the current rating of the page.
+    ///
+    /// If unavailable, the rating in [`PageInfo`] is used instead.
+    /// Used by `[[module Rate]]`.
+    fn get_page_rating(&self, info: &PageInfo) -> Option<f64> {
+        let _ = info;
+        None
+    }
+
+    /// Gets whether the current user is able to join the site.
+    ///
+    /// If unavailable, the site is assumed to be open.
+    /// Used by `[[module Join]]`.
+    fn get_join_state(&self, info: &PageInfo) -> Option<JoinState> {
+        let _ = info;
+        None
+    }
+
+    /// Gets the pages matching the given query, for the current pagination page.
+    ///
+    /// Which pagination page is current is up to the embedder, typically
+    /// from the URL being requested. Used by `[[module ListPages]]`.
+    fn get_list_pages(
+        &self,
+        info: &PageInfo,
+        query: &ListPagesQuery,
+    ) -> Option<ListPagesResult<'_>> {
+        let _ = (info, query);
+        None
+    }
+}
+
+impl dyn Handle + '_ {
+    pub fn get_link_label<F>(&self, link: &LinkLocation, label: &LinkLabel, f: F)
+    where
+        F: FnOnce(&str),
+    {
+        let page_title;
+        let label_text = match *label {
+            LinkLabel::Text(ref text) => text,
+            LinkLabel::Url(Some(ref text)) => text,
+            LinkLabel::Url(None) => match link {
+                LinkLocation::Url(url) => url,
+                LinkLocation::Page(page_ref) => page_ref.page(),
+            },
+            LinkLabel::Page => {
+                page_title = self.get_page_title(link);
+                &page_title
             }
-        }
+        };
+
+        f(label_text);
     }
+}
+
+/// A [`Handle`] implementation which returns placeholder data.
+///
+/// This is what renderers use if no other handle is specified.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DefaultHandle;
 
-    pub fn get_page_title(&self, link: &LinkLocation) -> String {
+impl Handle for DefaultHandle {
+    fn get_page_title(&self, link: &LinkLocation) -> String {
         info!("Fetching page title");
 
         // TODO
         format!("TODO: actual title ({:?})", link)
     }
 
-    pub fn get_user_info<'a>(&self, name: &'a str) -> Option<UserInfo<'a>> {
+    fn get_user_info<'a>(&self, name: &'a str) -> Option<UserInfo<'a>> {
         info!("Fetching user info (name '{name}')");
         let mut info = UserInfo::dummy();
         info.user_name = cow!(name);
@@ -68,7 +201,7 @@ impl Handle {
         Some(info)
     }
 
-    pub fn get_image_link<'a>(
+    fn get_image_link<'a>(
         &self,
         source: &ImageSource<'a>,
         info: &PageInfo,
@@ -91,55 +224,23 @@ impl Handle {
             ImageSource::File3 { site, page, file } => (site, page, file),
         };
 
-        // TODO: emit url
-        Some(Cow::Owned(format!(
-            "https://{site}.wjfiles.com/local--files/{page}/{file}",
-        )))
+        Some(Cow::Owned(settings.urls.file_url(site, page, file)))
     }
 
-    pub fn get_link_label<F>(&self, link: &LinkLocation, label: &LinkLabel, f: F)
-    where
-        F: FnOnce(&str),
-    {
-        let page_title;
-        let label_text = match *label {
-            LinkLabel::Text(ref text) => text,
-            LinkLabel::Url(Some(ref text)) => text,
-            LinkLabel::Url(None) => match link {
-                LinkLocation::Url(url) => url,
-                LinkLocation::Page(page_ref) => page_ref.page(),
-            },
-            LinkLabel::Page => {
-                page_title = self.get_page_title(link);
-                &page_title
-            }
-        };
-
-        f(label_text);
-    }
-
-    pub fn get_message(&self, language: &str, message: &str) -> &'static str {
+    fn get_message(&self, language: &str, message: &str) -> Cow<'static, str> {
         info!("Fetching message (language {language}, key {message})");
 
-        let _ = language;
-
-        // TODO
-        match message {
-            "button-copy-clipboard" => "Copy to Clipboard",
-            "collapsible-open" => "+ open block",
-            "collapsible-hide" => "- hide block",
-            "table-of-contents" => "Table of Contents",
-            "footnote" => "Footnote",
-            "footnote-block-title" => "Footnotes",
-            "image-context-bad" => "No images in this context",
-            _ => {
+        // No translations are available, so always use English
+        match english_message(message) {
+            Some(text) => Cow::Borrowed(text),
+            None => {
                 error!("Unknown message requested (key {message})");
-                "?"
+                cow!("?")
             }
         }
     }
 
-    pub fn post_html(&self, info: &PageInfo, html: &str) -> String {
+    fn post_html(&self, info: &PageInfo, html: &str) -> String {
         info!("Submitting HTML to create iframe-able snippet");
 
         let _ = info;
@@ -149,7 +250,7 @@ impl Handle {
         str!("https://example.com/")
     }
 
-    pub fn post_code(&self, index: NonZeroUsize, code: &str) {
+    fn post_code(&self, index: NonZeroUsize, code: &str) {
         info!("Submitting code snippet (index {})", index.get());
 
         let _ = index;
@@ -158,35 +259,3 @@ impl Handle {
         // TODO
     }
 }
-
-impl BuildSiteUrl for Handle {
-    fn build_url(&self, site: &str, path: &str) -> String {
-        // TODO make this a parser setting
-        // get url of wikijump instance here
-
-        let path = {
-            let mut path = str!(path);
-            normalize(&mut path);
-            path
-        };
-
-        // TODO
-        format!("https://{site}.wikijump.com/{path}")
-    }
-}
-
-#[derive(
-    IntoStaticStr, Serialize, Deserialize, Debug, Hash, Copy, Clone, PartialEq, Eq,
-)]
-#[serde(rename_all = "kebab-case")]
-pub enum ModuleRenderMode {
-    Html,
-    Text,
-}
-
-impl ModuleRenderMode {
-    #[inline]
-    pub fn name(self) -> &'static str {
-        self.into()
-    }
-}
@@ -104,6 +104,7 @@ where
 
     tag_method!(a);
     tag_method!(br);
+    tag_method!(button);
     tag_method!(code);
     tag_method!(dd);
     tag_method!(details);
@@ -152,6 +153,15 @@ impl<'c, 'i, 'h, 'e, 't> HtmlBuilderTag<'c, 'i, 'h, 'e, 't> {
         ctx.push_raw('<');
         ctx.push_raw_str(tag);
 
+        if let Some(span) = ctx.take_pending_span() {
+            str_write!(
+                ctx.buffer(),
+                " data-source-start=\"{}\" data-source-end=\"{}\"",
+                span.start,
+                span.end,
+            );
+        }
+
         HtmlBuilderTag {
             ctx,
             tag,
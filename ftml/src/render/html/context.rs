@@ -29,11 +29,12 @@ use crate::info;
 use crate::next_index::{NextIndex, TableOfContentsIndex};
 use crate::render::Handle;
 use crate::settings::WikitextSettings;
-use crate::tree::{Element, LinkLocation, VariableScopes};
+use crate::tree::{BibliographyList, Element, LinkLocation, VariableScopes};
 use crate::url::is_url;
 use std::borrow::Cow;
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
+use std::ops::Range;
 
 #[derive(Debug)]
 pub struct HtmlContext<'i, 'h, 'e, 't>
@@ -45,7 +46,7 @@ where
     meta: Vec<HtmlMeta>,
     backlinks: Backlinks<'static>,
     info: &'i PageInfo<'i>,
-    handle: &'h Handle,
+    handle: &'h dyn Handle,
     settings: &'e WikitextSettings,
     random: Random,
 
@@ -59,6 +60,7 @@ where
     //
     table_of_contents: &'e [Element<'t>],
     footnotes: &'e [Vec<Element<'t>>],
+    bibliography: &'e BibliographyList<'t>,
 
     //
     // Other fields to track
@@ -67,16 +69,24 @@ where
     table_of_contents_index: usize,
     equation_index: NonZeroUsize,
     footnote_index: NonZeroUsize,
+
+    //
+    // Source span output
+    //
+    source_spans: bool,
+    pending_span: Option<Range<usize>>,
 }
 
 impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
     #[inline]
     pub fn new(
         info: &'i PageInfo<'i>,
-        handle: &'h Handle,
+        handle: &'h dyn Handle,
         settings: &'e WikitextSettings,
         table_of_contents: &'e [Element<'t>],
         footnotes: &'e [Vec<Element<'t>>],
+        bibliography: &'e BibliographyList<'t>,
+        source_spans: bool,
     ) -> Self {
         HtmlContext {
             body: String::new(),
@@ -90,10 +100,13 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             variables: VariableScopes::new(),
             table_of_contents,
             footnotes,
+            bibliography,
             code_snippet_index: NonZeroUsize::new(1).unwrap(),
             table_of_contents_index: 0,
             equation_index: NonZeroUsize::new(1).unwrap(),
             footnote_index: NonZeroUsize::new(1).unwrap(),
+            source_spans,
+            pending_span: None,
         }
     }
 
@@ -144,7 +157,7 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
     }
 
     #[inline]
-    pub fn handle(&self) -> &'h Handle {
+    pub fn handle(&self) -> &'h dyn Handle {
         self.handle
     }
 
@@ -178,6 +191,11 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         self.footnotes
     }
 
+    #[inline]
+    pub fn bibliography(&self) -> &'e BibliographyList<'t> {
+        self.bibliography
+    }
+
     pub fn next_code_snippet_index(&mut self) -> NonZeroUsize {
         let index = self.code_snippet_index;
         self.code_snippet_index = NonZeroUsize::new(index.get() + 1).unwrap();
@@ -209,6 +227,26 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
             .map(|elements| elements.as_slice())
     }
 
+    // Source spans
+    #[inline]
+    pub fn source_spans(&self) -> bool {
+        self.source_spans
+    }
+
+    /// Sets the source span to be added to the next tag opened.
+    ///
+    /// Passing `None` discards any span which was not used,
+    /// such as for elements which produce only text.
+    #[inline]
+    pub fn set_pending_span(&mut self, span: Option<Range<usize>>) {
+        self.pending_span = span;
+    }
+
+    #[inline]
+    pub fn take_pending_span(&mut self) -> Option<Range<usize>> {
+        self.pending_span.take()
+    }
+
     // Backlinks
     #[inline]
     pub fn add_link(&mut self, link: &LinkLocation) {
@@ -250,6 +288,11 @@ impl<'i, 'h, 'e, 't> HtmlContext<'i, 'h, 'e, 't> {
         self.backlinks.included_pages.push(page.to_owned());
     }
 
+    #[inline]
+    pub fn add_included_elements(&mut self, page: &PageRef) {
+        self.backlinks.included_elements.push(page.to_owned());
+    }
+
     // Buffer management
     #[inline]
     pub fn buffer(&mut self) -> &mut String {
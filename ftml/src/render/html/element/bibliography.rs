@@ -0,0 +1,147 @@
+/*
+ * render/html/element/bibliography.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::tree::BibliographyEntry;
+
+pub fn render_bibliography_cite(ctx: &mut HtmlContext, label: &str) {
+    info!("Rendering bibliography citation (label '{label}')");
+
+    let (number, contents) = match ctx.bibliography().get(label) {
+        Some(entry) => entry,
+        None => {
+            debug!("No such bibliography entry found");
+
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-bibliography-ref wj-error-inline"))
+                .inner(format!("[{label}]"));
+
+            return;
+        }
+    };
+
+    let id = str!(number);
+    let reference_string = ctx
+        .handle()
+        .get_message(ctx.language(), "bibliography-reference");
+    let aria_label = format!("{reference_string} {number}.");
+
+    ctx.html()
+        .span()
+        .attr(attr!("class" => "wj-bibliography-ref"))
+        .contents(|ctx| {
+            ctx.push_raw('[');
+
+            // Reference marker that is hoverable
+            ctx.html()
+                .element("wj-bibliography-ref-marker")
+                .attr(attr!(
+                    "class" => "wj-bibliography-ref-marker",
+                    "role" => "link",
+                    "aria-label" => &aria_label,
+                    "data-id" => &id,
+                ))
+                .inner(&id);
+
+            ctx.push_raw(']');
+
+            // Tooltip shown on hover, see render_footnote().
+            ctx.html()
+                .span()
+                .attr(attr!(
+                    "class" => "wj-bibliography-ref-tooltip",
+                    "aria-hidden" => "true",
+                ))
+                .inner(contents);
+        });
+}
+
+pub fn render_bibliography_block(
+    ctx: &mut HtmlContext,
+    title: Option<&str>,
+    entries: &[BibliographyEntry],
+) {
+    info!(
+        "Rendering bibliography block (title {}, {} entries)",
+        title.unwrap_or("<default>"),
+        entries.len(),
+    );
+
+    let title_default;
+    let title: &str = match title {
+        Some(title) => title,
+        None => {
+            title_default = ctx
+                .handle()
+                .get_message(ctx.language(), "bibliography-block-title");
+
+            &title_default
+        }
+    };
+
+    let entries = ctx.bibliography().get_block_entries(entries);
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-bibliography"))
+        .contents(|ctx| {
+            ctx.html()
+                .div()
+                .attr(attr!("class" => "wj-title"))
+                .inner(title);
+
+            ctx.html().ol().contents(|ctx| {
+                for &(number, contents) in &entries {
+                    let id = &str!(number);
+
+                    ctx.html()
+                        .li()
+                        .attr(attr!(
+                            "class" => "wj-bibliography-list-item",
+                            "data-id" => id,
+                        ))
+                        .contents(|ctx| {
+                            // Number, with a period which can be styled separately
+                            ctx.html()
+                                .span()
+                                .attr(
+                                    attr!("class" => "wj-bibliography-list-item-marker"),
+                                )
+                                .contents(|ctx| {
+                                    str_write!(ctx, "{number}");
+
+                                    ctx.html()
+                                        .span()
+                                        .attr(attr!("class" => "wj-bibliography-sep"))
+                                        .inner(".");
+                                });
+
+                            ctx.html()
+                                .span()
+                                .attr(attr!(
+                                    "class" => "wj-bibliography-list-item-contents",
+                                ))
+                                .inner(contents);
+                        });
+                }
+            });
+        });
+}
@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::tree::{AttributeMap, Element};
+use std::borrow::Cow;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Collapsible<'a> {
@@ -76,11 +77,15 @@ pub fn render_collapsible(ctx: &mut HtmlContext, collapsible: Collapsible) {
         show_bottom,
     );
 
-    let show_text = show_text
-        .unwrap_or_else(|| ctx.handle().get_message(ctx.language(), "collapsible-open"));
+    let show_text = match show_text {
+        Some(text) => Cow::Borrowed(text),
+        None => ctx.handle().get_message(ctx.language(), "collapsible-open"),
+    };
 
-    let hide_text = hide_text
-        .unwrap_or_else(|| ctx.handle().get_message(ctx.language(), "collapsible-hide"));
+    let hide_text = match hide_text {
+        Some(text) => Cow::Borrowed(text),
+        None => ctx.handle().get_message(ctx.language(), "collapsible-hide"),
+    };
 
     ctx.html()
         .details()
@@ -103,13 +108,13 @@ pub fn render_collapsible(ctx: &mut HtmlContext, collapsible: Collapsible) {
                     ctx.html()
                         .span()
                         .attr(attr!("class" => "wj-collapsible-show-text"))
-                        .inner(show_text);
+                        .inner(&show_text);
 
                     // Block is unfolded text
                     ctx.html()
                         .span()
                         .attr(attr!("class" => "wj-collapsible-hide-text"))
-                        .inner(hide_text);
+                        .inner(&hide_text);
                 });
 
             // Content block
@@ -130,7 +135,7 @@ pub fn render_collapsible(ctx: &mut HtmlContext, collapsible: Collapsible) {
                         ctx.html()
                             .span()
                             .attr(attr!("class" => "wj-collapsible-hide-text"))
-                            .inner(hide_text);
+                            .inner(&hide_text);
                     });
             }
         });
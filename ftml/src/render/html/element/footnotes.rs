@@ -89,7 +89,7 @@ pub fn render_footnote_block(ctx: &mut HtmlContext, title: Option<&str>) {
                 .handle()
                 .get_message(ctx.language(), "footnote-block-title");
 
-            title_default
+            &title_default
         }
     };
 
@@ -19,8 +19,11 @@
  */
 
 use super::prelude::*;
-use crate::tree::{AttributeMap, FloatAlignment, ImageSource, LinkLocation};
+use crate::tree::{
+    AttributeMap, FloatAlignment, GalleryItem, GallerySize, ImageSource, LinkLocation,
+};
 use crate::url::normalize_link;
+use std::borrow::Cow;
 
 pub fn render_image(
     ctx: &mut HtmlContext,
@@ -90,7 +93,7 @@ fn render_image_element(
 
             match link {
                 Some(link) => {
-                    let url = normalize_link(link, ctx.handle());
+                    let url = normalize_link(link, &ctx.settings().urls);
                     ctx.html()
                         .a()
                         .attr(attr!("href" => &url))
@@ -111,5 +114,97 @@ fn render_image_missing(ctx: &mut HtmlContext) {
     ctx.html()
         .div()
         .attr(attr!("class" => "wj-error-block"))
-        .inner(message);
+        .inner(&message);
+}
+
+pub fn render_gallery(
+    ctx: &mut HtmlContext,
+    items: &[GalleryItem],
+    size: GallerySize,
+    viewer: bool,
+) {
+    info!(
+        "Rendering gallery (items {}, size {}, viewer {})",
+        items.len(),
+        size.name(),
+        viewer,
+    );
+
+    let label = ctx.handle().get_message(ctx.language(), "gallery-label");
+
+    ctx.html()
+        .ul()
+        .attr(attr!(
+            "class" => "wj-gallery " size.html_class(),
+            "aria-label" => &label,
+        ))
+        .contents(|ctx| {
+            for item in items {
+                render_gallery_item(ctx, item, viewer);
+            }
+        });
+}
+
+fn render_gallery_item(ctx: &mut HtmlContext, item: &GalleryItem, viewer: bool) {
+    let source_url =
+        ctx.handle()
+            .get_image_link(&item.source, ctx.info(), ctx.settings());
+
+    let url = match source_url {
+        Some(url) => url,
+        None => {
+            debug!("Gallery image URL unresolved, missing or error");
+
+            let message = ctx
+                .handle()
+                .get_message(ctx.language(), "image-context-bad");
+
+            ctx.html()
+                .li()
+                .attr(attr!("class" => "wj-gallery-item wj-error-inline"))
+                .inner(&message);
+
+            return;
+        }
+    };
+
+    // Use the title as the alternate text if there is one,
+    // so the image is described by more than its file name.
+    let alt_text = match &item.title {
+        Some(title) => title,
+        None => item.source.file_name(),
+    };
+
+    // If there is no explicit link, the viewer links to the full image.
+    let link = match &item.link {
+        Some(link) => Some(normalize_link(link, &ctx.settings().urls)),
+        None if viewer => Some(Cow::clone(&url)),
+        None => None,
+    };
+
+    ctx.html()
+        .li()
+        .attr(attr!("class" => "wj-gallery-item"))
+        .contents(|ctx| {
+            let build_image = |ctx: &mut HtmlContext| {
+                ctx.html().img().attr(attr!(
+                    "class" => "wj-gallery-image",
+                    "src" => &url,
+                    "alt" => alt_text,
+                    "title" => alt_text; if item.title.is_some(),
+                    "loading" => "lazy",
+                    "crossorigin",
+                ));
+            };
+
+            match &link {
+                Some(link) => {
+                    ctx.html()
+                        .a()
+                        .attr(attr!("class" => "wj-gallery-link", "href" => link))
+                        .contents(build_image);
+                }
+                None => build_image(ctx),
+            }
+        });
 }
@@ -29,6 +29,7 @@ pub fn render_include(
     elements: &[Element],
 ) {
     info!("Rendering include (location {location:?})");
+    ctx.add_included_elements(location);
     ctx.variables_mut().push_scope(variables);
     render_elements(ctx, elements);
     ctx.variables_mut().pop_scope();
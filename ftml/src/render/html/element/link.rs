@@ -60,7 +60,7 @@ pub fn render_link(
     // Add to backlinks
     ctx.add_link(link);
 
-    let url = normalize_link(link, ctx.handle());
+    let url = normalize_link(link, &ctx.settings().urls);
 
     let target_value = match target {
         Some(target) => target.html_attr(),
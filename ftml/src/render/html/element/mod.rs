@@ -20,6 +20,7 @@
 
 //! Module that implements HTML rendering for `Element` and its children.
 
+mod bibliography;
 mod collapsible;
 mod container;
 mod date;
@@ -33,6 +34,7 @@ mod input;
 mod link;
 mod list;
 mod math;
+mod module;
 mod table;
 mod tabs;
 mod text;
@@ -47,6 +49,7 @@ mod prelude {
     pub use crate::tree::{Element, SyntaxTree};
 }
 
+use self::bibliography::{render_bibliography_block, render_bibliography_cite};
 use self::collapsible::{render_collapsible, Collapsible};
 use self::container::{render_color, render_container};
 use self::date::render_date;
@@ -54,12 +57,13 @@ use self::definition_list::render_definition_list;
 use self::embed::render_embed;
 use self::footnotes::{render_footnote, render_footnote_block};
 use self::iframe::{render_html, render_iframe};
-use self::image::render_image;
+use self::image::{render_gallery, render_image};
 use self::include::{render_include, render_variable};
 use self::input::{render_checkbox, render_radio_button};
 use self::link::{render_anchor, render_link};
 use self::list::render_list;
 use self::math::{render_equation_reference, render_math_block, render_math_inline};
+use self::module::render_module;
 use self::table::render_table;
 use self::tabs::render_tabview;
 use self::text::{render_code, render_email, render_wikitext_raw};
@@ -67,7 +71,6 @@ use self::toc::render_table_of_contents;
 use self::user::render_user;
 use super::attributes::AddedAttributes;
 use super::HtmlContext;
-use crate::render::ModuleRenderMode;
 use crate::tree::Element;
 use ref_map::*;
 
@@ -90,10 +93,7 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
 
     match element {
         Element::Container(container) => render_container(ctx, container),
-        Element::Module(module) => {
-            ctx.handle()
-                .render_module(ctx.buffer(), module, ModuleRenderMode::Html);
-        }
+        Element::Module(module) => render_module(ctx, module),
         Element::Text(text) => ctx.push_escaped(text),
         Element::Raw(text) => render_wikitext_raw(ctx, text),
         Element::Variable(name) => render_variable(ctx, name),
@@ -120,6 +120,11 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
             alignment,
             attributes,
         } => render_image(ctx, source, link, *alignment, attributes),
+        Element::Gallery {
+            items,
+            size,
+            viewer,
+        } => render_gallery(ctx, items, *size, *viewer),
         Element::List {
             ltype,
             items,
@@ -164,6 +169,10 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
                 render_footnote_block(ctx, ref_cow!(title));
             }
         }
+        Element::BibliographyCite { label } => render_bibliography_cite(ctx, label),
+        Element::BibliographyBlock { title, entries } => {
+            render_bibliography_block(ctx, ref_cow!(title), entries)
+        }
         Element::User { name, show_avatar } => render_user(ctx, name, *show_avatar),
         Element::Date {
             value,
@@ -206,6 +215,14 @@ pub fn render_element(ctx: &mut HtmlContext, element: &Element) {
         Element::HorizontalRule => {
             ctx.html().hr();
         }
+        Element::Spanned { span, element } => {
+            if ctx.source_spans() {
+                ctx.set_pending_span(Some(span.clone()));
+            }
+
+            render_element(ctx, element);
+            ctx.set_pending_span(None);
+        }
         Element::Partial(_) => panic!("Encountered partial element during parsing"),
     }
 }
@@ -0,0 +1,349 @@
+/*
+ * render/html/element/module.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::data::{
+    CategoryListing, JoinState, ListPagesResult, PageListing, PageTreeNode,
+};
+use crate::render::list_pages;
+use crate::tree::{AttributeMap, ListPagesQuery, Module};
+use crate::url::normalize_href;
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+
+pub fn render_module(ctx: &mut HtmlContext, module: &Module) {
+    info!("Rendering module '{}'", module.name());
+
+    match module {
+        Module::Backlinks { page } => {
+            let page = page.as_deref().unwrap_or(&ctx.info().page);
+            let backlinks = ctx.handle().get_backlinks(ctx.info(), page);
+            render_backlinks(ctx, backlinks.as_deref());
+        }
+        Module::Categories { include_hidden } => {
+            let categories = ctx.handle().get_categories(ctx.info(), *include_hidden);
+            render_categories(ctx, categories.as_deref());
+        }
+        Module::Join {
+            button_text,
+            attributes,
+        } => {
+            let state = ctx
+                .handle()
+                .get_join_state(ctx.info())
+                .unwrap_or(JoinState::Open);
+
+            render_join(ctx, state, button_text.as_deref(), attributes);
+        }
+        Module::ListPages { query, template } => {
+            let result = ctx.handle().get_list_pages(ctx.info(), query);
+            render_list_pages(ctx, query, template, result.as_ref());
+        }
+        Module::PageTree {
+            root,
+            show_root,
+            depth,
+        } => {
+            let root = root.as_deref().unwrap_or(&ctx.info().page);
+            let tree = ctx.handle().get_page_tree(ctx.info(), root, *depth);
+            render_page_tree(ctx, tree.as_ref(), *show_root, *depth);
+        }
+        Module::Rate => {
+            let rating = ctx
+                .handle()
+                .get_page_rating(ctx.info())
+                .unwrap_or(ctx.info().rating);
+
+            render_rate(ctx, rating);
+        }
+    }
+}
+
+fn render_page_listing(ctx: &mut HtmlContext, page: &PageListing) {
+    let url = normalize_href(&page.slug);
+
+    ctx.html()
+        .a()
+        .attr(attr!("href" => &url))
+        .inner(&page.title);
+}
+
+fn render_backlinks(ctx: &mut HtmlContext, backlinks: Option<&[PageListing]>) {
+    debug!(
+        "Rendering backlinks module (pages {})",
+        match backlinks {
+            Some(pages) => pages.len().to_string(),
+            None => str!("<unavailable>"),
+        },
+    );
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-module-backlinks"))
+        .contents(|ctx| {
+            if let Some(pages) = backlinks {
+                if !pages.is_empty() {
+                    ctx.html().ul().contents(|ctx| {
+                        for page in pages {
+                            ctx.html()
+                                .li()
+                                .contents(|ctx| render_page_listing(ctx, page));
+                        }
+                    });
+                }
+            }
+        });
+}
+
+fn render_categories(ctx: &mut HtmlContext, categories: Option<&[CategoryListing]>) {
+    debug!(
+        "Rendering categories module (categories {})",
+        match categories {
+            Some(categories) => categories.len().to_string(),
+            None => str!("<unavailable>"),
+        },
+    );
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-module-categories"))
+        .contents(|ctx| {
+            let categories = match categories {
+                Some(categories) if !categories.is_empty() => categories,
+                _ => return,
+            };
+
+            ctx.html().ul().contents(|ctx| {
+                for category in categories {
+                    ctx.html().li().contents(|ctx| {
+                        ctx.html()
+                            .span()
+                            .attr(attr!("class" => "wj-module-categories-name"))
+                            .inner(&category.name);
+
+                        if category.pages.is_empty() {
+                            return;
+                        }
+
+                        ctx.html().ul().contents(|ctx| {
+                            for page in &category.pages {
+                                ctx.html()
+                                    .li()
+                                    .contents(|ctx| render_page_listing(ctx, page));
+                            }
+                        });
+                    });
+                }
+            });
+        });
+}
+
+fn render_join(
+    ctx: &mut HtmlContext,
+    state: JoinState,
+    button_text: Option<&str>,
+    attributes: &AttributeMap,
+) {
+    debug!(
+        "Rendering join module (state {}, button text {})",
+        state.name(),
+        button_text.unwrap_or("<default>"),
+    );
+
+    let message = match state {
+        JoinState::Open => "button-join-site",
+        JoinState::Application => "button-apply-site",
+        JoinState::Member | JoinState::Closed => {
+            ctx.html()
+                .div()
+                .attr(attr!("class" => "wj-module-join";; attributes));
+
+            return;
+        }
+    };
+
+    let button_text = match button_text {
+        Some(text) => Cow::Borrowed(text),
+        None => ctx.handle().get_message(ctx.language(), message),
+    };
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-module-join";; attributes))
+        .contents(|ctx| {
+            ctx.html()
+                .button()
+                .attr(attr!(
+                    "class" => "wj-module-join-button",
+                    "type" => "button",
+                    "data-join-state" => state.name(),
+                ))
+                .inner(&button_text);
+        });
+}
+
+fn render_list_pages(
+    ctx: &mut HtmlContext,
+    query: &ListPagesQuery,
+    template: &str,
+    result: Option<&ListPagesResult>,
+) {
+    debug!(
+        "Rendering list pages module (pages {}, separate {})",
+        match result {
+            Some(result) => result.entries.len().to_string(),
+            None => str!("<unavailable>"),
+        },
+        query.separate,
+    );
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-list-pages"))
+        .contents(|ctx| {
+            let result = match result {
+                Some(result) => result,
+                None => return,
+            };
+
+            for source in list_pages::build_sources(template, query, result) {
+                let tree = list_pages::parse_source(&source, ctx.info(), ctx.settings());
+
+                if query.separate {
+                    ctx.html()
+                        .div()
+                        .attr(attr!("class" => "wj-list-pages-item"))
+                        .contents(|ctx| render_elements(ctx, &tree.elements));
+                } else {
+                    render_elements(ctx, &tree.elements);
+                }
+            }
+
+            let page_count = list_pages::page_count(query, result);
+            if page_count > 1 {
+                render_list_pages_pager(ctx, result.current_page.get(), page_count);
+            }
+        });
+}
+
+fn render_list_pages_pager(ctx: &mut HtmlContext, current_page: u32, page_count: u32) {
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-list-pages-pager"))
+        .contents(|ctx| {
+            for page in 1..=page_count {
+                let label = page.to_string();
+
+                if page == current_page {
+                    ctx.html()
+                        .span()
+                        .attr(attr!("class" => "wj-list-pages-pager-current"))
+                        .inner(&label);
+                } else {
+                    let page_ref = ctx.info().page_ref();
+                    let url = format!("/{}/p/{}", page_ref.page(), page);
+
+                    ctx.html().a().attr(attr!("href" => &url)).inner(&label);
+                }
+            }
+        });
+}
+
+fn render_page_tree(
+    ctx: &mut HtmlContext,
+    tree: Option<&PageTreeNode>,
+    show_root: bool,
+    depth: Option<NonZeroU32>,
+) {
+    debug!(
+        "Rendering page tree module (show-root {}, depth {})",
+        show_root,
+        match depth {
+            Some(depth) => depth.to_string(),
+            None => str!("<unlimited>"),
+        },
+    );
+
+    fn render_nodes(ctx: &mut HtmlContext, nodes: &[PageTreeNode], remaining: u32) {
+        if nodes.is_empty() || remaining == 0 {
+            return;
+        }
+
+        ctx.html().ul().contents(|ctx| {
+            for node in nodes {
+                ctx.html().li().contents(|ctx| {
+                    render_page_listing(ctx, &node.page);
+                    render_nodes(ctx, &node.children, remaining - 1);
+                });
+            }
+        });
+    }
+
+    let remaining = depth.map(NonZeroU32::get).unwrap_or(u32::MAX);
+
+    ctx.html()
+        .div()
+        .attr(attr!("class" => "wj-module-page-tree"))
+        .contents(|ctx| {
+            if let Some(root) = tree {
+                if show_root {
+                    render_nodes(ctx, std::slice::from_ref(root), remaining);
+                } else {
+                    render_nodes(ctx, &root.children, remaining);
+                }
+            }
+        });
+}
+
+fn render_rate(ctx: &mut HtmlContext, rating: f64) {
+    debug!("Rendering rate module (rating {rating})");
+
+    let rating_label = ctx.handle().get_message(ctx.language(), "rating");
+    let rating_value = format!("{rating:+}");
+
+    ctx.html()
+        .div()
+        .attr(attr!(
+            "class" => "wj-module-rate",
+            "data-rating" => &rating.to_string(),
+        ))
+        .contents(|ctx| {
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-module-rate-label"))
+                .inner(&rating_label);
+
+            ctx.html()
+                .span()
+                .attr(attr!("class" => "wj-module-rate-value"))
+                .inner(&rating_value);
+
+            for (vote, label) in [("+1", "+"), ("-1", "-"), ("0", "x")] {
+                ctx.html()
+                    .button()
+                    .attr(attr!(
+                        "class" => "wj-module-rate-button",
+                        "type" => "button",
+                        "data-vote" => vote,
+                    ))
+                    .inner(label);
+            }
+        });
+}
@@ -78,7 +78,7 @@ pub fn render_code(ctx: &mut HtmlContext, language: Option<&str>, contents: &str
                         .attr(attr!(
                             "type" => "button",
                             "class" => "wj-code-copy",
-                            "title" => button_title,
+                            "title" => &button_title,
                         ))
                         .contents(|ctx| {
                             ctx.html().sprite("wj-clipboard");
@@ -97,7 +97,37 @@ pub fn render_code(ctx: &mut HtmlContext, language: Option<&str>, contents: &str
 
             // Code block containing highlighted contents
             ctx.html().pre().contents(|ctx| {
-                ctx.html().code().inner(contents);
+                ctx.html()
+                    .code()
+                    .contents(|ctx| render_code_contents(ctx, language, contents));
             });
         });
 }
+
+fn render_code_contents(ctx: &mut HtmlContext, language: Option<&str>, contents: &str) {
+    cfg_if! {
+        if #[cfg(feature = "highlight")] {
+            use crate::render::html::highlight::highlight;
+
+            if let Some(tokens) = language.and_then(|language| highlight(language, contents)) {
+                for (class, slice) in tokens {
+                    match class {
+                        Some(class) => {
+                            ctx.html()
+                                .span()
+                                .attr(attr!("class" => &class.html_class()))
+                                .inner(slice);
+                        }
+                        None => ctx.push_escaped(slice),
+                    }
+                }
+
+                return;
+            }
+        } else {
+            let _ = language;
+        }
+    }
+
+    ctx.push_escaped(contents);
+}
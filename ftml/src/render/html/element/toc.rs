@@ -65,7 +65,7 @@ pub fn render_table_of_contents(
             ctx.html()
                 .div()
                 .attr(attr!("class" => "title"))
-                .inner(table_of_contents_title);
+                .inner(&table_of_contents_title);
 
             // TOC List
             let table_of_contents = ctx.table_of_contents();
@@ -0,0 +1,354 @@
+/*
+ * render/html/highlight/languages.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Highlighting rules for each supported language.
+//!
+//! Rules are tried in order, so more specific ones (such as comments and strings,
+//! which can contain anything) must come before general ones (such as keywords).
+
+use super::HighlightClass::*;
+use super::Rule;
+
+// Common patterns
+
+const C_COMMENT: &str = r"//[^\n]*|/\*(?s:.*?)(?:\*/|\z)";
+const DOUBLE_QUOTE_STRING: &str = r#""(?:[^"\\\n]|\\(?s:.))*"?"#;
+const SINGLE_QUOTE_STRING: &str = r"'(?:[^'\\\n]|\\(?s:.))*'?";
+const C_NUMBER: &str = r"\b(?:0[xX][0-9a-fA-F_]+|0[oO][0-7_]+|0[bB][01_]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)";
+
+/// Makes a pattern which matches any of the given words.
+fn words(words: &[&str]) -> std::string::String {
+    format!(r"\b(?:{})\b", words.join("|"))
+}
+
+lazy_static! {
+    static ref RUST: Vec<Rule> = vec![
+        Rule::new(Comment, C_COMMENT),
+        Rule::new(Constant, r"#!?\[[^\]\n]*\]"),
+        Rule::new(String, r##"b?r#*"(?s:.*?)(?:"#*|\z)"##),
+        Rule::new(String, &format!("b?{DOUBLE_QUOTE_STRING}")),
+        Rule::new(
+            String,
+            r"b?'(?:[^'\\\n]|\\(?:[^\n]|u\{[0-9a-fA-F]{1,6}\}))'"
+        ),
+        Rule::new(
+            Number,
+            &format!(r"{C_NUMBER}(?:[iu](?:8|16|32|64|128|size)|f32|f64)?\b")
+        ),
+        Rule::new(
+            Keyword,
+            &words(&[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn",
+                "else", "enum", "extern", "fn", "for", "if", "impl", "in", "let", "loop",
+                "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+                "static", "struct", "super", "trait", "type", "unsafe", "use", "where",
+                "while",
+            ]),
+        ),
+        Rule::new(Boolean, &words(&["true", "false"])),
+        Rule::new(
+            Builtin,
+            &words(&[
+                "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8",
+                "i16", "i32", "i64", "i128", "isize", "f32", "f64",
+            ]),
+        ),
+        Rule::new(Function, r"[A-Za-z_]\w*!"),
+        Rule::new(ClassName, r"\b[A-Z]\w*\b"),
+    ];
+    static ref PYTHON: Vec<Rule> = vec![
+        Rule::new(Comment, r"#[^\n]*"),
+        Rule::new(
+            String,
+            r#"(?:\b[rRbBuUfF]{1,2})?(?:"""(?s:.*?)(?:"""|\z)|'''(?s:.*?)(?:'''|\z))"#
+        ),
+        Rule::new(
+            String,
+            &format!(
+                r"(?:\b[rRbBuUfF]{{1,2}})?(?:{DOUBLE_QUOTE_STRING}|{SINGLE_QUOTE_STRING})"
+            ),
+        ),
+        Rule::new(Constant, r"@[A-Za-z_][\w.]*"),
+        Rule::new(Number, &format!(r"{C_NUMBER}[jJ]?\b")),
+        Rule::new(
+            Keyword,
+            &words(&[
+                "and", "as", "assert", "async", "await", "break", "case", "class",
+                "continue", "def", "del", "elif", "else", "except", "finally", "for",
+                "from", "global", "if", "import", "in", "is", "lambda", "match",
+                "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+                "with", "yield",
+            ]),
+        ),
+        Rule::new(Boolean, &words(&["True", "False", "None"])),
+        Rule::new(
+            Builtin,
+            &words(&[
+                "bool", "bytes", "dict", "float", "int", "list", "object", "set", "str",
+                "tuple", "type", "self", "cls",
+            ]),
+        ),
+        Rule::new(Function, r"[A-Za-z_]\w*").followed_by(r"\("),
+    ];
+    static ref JAVASCRIPT: Vec<Rule> = vec![
+        Rule::new(Comment, C_COMMENT),
+        Rule::new(String, DOUBLE_QUOTE_STRING),
+        Rule::new(String, SINGLE_QUOTE_STRING),
+        Rule::new(String, r"`(?:[^`\\]|\\(?s:.))*`?"),
+        Rule::new(Number, &format!(r"{C_NUMBER}n?\b")),
+        Rule::new(
+            Keyword,
+            &words(&[
+                "abstract",
+                "as",
+                "async",
+                "await",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "const",
+                "continue",
+                "debugger",
+                "declare",
+                "default",
+                "delete",
+                "do",
+                "else",
+                "enum",
+                "export",
+                "extends",
+                "finally",
+                "for",
+                "from",
+                "function",
+                "get",
+                "if",
+                "implements",
+                "import",
+                "in",
+                "instanceof",
+                "interface",
+                "let",
+                "namespace",
+                "new",
+                "of",
+                "private",
+                "protected",
+                "public",
+                "readonly",
+                "return",
+                "set",
+                "static",
+                "super",
+                "switch",
+                "this",
+                "throw",
+                "try",
+                "type",
+                "typeof",
+                "var",
+                "void",
+                "while",
+                "with",
+                "yield",
+            ]),
+        ),
+        Rule::new(
+            Boolean,
+            &words(&["true", "false", "null", "undefined", "NaN", "Infinity"]),
+        ),
+        Rule::new(
+            Builtin,
+            &words(&[
+                "any", "bigint", "boolean", "never", "number", "object", "string",
+                "symbol", "unknown",
+            ]),
+        ),
+        Rule::new(Function, r"[A-Za-z_$][\w$]*").followed_by(r"\s*\("),
+        Rule::new(ClassName, r"\b[A-Z][\w$]*\b"),
+    ];
+    static ref C: Vec<Rule> = vec![
+        Rule::new(Comment, C_COMMENT),
+        Rule::new(Constant, r"#[ \t]*\w+(?:[ \t]*<[^>\n]*>)?"),
+        Rule::new(String, &format!("(?:L|u8|u|U)?{DOUBLE_QUOTE_STRING}")),
+        Rule::new(String, r"(?:L|u8|u|U)?'(?:[^'\\\n]|\\[^\n]+?)'"),
+        Rule::new(Number, &format!(r"{C_NUMBER}[uUlLfF]*\b")),
+        Rule::new(
+            Keyword,
+            &words(&[
+                "alignas",
+                "alignof",
+                "auto",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "const",
+                "const_cast",
+                "constexpr",
+                "continue",
+                "decltype",
+                "default",
+                "delete",
+                "do",
+                "dynamic_cast",
+                "else",
+                "enum",
+                "explicit",
+                "export",
+                "extern",
+                "for",
+                "friend",
+                "goto",
+                "if",
+                "inline",
+                "mutable",
+                "namespace",
+                "new",
+                "noexcept",
+                "operator",
+                "override",
+                "private",
+                "protected",
+                "public",
+                "register",
+                "reinterpret_cast",
+                "restrict",
+                "return",
+                "sizeof",
+                "static",
+                "static_assert",
+                "static_cast",
+                "struct",
+                "switch",
+                "template",
+                "this",
+                "throw",
+                "try",
+                "typedef",
+                "typename",
+                "union",
+                "using",
+                "virtual",
+                "volatile",
+                "while",
+            ]),
+        ),
+        Rule::new(Boolean, &words(&["true", "false", "NULL", "nullptr"])),
+        Rule::new(
+            Builtin,
+            &words(&[
+                "bool",
+                "char",
+                "double",
+                "float",
+                "int",
+                "long",
+                "short",
+                "signed",
+                "unsigned",
+                "void",
+                "size_t",
+                "ssize_t",
+                "ptrdiff_t",
+                "wchar_t",
+                "int8_t",
+                "int16_t",
+                "int32_t",
+                "int64_t",
+                "uint8_t",
+                "uint16_t",
+                "uint32_t",
+                "uint64_t",
+            ]),
+        ),
+        Rule::new(Function, r"[A-Za-z_]\w*").followed_by(r"\s*\("),
+    ];
+    static ref HTML_TAG: Vec<Rule> = vec![
+        Rule::new(AttrValue, r#""[^"]*"?|'[^']*'?"#),
+        Rule::new(AttrName, r#"[^\s<>/='"]+"#).followed_by(r"\s*="),
+        Rule::new(Punctuation, r"="),
+    ];
+    static ref HTML: Vec<Rule> = vec![
+        Rule::new(Comment, r"<!--(?s:.*?)(?:-->|\z)"),
+        Rule::new(Doctype, r"<![A-Za-z][^>]*>?"),
+        Rule::new(Constant, r"<\?(?s:.*?)(?:\?>|\z)"),
+        Rule::nested(
+            Some(Tag),
+            r#"</?[A-Za-z][\w:.-]*(?:[^<>"']|"[^"]*"|'[^']*')*>?"#,
+            &HTML_TAG
+        ),
+        Rule::new(
+            Constant,
+            r"&(?:[A-Za-z][A-Za-z0-9]*|#[0-9]+|#[xX][0-9a-fA-F]+);"
+        ),
+    ];
+    static ref CSS_DECLARATIONS: Vec<Rule> = vec![
+        Rule::new(Comment, r"/\*(?s:.*?)(?:\*/|\z)"),
+        Rule::new(String, DOUBLE_QUOTE_STRING),
+        Rule::new(String, SINGLE_QUOTE_STRING),
+        Rule::new(Property, r"-?[A-Za-z_][\w-]*").followed_by(r"\s*:"),
+        Rule::new(Keyword, r"!\s*important\b"),
+        Rule::new(Number, r"#[0-9a-fA-F]{3,8}\b"),
+        Rule::new(Number, r"-?(?:\d+\.?\d*|\.\d+)(?:%|[A-Za-z]+\b)?"),
+        Rule::new(Function, r"-?[A-Za-z_][\w-]*").followed_by(r"\("),
+        Rule::new(Punctuation, r"[{}:;,]"),
+    ];
+    static ref CSS: Vec<Rule> = vec![
+        Rule::new(Comment, r"/\*(?s:.*?)(?:\*/|\z)"),
+        Rule::new(String, DOUBLE_QUOTE_STRING),
+        Rule::new(String, SINGLE_QUOTE_STRING),
+        Rule::new(Atrule, r"@[\w-]+"),
+        Rule::nested(
+            None,
+            r#"\{(?:[^{}"']|"[^"]*"|'[^']*')*\}"#,
+            &CSS_DECLARATIONS
+        ),
+        Rule::new(Selector, r"[^\s{},;/@](?:[^{},;/]*[^\s{},;/])?")
+            .followed_by(r"\s*[{,]"),
+        Rule::new(Punctuation, r"[{},;]"),
+    ];
+    static ref JSON: Vec<Rule> = vec![
+        Rule::new(Property, r#""(?:[^"\\\n]|\\.)*""#).followed_by(r"\s*:"),
+        Rule::new(String, DOUBLE_QUOTE_STRING),
+        Rule::new(Number, r"-?\b\d+(?:\.\d+)?(?:[eE][+-]?\d+)?\b"),
+        Rule::new(Boolean, &words(&["true", "false", "null"])),
+        Rule::new(Punctuation, r"[{}\[\],:]"),
+    ];
+}
+
+/// Gets the rules for the given language, if it is supported.
+///
+/// The name must already be lowercase.
+pub fn get(language: &str) -> Option<&'static [Rule]> {
+    let rules: &[Rule] = match language {
+        "rust" | "rs" => &RUST,
+        "python" | "python3" | "py" => &PYTHON,
+        "javascript" | "js" | "jsx" | "mjs" | "typescript" | "ts" | "tsx" => &JAVASCRIPT,
+        "c" | "h" | "cpp" | "c++" | "cc" | "cxx" | "hpp" => &C,
+        "html" | "htm" | "xhtml" | "xml" | "svg" => &HTML,
+        "css" => &CSS,
+        "json" => &JSON,
+        _ => return None,
+    };
+
+    Some(rules)
+}
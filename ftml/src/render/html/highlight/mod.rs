@@ -0,0 +1,273 @@
+/*
+ * render/html/highlight/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Syntax highlighting for the contents of `[[code]]` blocks.
+//!
+//! Each language is a list of rules, which are tried in order at each position
+//! in the code. The first one to match produces a token, and if none do, the
+//! character (or whole word) is left as plain text. This is not a full lexer
+//! for any language, but it is enough to mark comments, strings, keywords,
+//! and so on for display.
+//!
+//! Wikitext is the exception, since it can use ftml's own tokenizer.
+//!
+//! Token classes are named after those from [Prism](https://prismjs.com/),
+//! so the same stylesheets work whether highlighting is done here or in the browser.
+
+mod languages;
+mod wikitext;
+
+#[cfg(test)]
+mod test;
+
+use regex::Regex;
+use std::ops::Range;
+
+/// The kind of a highlighted token, which determines its CSS class.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HighlightClass {
+    AttrName,
+    AttrValue,
+    Atrule,
+    Boolean,
+    Builtin,
+    ClassName,
+    Comment,
+    Constant,
+    Doctype,
+    Function,
+    Keyword,
+    Number,
+    Operator,
+    Property,
+    Punctuation,
+    Selector,
+    String,
+    Tag,
+    Url,
+    Variable,
+}
+
+impl HighlightClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            HighlightClass::AttrName => "attr-name",
+            HighlightClass::AttrValue => "attr-value",
+            HighlightClass::Atrule => "atrule",
+            HighlightClass::Boolean => "boolean",
+            HighlightClass::Builtin => "builtin",
+            HighlightClass::ClassName => "class-name",
+            HighlightClass::Comment => "comment",
+            HighlightClass::Constant => "constant",
+            HighlightClass::Doctype => "doctype",
+            HighlightClass::Function => "function",
+            HighlightClass::Keyword => "keyword",
+            HighlightClass::Number => "number",
+            HighlightClass::Operator => "operator",
+            HighlightClass::Property => "property",
+            HighlightClass::Punctuation => "punctuation",
+            HighlightClass::Selector => "selector",
+            HighlightClass::String => "string",
+            HighlightClass::Tag => "tag",
+            HighlightClass::Url => "url",
+            HighlightClass::Variable => "variable",
+        }
+    }
+
+    /// The CSS classes for a token of this kind.
+    pub fn html_class(self) -> String {
+        format!("wj-code-token wj-code-{}", self.name())
+    }
+}
+
+/// A piece of highlighted code, with its class if it isn't plain text.
+pub type HighlightToken<'a> = (Option<HighlightClass>, &'a str);
+
+/// Splits code in the given language into highlighted tokens.
+///
+/// The language name is case-insensitive. Returns `None` if the language is not supported.
+pub fn highlight<'a>(language: &str, code: &'a str) -> Option<Vec<HighlightToken<'a>>> {
+    let language = language.trim().to_ascii_lowercase();
+    let mut tokens = Tokens::default();
+
+    if wikitext::LANGUAGE_NAMES.contains(&language.as_str()) {
+        debug!("Highlighting code as wikitext");
+        wikitext::highlight(code, &mut tokens);
+    } else {
+        let rules = languages::get(&language)?;
+        debug!("Highlighting code as '{language}'");
+        highlight_rules(rules, code, 0..code.len(), None, &mut tokens);
+    }
+
+    Some(tokens.into_slices(code))
+}
+
+/// The list of tokens being built, as byte ranges in the code.
+#[derive(Debug, Default)]
+struct Tokens {
+    inner: Vec<(Option<HighlightClass>, Range<usize>)>,
+}
+
+impl Tokens {
+    /// Adds a token, joining it with the previous one if they have the same class.
+    fn push(&mut self, class: Option<HighlightClass>, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if let Some((last_class, last_range)) = self.inner.last_mut() {
+            if *last_class == class && last_range.end == range.start {
+                last_range.end = range.end;
+                return;
+            }
+        }
+
+        self.inner.push((class, range));
+    }
+
+    fn into_slices(self, code: &str) -> Vec<HighlightToken<'_>> {
+        self.inner
+            .into_iter()
+            .map(|(class, range)| (class, &code[range]))
+            .collect()
+    }
+}
+
+/// A rule which matches one kind of token at the current position.
+#[derive(Debug)]
+pub struct Rule {
+    class: Option<HighlightClass>,
+    regex: Regex,
+    followed_by: Option<Regex>,
+    inner: Option<&'static [Rule]>,
+}
+
+impl Rule {
+    /// Creates a rule which marks the text matching this pattern.
+    pub fn new(class: HighlightClass, pattern: &str) -> Self {
+        Rule {
+            class: Some(class),
+            regex: anchored_regex(pattern),
+            followed_by: None,
+            inner: None,
+        }
+    }
+
+    /// Creates a rule whose match is highlighted using other rules.
+    ///
+    /// Any text not matched by the inner rules is given `class`, if any.
+    pub fn nested(
+        class: Option<HighlightClass>,
+        pattern: &str,
+        inner: &'static [Rule],
+    ) -> Self {
+        Rule {
+            class,
+            regex: anchored_regex(pattern),
+            followed_by: None,
+            inner: Some(inner),
+        }
+    }
+
+    /// Only matches if the text after the match also matches this pattern.
+    ///
+    /// This is needed since the `regex` crate does not support lookahead.
+    pub fn followed_by(mut self, pattern: &str) -> Self {
+        self.followed_by = Some(anchored_regex(pattern));
+        self
+    }
+
+    /// Gets the length of this rule's match at the start of the text, if any.
+    fn match_len(&self, text: &str) -> Option<usize> {
+        let end = self.regex.find(text)?.end();
+        if end == 0 {
+            return None;
+        }
+
+        match self.followed_by {
+            Some(ref regex) if !regex.is_match(&text[end..]) => None,
+            _ => Some(end),
+        }
+    }
+}
+
+fn anchored_regex(pattern: &str) -> Regex {
+    Regex::new(&format!(r"\A(?:{pattern})")).expect("Invalid highlighting regex")
+}
+
+/// Splits part of the code into tokens using the given rules.
+///
+/// Text which is not matched by any rule is given `default_class`.
+fn highlight_rules(
+    rules: &[Rule],
+    code: &str,
+    range: Range<usize>,
+    default_class: Option<HighlightClass>,
+    tokens: &mut Tokens,
+) {
+    let mut index = range.start;
+    let mut plain_start = range.start;
+
+    while index < range.end {
+        let remaining = &code[index..range.end];
+        let matched = rules
+            .iter()
+            .find_map(|rule| rule.match_len(remaining).map(|len| (rule, len)));
+
+        match matched {
+            Some((rule, len)) => {
+                tokens.push(default_class, plain_start..index);
+
+                let token_range = index..index + len;
+                match rule.inner {
+                    Some(inner) => {
+                        highlight_rules(inner, code, token_range, rule.class, tokens)
+                    }
+                    None => tokens.push(rule.class, token_range),
+                }
+
+                index += len;
+                plain_start = index;
+            }
+            None => {
+                // Skip the whole word, so that rules don't
+                // match starting from the middle of one.
+                let mut chars = remaining.char_indices();
+                let (_, ch) = chars.next().expect("No characters remaining");
+
+                index += if is_word_char(ch) {
+                    chars
+                        .find(|&(_, ch)| !is_word_char(ch))
+                        .map(|(offset, _)| offset)
+                        .unwrap_or(remaining.len())
+                } else {
+                    ch.len_utf8()
+                };
+            }
+        }
+    }
+
+    tokens.push(default_class, plain_start..range.end);
+}
+
+#[inline]
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
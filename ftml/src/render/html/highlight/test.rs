@@ -0,0 +1,222 @@
+/*
+ * render/html/highlight/test.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{highlight, HighlightClass};
+use crate::data::PageInfo;
+use crate::render::html::HtmlRender;
+use crate::render::Render;
+use crate::settings::{WikitextMode, WikitextSettings};
+use proptest::prelude::*;
+
+const LANGUAGES: [&str; 8] = [
+    "rust",
+    "python",
+    "javascript",
+    "c",
+    "html",
+    "css",
+    "json",
+    "wikidot",
+];
+
+/// Gets only the highlighted tokens, as `(class name, text)` pairs.
+fn classes<'a>(language: &str, code: &'a str) -> Vec<(&'static str, &'a str)> {
+    highlight(language, code)
+        .expect("Language not supported")
+        .into_iter()
+        .filter_map(|(class, slice)| class.map(|class| (class.name(), slice)))
+        .collect()
+}
+
+#[test]
+fn languages() {
+    assert_eq!(
+        classes("rust", "fn main() { let x = 5_u8; } // done"),
+        vec![
+            ("keyword", "fn"),
+            ("keyword", "let"),
+            ("number", "5_u8"),
+            ("comment", "// done"),
+        ],
+    );
+
+    assert_eq!(
+        classes("Python", "def f(x):\n    return 'a' if x else None"),
+        vec![
+            ("keyword", "def"),
+            ("function", "f"),
+            ("keyword", "return"),
+            ("string", "'a'"),
+            ("keyword", "if"),
+            ("keyword", "else"),
+            ("boolean", "None"),
+        ],
+    );
+
+    assert_eq!(
+        classes("js", "const s = `x${y}`; // if\nnotify(null)"),
+        vec![
+            ("keyword", "const"),
+            ("string", "`x${y}`"),
+            ("comment", "// if"),
+            ("function", "notify"),
+            ("boolean", "null"),
+        ],
+    );
+
+    assert_eq!(
+        classes("c", "#include <stdio.h>\nint main(void) { return 0x1F; }"),
+        vec![
+            ("constant", "#include <stdio.h>"),
+            ("builtin", "int"),
+            ("function", "main"),
+            ("builtin", "void"),
+            ("keyword", "return"),
+            ("number", "0x1F"),
+        ],
+    );
+
+    assert_eq!(
+        classes("html", r#"<!-- x --><a href="/">link</a>"#),
+        vec![
+            ("comment", "<!-- x -->"),
+            ("tag", "<a "),
+            ("attr-name", "href"),
+            ("punctuation", "="),
+            ("attr-value", r#""/""#),
+            ("tag", ">"),
+            ("tag", "</a>"),
+        ],
+    );
+
+    assert_eq!(
+        classes("css", "a:hover, .b { color: #fff !important; }"),
+        vec![
+            ("selector", "a:hover"),
+            ("punctuation", ","),
+            ("selector", ".b"),
+            ("punctuation", "{"),
+            ("property", "color"),
+            ("punctuation", ":"),
+            ("number", "#fff"),
+            ("keyword", "!important"),
+            ("punctuation", ";"),
+            ("punctuation", "}"),
+        ],
+    );
+
+    assert_eq!(
+        classes("json", r#"{"a": [1.5, true, "b"]}"#),
+        vec![
+            ("punctuation", "{"),
+            ("property", r#""a""#),
+            ("punctuation", ":"),
+            ("punctuation", "["),
+            ("number", "1.5"),
+            ("punctuation", ","),
+            ("boolean", "true"),
+            ("punctuation", ","),
+            ("string", r#""b""#),
+            ("punctuation", "]}"),
+        ],
+    );
+}
+
+#[test]
+fn wikitext() {
+    assert_eq!(
+        classes(
+            "wikidot",
+            "+ Title\n[[div class=\"x\"]]\n**bold** [!-- note --] @@**@@\n[[/div]]",
+        ),
+        vec![
+            ("keyword", "+"),
+            ("punctuation", "[["),
+            ("tag", "div"),
+            ("attr-name", "class"),
+            ("operator", "="),
+            ("string", "\"x\""),
+            ("punctuation", "]]"),
+            ("operator", "**"),
+            ("operator", "**"),
+            ("comment", "[!-- note --]"),
+            ("punctuation", "@@"),
+            ("punctuation", "@@"),
+            ("punctuation", "[[/"),
+            ("tag", "div"),
+            ("punctuation", "]]"),
+        ],
+    );
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(highlight("none", "abc"), None);
+    assert_eq!(highlight("", "abc"), None);
+}
+
+#[test]
+fn render() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    let render = |text: &str| {
+        let tokens = crate::tokenize(text);
+        let (tree, _) = crate::parse(&tokens, &page_info, &settings).into();
+        let html = HtmlRender::default()
+            .render(&tree, &page_info, &settings)
+            .body;
+        let start = html.find("<code>").expect("No code element") + 6;
+        let end = html.find("</code>").expect("No code element end");
+        html[start..end].to_string()
+    };
+
+    assert_eq!(
+        render("[[code type=\"rust\"]]\nlet s = \"<a>\";\n[[/code]]"),
+        concat!(
+            r#"<span class="wj-code-token wj-code-keyword">let</span> s = "#,
+            r#"<span class="wj-code-token wj-code-string">&quot;&lt;a&gt;&quot;</span>;"#,
+        ),
+    );
+
+    // Without a known language, the code is left alone
+    assert_eq!(
+        render("[[code type=\"unknown\"]]\nlet s = \"<a>\";\n[[/code]]"),
+        "let s = &quot;&lt;a&gt;&quot;;",
+    );
+}
+
+#[test]
+fn class_names() {
+    assert_eq!(
+        HighlightClass::ClassName.html_class(),
+        "wj-code-token wj-code-class-name",
+    );
+}
+
+proptest! {
+    #[test]
+    fn highlight_prop(code in ".*", index in 0..LANGUAGES.len()) {
+        // Highlighting must not lose or reorder any text
+        let tokens = highlight(LANGUAGES[index], &code).unwrap();
+        let joined: String = tokens.iter().map(|(_, slice)| *slice).collect();
+        prop_assert_eq!(joined, code);
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ * render/html/highlight/wikitext.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Highlighting for wikitext, using the tokens from ftml's own lexer.
+//!
+//! Since highlighting does not run the parser, the meaning of each token
+//! is estimated from those around it, such as which block it is inside.
+
+use super::{HighlightClass, Tokens};
+use crate::parsing::Token;
+
+pub const LANGUAGE_NAMES: [&str; 4] = ["wikidot", "wikijump", "wikitext", "ftml"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    /// Regular text.
+    Text,
+
+    /// After the start of a block, expecting its name.
+    BlockName,
+
+    /// After a block's name, until the end of the block.
+    Block,
+
+    /// Inside a triple-bracket link.
+    Link,
+
+    /// Inside a comment, until its end.
+    Comment,
+
+    /// Inside some escaped text, until the given token closes it.
+    Raw(Token),
+}
+
+pub fn highlight(code: &str, tokens: &mut Tokens) {
+    let tokenization = crate::tokenize(code);
+    let all_tokens = tokenization.tokens();
+    let mut state = State::Text;
+    let mut line_start = true;
+
+    for (index, extracted) in all_tokens.iter().enumerate() {
+        let token = extracted.token;
+        let next_token = || {
+            all_tokens[index + 1..]
+                .iter()
+                .map(|extracted| extracted.token)
+                .find(|&token| token != Token::Whitespace)
+        };
+
+        let (class, new_state) = match (state, token) {
+            // Comments and escaped text
+            (State::Comment, Token::RightComment) => {
+                (Some(HighlightClass::Comment), State::Text)
+            }
+            (State::Comment, _) => (Some(HighlightClass::Comment), State::Comment),
+            (State::Raw(end), _) if token == end => {
+                (Some(HighlightClass::Punctuation), State::Text)
+            }
+            (State::Raw(end), _) => (None, State::Raw(end)),
+            (_, Token::LeftComment) => (Some(HighlightClass::Comment), State::Comment),
+            (_, Token::Raw) => {
+                (Some(HighlightClass::Punctuation), State::Raw(Token::Raw))
+            }
+            (_, Token::LeftRaw) => (
+                Some(HighlightClass::Punctuation),
+                State::Raw(Token::RightRaw),
+            ),
+            (_, Token::LeftMath) => (
+                Some(HighlightClass::Punctuation),
+                State::Raw(Token::RightMath),
+            ),
+
+            // Blocks
+            (
+                _,
+                Token::LeftBlock
+                | Token::LeftBlockEnd
+                | Token::LeftBlockAnchor
+                | Token::LeftBlockStar,
+            ) => (Some(HighlightClass::Punctuation), State::BlockName),
+            (State::BlockName, Token::Identifier) => {
+                (Some(HighlightClass::Tag), State::Block)
+            }
+            (State::BlockName, Token::Whitespace) => (None, State::BlockName),
+            (State::BlockName | State::Block, Token::RightBlock) => {
+                (Some(HighlightClass::Punctuation), State::Text)
+            }
+            (State::Block, Token::Identifier) if next_token() == Some(Token::Equals) => {
+                (Some(HighlightClass::AttrName), State::Block)
+            }
+            (State::BlockName | State::Block, Token::Equals) => {
+                (Some(HighlightClass::Operator), State::Block)
+            }
+            (State::BlockName | State::Block, Token::String) => {
+                (Some(HighlightClass::String), State::Block)
+            }
+
+            // Links
+            (_, Token::LeftLink | Token::LeftLinkStar) => {
+                (Some(HighlightClass::Punctuation), State::Link)
+            }
+            (State::Link, Token::RightLink) => {
+                (Some(HighlightClass::Punctuation), State::Text)
+            }
+            (State::Link, Token::Pipe) => {
+                (Some(HighlightClass::Punctuation), State::Link)
+            }
+            (_, Token::Url | Token::Email) => (Some(HighlightClass::Url), state),
+            (State::Link, Token::LineBreak | Token::ParagraphBreak) => {
+                (None, State::Text)
+            }
+            (State::Link, _) => (Some(HighlightClass::Url), State::Link),
+
+            // Line breaks end anything else which was unclosed
+            (_, Token::LineBreak | Token::ParagraphBreak) => (None, State::Text),
+            (State::BlockName | State::Block, _) => (None, State::Block),
+
+            // Line-level formatting
+            (
+                State::Text,
+                Token::Heading
+                | Token::BulletItem
+                | Token::NumberedItem
+                | Token::Quote
+                | Token::TripleDash,
+            ) if line_start => (Some(HighlightClass::Keyword), State::Text),
+            (
+                State::Text,
+                Token::TableColumn
+                | Token::TableColumnLeft
+                | Token::TableColumnRight
+                | Token::TableColumnCenter
+                | Token::TableColumnTitle,
+            ) => (Some(HighlightClass::Punctuation), State::Text),
+            (
+                State::Text,
+                Token::ClearFloatBoth | Token::ClearFloatLeft | Token::ClearFloatRight,
+            ) => (Some(HighlightClass::Keyword), State::Text),
+
+            // Inline formatting
+            (
+                State::Text,
+                Token::Bold
+                | Token::Italics
+                | Token::Underline
+                | Token::Superscript
+                | Token::Subscript
+                | Token::LeftMonospace
+                | Token::RightMonospace
+                | Token::DoubleDash
+                | Token::Color,
+            ) => (Some(HighlightClass::Operator), State::Text),
+            (State::Text, Token::Variable | Token::BibliographyCite) => {
+                (Some(HighlightClass::Variable), State::Text)
+            }
+
+            (_, _) => (None, state),
+        };
+
+        tokens.push(class, extracted.span.clone());
+        state = new_state;
+        line_start = match token {
+            Token::InputStart | Token::LineBreak | Token::ParagraphBreak => true,
+            Token::Whitespace => line_start,
+            _ => false,
+        };
+    }
+}
@@ -26,7 +26,9 @@ mod attributes;
 mod builder;
 mod context;
 mod element;
-mod escape;
+pub(crate) mod escape;
+#[cfg(feature = "highlight")]
+mod highlight;
 mod meta;
 mod output;
 mod random;
@@ -40,15 +42,79 @@ use super::prelude;
 
 use self::attributes::AddedAttributes;
 use self::context::HtmlContext;
+use self::element::render_elements;
 use crate::data::PageInfo;
-use crate::render::{Handle, Render};
+use crate::render::{DefaultHandle, Handle, Render};
 use crate::settings::WikitextSettings;
-use crate::tree::SyntaxTree;
+use crate::tree::{BibliographyList, Element, SyntaxTree};
 
-#[derive(Debug)]
-pub struct HtmlRender;
+#[derive(Debug, Copy, Clone)]
+pub struct HtmlRender<'h> {
+    handle: &'h dyn Handle,
+    source_spans: bool,
+}
+
+impl<'h> HtmlRender<'h> {
+    /// Creates a new HTML renderer which fetches external data using the given handle.
+    #[inline]
+    pub fn new(handle: &'h dyn Handle) -> Self {
+        HtmlRender {
+            handle,
+            source_spans: false,
+        }
+    }
+
+    /// Sets whether source spans are emitted in the output.
+    ///
+    /// If enabled, elements which were parsed with
+    /// [`WikitextSettings::record_spans`] have their byte offsets
+    /// added to their outermost tag as `data-source-start` and
+    /// `data-source-end` attributes.
+    ///
+    /// [`WikitextSettings::record_spans`]: crate::settings::WikitextSettings::record_spans
+    #[inline]
+    pub fn source_spans(mut self, value: bool) -> Self {
+        self.source_spans = value;
+        self
+    }
+
+    /// Renders the given elements, returning only the HTML body.
+    ///
+    /// Unlike [`render()`], the output is not wrapped in a `<wj-body>` element,
+    /// and there is no table of contents or footnote list to reference.
+    ///
+    /// [`render()`]: crate::render::Render::render
+    pub fn render_partial(
+        &self,
+        elements: &[Element],
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> String {
+        let bibliography = BibliographyList::default();
+        let mut ctx = HtmlContext::new(
+            page_info,
+            self.handle,
+            settings,
+            &[],
+            &[],
+            &bibliography,
+            self.source_spans,
+        );
+        render_elements(&mut ctx, elements);
+
+        let output: HtmlOutput = ctx.into();
+        output.body
+    }
+}
+
+impl Default for HtmlRender<'static> {
+    #[inline]
+    fn default() -> Self {
+        HtmlRender::new(&DefaultHandle)
+    }
+}
 
-impl Render for HtmlRender {
+impl Render for HtmlRender<'_> {
     type Output = HtmlOutput;
 
     fn render(
@@ -69,10 +135,12 @@ impl Render for HtmlRender {
 
         let mut ctx = HtmlContext::new(
             page_info,
-            &Handle,
+            self.handle,
             settings,
             &tree.table_of_contents,
             &tree.footnotes,
+            &tree.bibliography,
+            self.source_spans,
         );
 
         // Add styles
@@ -20,15 +20,102 @@
 
 use super::prelude::*;
 use super::HtmlRender;
+use crate::Utf16IndexMap;
 
 #[test]
 fn html() {
     let page_info = PageInfo::dummy();
     let settings = WikitextSettings::from_mode(WikitextMode::Page);
 
-    let result = SyntaxTree::from_element_result(vec![], vec![], vec![], vec![], vec![]);
+    let result = SyntaxTree::from_element_result(
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        crate::tree::BibliographyList::default(),
+    );
     let (tree, _) = result.into();
     if false {
-        let _output = HtmlRender.render(&tree, &page_info, &settings);
+        let _output = HtmlRender::default().render(&tree, &page_info, &settings);
     }
 }
+
+#[test]
+fn source_spans() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings {
+        record_spans: true,
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    };
+
+    let text = "Café **bold**\n\n[[span]]x[[/span]]";
+    let tokens = crate::tokenize(text);
+    let (tree, _) = crate::parse(&tokens, &page_info, &settings).into();
+
+    let render = |tree: &SyntaxTree| {
+        HtmlRender::default()
+            .source_spans(true)
+            .render(tree, &page_info, &settings)
+            .body
+    };
+
+    assert_eq!(
+        render(&tree),
+        concat!(
+            r#"<wj-body class="wj-body">"#,
+            r#"<p data-source-start="0" data-source-end="14">Café "#,
+            r#"<strong data-source-start="6" data-source-end="14">bold</strong></p>"#,
+            r#"<p data-source-start="16" data-source-end="34">"#,
+            r#"<span data-source-start="16" data-source-end="34">x</span></p>"#,
+            "</wj-body>",
+        ),
+    );
+
+    // With UTF-16 indices, the two-byte 'é' only counts once
+    let tree = tree.to_utf16_indices(&Utf16IndexMap::new(text));
+
+    assert_eq!(
+        render(&tree),
+        concat!(
+            r#"<wj-body class="wj-body">"#,
+            r#"<p data-source-start="0" data-source-end="13">Café "#,
+            r#"<strong data-source-start="5" data-source-end="13">bold</strong></p>"#,
+            r#"<p data-source-start="15" data-source-end="33">"#,
+            r#"<span data-source-start="15" data-source-end="33">x</span></p>"#,
+            "</wj-body>",
+        ),
+    );
+
+    // Spans are not emitted unless requested
+    let output = HtmlRender::default().render(&tree, &page_info, &settings);
+    assert!(!output.body.contains("data-source-start"));
+}
+
+#[test]
+fn gallery_local_paths() {
+    let page_info = PageInfo::dummy();
+    let text = "[[gallery]]\n: local.png\n: https://example.com/remote.png\n[[/gallery]]";
+
+    let render = |settings: &WikitextSettings| {
+        let tokens = crate::tokenize(text);
+        let (tree, _) = crate::parse(&tokens, &page_info, settings).into();
+        HtmlRender::default()
+            .render(&tree, &page_info, settings)
+            .body
+    };
+
+    // Attached files are shown when local paths are allowed
+    let output = render(&WikitextSettings::from_mode(WikitextMode::Page));
+    assert!(output.contains(r#"alt="local.png""#));
+    assert!(output.contains(r#"src="https://example.com/remote.png""#));
+
+    // Otherwise only URLs are resolved
+    let output = render(&WikitextSettings {
+        allow_local_paths: false,
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    });
+    assert!(!output.contains(r#"alt="local.png""#));
+    assert!(output.contains("wj-gallery-item wj-error-inline"));
+    assert!(output.contains(r#"src="https://example.com/remote.png""#));
+}
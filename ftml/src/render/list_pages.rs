@@ -0,0 +1,197 @@
+/*
+ * render/list_pages.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Shared logic for rendering `[[module ListPages]]`.
+//!
+//! Each matched page is substituted into the module's template, and the
+//! resultant wikitext is then parsed in [`WikitextMode::List`] and rendered
+//! in place of the module.
+
+use super::prelude::*;
+use super::wikitext::escape::escape;
+use crate::data::{ListPagesEntry, ListPagesResult};
+use crate::tree::ListPagesQuery;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+lazy_static! {
+    static ref VARIABLE_REGEX: Regex =
+        Regex::new(r"%%(?P<name>[a-z_]+)(?:\|(?P<format>.*?))?%%").unwrap();
+}
+
+/// Produces the wikitext sources to be rendered for this module.
+///
+/// If the query has `separate` set, then there is one per page,
+/// otherwise all the expanded templates are joined into one.
+pub fn build_sources(
+    template: &str,
+    query: &ListPagesQuery,
+    result: &ListPagesResult,
+) -> Vec<String> {
+    let start = (result.current_page.get() as usize - 1) * query.per_page.get() as usize;
+    let sources = result.entries.iter().enumerate().map(|(index, entry)| {
+        expand_template(template, entry, start + index + 1, result.total)
+    });
+
+    if query.separate {
+        sources.collect()
+    } else {
+        let sources: Vec<String> = sources.collect();
+        vec![sources.join("\n")]
+    }
+}
+
+/// Substitutes the variables for this page into the template.
+///
+/// The index is the one-based position of this page among all matches.
+/// Unknown variables are left as-is.
+///
+/// Page titles, names, authors, and tags are escaped, so that they appear as
+/// written rather than being interpreted as wikitext.
+/// Authors which cannot be written in a user block are shown as plain text.
+pub fn expand_template(
+    template: &str,
+    entry: &ListPagesEntry,
+    index: usize,
+    total: usize,
+) -> String {
+    let output = VARIABLE_REGEX.replace_all(template, |captures: &Captures| {
+        let start = captures.get(0).unwrap().start();
+        let line_start = template[..start]
+            .rsplit('\n')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .is_empty();
+
+        let format = captures.name("format").map(|mat| mat.as_str());
+        let text = |value: &str| {
+            let mut buffer = String::new();
+            escape(&mut buffer, value, line_start);
+            Cow::Owned(buffer)
+        };
+
+        let value: Cow<str> = match &captures["name"] {
+            "title" => text(&entry.title),
+            "title_linked" => Cow::Owned(format!(
+                "[[[{} | {} ]]]",
+                entry.slug,
+                link_label(&entry.title),
+            )),
+            "link" => Cow::Owned(format!("/{}", entry.slug)),
+            "name" => text(split_category(&entry.slug).1),
+            "fullname" => text(&entry.slug),
+            "category" => text(split_category(&entry.slug).0),
+            "content" => Cow::Borrowed(&entry.content),
+            "rating" => Cow::Owned(format!("{:+}", entry.rating)),
+            "created_at" => Cow::Owned(date_block(entry.created_at, format)),
+            "updated_at" => Cow::Owned(date_block(entry.updated_at, format)),
+            "created_by" => text(&entry.created_by),
+            "created_by_linked" => match user_block(&entry.created_by) {
+                Some(block) => Cow::Owned(block),
+                None => text(&entry.created_by),
+            },
+            "tags" => text(&entry.tags.join(" ")),
+            "index" => Cow::Owned(index.to_string()),
+            "total" => Cow::Owned(total.to_string()),
+            _ => Cow::Borrowed(&captures[0]),
+        };
+
+        value.into_owned()
+    });
+
+    output.into_owned()
+}
+
+/// Parses an expanded template, so it can be rendered.
+///
+/// This is done in list mode, so that pages being listed cannot
+/// themselves contain `ListPages` modules.
+pub fn parse_source(
+    source: &str,
+    page_info: &PageInfo,
+    settings: &WikitextSettings,
+) -> SyntaxTree<'static> {
+    let settings = WikitextSettings {
+        interwiki: settings.interwiki.clone(),
+        urls: settings.urls.clone(),
+        css: settings.css.clone(),
+        ..WikitextSettings::from_mode(WikitextMode::List)
+    };
+
+    let mut text = str!(source);
+    crate::preprocess(&mut text);
+
+    let tokens = crate::tokenize(&text);
+    let (tree, _warnings) = crate::parse(&tokens, page_info, &settings).into();
+    tree.to_owned()
+}
+
+/// Gets the total number of pagination pages.
+#[cfg(feature = "html")]
+pub fn page_count(query: &ListPagesQuery, result: &ListPagesResult) -> u32 {
+    let per_page = query.per_page.get() as usize;
+    let pages = result.total.div_ceil(per_page);
+    u32::try_from(pages).unwrap_or(u32::MAX)
+}
+
+/// Makes a title usable as the label of a triple-bracket link.
+///
+/// The label is not parsed as wikitext, but ends at a newline or `]]]`,
+/// so these are broken up.
+fn link_label(title: &str) -> String {
+    title.replace(['\r', '\n'], " ").replace("]]]", "]] ]")
+}
+
+/// Makes a user block showing the given user, if their name can be written in one.
+///
+/// The name in the block's head is not parsed as wikitext, but it ends at
+/// a bracket or newline, and is still preprocessed. Names which would not
+/// survive this cannot be used.
+fn user_block(name: &str) -> Option<String> {
+    let mut preprocessed = str!(name);
+    crate::preprocess(&mut preprocessed);
+
+    let usable = !name.is_empty()
+        && name == name.trim()
+        && name == preprocessed
+        && !name.contains(['[', ']', '\r', '\n']);
+
+    usable.then(|| format!("[[*user {name}]]"))
+}
+
+fn split_category(slug: &str) -> (&str, &str) {
+    match slug.split_once(':') {
+        Some((category, name)) => (category, name),
+        None => ("_default", slug),
+    }
+}
+
+fn date_block(timestamp: i64, format: Option<&str>) -> String {
+    match format {
+        Some(format) => {
+            format!(
+                "[[date {timestamp} format=\"{}\"]]",
+                format.replace('"', "")
+            )
+        }
+        None => format!("[[date {timestamp}]]"),
+    }
+}
@@ -0,0 +1,368 @@
+/*
+ * render/markdown/context.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::escape::escape;
+use crate::data::PageInfo;
+use crate::render::html::HtmlRender;
+use crate::render::Handle;
+use crate::settings::WikitextSettings;
+use crate::tree::{BibliographyList, Element, VariableScopes};
+use std::fmt::{self, Write};
+use std::mem;
+use std::num::NonZeroUsize;
+
+#[derive(Debug)]
+pub struct MarkdownContext<'i, 'h, 'e, 't>
+where
+    'e: 't,
+{
+    output: String,
+    info: &'i PageInfo<'i>,
+    handle: &'h dyn Handle,
+    settings: &'e WikitextSettings,
+
+    //
+    // Included page scopes
+    //
+    variables: VariableScopes,
+
+    //
+    // Elements from the syntax tree
+    //
+    table_of_contents: &'e [Element<'t>],
+    footnotes: &'e [Vec<Element<'t>>],
+    bibliography: &'e BibliographyList<'t>,
+
+    //
+    // Other fields to track
+    //
+    /// Strings to prepend to each new line, such as `> ` for blockquotes.
+    prefixes: Vec<String>,
+
+    /// How many line breaks are waiting to be written.
+    newlines: usize,
+
+    /// Whether the next text written starts a new line.
+    ///
+    /// If so, then the prefixes are added first.
+    line_start: bool,
+
+    /// How many prefixes apply to any pending blank lines.
+    ///
+    /// This is the fewest prefixes active since the line ended,
+    /// so that blank lines after a blockquote are not part of it.
+    blank_prefixes: usize,
+
+    /// Whether the pending newline is a hard line break.
+    hard_break: bool,
+
+    /// How deep we currently are in the list.
+    list_depth: usize,
+
+    /// The current footnote index, for rendering.
+    footnote_index: NonZeroUsize,
+}
+
+impl<'i, 'h, 'e, 't> MarkdownContext<'i, 'h, 'e, 't>
+where
+    'e: 't,
+{
+    #[inline]
+    pub fn new(
+        info: &'i PageInfo<'i>,
+        handle: &'h dyn Handle,
+        settings: &'e WikitextSettings,
+        table_of_contents: &'e [Element<'t>],
+        footnotes: &'e [Vec<Element<'t>>],
+        bibliography: &'e BibliographyList<'t>,
+    ) -> Self {
+        MarkdownContext {
+            output: String::new(),
+            info,
+            handle,
+            settings,
+            variables: VariableScopes::new(),
+            table_of_contents,
+            footnotes,
+            bibliography,
+            prefixes: Vec::new(),
+            newlines: 0,
+            line_start: true,
+            blank_prefixes: 0,
+            hard_break: false,
+            list_depth: 0,
+            footnote_index: NonZeroUsize::new(1).unwrap(),
+        }
+    }
+
+    // Getters
+    #[inline]
+    pub fn info(&self) -> &'i PageInfo<'i> {
+        self.info
+    }
+
+    #[inline]
+    pub fn settings(&self) -> &WikitextSettings {
+        self.settings
+    }
+
+    #[inline]
+    pub fn language(&self) -> &str {
+        &self.info.language
+    }
+
+    #[inline]
+    pub fn handle(&self) -> &'h dyn Handle {
+        self.handle
+    }
+
+    #[inline]
+    pub fn variables(&self) -> &VariableScopes {
+        &self.variables
+    }
+
+    #[inline]
+    pub fn variables_mut(&mut self) -> &mut VariableScopes {
+        &mut self.variables
+    }
+
+    #[inline]
+    pub fn table_of_contents(&self) -> &'e [Element<'t>] {
+        self.table_of_contents
+    }
+
+    #[inline]
+    pub fn footnotes(&self) -> &'e [Vec<Element<'t>>] {
+        self.footnotes
+    }
+
+    #[inline]
+    pub fn bibliography(&self) -> &'e BibliographyList<'t> {
+        self.bibliography
+    }
+
+    pub fn next_footnote_index(&mut self) -> NonZeroUsize {
+        let index = self.footnote_index;
+        self.footnote_index = NonZeroUsize::new(index.get() + 1).unwrap();
+        index
+    }
+
+    // Prefixes
+    #[inline]
+    pub fn push_prefix<S: Into<String>>(&mut self, prefix: S) {
+        self.prefixes.push(prefix.into());
+    }
+
+    #[inline]
+    pub fn pop_prefix(&mut self) {
+        self.prefixes.pop();
+        self.blank_prefixes = self.blank_prefixes.min(self.prefixes.len());
+    }
+
+    // List depth
+    #[inline]
+    pub fn list_depth(&self) -> usize {
+        self.list_depth
+    }
+
+    #[inline]
+    pub fn incr_list_depth(&mut self) {
+        self.list_depth += 1;
+    }
+
+    #[inline]
+    pub fn decr_list_depth(&mut self) {
+        self.list_depth -= 1;
+    }
+
+    // Buffer management
+
+    /// Adds any pending newlines and the line prefixes,
+    /// if we are at the start of a line.
+    ///
+    /// Newlines are only written once there is more text to add,
+    /// so that the output never has trailing blank lines.
+    fn start_line(&mut self) {
+        if !self.line_start {
+            return;
+        }
+
+        if !self.output.is_empty() {
+            // A hard line break only applies within a block
+            if self.hard_break && self.newlines == 1 {
+                self.output.push('\\');
+            }
+
+            for i in 0..self.newlines {
+                // Blank lines still need their prefixes (e.g. blockquotes),
+                // but without trailing whitespace.
+                if i > 0 {
+                    let start = self.output.len();
+
+                    for prefix in &self.prefixes[..self.blank_prefixes] {
+                        self.output.push_str(prefix);
+                    }
+
+                    let trimmed = self.output[start..].trim_end().len();
+                    self.output.truncate(start + trimmed);
+                }
+
+                self.output.push('\n');
+            }
+        }
+
+        for prefix in &self.prefixes {
+            self.output.push_str(prefix);
+        }
+
+        self.newlines = 0;
+        self.line_start = false;
+        self.hard_break = false;
+    }
+
+    /// Appends text to the output, as-is.
+    ///
+    /// Any newlines in the string are passed to `add_newline()`,
+    /// so that each line receives the appropriate prefixes.
+    pub fn push_raw_str(&mut self, s: &str) {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.add_newline();
+            }
+
+            if !line.is_empty() {
+                self.start_line();
+                self.output.push_str(line);
+            }
+        }
+    }
+
+    /// Appends text to the output, escaping any Markdown syntax.
+    pub fn push_escaped(&mut self, s: &str) {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.add_newline();
+            }
+
+            if !line.is_empty() {
+                let line_start = self.line_start;
+                self.start_line();
+                escape(&mut self.output, line, line_start);
+            }
+        }
+    }
+
+    /// Appends the given elements as HTML.
+    ///
+    /// This is used for elements which have no Markdown equivalent.
+    /// Markdown permits raw HTML, so these are passed through as-is.
+    pub fn push_html(&mut self, elements: &[Element]) {
+        let html = HtmlRender::new(self.handle).render_partial(
+            elements,
+            self.info,
+            self.settings,
+        );
+
+        self.push_raw_str(&html);
+    }
+
+    pub fn add_newline(&mut self) {
+        if !self.line_start {
+            self.blank_prefixes = self.prefixes.len();
+        }
+
+        self.newlines += 1;
+        self.line_start = true;
+    }
+
+    /// Adds a hard line break, which continues the current block.
+    ///
+    /// If the block ends instead, then this has no effect.
+    pub fn add_line_break(&mut self) {
+        if !self.line_start {
+            self.add_newline();
+            self.hard_break = true;
+        }
+    }
+
+    /// Ends the current line, if there is anything on it.
+    pub fn end_line(&mut self) {
+        if !self.line_start {
+            self.add_newline();
+        }
+    }
+
+    /// Ensures that the next text written starts a new block.
+    ///
+    /// In Markdown, blocks such as paragraphs and lists
+    /// are separated by a blank line.
+    pub fn end_block(&mut self) {
+        self.end_line();
+        self.newlines = self.newlines.max(2);
+    }
+
+    #[inline]
+    pub fn at_line_start(&self) -> bool {
+        self.line_start
+    }
+
+    /// Renders into a separate buffer, returning its contents.
+    ///
+    /// This is used for things which need to be post-processed,
+    /// such as table cells, which cannot span multiple lines.
+    pub fn capture<F>(&mut self, f: F) -> String
+    where
+        F: FnOnce(&mut Self),
+    {
+        let output = mem::take(&mut self.output);
+        let prefixes = mem::take(&mut self.prefixes);
+        let newlines = mem::replace(&mut self.newlines, 0);
+        let line_start = mem::replace(&mut self.line_start, true);
+        let blank_prefixes = mem::replace(&mut self.blank_prefixes, 0);
+        let hard_break = mem::replace(&mut self.hard_break, false);
+
+        f(self);
+
+        self.hard_break = hard_break;
+        self.blank_prefixes = blank_prefixes;
+        self.line_start = line_start;
+        self.newlines = newlines;
+        self.prefixes = prefixes;
+        mem::replace(&mut self.output, output)
+    }
+}
+
+impl<'i, 'h, 'e, 't> From<MarkdownContext<'i, 'h, 'e, 't>> for String {
+    #[inline]
+    fn from(ctx: MarkdownContext<'i, 'h, 'e, 't>) -> String {
+        ctx.output
+    }
+}
+
+impl<'i, 'h, 'e, 't> Write for MarkdownContext<'i, 'h, 'e, 't>
+where
+    'e: 't,
+{
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_raw_str(s);
+        Ok(())
+    }
+}
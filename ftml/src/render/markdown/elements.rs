@@ -0,0 +1,618 @@
+/*
+ * render/markdown/elements.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Module that implements Markdown rendering for `Element` and its children.
+
+use super::escape::escape;
+use super::MarkdownContext;
+use crate::render::html::escape::escape as escape_html;
+use crate::tree::{
+    Alignment, Container, ContainerType, Element, LinkLocation, ListItem, ListType, Table,
+};
+use crate::url::normalize_link;
+use std::borrow::Cow;
+use std::slice;
+
+pub fn render_elements(ctx: &mut MarkdownContext, elements: &[Element]) {
+    info!("Rendering elements (length {})", elements.len());
+
+    for element in elements {
+        render_element(ctx, element);
+    }
+}
+
+pub fn render_element(ctx: &mut MarkdownContext, element: &Element) {
+    info!("Rendering element {}", element.name());
+
+    match element {
+        Element::Container(container) => render_container(ctx, container),
+        Element::Module(_) => {
+            // Modules are dynamic, and so have no static Markdown form.
+        }
+        Element::Text(text) | Element::Raw(text) => ctx.push_escaped(text),
+        Element::Email(email) => str_write!(ctx, "<{email}>"),
+        Element::Variable(name) => {
+            let value = match ctx.variables().get(name) {
+                Some(value) => str!(value),
+                None => format!("{{${name}}}"),
+            };
+
+            info!(
+                "Rendering variable (name '{}', value {})",
+                name.as_ref(),
+                value,
+            );
+            ctx.push_escaped(&value);
+        }
+        Element::Table(table) => render_table(ctx, element, table),
+        Element::Anchor {
+            elements,
+            attributes,
+            ..
+        } => match attributes.get().get("href") {
+            Some(href) => {
+                let link = LinkLocation::parse(cow!(href));
+                let label = ctx.capture(|ctx| render_elements(ctx, elements));
+                let url = get_url_from_link(ctx, &link);
+
+                str_write!(ctx, "[{label}]({url})");
+            }
+            None => render_elements(ctx, elements),
+        },
+        Element::Link { link, label, .. } => {
+            let url = get_url_from_link(ctx, link);
+            let mut label_text = String::new();
+
+            ctx.handle().get_link_label(link, label, |label| {
+                escape(&mut label_text, label, false);
+            });
+
+            str_write!(ctx, "[{label_text}]({url})");
+        }
+        Element::Image {
+            source,
+            link,
+            attributes,
+            ..
+        } => {
+            let source_url =
+                ctx.handle()
+                    .get_image_link(source, ctx.info(), ctx.settings());
+
+            let url = match source_url {
+                Some(url) => url,
+                None => return render_html_block(ctx, element),
+            };
+
+            let mut image = String::from("![");
+            if let Some(alt_text) = attributes.get().get("alt") {
+                escape(&mut image, alt_text, false);
+            }
+
+            str_write!(image, "]({}", encode_url(&url));
+            if let Some(title) = attributes.get().get("title") {
+                let title = title.replace('\\', "\\\\").replace('"', "\\\"");
+                str_write!(image, " \"{title}\"");
+            }
+            image.push(')');
+
+            match link {
+                Some(link) => {
+                    let url = get_url_from_link(ctx, link);
+                    str_write!(ctx, "[{image}]({url})");
+                }
+                None => ctx.push_raw_str(&image),
+            }
+        }
+        Element::Gallery { items, .. } => {
+            let mut images = Vec::with_capacity(items.len());
+
+            for item in items {
+                let source_url =
+                    ctx.handle()
+                        .get_image_link(&item.source, ctx.info(), ctx.settings());
+
+                let url = match source_url {
+                    Some(url) => url,
+                    None => return render_html_block(ctx, element),
+                };
+
+                let mut image = String::from("![");
+                match &item.title {
+                    Some(title) => escape(&mut image, title, false),
+                    None => escape(&mut image, item.source.file_name(), false),
+                }
+                str_write!(image, "]({})", encode_url(&url));
+
+                if let Some(link) = &item.link {
+                    let url = get_url_from_link(ctx, link);
+                    image = format!("[{image}]({url})");
+                }
+
+                images.push(image);
+            }
+
+            // Markdown has no galleries, so the images are shown as a list
+            ctx.end_block();
+
+            for image in images {
+                ctx.end_line();
+                ctx.push_raw_str("- ");
+                ctx.push_raw_str(&image);
+            }
+
+            ctx.end_block();
+        }
+        Element::List { ltype, items, .. } => {
+            // Nested lists must not be separated from their parent item
+            if ctx.list_depth() == 0 {
+                ctx.end_block();
+            } else {
+                ctx.end_line();
+            }
+
+            ctx.incr_list_depth();
+
+            let mut index = 1;
+            let mut marker_width = 2;
+
+            for item in items {
+                match item {
+                    ListItem::Elements { elements, .. } => {
+                        // Don't do anything if it's empty
+                        if elements.is_empty() {
+                            continue;
+                        }
+
+                        let marker = match ltype {
+                            ListType::Bullet | ListType::Generic => str!("- "),
+                            ListType::Numbered => format!("{index}. "),
+                        };
+
+                        index += 1;
+                        marker_width = marker.len();
+
+                        // Render elements for this list item,
+                        // indenting any further lines past the marker.
+                        let contents = ctx.capture(|ctx| render_elements(ctx, elements));
+
+                        ctx.end_line();
+                        ctx.push_raw_str(&marker);
+                        ctx.push_prefix(" ".repeat(marker_width));
+                        ctx.push_raw_str(&contents);
+                        ctx.pop_prefix();
+                    }
+                    ListItem::SubList { element } => {
+                        ctx.push_prefix(" ".repeat(marker_width));
+                        render_element(ctx, element);
+                        ctx.pop_prefix();
+                    }
+                }
+            }
+
+            ctx.decr_list_depth();
+
+            if ctx.list_depth() == 0 {
+                ctx.end_block();
+            }
+        }
+        Element::RadioButton { .. }
+        | Element::AnchorName(_)
+        | Element::MathInline { .. }
+        | Element::EquationReference(_) => {
+            ctx.push_html(slice::from_ref(element));
+        }
+        Element::TabView(_)
+        | Element::DefinitionList(_)
+        | Element::Math { .. }
+        | Element::Embed(_)
+        | Element::Html { .. }
+        | Element::Iframe { .. } => render_html_block(ctx, element),
+        Element::CheckBox { checked, .. } => {
+            // Becomes a task list item, if at the start of a list item
+            ctx.push_raw_str(if *checked { "[x] " } else { "[ ] " });
+        }
+        Element::Collapsible {
+            elements,
+            start_open,
+            show_text,
+            ..
+        } => {
+            let show_text = match show_text {
+                Some(text) => Cow::Borrowed(text.as_ref()),
+                None => ctx.handle().get_message(ctx.language(), "collapsible-open"),
+            };
+
+            let mut summary = String::new();
+            escape_html(&mut summary, &show_text);
+
+            ctx.end_block();
+            ctx.push_raw_str(if *start_open {
+                "<details open>"
+            } else {
+                "<details>"
+            });
+            ctx.add_newline();
+            str_write!(ctx, "<summary>{summary}</summary>");
+            ctx.end_block();
+
+            // Collapsible contents
+            render_elements(ctx, elements);
+
+            ctx.end_block();
+            ctx.push_raw_str("</details>");
+            ctx.end_block();
+        }
+        Element::TableOfContents { .. } => {
+            info!("Rendering table of contents");
+
+            let table_of_contents_title = ctx
+                .handle()
+                .get_message(ctx.language(), "table-of-contents");
+
+            ctx.end_block();
+            ctx.push_escaped(&table_of_contents_title);
+            ctx.end_block();
+            render_elements(ctx, ctx.table_of_contents());
+        }
+        Element::Footnote => {
+            info!("Rendering footnote reference");
+
+            let index = ctx.next_footnote_index();
+            str_write!(ctx, "[^{index}]");
+        }
+        Element::FootnoteBlock { hide, .. } => {
+            info!("Rendering footnote block");
+
+            if *hide || ctx.footnotes().is_empty() {
+                return;
+            }
+
+            // Markdown renderers produce their own footnote list
+            // from the definitions, so no title is needed.
+            for (index, contents) in ctx.footnotes().iter().enumerate() {
+                let contents = ctx.capture(|ctx| render_elements(ctx, contents));
+
+                ctx.end_block();
+                str_write!(ctx, "[^{}]: ", index + 1);
+                ctx.push_prefix("    ");
+                ctx.push_raw_str(&contents);
+                ctx.pop_prefix();
+            }
+
+            ctx.end_block();
+        }
+        Element::BibliographyCite { label } => {
+            info!("Rendering bibliography citation");
+
+            let text = match ctx.bibliography().get(label) {
+                Some((number, _)) => format!("[{number}]"),
+                None => format!("[{label}]"),
+            };
+
+            ctx.push_escaped(&text);
+        }
+        Element::BibliographyBlock { title, entries } => {
+            info!("Rendering bibliography block");
+
+            let title = match title {
+                Some(title) => Cow::Borrowed(title.as_ref()),
+                None => ctx
+                    .handle()
+                    .get_message(ctx.language(), "bibliography-block-title"),
+            };
+
+            ctx.end_block();
+            ctx.push_escaped(&title);
+            ctx.end_block();
+
+            for (number, contents) in ctx.bibliography().get_block_entries(entries) {
+                let contents = ctx.capture(|ctx| render_elements(ctx, contents));
+
+                ctx.end_line();
+                str_write!(ctx, "{number}. ");
+                ctx.push_prefix("   ");
+                ctx.push_raw_str(&contents);
+                ctx.pop_prefix();
+            }
+
+            ctx.end_block();
+        }
+        Element::User { name, .. } => match ctx.handle().get_user_info(name) {
+            Some(info) => {
+                let mut user_name = String::new();
+                escape(&mut user_name, &info.user_name, false);
+                str_write!(ctx, "[{user_name}]({})", encode_url(&info.user_profile_url),);
+            }
+            None => ctx.push_escaped(name),
+        },
+        Element::Date { value, format, .. } => {
+            ctx.push_escaped(&value.format(format.as_ref()));
+        }
+        Element::Color { elements, .. } => render_elements(ctx, elements),
+        Element::Code { contents, language } => {
+            let language = match language {
+                Some(language) => language,
+                None => "",
+            };
+
+            let fence = "`".repeat(longest_backtick_run(contents).max(2) + 1);
+
+            ctx.end_block();
+            str_write!(ctx, "{fence}{language}");
+            ctx.add_newline();
+            ctx.push_raw_str(contents);
+            ctx.add_newline();
+            ctx.push_raw_str(&fence);
+            ctx.end_block();
+        }
+        Element::Include {
+            variables,
+            elements,
+            ..
+        } => {
+            info!(
+                "Rendering include (variables length {}, elements length {})",
+                variables.len(),
+                elements.len(),
+            );
+
+            ctx.variables_mut().push_scope(variables);
+            render_elements(ctx, elements);
+            ctx.variables_mut().pop_scope();
+        }
+        Element::LineBreak => ctx.add_line_break(),
+        Element::LineBreaks(amount) => {
+            ctx.push_raw_str(&"<br>".repeat(amount.get() as usize));
+            ctx.add_newline();
+        }
+        Element::ClearFloat(_) => {
+            // Markdown has no floats to clear
+        }
+        Element::HorizontalRule => {
+            ctx.end_block();
+            ctx.push_raw_str("---");
+            ctx.end_block();
+        }
+        Element::Spanned { element, .. } => render_element(ctx, element),
+        Element::Partial(_) => panic!("Encountered partial element during parsing"),
+    }
+}
+
+fn render_container(ctx: &mut MarkdownContext, container: &Container) {
+    let elements = container.elements();
+
+    match container.ctype() {
+        // Don't render these at all.
+        ContainerType::Hidden | ContainerType::Invisible => (),
+
+        // Markdown formatting
+        ContainerType::Bold => render_wrapped(ctx, elements, "**", "**"),
+        ContainerType::Italics => render_wrapped(ctx, elements, "*", "*"),
+        ContainerType::Strikethrough => render_wrapped(ctx, elements, "~~", "~~"),
+        ContainerType::Monospace => match plain_text(elements) {
+            Some(text) if !text.trim().is_empty() => {
+                let fence = "`".repeat(longest_backtick_run(&text) + 1);
+                let padding = if text.starts_with('`') || text.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+
+                str_write!(ctx, "{fence}{padding}{text}{padding}{fence}");
+            }
+            _ => render_wrapped(ctx, elements, "<code>", "</code>"),
+        },
+
+        // Formatting with no Markdown equivalent, use HTML
+        ContainerType::Underline => render_wrapped(ctx, elements, "<u>", "</u>"),
+        ContainerType::Superscript => render_wrapped(ctx, elements, "<sup>", "</sup>"),
+        ContainerType::Subscript => render_wrapped(ctx, elements, "<sub>", "</sub>"),
+        ContainerType::Mark => render_wrapped(ctx, elements, "<mark>", "</mark>"),
+        ContainerType::Insertion => render_wrapped(ctx, elements, "<ins>", "</ins>"),
+        ContainerType::Deletion => render_wrapped(ctx, elements, "<del>", "</del>"),
+        ContainerType::Ruby => render_wrapped(ctx, elements, "<ruby>", "</ruby>"),
+        ContainerType::RubyText => render_wrapped(ctx, elements, "<rt>", "</rt>"),
+
+        // Blocks
+        ContainerType::Div | ContainerType::Paragraph | ContainerType::Align(_) => {
+            ctx.end_block();
+            render_elements(ctx, elements);
+            ctx.end_block();
+        }
+        ContainerType::Blockquote => {
+            ctx.end_block();
+            ctx.push_prefix("> ");
+            render_elements(ctx, elements);
+            ctx.pop_prefix();
+            ctx.end_block();
+        }
+        ContainerType::Header(heading) => {
+            let contents = ctx.capture(|ctx| render_elements(ctx, elements));
+            let contents = single_line(&contents);
+            let level = heading.level.value() as usize;
+
+            ctx.end_block();
+            str_write!(ctx, "{} {}", "#".repeat(level), contents.trim());
+            ctx.end_block();
+        }
+
+        // Inline or miscellaneous container, styling is dropped.
+        _ => render_elements(ctx, elements),
+    }
+}
+
+/// Renders elements surrounded by the given delimiters.
+///
+/// Markdown emphasis cannot begin or end with whitespace,
+/// so any is moved outside the delimiters.
+fn render_wrapped(
+    ctx: &mut MarkdownContext,
+    elements: &[Element],
+    open: &str,
+    close: &str,
+) {
+    let contents = ctx.capture(|ctx| render_elements(ctx, elements));
+    let trimmed = contents.trim();
+
+    if trimmed.is_empty() {
+        ctx.push_raw_str(&contents);
+        return;
+    }
+
+    let start = contents.len() - contents.trim_start().len();
+    let end = start + trimmed.len();
+
+    ctx.push_raw_str(&contents[..start]);
+    ctx.push_raw_str(open);
+    ctx.push_raw_str(trimmed);
+    ctx.push_raw_str(close);
+    ctx.push_raw_str(&contents[end..]);
+}
+
+fn render_table(ctx: &mut MarkdownContext, element: &Element, table: &Table) {
+    if table.rows.is_empty() {
+        return;
+    }
+
+    // Markdown tables have no column spans.
+    let spans_columns = table
+        .rows
+        .iter()
+        .flat_map(|row| &row.cells)
+        .any(|cell| cell.column_span.get() > 1);
+
+    if spans_columns {
+        return render_html_block(ctx, element);
+    }
+
+    // Render each cell, which must fit on one line
+    let mut rows = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        let mut cells = Vec::with_capacity(row.cells.len());
+
+        for cell in &row.cells {
+            let contents = ctx.capture(|ctx| render_elements(ctx, &cell.elements));
+            let contents = contents.trim().replace("\\\n", "<br>");
+
+            // Contents that span multiple lines are blocks, such as lists
+            if contents.contains('\n') {
+                return render_html_block(ctx, element);
+            }
+
+            cells.push(contents);
+        }
+
+        rows.push(cells);
+    }
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return;
+    }
+
+    // The first row is the header
+    let delimiters = (0..columns).map(|i| {
+        match table.rows[0].cells.get(i).and_then(|cell| cell.align) {
+            Some(Alignment::Left) => ":---",
+            Some(Alignment::Center) => ":---:",
+            Some(Alignment::Right) => "---:",
+            Some(Alignment::Justify) | None => "---",
+        }
+    });
+
+    let delimiter_row: Vec<String> = delimiters.map(String::from).collect();
+    rows.insert(1, delimiter_row);
+
+    ctx.end_block();
+    for cells in rows {
+        ctx.push_raw_str("|");
+
+        for i in 0..columns {
+            let contents = cells.get(i).map(String::as_str).unwrap_or("");
+            str_write!(ctx, " {contents} |");
+        }
+
+        ctx.add_newline();
+    }
+    ctx.end_block();
+}
+
+fn render_html_block(ctx: &mut MarkdownContext, element: &Element) {
+    ctx.end_block();
+    ctx.push_html(slice::from_ref(element));
+    ctx.end_block();
+}
+
+/// Gets the contents of these elements, if they are only text.
+fn plain_text(elements: &[Element]) -> Option<String> {
+    let mut output = String::new();
+
+    for element in elements {
+        match element {
+            Element::Text(text) | Element::Raw(text) => output.push_str(text),
+            _ => return None,
+        }
+    }
+
+    Some(output)
+}
+
+/// Joins lines together, for contexts which only permit one line.
+fn single_line(contents: &str) -> String {
+    contents.replace("\\\n", " ").replace('\n', " ")
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Escapes characters which would end a link destination.
+fn encode_url(url: &str) -> Cow<'_, str> {
+    if url.contains([' ', '(', ')', '<', '>']) {
+        let url = url
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29")
+            .replace('<', "%3C")
+            .replace('>', "%3E");
+
+        Cow::Owned(url)
+    } else {
+        Cow::Borrowed(url)
+    }
+}
+
+fn get_url_from_link<'a>(
+    ctx: &MarkdownContext,
+    link: &'a LinkLocation<'a>,
+) -> Cow<'a, str> {
+    let url = normalize_link(link, &ctx.settings().urls);
+
+    // TODO: when we remove inline javascript stuff
+    if url.as_ref() == "javascript:;" {
+        return Cow::Borrowed("#");
+    }
+
+    match encode_url(&url) {
+        Cow::Borrowed(_) => url,
+        Cow::Owned(encoded) => Cow::Owned(encoded),
+    }
+}
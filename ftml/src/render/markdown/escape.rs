@@ -0,0 +1,85 @@
+/*
+ * render/markdown/escape.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Characters which have inline meaning anywhere in Markdown text.
+const INLINE_CHARACTERS: [char; 10] = ['\\', '`', '*', '_', '[', ']', '<', '>', '|', '~'];
+
+/// Characters which start a block if they begin a line.
+const LINE_START_CHARACTERS: [char; 4] = ['#', '-', '+', '='];
+
+/// Escapes a line of text so that it is not interpreted as Markdown.
+///
+/// If `line_start` is set, then the text begins a line, and so
+/// characters which would begin a heading or list are also escaped.
+pub fn escape(buffer: &mut String, s: &str, line_start: bool) {
+    // Avoid a leading ordered list marker, e.g. "1. " or "1) "
+    let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let ordered_list = line_start
+        && digits > 0
+        && matches!(s[digits..].chars().next(), Some('.') | Some(')'));
+
+    for (i, ch) in s.char_indices() {
+        let escape = INLINE_CHARACTERS.contains(&ch)
+            || (i == 0 && line_start && LINE_START_CHARACTERS.contains(&ch))
+            || (i == digits && ordered_list);
+
+        if escape {
+            buffer.push('\\');
+        }
+
+        match ch {
+            '\0' => buffer.push(' '), // convert NULL chars to spaces
+            _ => buffer.push(ch),
+        }
+    }
+}
+
+#[test]
+fn test() {
+    macro_rules! test {
+        ($input:expr, $line_start:expr, $expected:expr $(,)?) => {{
+            let mut buffer = String::new();
+            escape(&mut buffer, $input, $line_start);
+
+            assert_eq!(
+                &buffer, $expected,
+                "Escaped Markdown doesn't match expected",
+            );
+        }};
+    }
+
+    test!("", true, "");
+    test!("Hello, world!", true, "Hello, world!");
+    test!(
+        "**bold** and _italics_",
+        false,
+        "\\*\\*bold\\*\\* and \\_italics\\_"
+    );
+    test!("[link](url)", false, "\\[link\\](url)");
+    test!("a <b> c", false, "a \\<b\\> c");
+    test!("# heading", true, "\\# heading");
+    test!("# heading", false, "# heading");
+    test!("- item", true, "\\- item");
+    test!("1. item", true, "1\\. item");
+    test!("2021) year", true, "2021\\) year");
+    test!("1. item", false, "1. item");
+    test!("10 apples", true, "10 apples");
+    test!("~~strike~~ | pipe", false, "\\~\\~strike\\~\\~ \\| pipe");
+}
@@ -0,0 +1,153 @@
+/*
+ * render/markdown/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Renders a syntax tree as Markdown.
+//!
+//! The output is [CommonMark], using the [GitHub Flavored Markdown] extensions
+//! for tables, strikethrough, task list items, and footnotes.
+//!
+//! Styling which Markdown cannot express, such as colors, sizes,
+//! alignment, and CSS classes or other attributes, is dropped.
+//!
+//! Some constructs have no Markdown equivalent at all. Because Markdown
+//! permits raw HTML, these are instead emitted as HTML, as rendered by
+//! [`HtmlRender`]:
+//!
+//! * Formatting: underline, superscript, subscript, highlighting,
+//!   insertions and deletions are wrapped in their HTML tags,
+//!   with their contents still rendered as Markdown.
+//! * Ruby text is wrapped in `<ruby>` and `<rt>` tags.
+//! * Collapsibles become `<details>` blocks, with the contents
+//!   still rendered as Markdown.
+//! * Tab views, definition lists, radio buttons, anchor names,
+//!   math blocks, inline math, equation references, embeds,
+//!   HTML blocks, iframes, and unresolvable images are rendered entirely as HTML.
+//! * Tables containing cells which span multiple columns are rendered as HTML.
+//! * Multiple line breaks become repeated `<br>` tags.
+//!
+//! Footnotes are written as footnote definitions at the location of the
+//! footnote block. The block's title is omitted, since Markdown renderers
+//! generate their own footnote list.
+//!
+//! Modules are not rendered, since their contents are dynamic.
+//!
+//! [CommonMark]: https://commonmark.org/
+//! [GitHub Flavored Markdown]: https://github.github.com/gfm/
+//! [`HtmlRender`]: crate::render::html::HtmlRender
+
+mod context;
+mod elements;
+mod escape;
+
+use self::context::MarkdownContext;
+use self::elements::render_elements;
+use crate::data::PageInfo;
+use crate::render::{DefaultHandle, Handle, Render};
+use crate::settings::WikitextSettings;
+use crate::tree::{BibliographyList, Element, SyntaxTree};
+
+#[derive(Debug, Copy, Clone)]
+pub struct MarkdownRender<'h> {
+    handle: &'h dyn Handle,
+}
+
+impl<'h> MarkdownRender<'h> {
+    /// Creates a new Markdown renderer which fetches external data using the given handle.
+    #[inline]
+    pub fn new(handle: &'h dyn Handle) -> Self {
+        MarkdownRender { handle }
+    }
+
+    #[inline]
+    pub fn render_partial(
+        &self,
+        elements: &[Element],
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> String {
+        self.render_partial_direct(
+            elements,
+            page_info,
+            settings,
+            &[],
+            &[],
+            &BibliographyList::default(),
+        )
+    }
+
+    fn render_partial_direct(
+        &self,
+        elements: &[Element],
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+        table_of_contents: &[Element],
+        footnotes: &[Vec<Element>],
+        bibliography: &BibliographyList,
+    ) -> String {
+        info!(
+            "Rendering Markdown (site {}, page {}, category {})",
+            page_info.site.as_ref(),
+            page_info.page.as_ref(),
+            match &page_info.category {
+                Some(category) => category.as_ref(),
+                None => "_default",
+            },
+        );
+
+        let mut ctx = MarkdownContext::new(
+            page_info,
+            self.handle,
+            settings,
+            table_of_contents,
+            footnotes,
+            bibliography,
+        );
+        render_elements(&mut ctx, elements);
+        ctx.into()
+    }
+}
+
+impl Default for MarkdownRender<'static> {
+    #[inline]
+    fn default() -> Self {
+        MarkdownRender::new(&DefaultHandle)
+    }
+}
+
+impl Render for MarkdownRender<'_> {
+    type Output = String;
+
+    #[inline]
+    fn render(
+        &self,
+        tree: &SyntaxTree,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> String {
+        self.render_partial_direct(
+            &tree.elements,
+            page_info,
+            settings,
+            &tree.table_of_contents,
+            &tree.footnotes,
+            &tree.bibliography,
+        )
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ * render/messages.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! User-facing strings which renderers request through [`Handle::get_message`].
+//!
+//! Embedders are expected to localize these according to the page's language.
+//! The English versions here are the fallback used when no translation exists.
+//!
+//! [`Handle::get_message`]: crate::render::Handle::get_message
+
+/// All message keys which renderers may request.
+pub const MESSAGE_KEYS: [&str; 13] = [
+    "button-copy-clipboard",
+    "collapsible-open",
+    "collapsible-hide",
+    "table-of-contents",
+    "footnote",
+    "footnote-block-title",
+    "bibliography-reference",
+    "bibliography-block-title",
+    "image-context-bad",
+    "gallery-label",
+    "button-join-site",
+    "button-apply-site",
+    "rating",
+];
+
+/// Gets the English text for the given message key.
+///
+/// Returns `None` if the key is not one of [`MESSAGE_KEYS`].
+pub fn english_message(key: &str) -> Option<&'static str> {
+    let message = match key {
+        "button-copy-clipboard" => "Copy to Clipboard",
+        "collapsible-open" => "+ open block",
+        "collapsible-hide" => "- hide block",
+        "table-of-contents" => "Table of Contents",
+        "footnote" => "Footnote",
+        "footnote-block-title" => "Footnotes",
+        "bibliography-reference" => "Reference",
+        "bibliography-block-title" => "Bibliography",
+        "image-context-bad" => "No images in this context",
+        "gallery-label" => "Image gallery",
+        "button-join-site" => "Join this site",
+        "button-apply-site" => "Apply to join this site",
+        "rating" => "rating:",
+        _ => return None,
+    };
+
+    Some(message)
+}
+
+#[test]
+fn english_messages() {
+    for key in MESSAGE_KEYS {
+        assert!(
+            english_message(key).is_some(),
+            "No English message for key {key:?}",
+        );
+    }
+
+    assert_eq!(english_message("nonexistent-key"), None);
+}
@@ -27,15 +27,22 @@ mod prelude {
 
 pub mod debug;
 pub mod json;
+pub mod messages;
 pub mod null;
 pub mod text;
+pub mod wikitext;
 
 #[cfg(feature = "html")]
 pub mod html;
 
+#[cfg(feature = "html")]
+pub mod markdown;
+
 mod handle;
+mod list_pages;
+
+pub use self::handle::{DefaultHandle, Handle};
 
-use self::handle::{Handle, ModuleRenderMode};
 use crate::data::PageInfo;
 use crate::settings::WikitextSettings;
 use crate::tree::SyntaxTree;
@@ -45,7 +45,14 @@ impl Render for NullRender {
 fn null() {
     let page_info = PageInfo::dummy();
     let settings = WikitextSettings::from_mode(WikitextMode::Page);
-    let result = SyntaxTree::from_element_result(vec![], vec![], vec![], vec![], vec![]);
+    let result = SyntaxTree::from_element_result(
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        crate::tree::BibliographyList::default(),
+    );
     let (tree, _) = result.into();
     let output = NullRender.render(&tree, &page_info, &settings);
 
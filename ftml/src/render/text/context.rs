@@ -22,7 +22,7 @@ use crate::data::PageInfo;
 use crate::non_empty_vec::NonEmptyVec;
 use crate::render::Handle;
 use crate::settings::WikitextSettings;
-use crate::tree::{Element, VariableScopes};
+use crate::tree::{BibliographyList, Element, VariableScopes};
 use std::fmt::{self, Write};
 use std::num::NonZeroUsize;
 
@@ -33,7 +33,7 @@ where
 {
     output: String,
     info: &'i PageInfo<'i>,
-    handle: &'h Handle,
+    handle: &'h dyn Handle,
     settings: &'e WikitextSettings,
 
     //
@@ -46,6 +46,7 @@ where
     //
     table_of_contents: &'e [Element<'t>],
     footnotes: &'e [Vec<Element<'t>>],
+    bibliography: &'e BibliographyList<'t>,
 
     //
     // Other fields to track
@@ -75,10 +76,11 @@ where
     #[inline]
     pub fn new(
         info: &'i PageInfo<'i>,
-        handle: &'h Handle,
+        handle: &'h dyn Handle,
         settings: &'e WikitextSettings,
         table_of_contents: &'e [Element<'t>],
         footnotes: &'e [Vec<Element<'t>>],
+        bibliography: &'e BibliographyList<'t>,
     ) -> Self {
         TextContext {
             output: String::new(),
@@ -88,6 +90,7 @@ where
             variables: VariableScopes::new(),
             table_of_contents,
             footnotes,
+            bibliography,
             prefixes: Vec::new(),
             list_depths: NonEmptyVec::new(1),
             invisible: 0,
@@ -118,7 +121,7 @@ where
     }
 
     #[inline]
-    pub fn handle(&self) -> &'h Handle {
+    pub fn handle(&self) -> &'h dyn Handle {
         self.handle
     }
 
@@ -142,6 +145,11 @@ where
         self.footnotes
     }
 
+    #[inline]
+    pub fn bibliography(&self) -> &'e BibliographyList<'t> {
+        self.bibliography
+    }
+
     pub fn next_equation_index(&mut self) -> NonZeroUsize {
         let index = self.equation_index;
         self.equation_index = NonZeroUsize::new(index.get() + 1).unwrap();
@@ -20,8 +20,8 @@
 
 //! Module that implements text rendering for `Element` and its children.
 
+use super::module::render_module;
 use super::TextContext;
-use crate::render::ModuleRenderMode;
 use crate::tree::{
     ContainerType, DefinitionListItem, Element, LinkLocation, ListItem, ListType, Tab,
 };
@@ -104,10 +104,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 ctx.disable_invisible();
             }
         }
-        Element::Module(module) => {
-            ctx.handle()
-                .render_module(ctx.buffer(), module, ModuleRenderMode::Text)
-        }
+        Element::Module(module) => render_module(ctx, module),
         Element::Text(text) | Element::Raw(text) | Element::Email(text) => {
             ctx.push_str(text)
         }
@@ -216,6 +213,23 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 }
             }
         }
+        Element::Gallery { items, .. } => {
+            if !ctx.ends_with_newline() {
+                ctx.add_newline();
+            }
+
+            for item in items {
+                ctx.push_str("* ");
+                ctx.push_str(item.source.file_name());
+
+                if let Some(title) = &item.title {
+                    ctx.push(' ');
+                    ctx.push_str(title);
+                }
+
+                ctx.add_newline();
+            }
+        }
         Element::List { ltype, items, .. } => {
             if !ctx.ends_with_newline() {
                 ctx.add_newline();
@@ -285,7 +299,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
             macro_rules! get_text {
                 ($input:expr, $message:expr) => {
                     match $input {
-                        Some(ref text) => &text,
+                        Some(ref text) => Cow::Borrowed(text.as_ref()),
                         None => ctx.handle().get_message(ctx.language(), $message),
                     }
                 };
@@ -296,11 +310,11 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
 
             // Top of collapsible
             ctx.add_newline();
-            ctx.push_str(show_text);
+            ctx.push_str(&show_text);
             ctx.add_newline();
 
             if *show_top {
-                ctx.push_str(hide_text);
+                ctx.push_str(&hide_text);
                 ctx.add_newline();
             }
 
@@ -310,7 +324,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
             // Bottom of collapsible
             if *show_bottom {
                 ctx.add_newline();
-                ctx.push_str(hide_text);
+                ctx.push_str(&hide_text);
                 ctx.add_newline();
             }
         }
@@ -322,7 +336,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 .get_message(ctx.language(), "table-of-contents");
 
             ctx.add_newline();
-            ctx.push_str(table_of_contents_title);
+            ctx.push_str(&table_of_contents_title);
             ctx.add_newline();
             render_elements(ctx, ctx.table_of_contents());
         }
@@ -348,7 +362,7 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                         .handle()
                         .get_message(ctx.language(), "footnote-block-title");
 
-                    title_default
+                    &title_default
                 }
             };
 
@@ -364,6 +378,40 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
                 ctx.add_newline();
             }
         }
+        Element::BibliographyCite { label } => {
+            info!("Rendering bibliography citation");
+
+            match ctx.bibliography().get(label) {
+                Some((number, _)) => str_write!(ctx, "[{number}]"),
+                None => str_write!(ctx, "[{label}]"),
+            }
+        }
+        Element::BibliographyBlock { title, entries } => {
+            info!("Rendering bibliography block");
+
+            let title_default;
+            let title: &str = match title {
+                Some(title) => title.as_ref(),
+                None => {
+                    title_default = ctx
+                        .handle()
+                        .get_message(ctx.language(), "bibliography-block-title");
+
+                    &title_default
+                }
+            };
+
+            ctx.add_newline();
+            ctx.push_str(title);
+            ctx.add_newline();
+
+            for (number, contents) in ctx.bibliography().get_block_entries(entries) {
+                str_write!(ctx, "{number}. ");
+
+                render_elements(ctx, contents);
+                ctx.add_newline();
+            }
+        }
         Element::User { name, .. } => ctx.push_str(name),
         Element::Date { value, format, .. } => {
             str_write!(ctx, "{}", value.format(format.as_ref()));
@@ -446,12 +494,13 @@ pub fn render_element(ctx: &mut TextContext, element: &Element) {
             ctx.push_str("------");
             ctx.add_newline();
         }
+        Element::Spanned { element, .. } => render_element(ctx, element),
         Element::Partial(_) => panic!("Encountered partial element during parsing"),
     }
 }
 
 fn get_url_from_link<'a>(ctx: &TextContext, link: &'a LinkLocation<'a>) -> Cow<'a, str> {
-    let url = normalize_link(link, ctx.handle());
+    let url = normalize_link(link, &ctx.settings().urls);
 
     // TODO: when we remove inline javascript stuff
     if url.as_ref() == "javascript:;" {
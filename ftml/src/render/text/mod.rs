@@ -20,18 +20,27 @@
 
 mod context;
 mod elements;
+mod module;
 
 use self::context::TextContext;
 use self::elements::render_elements;
 use crate::data::PageInfo;
-use crate::render::{Handle, Render};
+use crate::render::{DefaultHandle, Handle, Render};
 use crate::settings::WikitextSettings;
-use crate::tree::{Element, SyntaxTree};
+use crate::tree::{BibliographyList, Element, SyntaxTree};
 
-#[derive(Debug)]
-pub struct TextRender;
+#[derive(Debug, Copy, Clone)]
+pub struct TextRender<'h> {
+    handle: &'h dyn Handle,
+}
+
+impl<'h> TextRender<'h> {
+    /// Creates a new text renderer which fetches external data using the given handle.
+    #[inline]
+    pub fn new(handle: &'h dyn Handle) -> Self {
+        TextRender { handle }
+    }
 
-impl TextRender {
     #[inline]
     pub fn render_partial(
         &self,
@@ -39,7 +48,14 @@ impl TextRender {
         page_info: &PageInfo,
         settings: &WikitextSettings,
     ) -> String {
-        self.render_partial_direct(elements, page_info, settings, &[], &[])
+        self.render_partial_direct(
+            elements,
+            page_info,
+            settings,
+            &[],
+            &[],
+            &BibliographyList::default(),
+        )
     }
 
     fn render_partial_direct(
@@ -49,6 +65,7 @@ impl TextRender {
         settings: &WikitextSettings,
         table_of_contents: &[Element],
         footnotes: &[Vec<Element>],
+        bibliography: &BibliographyList,
     ) -> String {
         info!(
             "Rendering text (site {}, page {}, category {})",
@@ -60,8 +77,14 @@ impl TextRender {
             },
         );
 
-        let mut ctx =
-            TextContext::new(page_info, &Handle, settings, table_of_contents, footnotes);
+        let mut ctx = TextContext::new(
+            page_info,
+            self.handle,
+            settings,
+            table_of_contents,
+            footnotes,
+            bibliography,
+        );
         render_elements(&mut ctx, elements);
 
         // Remove leading and trailing newlines
@@ -77,7 +100,14 @@ impl TextRender {
     }
 }
 
-impl Render for TextRender {
+impl Default for TextRender<'static> {
+    #[inline]
+    fn default() -> Self {
+        TextRender::new(&DefaultHandle)
+    }
+}
+
+impl Render for TextRender<'_> {
     type Output = String;
 
     #[inline]
@@ -93,6 +123,7 @@ impl Render for TextRender {
             settings,
             &tree.table_of_contents,
             &tree.footnotes,
+            &tree.bibliography,
         )
     }
 }
@@ -0,0 +1,157 @@
+/*
+ * render/text/module.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Module that implements text rendering for `Module`.
+
+use super::elements::render_elements;
+use super::TextContext;
+use crate::data::{JoinState, PageTreeNode};
+use crate::render::list_pages;
+use crate::tree::Module;
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+
+pub fn render_module(ctx: &mut TextContext, module: &Module) {
+    info!("Rendering module '{}'", module.name());
+
+    match module {
+        Module::Backlinks { page } => {
+            let page = page.as_deref().unwrap_or(&ctx.info().page);
+            let backlinks = ctx.handle().get_backlinks(ctx.info(), page);
+
+            if let Some(pages) = backlinks {
+                start_line(ctx);
+
+                for page in pages {
+                    ctx.push_str("* ");
+                    ctx.push_str(&page.title);
+                    ctx.add_newline();
+                }
+            }
+        }
+        Module::Categories { include_hidden } => {
+            let categories = ctx.handle().get_categories(ctx.info(), *include_hidden);
+
+            if let Some(categories) = categories {
+                start_line(ctx);
+
+                for category in categories {
+                    ctx.push_str(&category.name);
+                    ctx.add_newline();
+
+                    for page in category.pages {
+                        ctx.push_str(" * ");
+                        ctx.push_str(&page.title);
+                        ctx.add_newline();
+                    }
+                }
+            }
+        }
+        Module::Join { button_text, .. } => {
+            let message = match ctx.handle().get_join_state(ctx.info()) {
+                Some(JoinState::Open) | None => "button-join-site",
+                Some(JoinState::Application) => "button-apply-site",
+                Some(JoinState::Member) | Some(JoinState::Closed) => return,
+            };
+
+            let button_text = match button_text {
+                Some(text) => Cow::Borrowed(text.as_ref()),
+                None => ctx.handle().get_message(ctx.language(), message),
+            };
+
+            start_line(ctx);
+            ctx.push_str(&button_text);
+            ctx.add_newline();
+        }
+        Module::ListPages { query, template } => {
+            let result = match ctx.handle().get_list_pages(ctx.info(), query) {
+                Some(result) => result,
+                None => return,
+            };
+
+            for source in list_pages::build_sources(template, query, &result) {
+                let tree = list_pages::parse_source(&source, ctx.info(), ctx.settings());
+
+                start_line(ctx);
+                render_elements(ctx, &tree.elements);
+            }
+        }
+        Module::PageTree {
+            root,
+            show_root,
+            depth,
+        } => {
+            let root = root.as_deref().unwrap_or(&ctx.info().page);
+            let tree = ctx.handle().get_page_tree(ctx.info(), root, *depth);
+            let remaining = depth.map(NonZeroU32::get).unwrap_or(u32::MAX);
+
+            if let Some(root) = tree {
+                start_line(ctx);
+
+                if *show_root {
+                    render_page_tree(ctx, std::slice::from_ref(&root), 1, remaining);
+                } else {
+                    render_page_tree(ctx, &root.children, 1, remaining);
+                }
+            }
+        }
+        Module::Rate => {
+            let rating = ctx
+                .handle()
+                .get_page_rating(ctx.info())
+                .unwrap_or(ctx.info().rating);
+
+            let rating_label = ctx.handle().get_message(ctx.language(), "rating");
+
+            start_line(ctx);
+            str_write!(ctx, "{rating_label} {rating:+}");
+            ctx.add_newline();
+        }
+    }
+}
+
+fn render_page_tree(
+    ctx: &mut TextContext,
+    nodes: &[PageTreeNode],
+    depth: usize,
+    remaining: u32,
+) {
+    if remaining == 0 {
+        return;
+    }
+
+    for node in nodes {
+        for _ in 0..depth {
+            ctx.push(' ');
+        }
+
+        ctx.push_str("* ");
+        ctx.push_str(&node.page.title);
+        ctx.add_newline();
+
+        render_page_tree(ctx, &node.children, depth + 1, remaining - 1);
+    }
+}
+
+fn start_line(ctx: &mut TextContext) {
+    if !ctx.ends_with_newline() {
+        ctx.add_newline();
+    }
+}
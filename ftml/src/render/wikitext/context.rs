@@ -0,0 +1,319 @@
+/*
+ * render/wikitext/context.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::escape::{
+    escape, has_open_string, joins_bracket, push_raw, push_string, starts_line_syntax,
+};
+use super::{EMPTY_COMMENT, END_STRING_COMMENT, LEFT_BRACKET_CHARACTER};
+use crate::data::PageInfo;
+use crate::settings::WikitextSettings;
+use crate::tree::{AttributeMap, Element};
+use std::fmt::{self, Write};
+use std::mem;
+
+#[derive(Debug)]
+pub struct WikitextContext<'i, 'e, 't>
+where
+    'e: 't,
+{
+    output: String,
+    info: &'i PageInfo<'i>,
+    settings: &'e WikitextSettings,
+
+    //
+    // Elements from the syntax tree
+    //
+    footnotes: &'e [Vec<Element<'t>>],
+
+    //
+    // Other fields to track
+    //
+    /// Whether the next text written starts a new line.
+    line_start: bool,
+
+    /// The index of the next footnote to be written.
+    footnote_index: usize,
+
+    /// Whether line breaks are written with underscores, as in ` _`.
+    ///
+    /// This is for syntax which a newline would end, such as headings.
+    underscore_line_breaks: bool,
+}
+
+impl<'i, 'e, 't> WikitextContext<'i, 'e, 't>
+where
+    'e: 't,
+{
+    #[inline]
+    pub fn new(
+        info: &'i PageInfo<'i>,
+        settings: &'e WikitextSettings,
+        footnotes: &'e [Vec<Element<'t>>],
+    ) -> Self {
+        WikitextContext {
+            output: String::new(),
+            info,
+            settings,
+            footnotes,
+            line_start: true,
+            footnote_index: 0,
+            underscore_line_breaks: false,
+        }
+    }
+
+    // Getters
+    #[inline]
+    pub fn info(&self) -> &'i PageInfo<'i> {
+        self.info
+    }
+
+    #[inline]
+    pub fn settings(&self) -> &WikitextSettings {
+        self.settings
+    }
+
+    /// Gets the contents of the next footnote, if any remain.
+    pub fn next_footnote(&mut self) -> Option<&'e [Element<'t>]> {
+        let footnote = self.footnotes.get(self.footnote_index);
+        self.footnote_index += 1;
+        footnote.map(|elements| elements.as_slice())
+    }
+
+    /// Skips over footnotes which will not be written.
+    ///
+    /// This is used for included pages, since their footnotes
+    /// come from the page being included rather than this one.
+    #[inline]
+    pub fn skip_footnotes(&mut self, count: usize) {
+        self.footnote_index += count;
+    }
+
+    // Buffer management
+
+    /// Appends wikitext to the output, as-is.
+    ///
+    /// The exceptions are where it would be read together with the output before it,
+    /// in which case they are separated as by [`separate`](Self::separate).
+    pub fn push_str(&mut self, s: &str) {
+        if !s.is_empty() {
+            self.separate(s);
+            self.output.push_str(s);
+            self.line_start = s.ends_with('\n');
+        }
+    }
+
+    /// Appends text to the output, escaping any wikitext syntax.
+    ///
+    /// Text which would begin a block at the start of a line
+    /// is preceded by an empty comment, so that it no longer does.
+    pub fn push_escaped(&mut self, s: &str) {
+        if !s.is_empty() {
+            if self.line_start && starts_line_syntax(s) {
+                self.output.push_str(EMPTY_COMMENT);
+            }
+
+            self.separate(s);
+            escape(&mut self.output, s, false);
+            self.line_start = false;
+        }
+    }
+
+    /// Separates the output from wikitext after it, if they would be read together.
+    ///
+    /// A newline after a backslash would be removed by the preprocessor to join the lines,
+    /// and one after an underscore would be read as a line break, so an empty comment
+    /// is added between them. The same is done for others which would be read as one token,
+    /// such as two runs of `]`, a `+` and a `*`, or the parts of a comment delimiter.
+    /// And a `[` followed by syntax such as `[` or `*` would form one token,
+    /// so it is written as a character block instead.
+    fn separate(&mut self, s: &str) {
+        if (s.starts_with('\n') && self.output.ends_with(['\\', '_']))
+            || (s.starts_with(']') && self.output.ends_with(']'))
+            || (s.starts_with(['-', ']']) && self.output.ends_with("--"))
+            || (s.starts_with("--") && self.output.ends_with("[!"))
+            || (s.starts_with('*') && self.output.ends_with('+'))
+        {
+            self.output.push_str(EMPTY_COMMENT);
+        }
+
+        if joins_bracket(s) && self.output.ends_with('[') {
+            self.output.pop();
+            self.output.push_str(LEFT_BRACKET_CHARACTER);
+        }
+    }
+
+    /// Appends text to the output as a single raw block.
+    pub fn push_raw(&mut self, s: &str) {
+        push_raw(&mut self.output, s);
+        self.line_start = false;
+    }
+
+    /// Appends the head of a block, up to but excluding the closing `]]`.
+    ///
+    /// This is so that any further arguments can be added after.
+    pub fn push_block_start(&mut self, star: bool, name: &str) {
+        self.push_str(if star { "[[*" } else { "[[" });
+        self.push_str(name);
+    }
+
+    /// Appends a complete block end, such as `[[/div]]`.
+    pub fn push_block_end(&mut self, name: &str) {
+        self.push_str("[[/");
+        self.push_str(name);
+        self.push_str("]]");
+    }
+
+    /// Appends a block argument in the form ` key="value"`.
+    pub fn push_argument(&mut self, key: &str, value: &str) {
+        self.output.push(' ');
+        self.output.push_str(key);
+        self.output.push('=');
+        push_string(&mut self.output, value);
+        self.line_start = false;
+    }
+
+    /// Appends each attribute as a block argument.
+    pub fn push_attributes(&mut self, attributes: &AttributeMap) {
+        for (key, value) in attributes.get() {
+            self.push_argument(key, value);
+        }
+    }
+
+    /// Ends the current line, if there is anything on it.
+    pub fn end_line(&mut self) {
+        if !self.line_start {
+            self.push_str("\n");
+        }
+    }
+
+    /// Ensures that the next text written starts a new paragraph.
+    ///
+    /// In wikitext, paragraphs are separated by a blank line.
+    pub fn end_block(&mut self) {
+        self.end_line();
+
+        if !self.output.is_empty() && !self.output.ends_with("\n\n") {
+            self.push_str("\n");
+        }
+    }
+
+    /// Gets the current length of the output, for use with `end_string()`.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Ends any string left open in the output written since `start`.
+    ///
+    /// Quoted strings are single tokens, so a lone `"` would otherwise be
+    /// matched with the next one on the line, taking all syntax in between.
+    pub fn end_string(&mut self, start: usize) {
+        if has_open_string(&self.output[start..]) {
+            self.push_str(END_STRING_COMMENT);
+        }
+    }
+
+    #[inline]
+    pub fn at_line_start(&self) -> bool {
+        self.line_start
+    }
+
+    #[inline]
+    pub fn ends_with(&self, s: &str) -> bool {
+        self.output.ends_with(s)
+    }
+
+    #[inline]
+    pub fn last_char(&self) -> Option<char> {
+        self.output.chars().next_back()
+    }
+
+    /// Marks the following text as being at the start of a line.
+    ///
+    /// This is for line prefixes, such as `> ` for blockquotes,
+    /// after which text is still interpreted as starting a line.
+    #[inline]
+    pub fn set_line_start(&mut self) {
+        self.line_start = true;
+    }
+
+    /// Renders into a separate buffer, returning its contents.
+    ///
+    /// This is used for things which need to be inspected before being written,
+    /// such as the contents of formatting, which may need a block instead.
+    pub fn capture<F>(&mut self, line_start: bool, f: F) -> String
+    where
+        F: FnOnce(&mut Self),
+    {
+        let output = mem::take(&mut self.output);
+        let previous_line_start = mem::replace(&mut self.line_start, line_start);
+        f(self);
+        self.line_start = previous_line_start;
+        mem::replace(&mut self.output, output)
+    }
+
+    /// Renders with line breaks written using underscores, or not.
+    pub fn with_underscore_line_breaks<F>(&mut self, value: bool, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let previous = mem::replace(&mut self.underscore_line_breaks, value);
+        f(self);
+        self.underscore_line_breaks = previous;
+    }
+
+    #[inline]
+    pub fn underscore_line_breaks(&self) -> bool {
+        self.underscore_line_breaks
+    }
+
+    /// Removes the given text from the end of the output, if present.
+    pub fn trim_end_str(&mut self, s: &str) {
+        if self.output.ends_with(s) {
+            self.output.truncate(self.output.len() - s.len());
+            self.line_start = self.output.is_empty() || self.output.ends_with('\n');
+        }
+    }
+
+    /// Removes any trailing newlines from the output.
+    pub fn trim_end_newlines(&mut self) {
+        let length = self.output.trim_end_matches('\n').len();
+        self.output.truncate(length);
+        self.line_start = self.output.is_empty();
+    }
+}
+
+impl<'i, 'e, 't> From<WikitextContext<'i, 'e, 't>> for String {
+    #[inline]
+    fn from(ctx: WikitextContext<'i, 'e, 't>) -> String {
+        ctx.output
+    }
+}
+
+impl<'i, 'e, 't> Write for WikitextContext<'i, 'e, 't>
+where
+    'e: 't,
+{
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
@@ -0,0 +1,1392 @@
+/*
+ * render/wikitext/elements.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Module that implements wikitext rendering for `Element` and its children.
+//!
+//! There are two kinds of contexts elements are rendered in:
+//! * Paragraph contexts, such as the top level or the body of a `[[div]]`,
+//!   where each element is a separate block, and inline elements are grouped
+//!   into paragraphs which are separated by blank lines.
+//! * Inline contexts, such as a paragraph or the body of a `[[span]]`,
+//!   where each newline is a line break.
+
+use super::escape::{joins_bracket, starts_link};
+use super::module::render_module;
+use super::{WikitextContext, EMPTY_COMMENT, LEFT_BRACKET_CHARACTER};
+use crate::tree::{
+    Alignment, AnchorTarget, AttributeMap, ClearFloat, Container, ContainerType, Date,
+    DefinitionListItem, Element, Embed, FloatAlignment, GallerySize, ImageSource,
+    LinkLabel, LinkLocation, LinkType, ListItem, ListType, Table, TableCell,
+};
+use crate::url::is_url;
+use regex::Regex;
+use std::borrow::Cow;
+
+lazy_static! {
+    static ref BARE_URL_REGEX: Regex =
+        Regex::new(r"^(?:https?|ftp)://[^\s|\[\]]+$").unwrap();
+}
+
+/// Renders elements in a paragraph context.
+///
+/// Each element is its own block, separated by blank lines.
+pub fn render_elements(ctx: &mut WikitextContext, elements: &[Element]) {
+    info!("Rendering elements (length {})", elements.len());
+
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            ctx.end_block();
+        }
+
+        match element {
+            // Plain paragraphs are just their contents,
+            // as separating them with blank lines creates them.
+            Element::Container(container)
+                if container.ctype() == ContainerType::Paragraph
+                    && container.attributes().get().is_empty() =>
+            {
+                render_inline(ctx, container.elements());
+
+                // A line of only whitespace would be emptied by the preprocessor,
+                // such as one before a block on the same line in an included page.
+                let whitespace = container.elements().iter().all(|element| {
+                    matches!(
+                        element.unspanned(),
+                        Element::Text(text) if text.trim().is_empty(),
+                    )
+                });
+
+                if whitespace {
+                    ctx.push_str(EMPTY_COMMENT);
+                }
+            }
+
+            // Adjacent native lists of the same type would be read as one list,
+            // even with a blank line between them.
+            Element::List { ltype, .. }
+                if index > 0
+                    && matches!(
+                        elements[index - 1].unspanned(),
+                        Element::List { ltype: previous, .. } if previous == ltype,
+                    ) =>
+            {
+                render_element(ctx, element, false);
+            }
+
+            _ => render_element(ctx, element, true),
+        }
+    }
+}
+
+/// Renders elements in an inline context.
+///
+/// This is for the contents of paragraphs, and blocks which do not form paragraphs.
+pub fn render_inline(ctx: &mut WikitextContext, elements: &[Element]) {
+    info!("Rendering inline elements (length {})", elements.len());
+
+    for (index, element) in elements.iter().enumerate() {
+        match element {
+            Element::LineBreak => render_line_break(ctx, elements.get(index + 1)),
+            _ if ctx.ends_with("[") => {
+                // A '[' followed by '[' or similar would be read as one token,
+                // or as a link if followed by a URL or path, and a comment would itself
+                // form a block with it.
+                let output =
+                    ctx.capture(false, |ctx| render_inline_element(ctx, element));
+
+                if joins_bracket(&output) || starts_link(&output) {
+                    ctx.trim_end_str("[");
+                    ctx.push_str(LEFT_BRACKET_CHARACTER);
+                }
+
+                ctx.push_str(&output);
+            }
+            Element::Text(text) => {
+                // Adjacent text which would be read as one token is kept separate
+                if let Some(Element::Text(previous)) =
+                    index.checked_sub(1).map(|i| &elements[i])
+                {
+                    if joins_text(previous, text) {
+                        ctx.push_str(EMPTY_COMMENT);
+                    }
+                }
+
+                render_element(ctx, element, false);
+            }
+            _ => render_inline_element(ctx, element),
+        }
+
+        let next = elements.get(index + 1);
+        let next_space = matches!(
+            next,
+            Some(Element::Text(text)) if text.starts_with([' ', '\t']),
+        );
+
+        let separate = match element.unspanned() {
+            // Checkboxes and radio buttons consume a space after them
+            Element::CheckBox { .. } | Element::RadioButton { .. } => next_space,
+
+            // Line blocks consume a newline after them, such as one ending a list item
+            Element::LineBreaks(_) => next.is_none(),
+
+            // Bare URLs continue until whitespace
+            Element::Link { .. } => {
+                is_bare_url_element(element)
+                    && !next_space
+                    && !matches!(next, None | Some(Element::LineBreak))
+            }
+            _ => false,
+        };
+
+        if separate {
+            ctx.push_str(EMPTY_COMMENT);
+        }
+    }
+}
+
+/// Renders an element in an inline context.
+///
+/// Any string left open by the element, such as from a `"` in raw text,
+/// is ended after it so that it does not continue into later wikitext.
+fn render_inline_element(ctx: &mut WikitextContext, element: &Element) {
+    let start = ctx.position();
+    render_element(ctx, element, false);
+    ctx.end_string(start);
+}
+
+/// Renders an element.
+///
+/// If `block_start` is true, the element is at the start of its own block,
+/// and so syntax which must begin a line (such as native lists) is available.
+pub fn render_element(ctx: &mut WikitextContext, element: &Element, block_start: bool) {
+    info!("Rendering element {}", element.name());
+
+    match element {
+        Element::Container(container) => render_container(ctx, container, block_start),
+        Element::Module(module) => render_module(ctx, module),
+        Element::Text(text) => ctx.push_escaped(&text.replace('\n', " ")),
+        Element::Raw(text) => ctx.push_raw(&text.replace('\n', " ")),
+        Element::Variable(name) => str_write!(ctx, "{{${name}}}"),
+        Element::Email(email) => ctx.push_str(email),
+        Element::Table(table) => render_table(ctx, table, block_start),
+        Element::TabView(tabs) => {
+            ctx.push_str("[[tabview]]\n");
+
+            for tab in tabs {
+                str_write!(ctx, "[[tab {}]]\n", tab.label);
+                render_elements(ctx, &tab.elements);
+                ctx.end_line();
+                ctx.push_str("[[/tab]]\n");
+            }
+
+            ctx.push_block_end("tabview");
+        }
+        Element::Anchor {
+            target,
+            attributes,
+            elements,
+        } => {
+            let star = *target == Some(AnchorTarget::NewTab);
+            render_inline_block(ctx, star, "a", attributes, elements);
+        }
+        Element::AnchorName(name) => str_write!(ctx, "[[# {name}]]"),
+        Element::Link {
+            ltype,
+            link,
+            label,
+            target,
+        } => render_link(ctx, *ltype, link, label, *target),
+        Element::Image {
+            source,
+            link,
+            alignment,
+            attributes,
+        } => {
+            let name = match alignment {
+                Some(FloatAlignment { align, float }) => match (align, float) {
+                    (Alignment::Center, false) => "=image",
+                    (Alignment::Left, false) => "<image",
+                    (Alignment::Right, false) => ">image",
+                    (Alignment::Left, true) => "f<image",
+                    (Alignment::Right, true) => "f>image",
+                    _ => "image",
+                },
+                None => "image",
+            };
+
+            let source = image_source(source);
+
+            ctx.push_block_start(false, name);
+            str_write!(ctx, " {source}");
+
+            // Otherwise "$]]" would be read as the end of inline math
+            if source.ends_with('$') {
+                ctx.push_str(" ");
+            }
+
+            if let Some(link) = link {
+                ctx.push_argument("link", &link_location(link));
+            }
+
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Element::Gallery {
+            items,
+            size,
+            viewer,
+        } => {
+            ctx.push_block_start(false, "gallery");
+
+            if *size != GallerySize::default() {
+                ctx.push_argument("size", size.name());
+            }
+
+            if !viewer {
+                ctx.push_argument("viewer", "false");
+            }
+
+            ctx.push_str("]]\n");
+
+            for item in items {
+                str_write!(ctx, ": {}", image_source(&item.source));
+
+                if let Some(link) = &item.link {
+                    ctx.push_argument("link", &link_location(link));
+                }
+
+                if let Some(title) = &item.title {
+                    ctx.push_argument("title", title);
+                }
+
+                ctx.push_str("\n");
+            }
+
+            ctx.push_block_end("gallery");
+        }
+        Element::List {
+            ltype,
+            attributes,
+            items,
+        } => render_list(ctx, *ltype, attributes, items, block_start),
+        Element::DefinitionList(items) => render_definition_list(ctx, items),
+        Element::RadioButton {
+            name,
+            checked,
+            attributes,
+        } => {
+            ctx.push_block_start(*checked, "radio");
+            str_write!(ctx, " {name}");
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Element::CheckBox {
+            checked,
+            attributes,
+        } => {
+            ctx.push_block_start(*checked, "checkbox");
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Element::Collapsible {
+            elements,
+            attributes,
+            start_open,
+            show_text,
+            hide_text,
+            show_top,
+            show_bottom,
+        } => {
+            ctx.push_block_start(false, "collapsible");
+
+            if let Some(text) = show_text {
+                ctx.push_argument("show", text);
+            }
+
+            if let Some(text) = hide_text {
+                ctx.push_argument("hide", text);
+            }
+
+            if *start_open {
+                ctx.push_argument("folded", "no");
+            }
+
+            match (show_top, show_bottom) {
+                (true, false) => (),
+                (false, true) => ctx.push_argument("hideLocation", "bottom"),
+                (true, true) => ctx.push_argument("hideLocation", "both"),
+                (false, false) => ctx.push_argument("hideLocation", "neither"),
+            }
+
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]\n");
+            render_elements(ctx, elements);
+            ctx.end_line();
+            ctx.push_block_end("collapsible");
+        }
+        Element::TableOfContents { attributes, align } => {
+            let name = match align {
+                Some(Alignment::Left) => "f<toc",
+                Some(Alignment::Right) => "f>toc",
+                _ => "toc",
+            };
+
+            ctx.push_block_start(false, name);
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Element::Footnote => {
+            let contents = ctx.next_footnote().unwrap_or(&[]);
+
+            ctx.push_str("[[footnote]]");
+
+            // Footnotes with a single paragraph are unwrapped
+            //
+            // Newlines within are part of the block, so they end no line syntax.
+            ctx.with_underscore_line_breaks(false, |ctx| {
+                if contents.iter().all(|element| element.paragraph_safe()) {
+                    render_inline(ctx, contents);
+                } else {
+                    render_elements(ctx, contents);
+                }
+            });
+
+            ctx.push_block_end("footnote");
+        }
+        Element::FootnoteBlock { title, hide } => {
+            ctx.push_block_start(false, "footnoteblock");
+
+            if let Some(title) = title {
+                ctx.push_argument("title", title);
+            }
+
+            if *hide {
+                ctx.push_argument("hide", "true");
+            }
+
+            ctx.push_str("]]");
+        }
+        Element::BibliographyCite { label } => str_write!(ctx, "((bibcite {label}))"),
+        Element::BibliographyBlock { title, entries } => {
+            ctx.push_block_start(false, "bibliography");
+
+            if let Some(title) = title {
+                ctx.push_argument("title", title);
+            }
+
+            ctx.push_str("]]\n");
+
+            let items = entries
+                .iter()
+                .map(|entry| DefinitionListItem {
+                    key: vec![Element::Text(Cow::Borrowed(&entry.label))],
+                    value: entry.contents.clone(),
+                })
+                .collect::<Vec<_>>();
+
+            render_definition_list(ctx, &items);
+            ctx.push_block_end("bibliography");
+        }
+        Element::User { name, show_avatar } => {
+            ctx.push_block_start(*show_avatar, "user");
+            str_write!(ctx, " {name}]]");
+        }
+        Element::Date {
+            value,
+            format,
+            hover,
+        } => {
+            ctx.push_block_start(false, "date");
+
+            match value {
+                Date::Date(date) => str_write!(ctx, " {}", date.format("%F")),
+                Date::DateTime(datetime) => {
+                    str_write!(ctx, " {}", datetime.format("%FT%T"))
+                }
+                Date::DateTimeTz(datetime) => {
+                    str_write!(ctx, " {}", datetime.to_rfc3339())
+                }
+            }
+
+            if let Some(format) = format {
+                ctx.push_argument("format", format);
+            }
+
+            if !hover {
+                ctx.push_argument("hover", "false");
+            }
+
+            ctx.push_str("]]");
+        }
+        Element::Color { color, elements } => {
+            str_write!(ctx, "##{color}|");
+            render_inline(ctx, elements);
+            ctx.push_str("##");
+        }
+        Element::Code { contents, language } => {
+            ctx.push_block_start(false, "code");
+
+            if let Some(language) = language {
+                ctx.push_argument("type", language);
+            }
+
+            ctx.push_str("]]\n");
+            ctx.push_str(contents);
+            ctx.end_line();
+            ctx.push_block_end("code");
+        }
+        Element::Math { name, latex_source } => {
+            ctx.push_block_start(false, "math");
+
+            if let Some(name) = name {
+                str_write!(ctx, " {name}");
+            }
+
+            str_write!(ctx, "]]\n{latex_source}\n");
+            ctx.push_block_end("math");
+        }
+        Element::MathInline { latex_source } => {
+            str_write!(ctx, "[[$ {latex_source} $]]");
+        }
+        Element::EquationReference(name) => str_write!(ctx, "[[eref {name}]]"),
+        Element::Embed(embed) => {
+            ctx.push_block_start(false, "embed");
+
+            match embed {
+                Embed::Youtube { video_id } => {
+                    ctx.push_str(" youtube");
+                    ctx.push_argument("video", video_id);
+                }
+                Embed::Vimeo { video_id } => {
+                    ctx.push_str(" vimeo");
+                    ctx.push_argument("video", video_id);
+                }
+                Embed::GithubGist { username, hash } => {
+                    ctx.push_str(" github-gist");
+                    ctx.push_argument("username", username);
+                    ctx.push_argument("hash", hash);
+                }
+                Embed::GitlabSnippet { snippet_id } => {
+                    ctx.push_str(" gitlab-snippet");
+                    ctx.push_argument("id", snippet_id);
+                }
+            }
+
+            ctx.push_str("]]");
+        }
+        Element::Html { contents } => {
+            ctx.push_str("[[html]]\n");
+            ctx.push_str(contents);
+            ctx.end_line();
+            ctx.push_block_end("html");
+        }
+        Element::Iframe { url, attributes } => {
+            str_write!(ctx, "[[iframe {url}");
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Element::Include {
+            variables,
+            location,
+            elements,
+            ..
+        } => {
+            // The included page is fetched again when parsing,
+            // so its footnotes are not ours to write.
+            ctx.skip_footnotes(count_footnotes(elements));
+
+            let mut variables: Vec<_> = variables.iter().collect();
+            variables.sort();
+
+            str_write!(ctx, "[[include-elements {location}");
+
+            for (key, value) in variables {
+                ctx.push_argument(key, value);
+            }
+
+            ctx.push_str("]]");
+        }
+        Element::LineBreak => render_line_break(ctx, None),
+        Element::LineBreaks(amount) => str_write!(ctx, "[[lines {amount}]]"),
+        Element::ClearFloat(clear_float) => {
+            ctx.end_line();
+            ctx.push_str(match clear_float {
+                ClearFloat::Both => "~~~~",
+                ClearFloat::Left => "~~~~<",
+                ClearFloat::Right => "~~~~>",
+            });
+            ctx.end_line();
+        }
+        Element::HorizontalRule => {
+            ctx.end_line();
+            ctx.push_str("----");
+            ctx.end_line();
+        }
+        Element::Spanned { element, .. } => render_element(ctx, element, block_start),
+        Element::Partial(_) => {
+            warn!("Encountered partial element during rendering, ignoring");
+        }
+    }
+}
+
+/// Determines if two pieces of text would be joined into one if written next to each other.
+fn joins_text(before: &str, after: &str) -> bool {
+    fn class(ch: char) -> u8 {
+        match ch {
+            _ if ch.is_ascii_alphanumeric() => 0,
+            ' ' | '\t' => 1,
+            _ => 2,
+        }
+    }
+
+    match (before.chars().next_back(), after.chars().next()) {
+        (Some(last), Some(first)) => class(last) == class(first),
+        _ => false,
+    }
+}
+
+fn render_line_break(ctx: &mut WikitextContext, next: Option<&Element>) {
+    // Newlines directly after a block head are consumed by it
+    if ctx.ends_with("]]") {
+        ctx.push_str(EMPTY_COMMENT);
+    }
+
+    if ctx.underscore_line_breaks() {
+        // Whitespace before the underscore would be read as part of it
+        if ctx.ends_with(" ") || ctx.ends_with("\t") {
+            ctx.push_str(EMPTY_COMMENT);
+        }
+
+        ctx.push_str(" _\n");
+    } else {
+        ctx.push_str("\n");
+    }
+
+    // Line breaks at the end, or before another line break, would
+    // instead form a paragraph break or be trimmed.
+    //
+    // And newlines before a block are consumed by it.
+    let separate = match next {
+        None => true,
+        Some(element) => !continues_line(element),
+    };
+
+    if separate {
+        ctx.push_str(EMPTY_COMMENT);
+    }
+}
+
+/// Determines if this element can follow a newline without changing its meaning.
+fn continues_line(element: &Element) -> bool {
+    match element {
+        Element::Text(text) => !text.is_empty(),
+        Element::Raw(_)
+        | Element::Variable(_)
+        | Element::Email(_)
+        | Element::AnchorName(_)
+        | Element::Link { .. }
+        | Element::Image { .. }
+        | Element::RadioButton { .. }
+        | Element::CheckBox { .. }
+        | Element::Footnote
+        | Element::BibliographyCite { .. }
+        | Element::User { .. }
+        | Element::Date { .. }
+        | Element::Color { .. }
+        | Element::MathInline { .. }
+        | Element::EquationReference(_) => true,
+        Element::Container(container) => matches!(
+            container.ctype(),
+            ContainerType::Bold
+                | ContainerType::Italics
+                | ContainerType::Underline
+                | ContainerType::Superscript
+                | ContainerType::Subscript
+                | ContainerType::Strikethrough
+                | ContainerType::Monospace
+                | ContainerType::Span
+                | ContainerType::Mark
+                | ContainerType::Insertion
+                | ContainerType::Deletion
+                | ContainerType::Size,
+        ),
+        _ => false,
+    }
+}
+
+fn render_container(ctx: &mut WikitextContext, container: &Container, block_start: bool) {
+    let attributes = container.attributes();
+    let elements = container.elements();
+
+    match container.ctype() {
+        ContainerType::Bold => render_formatting(ctx, container, Some(("**", "**")), "b"),
+        ContainerType::Italics => {
+            render_formatting(ctx, container, Some(("//", "//")), "i")
+        }
+        ContainerType::Underline => {
+            render_formatting(ctx, container, Some(("__", "__")), "u")
+        }
+        ContainerType::Superscript => {
+            render_formatting(ctx, container, Some(("^^", "^^")), "sup")
+        }
+        ContainerType::Subscript => {
+            render_formatting(ctx, container, Some((",,", ",,")), "sub")
+        }
+        ContainerType::Strikethrough => {
+            render_formatting(ctx, container, Some(("--", "--")), "s")
+        }
+        ContainerType::Monospace => {
+            render_formatting(ctx, container, Some(("{{", "}}")), "tt")
+        }
+        ContainerType::Span => render_formatting(ctx, container, None, "span"),
+        ContainerType::Mark => render_formatting(ctx, container, None, "mark"),
+        ContainerType::Insertion => render_formatting(ctx, container, None, "ins"),
+        ContainerType::Deletion => render_formatting(ctx, container, None, "del"),
+        ContainerType::Hidden => {
+            render_inline_block(ctx, false, "hidden", attributes, elements)
+        }
+        ContainerType::Invisible => {
+            render_inline_block(ctx, false, "invisible", attributes, elements)
+        }
+        ContainerType::Ruby => {
+            render_inline_block(ctx, false, "ruby", attributes, elements)
+        }
+        ContainerType::RubyText => {
+            render_inline_block(ctx, false, "rt", attributes, elements)
+        }
+        ContainerType::Size => {
+            // Extract the size from the style set by the block
+            let size = attributes
+                .get()
+                .get("style")
+                .and_then(|style| style.strip_prefix("font-size: "))
+                .and_then(|style| style.strip_suffix(';'))
+                .unwrap_or("100%");
+
+            str_write!(ctx, "[[size {size}]]");
+            render_inline(ctx, elements);
+            ctx.push_block_end("size");
+        }
+        ContainerType::Paragraph => {
+            ctx.push_block_start(false, "p");
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]\n");
+            render_inline(ctx, elements);
+            ctx.end_line();
+            ctx.push_block_end("p");
+        }
+        ContainerType::Div => {
+            if has_paragraphs(elements) {
+                render_paragraph_block(ctx, "div", attributes, elements);
+            } else {
+                ctx.push_block_start(false, "div_");
+                ctx.push_attributes(attributes);
+                ctx.push_str("]]\n");
+                render_inline(ctx, elements);
+                ctx.end_line();
+                ctx.push_block_end("div_");
+            }
+        }
+        ContainerType::Blockquote => {
+            if block_start && attributes.get().is_empty() && native_blockquote(elements) {
+                ctx.end_line();
+                render_native_blockquote(ctx, elements, 1);
+            } else {
+                render_paragraph_block(ctx, "blockquote", attributes, elements);
+            }
+        }
+        ContainerType::Align(alignment) => {
+            let name = match alignment {
+                Alignment::Left => "<",
+                Alignment::Right => ">",
+                Alignment::Center => "=",
+                Alignment::Justify => "==",
+            };
+
+            if alignment == Alignment::Center
+                && block_start
+                && attributes.get().is_empty()
+                && native_center(elements)
+            {
+                ctx.end_line();
+                ctx.push_str("= ");
+
+                // A newline ends the centered text, so line breaks must use underscores
+                ctx.with_underscore_line_breaks(true, |ctx| render_inline(ctx, elements));
+            } else {
+                render_paragraph_block(ctx, name, &AttributeMap::new(), elements);
+            }
+        }
+        ContainerType::Header(heading) => {
+            ctx.end_line();
+
+            let mut head = "+".repeat(usize::from(heading.level.value()));
+            if !heading.has_toc {
+                head.push('*');
+            }
+
+            head.push(' ');
+            ctx.push_str(&head);
+            ctx.with_underscore_line_breaks(true, |ctx| render_inline(ctx, elements));
+            ctx.end_line();
+        }
+    }
+}
+
+/// Renders formatting, such as bold, using its native syntax if possible.
+///
+/// The native syntax can't be used if there are attributes, or if the
+/// contents would interfere with the delimiters, in which case the
+/// block of the given name is used instead.
+fn render_formatting(
+    ctx: &mut WikitextContext,
+    container: &Container,
+    delimiters: Option<(&str, &str)>,
+    block_name: &str,
+) {
+    let contents = ctx.capture(false, |ctx| render_inline(ctx, container.elements()));
+
+    let native = delimiters.filter(|(open, close)| {
+        let first = open.chars().next();
+        let last = close.chars().next_back();
+
+        container.attributes().get().is_empty()
+            && !contents.is_empty()
+            && !contents.contains('\n')
+            && !contents.contains(open)
+            && !contents.contains(close)
+            && !contents.starts_with(char::is_whitespace)
+            && !contents.ends_with(char::is_whitespace)
+            && contents.chars().next() != first
+            && contents.chars().next_back() != last
+            && ctx.last_char() != first
+            && !container.elements().last().is_some_and(is_bare_url_element)
+            && !(contents.ends_with('[') && starts_link(close))
+    });
+
+    match native {
+        Some((open, close)) => {
+            ctx.push_str(open);
+            ctx.push_str(&contents);
+            ctx.push_str(close);
+        }
+        None => {
+            ctx.push_block_start(false, block_name);
+            ctx.push_attributes(container.attributes());
+            ctx.push_str("]]");
+            ctx.push_str(&contents);
+            ctx.push_block_end(block_name);
+        }
+    }
+}
+
+/// Renders a block whose body is in a paragraph context.
+fn render_paragraph_block(
+    ctx: &mut WikitextContext,
+    name: &str,
+    attributes: &AttributeMap,
+    elements: &[Element],
+) {
+    ctx.push_block_start(false, name);
+    ctx.push_attributes(attributes);
+    ctx.push_str("]]\n");
+    render_elements(ctx, elements);
+    ctx.end_line();
+    ctx.push_block_end(name);
+}
+
+/// Renders a block whose body is in an inline context.
+fn render_inline_block(
+    ctx: &mut WikitextContext,
+    star: bool,
+    name: &str,
+    attributes: &AttributeMap,
+    elements: &[Element],
+) {
+    ctx.push_block_start(star, name);
+    ctx.push_attributes(attributes);
+    ctx.push_str("]]");
+    render_inline(ctx, elements);
+    ctx.push_block_end(name);
+}
+
+/// Determines if these elements came from a body with paragraphs.
+fn has_paragraphs(elements: &[Element]) -> bool {
+    elements.iter().all(|element| match element {
+        Element::Container(container) => {
+            container.ctype() == ContainerType::Paragraph
+                || !container.ctype().paragraph_safe()
+        }
+        Element::Partial(_) => true,
+        _ => !element.paragraph_safe(),
+    })
+}
+
+/// Determines if these elements can be written on a single line.
+fn native_line(elements: &[Element]) -> bool {
+    elements.iter().all(|element| {
+        !matches!(element, Element::LineBreak | Element::Partial(_))
+            && element.paragraph_safe()
+    })
+}
+
+/// Determines if these elements can be written as a centered line, i.e. `= text`.
+///
+/// Unlike other native lines, these can contain line breaks.
+fn native_center(elements: &[Element]) -> bool {
+    !elements.is_empty()
+        && elements.iter().all(|element| {
+            !matches!(element, Element::Partial(_)) && element.paragraph_safe()
+        })
+}
+
+// Blockquotes
+
+fn native_blockquote(elements: &[Element]) -> bool {
+    !elements.is_empty()
+        && elements
+            .iter()
+            .enumerate()
+            .all(|(index, element)| match element {
+                // Nested blockquotes must be on their own line
+                Element::Container(container)
+                    if container.ctype() == ContainerType::Blockquote =>
+                {
+                    container.attributes().get().is_empty()
+                        && index > 0
+                        && elements[index - 1] == Element::LineBreak
+                        && native_blockquote(container.elements())
+                }
+                Element::Partial(_) => false,
+                _ => element.paragraph_safe(),
+            })
+}
+
+fn render_native_blockquote(
+    ctx: &mut WikitextContext,
+    elements: &[Element],
+    depth: usize,
+) {
+    let prefix = format!("{} ", ">".repeat(depth));
+    ctx.push_str(&prefix);
+    ctx.set_line_start();
+
+    for element in elements {
+        match element {
+            Element::LineBreak => {
+                ctx.push_str("\n");
+                ctx.push_str(&prefix);
+                ctx.set_line_start();
+            }
+            Element::Container(container)
+                if container.ctype() == ContainerType::Blockquote =>
+            {
+                // Replace the prefix of the line we were about to write
+                ctx.trim_end_str(&prefix);
+                render_native_blockquote(ctx, container.elements(), depth + 1);
+                ctx.push_str("\n");
+                ctx.push_str(&prefix);
+                ctx.set_line_start();
+            }
+            _ => render_element(ctx, element, false),
+        }
+    }
+
+    ctx.trim_end_str(&prefix);
+    ctx.trim_end_newlines();
+}
+
+// Links
+
+fn render_link(
+    ctx: &mut WikitextContext,
+    ltype: LinkType,
+    link: &LinkLocation,
+    label: &LinkLabel,
+    target: Option<AnchorTarget>,
+) {
+    let star = target == Some(AnchorTarget::NewTab);
+
+    // Anchor links, e.g. [#anchor label]
+    if let (LinkType::Anchor, LinkLocation::Url(url), LinkLabel::Text(text)) =
+        (ltype, link, label)
+    {
+        let name = match url.as_ref() {
+            "javascript:;" => "",
+            _ => url.strip_prefix('#').unwrap_or(url),
+        };
+
+        str_write!(ctx, "[#{name} {text}]");
+        return;
+    }
+
+    if let LinkLocation::Url(url) = link {
+        // Bare URLs, e.g. https://example.com
+        if is_bare_url(ltype, url, label, target) {
+            // Otherwise the scheme would be read as part of the word before it
+            if ctx.last_char().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                ctx.push_str(EMPTY_COMMENT);
+            }
+
+            ctx.push_str(url);
+            return;
+        }
+
+        // Single-bracket links, e.g. [https://example.com label]
+        //
+        // These are also needed for local URLs, which are page links
+        // in triple-bracket links.
+        if let LinkLabel::Text(text) = label {
+            if ltype != LinkType::Interwiki
+                && (is_url(url) || url.starts_with('/'))
+                && !url.contains(char::is_whitespace)
+            {
+                // Written at once, since the URL would be read as such after a '['
+                let open = if star { "[*" } else { "[" };
+                ctx.push_str(&format!("{open}{url} {text}]"));
+                return;
+            }
+        }
+    }
+
+    // Triple-bracket links, e.g. [[[page | label]]]
+    let url = match (ltype, link) {
+        (LinkType::Interwiki, LinkLocation::Url(url)) => match interwiki_link(ctx, url) {
+            Some(link) => Cow::Owned(link),
+            None => Cow::Borrowed(url.as_ref()),
+        },
+        (_, LinkLocation::Url(url)) => Cow::Borrowed(url.as_ref()),
+        (_, LinkLocation::Page(page_ref)) => Cow::Owned(page_ref.to_string()),
+    };
+
+    ctx.push_str(if star { "[[[*" } else { "[[[" });
+    push_link_part(ctx, &url);
+
+    match label {
+        LinkLabel::Text(text) => {
+            ctx.push_str("|");
+            push_link_part(ctx, text);
+        }
+        LinkLabel::Page => ctx.push_str("|"),
+        LinkLabel::Url(_) => (),
+    }
+
+    ctx.push_str("]]]");
+}
+
+/// Determines if this link can be written as a bare URL, e.g. `https://example.com`.
+fn is_bare_url(
+    ltype: LinkType,
+    url: &str,
+    label: &LinkLabel,
+    target: Option<AnchorTarget>,
+) -> bool {
+    ltype == LinkType::Direct
+        && target.is_none()
+        && *label == LinkLabel::Url(None)
+        && BARE_URL_REGEX.is_match(url)
+}
+
+/// Determines if this element is a link written as a bare URL.
+///
+/// Since these continue until whitespace, they would take in any syntax directly after them.
+fn is_bare_url_element(element: &Element) -> bool {
+    match element.unspanned() {
+        Element::Link {
+            ltype,
+            link: LinkLocation::Url(url),
+            label,
+            target,
+        } => is_bare_url(*ltype, url, label, *target),
+        _ => false,
+    }
+}
+
+/// Writes part of a triple-bracket link.
+///
+/// Since these are trimmed when parsed, spaces are added where the
+/// text would otherwise merge with the surrounding brackets, such as
+/// `$]]` being read as the end of inline math.
+fn push_link_part(ctx: &mut WikitextContext, part: &str) {
+    if part.starts_with(['[', '*']) {
+        ctx.push_str(" ");
+    }
+
+    ctx.push_str(part);
+
+    if part.ends_with(['$', ']']) {
+        ctx.push_str(" ");
+    }
+}
+
+/// Produces the interwiki link which expands to the given URL, if any.
+fn interwiki_link(ctx: &WikitextContext, url: &str) -> Option<String> {
+    let interwiki = &ctx.settings().interwiki;
+    let mut prefixes: Vec<_> = interwiki.prefixes.iter().collect();
+    prefixes.sort();
+
+    for (prefix, template) in prefixes {
+        let (before, after) = match template.split_once("$$") {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let path = match url
+            .strip_prefix(before)
+            .and_then(|rest| rest.strip_suffix(after))
+        {
+            Some(path) if !path.is_empty() => path,
+            _ => continue,
+        };
+
+        let link = format!("{prefix}:{path}");
+        if interwiki.build(&link).as_deref() == Some(url) {
+            return Some(format!("!{link}"));
+        }
+    }
+
+    None
+}
+
+/// Gets the string form of a link location, as used by the `link` argument.
+fn image_source(source: &ImageSource) -> String {
+    match source {
+        ImageSource::Url(url) => url.to_string(),
+        ImageSource::File1 { file } if file.is_empty() => str!("/"),
+        ImageSource::File1 { file } => file.to_string(),
+        ImageSource::File2 { page, file } => format!("{page}/{file}"),
+        ImageSource::File3 { site, page, file } => format!("{site}/{page}/{file}"),
+    }
+}
+
+fn link_location<'a>(link: &'a LinkLocation) -> Cow<'a, str> {
+    match link {
+        LinkLocation::Url(url) => Cow::Borrowed(url),
+        LinkLocation::Page(page_ref) => Cow::Owned(page_ref.to_string()),
+    }
+}
+
+// Lists
+
+fn render_list(
+    ctx: &mut WikitextContext,
+    ltype: ListType,
+    attributes: &AttributeMap,
+    items: &[ListItem],
+    block_start: bool,
+) {
+    // Generic lists only come from native lists which are indented.
+    if ltype == ListType::Generic
+        || (block_start && native_list(ltype, attributes, items))
+    {
+        ctx.end_line();
+        render_native_list(ctx, ltype, items, 0);
+        return;
+    }
+
+    let name = match ltype {
+        ListType::Numbered => "ol",
+        _ => "ul",
+    };
+
+    ctx.push_block_start(false, name);
+    ctx.push_attributes(attributes);
+    ctx.push_str("]]\n");
+
+    for item in items {
+        match item {
+            ListItem::Elements {
+                attributes,
+                elements,
+            } => render_inline_block(ctx, false, "li", attributes, elements),
+            ListItem::SubList { element } => render_element(ctx, element, false),
+        }
+
+        ctx.end_line();
+    }
+
+    ctx.push_block_end(name);
+}
+
+/// Determines if a list can be written in native syntax.
+///
+/// The parser creates lists from indentation, so all lists must be
+/// consistent with that. For instance, two sub-lists of the same type
+/// cannot be adjacent, as they would be joined.
+fn native_list(ltype: ListType, attributes: &AttributeMap, items: &[ListItem]) -> bool {
+    if !attributes.get().is_empty() || items.is_empty() {
+        return false;
+    }
+
+    // If the list starts with a sub-list, then it has its type
+    if let Some(ListItem::SubList { element }) = items.first() {
+        match element.unspanned() {
+            Element::List {
+                ltype: sub_ltype, ..
+            } if *sub_ltype == ltype => (),
+            _ => return false,
+        }
+    }
+
+    let mut previous_ltype = None;
+
+    items.iter().all(|item| match item {
+        ListItem::Elements {
+            attributes,
+            elements,
+        } => {
+            previous_ltype = None;
+            ltype != ListType::Generic
+                && attributes.get().is_empty()
+                && native_line(elements)
+        }
+        ListItem::SubList { element } => match element.unspanned() {
+            Element::List {
+                ltype: sub_ltype,
+                attributes,
+                items,
+            } => {
+                let adjacent = previous_ltype == Some(*sub_ltype);
+                previous_ltype = Some(*sub_ltype);
+
+                !adjacent
+                    && *sub_ltype != ListType::Generic
+                    && native_list(*sub_ltype, attributes, items)
+            }
+            _ => false,
+        },
+    })
+}
+
+fn render_native_list(
+    ctx: &mut WikitextContext,
+    ltype: ListType,
+    items: &[ListItem],
+    depth: usize,
+) {
+    for item in items {
+        match item {
+            ListItem::Elements { elements, .. } => {
+                for _ in 0..depth {
+                    ctx.push_str(" ");
+                }
+
+                ctx.push_str(match ltype {
+                    ListType::Numbered => "# ",
+                    _ => "* ",
+                });
+
+                render_inline(ctx, elements);
+                ctx.end_line();
+            }
+            ListItem::SubList { element } => {
+                if let Element::List { ltype, items, .. } = element.unspanned() {
+                    render_native_list(ctx, *ltype, items, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+fn render_definition_list(ctx: &mut WikitextContext, items: &[DefinitionListItem]) {
+    ctx.end_line();
+
+    for DefinitionListItem { key, value } in items {
+        // The key is ended by " :", so any within must be escaped
+        let key = ctx.capture(false, |ctx| render_inline(ctx, key));
+        let key = key.replace(" :", " @@:@@");
+
+        // A newline would end the item, so line breaks must use underscores
+        let value = ctx.capture(false, |ctx| {
+            ctx.with_underscore_line_breaks(true, |ctx| render_inline(ctx, value));
+        });
+
+        ctx.push_str(": ");
+        ctx.push_str(&key);
+        ctx.push_str(" : ");
+        ctx.push_str(&value);
+        ctx.end_line();
+    }
+}
+
+// Tables
+
+fn render_table(ctx: &mut WikitextContext, table: &Table, block_start: bool) {
+    if block_start && native_table(table) {
+        ctx.end_line();
+
+        for row in &table.rows {
+            for cell in &row.cells {
+                for _ in 1..cell.column_span.get() {
+                    ctx.push_str("||");
+                }
+
+                ctx.push_str(match (cell.header, cell.align) {
+                    (true, _) => "||~ ",
+                    (false, Some(Alignment::Left)) => "||< ",
+                    (false, Some(Alignment::Center)) => "||= ",
+                    (false, Some(Alignment::Right)) => "||> ",
+                    (false, _) => "|| ",
+                });
+
+                render_inline(ctx, &cell.elements);
+                ctx.push_str(" ");
+            }
+
+            ctx.push_str("||\n");
+        }
+
+        return;
+    }
+
+    ctx.push_block_start(false, "table");
+    ctx.push_attributes(&table.attributes);
+    ctx.push_str("]]\n");
+
+    for row in &table.rows {
+        ctx.push_block_start(false, "row");
+        ctx.push_attributes(&row.attributes);
+        ctx.push_str("]]\n");
+
+        for cell in &row.cells {
+            let name = if cell.header { "hcell" } else { "cell" };
+
+            ctx.push_block_start(false, name);
+
+            if cell.column_span.get() > 1 {
+                ctx.push_argument("colspan", &cell.column_span.to_string());
+            }
+
+            ctx.push_attributes(&cell.attributes);
+            ctx.push_str("]]");
+            render_inline(ctx, &cell.elements);
+            ctx.push_block_end(name);
+            ctx.end_line();
+        }
+
+        ctx.push_block_end("row");
+        ctx.end_line();
+    }
+
+    ctx.push_block_end("table");
+}
+
+/// Determines if a table can be written in native syntax.
+fn native_table(table: &Table) -> bool {
+    let native_cell = |cell: &TableCell| {
+        cell.attributes.get().is_empty()
+            && !(cell.header && cell.align.is_some())
+            && cell.align != Some(Alignment::Justify)
+            && native_line(&cell.elements)
+            && !matches!(cell.elements.first(), Some(element) if element.is_whitespace())
+            && !matches!(cell.elements.last(), Some(element) if element.is_whitespace())
+    };
+
+    let attributes = table.attributes.get();
+
+    attributes.len() == 1
+        && attributes.get("class").map(|class| class.as_ref()) == Some("wj-table")
+        && !table.rows.is_empty()
+        && table.rows.iter().all(|row| {
+            row.attributes.get().is_empty()
+                && !row.cells.is_empty()
+                && row.cells.iter().all(native_cell)
+        })
+}
+
+// Utilities
+
+/// Counts the footnotes within these elements.
+fn count_footnotes(elements: &[Element]) -> usize {
+    elements
+        .iter()
+        .map(|element| match element {
+            Element::Footnote => 1,
+            Element::Container(container) => count_footnotes(container.elements()),
+            Element::Table(table) => table
+                .rows
+                .iter()
+                .flat_map(|row| &row.cells)
+                .map(|cell| count_footnotes(&cell.elements))
+                .sum(),
+            Element::TabView(tabs) => {
+                tabs.iter().map(|tab| count_footnotes(&tab.elements)).sum()
+            }
+            Element::List { items, .. } => items
+                .iter()
+                .map(|item| match item {
+                    ListItem::Elements { elements, .. } => count_footnotes(elements),
+                    ListItem::SubList { element } => {
+                        count_footnotes(std::slice::from_ref(element.as_ref()))
+                    }
+                })
+                .sum(),
+            Element::DefinitionList(items) => items
+                .iter()
+                .map(|item| count_footnotes(&item.key) + count_footnotes(&item.value))
+                .sum(),
+            Element::Anchor { elements, .. }
+            | Element::Collapsible { elements, .. }
+            | Element::Color { elements, .. }
+            | Element::Include { elements, .. } => count_footnotes(elements),
+            Element::Spanned { element, .. } => {
+                count_footnotes(std::slice::from_ref(element.as_ref()))
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Gets the elements before the footnote block at the end, if the parser would add it.
+///
+/// The parser adds a footnote block only if none are present,
+/// so it can only be removed if it is the only one.
+pub fn trailing_footnote_block<'a, 't>(
+    elements: &'a [Element<'t>],
+) -> Option<&'a [Element<'t>]> {
+    match elements.split_last() {
+        Some((
+            Element::FootnoteBlock {
+                title: None,
+                hide: false,
+            },
+            rest,
+        )) if !has_footnote_block(rest) => Some(rest),
+        _ => None,
+    }
+}
+
+fn has_footnote_block(elements: &[Element]) -> bool {
+    elements.iter().any(|element| match element {
+        Element::FootnoteBlock { .. } => true,
+        Element::Container(container) => has_footnote_block(container.elements()),
+        Element::TabView(tabs) => {
+            tabs.iter().any(|tab| has_footnote_block(&tab.elements))
+        }
+        Element::Anchor { elements, .. }
+        | Element::Collapsible { elements, .. }
+        | Element::Color { elements, .. }
+        | Element::Include { elements, .. } => has_footnote_block(elements),
+        Element::Spanned { element, .. } => {
+            has_footnote_block(std::slice::from_ref(element.as_ref()))
+        }
+        _ => false,
+    })
+}
@@ -0,0 +1,323 @@
+/*
+ * render/wikitext/escape.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::EMPTY_COMMENT;
+use crate::url::is_url;
+use regex::Regex;
+
+/// Sequences which have meaning anywhere in wikitext.
+///
+/// Longer sequences must come before any of their prefixes.
+const INLINE_SEQUENCES: [&str; 18] = [
+    "[!--", "--]", "**", "//", "__", "^^", ",,", "##", "{{", "}}", "{$", "[[", "]]",
+    "||", "<<", "@@", "@<", ">@",
+];
+
+/// A character block producing a double quote.
+const QUOTE_CHARACTER: &str = "[[char quot]]";
+
+/// Characters which start a block if they begin a line.
+const LINE_START_CHARACTERS: [char; 8] = ['*', '#', '+', '>', '=', ':', '|', '~'];
+
+/// Characters which, if they follow `[`, begin a single-bracket link.
+const LINK_START_CHARACTERS: [char; 5] = ['[', '#', '*', '/', '!'];
+
+lazy_static! {
+    static ref URL_REGEX: Regex = Regex::new(r"^(?:https?|ftp)://[^\s|\[\]]+").unwrap();
+    static ref STRING_REGEX: Regex =
+        Regex::new(r#"^"(?:[^\n\r"\\]|\\["\\rnt'])*""#).unwrap();
+    static ref STRING_START_REGEX: Regex =
+        Regex::new(r#"^"(?:[^\n\r"\\]|\\["\\rnt'])*"#).unwrap();
+}
+
+/// Escapes text so that it is not interpreted as wikitext.
+///
+/// Sequences with special meaning are wrapped in raw blocks (`@@`),
+/// with the rest of the text left as-is so that it remains readable.
+/// URLs and quoted strings are passed through unchanged, since they are
+/// already treated as a single unit by the tokenizer. A lone `"` is written
+/// as a character block instead, as it could otherwise begin a string
+/// which ends with a quote in later wikitext. A `.` which would complete an
+/// ellipsis with the output before it is preceded by an empty comment,
+/// so that typography does not replace them.
+///
+/// If `line_start` is set, then the text begins a line, and so
+/// a character which would begin a heading, list, or other block is also escaped.
+/// Leading whitespace is skipped for this check, since list items may be indented.
+pub fn escape(buffer: &mut String, s: &str, line_start: bool) {
+    if line_start && starts_line_syntax(s) {
+        // An empty raw means the text is no longer at the start of the line
+        buffer.push_str("@@@@");
+    }
+
+    let mut rest = s;
+    while let Some(ch) = rest.chars().next() {
+        let length = special_length(rest);
+
+        if length > 0 {
+            push_raw(buffer, &rest[..length]);
+            rest = &rest[length..];
+            continue;
+        }
+
+        if let Some(mtch) = URL_REGEX.find(rest).or_else(|| STRING_REGEX.find(rest)) {
+            buffer.push_str(mtch.as_str());
+            rest = &rest[mtch.end()..];
+            continue;
+        }
+
+        match ch {
+            '\0' => buffer.push(' '), // convert NULL chars to spaces
+            '"' => buffer.push_str(QUOTE_CHARACTER),
+            '@' if buffer.ends_with(['>', '@']) => {
+                buffer.push_str(EMPTY_COMMENT);
+                buffer.push('@');
+            }
+            '.' if buffer.ends_with("..") || buffer.ends_with(". . ") => {
+                // Typography would make this an ellipsis
+                buffer.push_str(EMPTY_COMMENT);
+                buffer.push('.');
+            }
+            _ => buffer.push(ch),
+        }
+
+        rest = &rest[ch.len_utf8()..];
+    }
+}
+
+/// Determines if the text would begin a heading, list, or other block at the start of a line.
+#[inline]
+pub fn starts_line_syntax(s: &str) -> bool {
+    s.trim_start().starts_with(LINE_START_CHARACTERS)
+}
+
+/// Determines if the text would be read as one token with a `[` before it.
+#[inline]
+pub fn joins_bracket(s: &str) -> bool {
+    s.starts_with(['[', '#', '*']) || s.starts_with("!--")
+}
+
+/// Determines if the text begins with a URL or path, which would form a link with a `[` before it.
+#[inline]
+pub fn starts_link(s: &str) -> bool {
+    s.starts_with('/') || URL_REGEX.is_match(s)
+}
+
+/// Determines if the wikitext has a `"` which could begin a string ending after it.
+///
+/// This is the case if nothing after the `"` prevents it from being a string,
+/// such as a newline or an invalid escape, and it is not already part of one.
+pub fn has_open_string(s: &str) -> bool {
+    let mut rest = s;
+
+    while let Some(index) = rest.find('"') {
+        rest = &rest[index..];
+
+        if let Some(mtch) = STRING_REGEX.find(rest) {
+            rest = &rest[mtch.end()..];
+            continue;
+        }
+
+        // Reaching the end means a later '"' could close it
+        let length = STRING_START_REGEX.find(rest).map_or(0, |mtch| mtch.end());
+        if length == rest.len() {
+            return true;
+        }
+
+        rest = &rest[1..];
+    }
+
+    false
+}
+
+/// Determines how many bytes at the start of the string must be escaped.
+///
+/// Returns zero if the string does not begin with special syntax.
+fn special_length(s: &str) -> usize {
+    for sequence in INLINE_SEQUENCES {
+        if s.starts_with(sequence) {
+            return sequence.len();
+        }
+    }
+
+    // Runs of dashes or tildes, as a whole
+    let dashes = s.len() - s.trim_start_matches('-').len();
+    if dashes >= 2 {
+        return dashes;
+    }
+
+    let tildes = s.len() - s.trim_start_matches('~').len();
+    if tildes >= 3 {
+        return tildes;
+    }
+
+    // Single-bracket links
+    if let Some(after) = s.strip_prefix('[') {
+        if after.starts_with(LINK_START_CHARACTERS) || is_url(after) {
+            return 1;
+        }
+    }
+
+    0
+}
+
+/// Wraps the given text in a raw block, choosing delimiters it does not conflict with.
+///
+/// Raw text ending in `>` would be read as ending with `>@`,
+/// so any trailing `>` characters are placed after the raw block instead.
+/// They must not be followed by `@`.
+///
+/// If the buffer ends with `@` or `>`, an empty comment is added first,
+/// so that it is not read together with the opening of the raw block.
+pub fn push_raw(buffer: &mut String, s: &str) {
+    debug_assert!(!s.contains('\n'), "Raw text cannot contain newlines");
+
+    if buffer.ends_with(['@', '>']) {
+        buffer.push_str(EMPTY_COMMENT);
+    }
+
+    let trimmed = s.trim_end_matches('>');
+    if !trimmed.is_empty() && trimmed.len() < s.len() {
+        push_raw(buffer, trimmed);
+        buffer.push_str(&s[trimmed.len()..]);
+        return;
+    }
+
+    // Only "@<" can contain "@@" or end with '@', but it cannot contain ">@"
+    if s.contains('@') && !s.contains(">@") {
+        buffer.push_str("@<");
+        buffer.push_str(s);
+        buffer.push_str(">@");
+    } else {
+        buffer.push_str("@@");
+        buffer.push_str(s);
+        buffer.push_str("@@");
+    }
+}
+
+/// Writes a double-quoted string, as used for block arguments.
+///
+/// Backslashes, quotes, and control characters are escaped.
+pub fn push_string(buffer: &mut String, s: &str) {
+    buffer.push('"');
+
+    for ch in s.chars() {
+        match ch {
+            '\\' => buffer.push_str(r"\\"),
+            '"' => buffer.push_str(r#"\""#),
+            '\r' => buffer.push_str(r"\r"),
+            '\n' => buffer.push_str(r"\n"),
+            '\t' => buffer.push_str(r"\t"),
+            _ => buffer.push(ch),
+        }
+    }
+
+    buffer.push('"');
+}
+
+#[test]
+fn test() {
+    macro_rules! test {
+        ($input:expr, $line_start:expr, $expected:expr $(,)?) => {{
+            let mut buffer = String::new();
+            escape(&mut buffer, $input, $line_start);
+
+            assert_eq!(
+                &buffer, $expected,
+                "Escaped wikitext doesn't match expected",
+            );
+        }};
+    }
+
+    test!("", true, "");
+    test!("Hello, world!", true, "Hello, world!");
+    test!(
+        "**bold** and //italics//",
+        false,
+        "@@**@@bold@@**@@ and @@//@@italics@@//@@",
+    );
+    test!(
+        "see https://example.com/a//b",
+        false,
+        "see https://example.com/a//b"
+    );
+    test!("a -- b --- c - d", false, "a @@--@@ b @@---@@ c - d");
+    test!("[[div]]", false, "@@[[@@div@@]]@@");
+    test!("[citation needed]", false, "[citation needed]");
+    test!(
+        "[https://example.com label]",
+        false,
+        "@@[@@https://example.com label]"
+    );
+    test!("[#anchor]", false, "@@[@@#anchor]");
+    test!("a @@ b", false, "a @<@@>@ b");
+    test!("a >@ b", false, "a @@>@@@ b");
+    test!("<b>text</b>", false, "<b>text</b>");
+    test!(r#"say "**hi**""#, false, r#"say "**hi**""#);
+    test!(r#"say "**hi"#, false, r#"say [[char quot]]@@**@@hi"#);
+    test!("+ heading", true, "@@@@+ heading");
+    test!("+ heading", false, "+ heading");
+    test!("* item", true, "@@@@* item");
+    test!("  * item", true, "@@@@  * item");
+    test!("1 + 1 = 2", true, "1 + 1 = 2");
+    test!("wait...", false, "wait..[!----].");
+    test!("wait . . . and . .", false, "wait . . [!----]. and . .");
+}
+
+#[test]
+fn string() {
+    macro_rules! test {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let mut buffer = String::new();
+            push_string(&mut buffer, $input);
+
+            assert_eq!(&buffer, $expected, "Quoted string doesn't match expected");
+        }};
+    }
+
+    test!("", r#""""#);
+    test!("apple", r#""apple""#);
+    test!(r#"say "hi""#, r#""say \"hi\"""#);
+    test!(r"C:\dir", r#""C:\\dir""#);
+    test!("line 1\nline 2\t!", r#""line 1\nline 2\t!""#);
+}
+
+#[test]
+fn open_string() {
+    macro_rules! test {
+        ($input:expr, $expected:expr $(,)?) => {{
+            assert_eq!(
+                has_open_string($input),
+                $expected,
+                "Open string check for {:?} doesn't match expected",
+                $input,
+            );
+        }};
+    }
+
+    test!("", false);
+    test!("apple", false);
+    test!(r#"say "hi""#, false);
+    test!(r#"say "hi"#, true);
+    test!(r#""a" "b" "c"#, true);
+    test!(r#"say "hi\" there"#, true);
+    test!(r#"say "hi\x there"#, false);
+    test!("say \"hi\nthere", false);
+}
@@ -0,0 +1,156 @@
+/*
+ * render/wikitext/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Renders a syntax tree back into wikitext.
+//!
+//! The output is canonical: parsing it produces the same syntax tree,
+//! so a tree can be modified programmatically and then saved as a page.
+//! It is not necessarily the same text the tree was originally parsed from.
+//!
+//! Native syntax (such as `**bold**`, `* lists`, or `|| tables ||`) is used
+//! where it can represent an element exactly, and otherwise the equivalent
+//! block (such as `[[b]]`, `[[ul]]`, or `[[table]]`) is used instead.
+//!
+//! Some information in a syntax tree cannot be expressed in wikitext,
+//! and is dropped:
+//!
+//! * Attributes on headings, native tables, and native definition lists.
+//! * Link and anchor targets other than opening in a new tab.
+//! * Sub-second precision in dates without a timezone.
+//! * The table of contents, which is generated from the headings.
+//!
+//! Included pages are written as `[[include-elements]]` blocks,
+//! rather than their contents, since the parser fetches them again.
+//! Page styles are written as `[[css]]` blocks at the end of the page.
+
+mod context;
+mod elements;
+pub(crate) mod escape;
+mod module;
+
+use self::context::WikitextContext;
+use self::elements::{render_elements, trailing_footnote_block};
+use crate::data::PageInfo;
+use crate::render::Render;
+use crate::settings::WikitextSettings;
+use crate::tree::{Element, SyntaxTree};
+use std::borrow::Cow;
+
+/// A comment containing nothing, used to separate syntax.
+///
+/// For instance, this keeps a newline from being consumed by a block after it.
+const EMPTY_COMMENT: &str = "[!----]";
+
+/// A character block producing a `[`.
+///
+/// This is for a `[` in text which would otherwise be read
+/// together with the syntax after it, such as `[[`.
+const LEFT_BRACKET_CHARACTER: &str = "[[char lsqb]]";
+
+/// A comment which ends any string before it.
+///
+/// Strings cannot contain a backslash that is not part of an escape,
+/// so this keeps a lone `"` from being paired with a later one.
+const END_STRING_COMMENT: &str = r"[!--\--]";
+
+/// Escapes text, so that it appears as written when placed in wikitext.
+///
+/// If `line_start` is set, then the text begins a line, and so anything
+/// which would begin a heading, list, or other block there is also escaped.
+pub fn escape_text(s: &str, line_start: bool) -> String {
+    let mut buffer = String::new();
+    escape::escape(&mut buffer, s, line_start);
+    buffer
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct WikitextRender;
+
+impl WikitextRender {
+    #[inline]
+    pub fn render_partial(
+        &self,
+        elements: &[Element],
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> String {
+        self.render_partial_direct(elements, page_info, settings, &[], &[])
+    }
+
+    fn render_partial_direct(
+        &self,
+        elements: &[Element],
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+        footnotes: &[Vec<Element>],
+        styles: &[Cow<str>],
+    ) -> String {
+        info!(
+            "Rendering wikitext (site {}, page {}, category {})",
+            page_info.site.as_ref(),
+            page_info.page.as_ref(),
+            match &page_info.category {
+                Some(category) => category.as_ref(),
+                None => "_default",
+            },
+        );
+
+        let mut ctx = WikitextContext::new(page_info, settings, footnotes);
+        render_elements(&mut ctx, elements);
+
+        for style in styles {
+            ctx.end_block();
+            ctx.push_block_start(false, "css");
+            ctx.push_str("]]\n");
+            ctx.push_str(style);
+            ctx.end_line();
+            ctx.push_block_end("css");
+        }
+
+        ctx.trim_end_newlines();
+        ctx.into()
+    }
+}
+
+impl Render for WikitextRender {
+    type Output = String;
+
+    fn render(
+        &self,
+        tree: &SyntaxTree,
+        page_info: &PageInfo,
+        settings: &WikitextSettings,
+    ) -> String {
+        // The parser adds a footnote block if there isn't one already,
+        // so there's no need to write it out.
+        let elements = match trailing_footnote_block(&tree.elements) {
+            Some(elements) => elements,
+            None => &tree.elements,
+        };
+
+        self.render_partial_direct(
+            elements,
+            page_info,
+            settings,
+            &tree.footnotes,
+            &tree.styles,
+        )
+    }
+}
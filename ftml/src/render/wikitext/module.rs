@@ -0,0 +1,179 @@
+/*
+ * render/wikitext/module.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::WikitextContext;
+use crate::tree::{
+    ListPagesOrderField, ListPagesQuery, Module, RatingComparison,
+    LIST_PAGES_DEFAULT_PER_PAGE,
+};
+
+pub fn render_module(ctx: &mut WikitextContext, module: &Module) {
+    info!("Rendering module '{}'", module.name());
+
+    match module {
+        Module::Backlinks { page } => {
+            ctx.push_str("[[module Backlinks");
+
+            if let Some(page) = page {
+                ctx.push_argument("page", page);
+            }
+
+            ctx.push_str("]]");
+        }
+        Module::Categories { include_hidden } => {
+            ctx.push_str("[[module Categories");
+
+            if *include_hidden {
+                ctx.push_argument("includeHidden", "true");
+            }
+
+            ctx.push_str("]]");
+        }
+        Module::Join {
+            button_text,
+            attributes,
+        } => {
+            ctx.push_str("[[module Join");
+
+            if let Some(text) = button_text {
+                ctx.push_argument("button", text);
+            }
+
+            ctx.push_attributes(attributes);
+            ctx.push_str("]]");
+        }
+        Module::ListPages { query, template } => {
+            ctx.push_str("[[module ListPages");
+            render_list_pages_query(ctx, query);
+            ctx.push_str("]]\n");
+            ctx.push_str(template);
+            ctx.end_line();
+            ctx.push_str("[[/module]]");
+        }
+        Module::PageTree {
+            root,
+            show_root,
+            depth,
+        } => {
+            ctx.push_str("[[module PageTree");
+
+            if let Some(root) = root {
+                ctx.push_argument("root", root);
+            }
+
+            if *show_root {
+                ctx.push_argument("showRoot", "true");
+            }
+
+            if let Some(depth) = depth {
+                ctx.push_argument("depth", &depth.to_string());
+            }
+
+            ctx.push_str("]]");
+        }
+        Module::Rate => ctx.push_str("[[module Rate]]"),
+    }
+}
+
+fn render_list_pages_query(ctx: &mut WikitextContext, query: &ListPagesQuery) {
+    // Categories, with exclusions prefixed by '-'
+    let mut categories = Vec::new();
+
+    match &query.categories {
+        Some(included) => {
+            categories.extend(included.iter().map(|category| str!(category)));
+        }
+        None if !query.excluded_categories.is_empty() => categories.push(str!("*")),
+        None => (),
+    }
+
+    for category in &query.excluded_categories {
+        categories.push(format!("-{category}"));
+    }
+
+    if !categories.is_empty() {
+        ctx.push_argument("category", &categories.join(" "));
+    }
+
+    // Tags, with '+' for required tags and '-' for excluded ones
+    let tags: Vec<_> = query
+        .tags
+        .iter()
+        .map(|tag| str!(tag))
+        .chain(query.required_tags.iter().map(|tag| format!("+{tag}")))
+        .chain(query.excluded_tags.iter().map(|tag| format!("-{tag}")))
+        .collect();
+
+    if !tags.is_empty() {
+        ctx.push_argument("tags", &tags.join(" "));
+    }
+
+    if let Some(created_by) = &query.created_by {
+        ctx.push_argument("created_by", created_by);
+    }
+
+    if let Some(rating) = query.rating {
+        let comparison = match rating.comparison {
+            RatingComparison::Equal => "=",
+            RatingComparison::NotEqual => "!=",
+            RatingComparison::Greater => ">",
+            RatingComparison::GreaterOrEqual => ">=",
+            RatingComparison::Less => "<",
+            RatingComparison::LessOrEqual => "<=",
+        };
+
+        ctx.push_argument("rating", &format!("{comparison}{}", rating.value));
+    }
+
+    if query.order != Default::default() {
+        let field = match query.order.field {
+            ListPagesOrderField::Name => "name",
+            ListPagesOrderField::Title => "title",
+            ListPagesOrderField::CreatedAt => "created_at",
+            ListPagesOrderField::CreatedBy => "created_by",
+            ListPagesOrderField::UpdatedAt => "updated_at",
+            ListPagesOrderField::Rating => "rating",
+            ListPagesOrderField::Random => "random",
+        };
+        let direction = if query.order.descending {
+            "desc"
+        } else {
+            "asc"
+        };
+
+        ctx.push_argument("order", &format!("{field} {direction}"));
+    }
+
+    if query.offset > 0 {
+        ctx.push_argument("offset", &query.offset.to_string());
+    }
+
+    if let Some(limit) = query.limit {
+        ctx.push_argument("limit", &limit.to_string());
+    }
+
+    if query.per_page.get() != LIST_PAGES_DEFAULT_PER_PAGE {
+        ctx.push_argument("perPage", &query.per_page.to_string());
+    }
+
+    if !query.separate {
+        ctx.push_argument("separate", "false");
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * settings/css.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+
+/// The at-rules which are permitted by default.
+///
+/// Notably, this excludes `@import`, which fetches stylesheets
+/// from elsewhere, as well as `@charset` and `@namespace`.
+pub const DEFAULT_ALLOWED_AT_RULES: [&str; 11] = [
+    "container",
+    "counter-style",
+    "font-face",
+    "font-feature-values",
+    "keyframes",
+    "layer",
+    "media",
+    "page",
+    "supports",
+    "-moz-keyframes",
+    "-webkit-keyframes",
+];
+
+/// The URL schemes which are permitted by default.
+pub const DEFAULT_ALLOWED_URL_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Settings describing what CSS from users is permitted.
+///
+/// This applies to the contents of `[[css]]` blocks and `[[module CSS]]`,
+/// as well as `style` attributes. Anything not permitted is removed,
+/// along with a warning.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CssSettings {
+    /// Which at-rules are permitted, such as `media`, without the `@`.
+    ///
+    /// These are matched case-insensitively.
+    pub allowed_at_rules: Vec<Cow<'static, str>>,
+
+    /// Which URL schemes are permitted, such as `https`, without the colon.
+    ///
+    /// These are matched case-insensitively. URLs without a scheme, such
+    /// as `/local--files/page/image.png`, are relative and always permitted.
+    /// Protocol-relative URLs (beginning with `//`) are only permitted
+    /// if `http` or `https` is.
+    pub allowed_url_schemes: Vec<Cow<'static, str>>,
+
+    /// The class of the element which the page's contents are rendered within, if any.
+    ///
+    /// If set, selectors in stylesheets are scoped to within this element, so that
+    /// they cannot affect the rest of the site. For instance, with the class `page-content`,
+    /// the selector `a, body .title` becomes `.page-content a, .page-content .title`.
+    ///
+    /// This must be a valid CSS class name.
+    pub scope_class: Option<Cow<'static, str>>,
+}
+
+impl CssSettings {
+    /// Whether the given at-rule, without the `@`, is permitted.
+    pub fn allows_at_rule(&self, name: &str) -> bool {
+        self.allowed_at_rules
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the given URL scheme, without the colon, is permitted.
+    pub fn allows_url_scheme(&self, scheme: &str) -> bool {
+        self.allowed_url_schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+}
+
+impl Default for CssSettings {
+    fn default() -> Self {
+        CssSettings {
+            allowed_at_rules: DEFAULT_ALLOWED_AT_RULES
+                .iter()
+                .map(|&name| cow!(name))
+                .collect(),
+            allowed_url_schemes: DEFAULT_ALLOWED_URL_SCHEMES
+                .iter()
+                .map(|&scheme| cow!(scheme))
+                .collect(),
+            scope_class: None,
+        }
+    }
+}
@@ -18,9 +18,16 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+mod css;
 mod interwiki;
+mod url;
 
+pub use self::css::{CssSettings, DEFAULT_ALLOWED_AT_RULES, DEFAULT_ALLOWED_URL_SCHEMES};
 pub use self::interwiki::{InterwikiSettings, DEFAULT_INTERWIKI, EMPTY_INTERWIKI};
+pub use self::url::UrlSettings;
+
+/// The default value for [`WikitextSettings::max_include_depth`].
+pub const DEFAULT_MAX_INCLUDE_DEPTH: u32 = 5;
 
 /// Settings to tweak behavior in the ftml parser and renderer.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -46,6 +53,14 @@ pub struct WikitextSettings {
     /// It is off by default.
     pub use_include_compatibility: bool,
 
+    /// How deeply nested `[[include-messy]]` blocks are expanded.
+    ///
+    /// Includes in the original wikitext have a depth of one, includes within
+    /// those pages have a depth of two, and so on. Blocks beyond this depth
+    /// are not expanded, and the includer's wikitext for this is used instead.
+    #[serde(default = "default_max_include_depth")]
+    pub max_include_depth: u32,
+
     /// Whether IDs should have true values, or be excluded or randomly generated.
     ///
     /// In the latter case, IDs can be used for navigation, for instance
@@ -72,6 +87,18 @@ pub struct WikitextSettings {
     /// * Images
     pub allow_local_paths: bool,
 
+    /// Whether to record where each element came from in the source.
+    ///
+    /// If enabled, elements and paragraphs are wrapped in [`Element::Spanned`],
+    /// which carries the byte offsets of the wikitext they were parsed from.
+    /// Elements from included pages are not given spans.
+    ///
+    /// This is off by default, since it adds a node for each element.
+    ///
+    /// [`Element::Spanned`]: crate::tree::Element::Spanned
+    #[serde(default)]
+    pub record_spans: bool,
+
     /// What interwiki prefixes are supported.
     ///
     /// All instances of `$$` in the destination URL are replaced with the link provided
@@ -85,48 +112,85 @@ pub struct WikitextSettings {
     ///   any beyond that are considered part of the link.
     /// * By convention, prefixes should be all-lowercase.
     pub interwiki: InterwikiSettings,
+
+    /// Where sites and their uploaded files are hosted.
+    ///
+    /// This is used to build links to pages on other sites,
+    /// and the URLs of images and other files attached to pages.
+    #[serde(default)]
+    pub urls: UrlSettings,
+
+    /// What CSS is permitted in stylesheets and `style` attributes.
+    ///
+    /// By default, `@import` and URLs with schemes other than `http` and `https`
+    /// are removed, and selectors are not scoped.
+    #[serde(default)]
+    pub css: CssSettings,
+}
+
+#[inline]
+fn default_max_include_depth() -> u32 {
+    DEFAULT_MAX_INCLUDE_DEPTH
 }
 
 impl WikitextSettings {
     pub fn from_mode(mode: WikitextMode) -> Self {
         let interwiki = DEFAULT_INTERWIKI.clone();
+        let urls = UrlSettings::default();
+        let css = CssSettings::default();
 
         match mode {
             WikitextMode::Page => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
                 use_true_ids: true,
                 isolate_user_ids: false,
                 allow_local_paths: true,
+                record_spans: false,
                 interwiki,
+                urls,
+                css,
             },
             WikitextMode::Draft => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 allow_local_paths: true,
+                record_spans: false,
                 interwiki,
+                urls,
+                css,
             },
             WikitextMode::ForumPost | WikitextMode::DirectMessage => WikitextSettings {
                 mode,
                 enable_page_syntax: false,
                 use_include_compatibility: false,
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 allow_local_paths: false,
+                record_spans: false,
                 interwiki,
+                urls,
+                css,
             },
             WikitextMode::List => WikitextSettings {
                 mode,
                 enable_page_syntax: true,
                 use_include_compatibility: false,
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
                 use_true_ids: false,
                 isolate_user_ids: false,
                 allow_local_paths: true,
+                record_spans: false,
                 interwiki,
+                urls,
+                css,
             },
         }
     }
@@ -156,3 +220,27 @@ pub enum WikitextMode {
     /// Processing for modules or other contexts such as `ListPages`.
     List,
 }
+
+#[test]
+fn settings_defaults() {
+    let json = r#"{
+        "mode": "page",
+        "enable-page-syntax": true,
+        "use-include-compatibility": false,
+        "use-true-ids": true,
+        "isolate-user-ids": false,
+        "allow-local-paths": true,
+        "interwiki": {}
+    }"#;
+
+    let settings: WikitextSettings = serde_json::from_str(json).unwrap();
+    let expected = WikitextSettings {
+        interwiki: InterwikiSettings::default(),
+        ..WikitextSettings::from_mode(WikitextMode::Page)
+    };
+
+    assert_eq!(
+        settings, expected,
+        "Omitted settings don't match those from the mode",
+    );
+}
@@ -0,0 +1,187 @@
+/*
+ * settings/url.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::url::BuildSiteUrl;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use wikidot_normalize::normalize;
+
+/// Settings describing where sites and their uploaded files are hosted.
+///
+/// By default, each site is a subdomain of the main domain
+/// (e.g. `https://scp-wiki.wikijump.com/scp-001`), and each site's files
+/// are a subdomain of the files domain
+/// (e.g. `https://scp-wiki.wjfiles.com/local--files/scp-001/image.png`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UrlSettings {
+    /// The URL scheme used for sites and files, such as `https`.
+    pub scheme: Cow<'static, str>,
+
+    /// The domain which sites are hosted under.
+    ///
+    /// This may include a port, for instance `localhost:8080`.
+    pub main_domain: Cow<'static, str>,
+
+    /// The domain which uploaded files are hosted under.
+    ///
+    /// This should be different from the main domain, so that
+    /// user-uploaded content cannot access the site's cookies.
+    pub files_domain: Cow<'static, str>,
+
+    /// Whether sites are distinguished by path rather than by subdomain.
+    ///
+    /// If set, then a site's URLs take the form `{main_domain}/{site}/{path}`,
+    /// and its files `{files_domain}/{site}/local--files/{page}/{file}`.
+    pub path_sites: bool,
+
+    /// Sites which are served from their own domain.
+    ///
+    /// This maps the site slug to the domain, such as `scpwiki.com`.
+    /// Uploaded files are still hosted under the files domain.
+    pub custom_domains: HashMap<Cow<'static, str>, Cow<'static, str>>,
+}
+
+impl UrlSettings {
+    /// Builds the URL to a page or other path on the given site.
+    pub fn site_url(&self, site: &str, path: &str) -> String {
+        let UrlSettings {
+            scheme,
+            main_domain,
+            path_sites,
+            custom_domains,
+            ..
+        } = self;
+
+        let path = {
+            let mut path = str!(path);
+            normalize(&mut path);
+            path
+        };
+
+        match custom_domains.get(site) {
+            Some(domain) => format!("{scheme}://{domain}/{path}"),
+            None if *path_sites => format!("{scheme}://{main_domain}/{site}/{path}"),
+            None => format!("{scheme}://{site}.{main_domain}/{path}"),
+        }
+    }
+
+    /// Builds the URL to a file uploaded to a page on the given site.
+    pub fn file_url(&self, site: &str, page: &str, file: &str) -> String {
+        let UrlSettings {
+            scheme,
+            files_domain,
+            path_sites,
+            ..
+        } = self;
+
+        if *path_sites {
+            format!("{scheme}://{files_domain}/{site}/local--files/{page}/{file}")
+        } else {
+            format!("{scheme}://{site}.{files_domain}/local--files/{page}/{file}")
+        }
+    }
+}
+
+impl Default for UrlSettings {
+    fn default() -> Self {
+        UrlSettings {
+            scheme: cow!("https"),
+            main_domain: cow!("wikijump.com"),
+            files_domain: cow!("wjfiles.com"),
+            path_sites: false,
+            custom_domains: HashMap::new(),
+        }
+    }
+}
+
+impl BuildSiteUrl for UrlSettings {
+    #[inline]
+    fn build_url(&self, site: &str, path: &str) -> String {
+        self.site_url(site, path)
+    }
+}
+
+#[test]
+fn urls() {
+    let mut settings = UrlSettings::default();
+
+    macro_rules! check {
+        ($site:expr, $path:expr, $site_url:expr, $file_url:expr $(,)?) => {{
+            assert_eq!(
+                settings.site_url($site, $path),
+                $site_url,
+                "Actual site URL doesn't match expected",
+            );
+
+            assert_eq!(
+                settings.file_url($site, $path, "image.png"),
+                $file_url,
+                "Actual file URL doesn't match expected",
+            );
+        }};
+    }
+
+    check!(
+        "scp-wiki",
+        "scp-001",
+        "https://scp-wiki.wikijump.com/scp-001",
+        "https://scp-wiki.wjfiles.com/local--files/scp-001/image.png",
+    );
+    check!(
+        "scp-wiki",
+        "Main Page",
+        "https://scp-wiki.wikijump.com/main-page",
+        "https://scp-wiki.wjfiles.com/local--files/Main Page/image.png",
+    );
+
+    settings.scheme = cow!("http");
+    settings.main_domain = cow!("wiki.example.com");
+    settings.files_domain = cow!("files.example.net");
+    check!(
+        "sandbox",
+        "start",
+        "http://sandbox.wiki.example.com/start",
+        "http://sandbox.files.example.net/local--files/start/image.png",
+    );
+
+    settings
+        .custom_domains
+        .insert(cow!("scp-wiki"), cow!("scpwiki.com"));
+    check!(
+        "scp-wiki",
+        "scp-001",
+        "http://scpwiki.com/scp-001",
+        "http://scp-wiki.files.example.net/local--files/scp-001/image.png",
+    );
+
+    settings.path_sites = true;
+    check!(
+        "sandbox",
+        "start",
+        "http://wiki.example.com/sandbox/start",
+        "http://files.example.net/sandbox/local--files/start/image.png",
+    );
+    check!(
+        "scp-wiki",
+        "scp-001",
+        "http://scpwiki.com/scp-001",
+        "http://files.example.net/scp-wiki/local--files/scp-001/image.png",
+    );
+}
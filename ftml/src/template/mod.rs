@@ -0,0 +1,136 @@
+/*
+ * template/mod.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module implements Wikidot "live templates".
+//!
+//! A category may have a `_template` page, whose wikitext wraps the contents
+//! of every other page in that category. Variables such as `%%content%%`
+//! and `%%title%%` in the template are substituted with the values for the
+//! page being rendered, and the resultant wikitext is then parsed as usual.
+//!
+//! Templates which do not contain any `%%content%%` variables are not
+//! live templates, and so are not applied.
+
+#[cfg(test)]
+mod test;
+
+use crate::data::PageInfo;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+lazy_static! {
+    static ref VARIABLE_REGEX: Regex = Regex::new(
+        r"%%(?P<name>[a-z_]+)(?:\{(?P<index>[0-9]+)\})?(?:\|(?P<format>.*?))?%%",
+    )
+    .unwrap();
+    static ref CONTENT_REGEX: Regex = Regex::new(r"%%content(\{[0-9]+\})?%%").unwrap();
+    static ref SEPARATOR_REGEX: Regex = Regex::new(r"(?m)^====[ \t]*$\n?").unwrap();
+}
+
+/// Determines if the given template wikitext is a live template.
+///
+/// That is, whether it contains either `%%content%%` or `%%content{n}%%`.
+pub fn is_live_template(template: &str) -> bool {
+    CONTENT_REGEX.is_match(template)
+}
+
+/// Applies a live template to the wikitext of a page.
+///
+/// The following variables are substituted:
+/// * `%%content%%` &mdash; The full wikitext of the page.
+/// * `%%content{n}%%` &mdash; The `n`th section of the page, starting from one,
+///   where sections are separated by lines consisting of `====`.
+/// * `%%title%%` &mdash; The title of the page.
+/// * `%%page_unix_name%%` &mdash; The slug of the page, including its category.
+/// * `%%name%%` &mdash; The slug of the page, without its category.
+/// * `%%category%%` &mdash; The category of the page.
+/// * `%%site%%` &mdash; The slug of the site the page is on.
+/// * `%%rating%%` &mdash; The rating of the page.
+/// * `%%tags%%` &mdash; The tags of the page, separated by spaces.
+/// * `%%date%%` &mdash; When the page was created, with an optional format,
+///   as in `%%date|%Y-%m-%d%%`.
+///
+/// Unknown variables are left as-is. If the template is not a live template,
+/// then the page's wikitext is returned unmodified.
+///
+/// The `created_at` argument is a UNIX timestamp.
+pub fn apply_template(
+    template: &str,
+    content: &str,
+    page_info: &PageInfo,
+    created_at: i64,
+) -> String {
+    if !is_live_template(template) {
+        info!("Template has no content variables, not applying");
+        return str!(content);
+    }
+
+    info!(
+        "Applying live template to page (content length {})",
+        content.len(),
+    );
+
+    let sections: Vec<&str> = SEPARATOR_REGEX.split(content).collect();
+    let output = VARIABLE_REGEX.replace_all(template, |captures: &Captures| {
+        let index = captures.name("index").map(|mtch| mtch.as_str());
+        let format = captures.name("format").map(|mtch| mtch.as_str());
+        let value: Cow<str> = match (&captures["name"], index) {
+            ("content", None) => Cow::Borrowed(content),
+            ("content", Some(index)) => {
+                let section = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| index.checked_sub(1))
+                    .and_then(|index| sections.get(index))
+                    .copied()
+                    .unwrap_or("");
+
+                Cow::Borrowed(section.trim_end_matches('\n'))
+            }
+            (_, Some(_)) => Cow::Borrowed(&captures[0]),
+            ("title", None) => Cow::Borrowed(&page_info.title),
+            ("page_unix_name", None) => match page_info.category {
+                Some(ref category) => {
+                    Cow::Owned(format!("{}:{}", category, page_info.page))
+                }
+                None => Cow::Borrowed(&page_info.page),
+            },
+            ("name", None) => Cow::Borrowed(&page_info.page),
+            ("category", None) => {
+                Cow::Borrowed(page_info.category.as_deref().unwrap_or("_default"))
+            }
+            ("site", None) => Cow::Borrowed(&page_info.site),
+            ("rating", None) => Cow::Owned(format!("{:+}", page_info.rating)),
+            ("tags", None) => Cow::Owned(page_info.tags.join(" ")),
+            ("date", None) => Cow::Owned(match format {
+                Some(format) => format!(
+                    "[[date {created_at} format=\"{}\"]]",
+                    format.replace('"', ""),
+                ),
+                None => format!("[[date {created_at}]]"),
+            }),
+            _ => Cow::Borrowed(&captures[0]),
+        };
+
+        value.into_owned()
+    });
+
+    output.into_owned()
+}
@@ -0,0 +1,99 @@
+/*
+ * template/test.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::{apply_template, is_live_template};
+use crate::data::PageInfo;
+use std::borrow::Cow;
+
+#[test]
+fn live_template() {
+    assert!(is_live_template("%%content%%"));
+    assert!(is_live_template("**Header**\n%%content{2}%%\nFooter"));
+    assert!(!is_live_template("This page has no content."));
+    assert!(!is_live_template("%%title%% %%contents%%"));
+}
+
+#[test]
+fn template() {
+    let mut page_info = PageInfo::dummy();
+    page_info.category = Some(Cow::Borrowed("fragment"));
+
+    macro_rules! test {
+        ($template:expr, $content:expr, $expected:expr $(,)?) => {{
+            let actual = apply_template($template, $content, &page_info, 1000000000);
+
+            println!("Template: {:?}", $template);
+            println!("Content:  {:?}", $content);
+            println!("Output:   {:?}", actual);
+            println!();
+
+            assert_eq!(
+                actual, $expected,
+                "Actual template output doesn't match expected",
+            );
+        }};
+    }
+
+    test!("Not a live template", "Apple", "Apple");
+    test!("%%content%%", "Apple", "Apple");
+    test!(
+        "Before\n%%content%%\nAfter",
+        "Apple",
+        "Before\nApple\nAfter"
+    );
+    test!(
+        "[[div class=\"wrapper\"]]\n%%content%%\n[[/div]]",
+        "Apple\n\nBanana",
+        "[[div class=\"wrapper\"]]\nApple\n\nBanana\n[[/div]]",
+    );
+    test!(
+        "# %%content{2}%%\n# %%content{1}%%\n# %%content{3}%%",
+        "Apple\n====\nBanana\n====\nCherry",
+        "# Banana\n# Apple\n# Cherry",
+    );
+    test!(
+        "[%%content{1}%%] [%%content{4}%%] [%%content{0}%%]",
+        "Apple\n====\nBanana",
+        "[Apple] [] []",
+    );
+    test!(
+        "%%title%% (%%page_unix_name%%, %%name%% in %%category%% on %%site%%)\n%%content%%",
+        "Apple",
+        "A page for the age (fragment:some-page, some-page in fragment on sandbox)\nApple",
+    );
+    test!(
+        "%%content%% %%rating%% %%tags%%",
+        "Apple",
+        "Apple +69 tale _cc",
+    );
+    test!(
+        "%%content%%\n%%date%%\n%%date|%Y-%m-%d%%",
+        "Apple",
+        "Apple\n[[date 1000000000]]\n[[date 1000000000 format=\"%Y-%m-%d\"]]",
+    );
+    test!(
+        "%%content%% %%unknown%% %%title{1}%%",
+        "Apple",
+        "Apple %%unknown%% %%title{1}%%",
+    );
+
+    // Variables in the page content are not substituted
+    test!("%%content%%", "%%title%%", "%%title%%");
+}
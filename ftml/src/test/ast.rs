@@ -27,10 +27,13 @@ use super::includer::TestIncluder;
 use crate::data::PageInfo;
 use crate::parsing::ParseWarning;
 use crate::render::html::HtmlRender;
+use crate::render::markdown::MarkdownRender;
 use crate::render::text::TextRender;
+use crate::render::wikitext::WikitextRender;
 use crate::render::Render;
 use crate::settings::{WikitextMode, WikitextSettings};
-use crate::tree::SyntaxTree;
+use crate::tree::{strip_spans, SyntaxTree};
+use crate::utf16::Utf16IndexMap;
 use std::borrow::Cow;
 use std::fs::{self, File};
 use std::io::Read;
@@ -121,6 +124,9 @@ struct Test<'a> {
 
     #[serde(skip)]
     text: String,
+
+    #[serde(skip)]
+    markdown: String,
 }
 
 impl Test<'_> {
@@ -177,6 +183,7 @@ impl Test<'_> {
         test.name = str!(name);
         test.html = load_output!("HTML", "html");
         test.text = load_output!("text", "txt");
+        test.markdown = load_output!("Markdown", "md");
         test
     }
 
@@ -211,16 +218,24 @@ impl Test<'_> {
 
         let settings = WikitextSettings::from_mode(WikitextMode::Page);
 
-        let (mut text, _pages) =
-            crate::include(&self.input, &settings, TestIncluder, || unreachable!())
-                .void_unwrap();
+        let (mut text, _pages) = crate::include(
+            &self.input,
+            &page_info,
+            &settings,
+            TestIncluder,
+            || unreachable!(),
+        )
+        .void_unwrap();
 
         crate::preprocess(&mut text);
         let tokens = crate::tokenize(&text);
-        let result = crate::parse(&tokens, &page_info, &settings);
+        let result =
+            crate::parse_with_includer(&tokens, &page_info, &settings, TestIncluder);
         let (tree, warnings) = result.into();
-        let html_output = HtmlRender.render(&tree, &page_info, &settings);
-        let text_output = TextRender.render(&tree, &page_info, &settings);
+        let html_output = HtmlRender::default().render(&tree, &page_info, &settings);
+        let text_output = TextRender::default().render(&tree, &page_info, &settings);
+        let markdown_output =
+            MarkdownRender::default().render(&tree, &page_info, &settings);
 
         fn json<T>(object: &T) -> String
         where
@@ -276,6 +291,94 @@ impl Test<'_> {
                 &tree,
             );
         }
+
+        if warnings.is_empty() {
+            let wikitext = WikitextRender.render(&tree, &page_info, &settings);
+            let (mut text, _pages) = crate::include(
+                &wikitext,
+                &page_info,
+                &settings,
+                TestIncluder,
+                || unreachable!(),
+            )
+            .void_unwrap();
+
+            crate::preprocess(&mut text);
+            let tokens = crate::tokenize(&text);
+            let result =
+                crate::parse_with_includer(&tokens, &page_info, &settings, TestIncluder);
+            let (wikitext_tree, _) = result.into();
+
+            if wikitext_tree != tree {
+                panic!(
+                    "Running test '{}' failed! Tree from wikitext does not match:\nWikitext: {:?}\nExpected: {:#?}\nActual: {:#?}",
+                    self.name,
+                    wikitext,
+                    tree,
+                    wikitext_tree,
+                );
+            }
+        }
+
+        // Check that recording spans doesn't otherwise change the tree
+        let spans_settings = WikitextSettings {
+            record_spans: true,
+            ..settings.clone()
+        };
+        let result = crate::parse_with_includer(
+            &tokens,
+            &page_info,
+            &spans_settings,
+            TestIncluder,
+        );
+        let (spans_tree, spans_warnings) = result.into();
+
+        // Panics if any span is out of range, or not on a character boundary
+        let _ = spans_tree.to_utf16_indices(&Utf16IndexMap::new(&text));
+
+        let mut stripped_tree = spans_tree.clone();
+        strip_spans(&mut stripped_tree.elements);
+        strip_spans(&mut stripped_tree.table_of_contents);
+
+        for footnote in &mut stripped_tree.footnotes {
+            strip_spans(footnote);
+        }
+
+        for entry in stripped_tree.bibliography.entries_mut() {
+            strip_spans(&mut entry.contents);
+        }
+
+        if stripped_tree != tree || spans_warnings != warnings {
+            panic!(
+                "Running test '{}' failed! Tree with spans does not match:\nExpected: {:#?}\nActual: {:#?}",
+                self.name,
+                tree,
+                stripped_tree,
+            );
+        }
+
+        let spans_html_output =
+            HtmlRender::default().render(&spans_tree, &page_info, &settings);
+
+        if spans_html_output.body != html_output.body {
+            panic!(
+                "Running test '{}' failed! HTML from tree with spans does not match:\nExpected: {:?}\nActual:   {:?}",
+                self.name,
+                html_output.body,
+                spans_html_output.body,
+            );
+        }
+
+        if markdown_output != self.markdown {
+            panic!(
+                "Running test '{}' failed! Markdown output does not match:\nExpected: {:?}\nActual:   {:?}\n\n{}\n\nTree (correct): {:#?}",
+                self.name,
+                self.markdown,
+                markdown_output,
+                markdown_output,
+                &tree,
+            );
+        }
     }
 }
 
@@ -336,7 +439,7 @@ fn ast_and_html() {
             Some("json") => Some(Test::load(&path, &stem)),
 
             // We expect these, don't print anything
-            Some("html") | Some("txt") => None,
+            Some("html") | Some("txt") | Some("md") => None,
 
             // Print for other, unexpected files
             _ => {
@@ -0,0 +1,503 @@
+/*
+ * test/css.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tests for sanitizing CSS written by users.
+
+use crate::css::{sanitize_style, sanitize_stylesheet, SanitizedCss};
+use crate::data::PageInfo;
+use crate::parsing::ParseWarningKind;
+use crate::render::{html::HtmlRender, Render};
+use crate::settings::{CssSettings, WikitextMode, WikitextSettings};
+use proptest::prelude::*;
+use std::borrow::Cow;
+
+/// Known ways of injecting scripts or external resources with CSS.
+///
+/// Each of these must be removed, in any context.
+const INJECTION_VECTORS: [&str; 31] = [
+    "@import url(https://example.com/evil.css);",
+    "@import 'https://example.com/evil.css';",
+    "@IMPORT \"evil.css\";",
+    "@im\\port url(evil.css);",
+    "@\\69 mport url(evil.css);",
+    "@charset \"utf-7\";",
+    "@namespace url(http://www.w3.org/1999/xhtml);",
+    "a { background: url(javascript:alert(1)) }",
+    "a { background: url('javascript:alert(1)') }",
+    "a { background: url( \"  JaVaScRiPt:alert(1)\" ) }",
+    "a { background: url(java\\9 script:alert(1)) }",
+    "a { background: url(\"java\\\nscript:alert(1)\") }",
+    "a { background: url(java\\73 cript:alert(1)) }",
+    "a { background: \\75 rl(javascript:alert(1)) }",
+    "a { background: url(vbscript:msgbox(1)) }",
+    "a { background: url(data:text/html;base64,PHNjcmlwdD4=) }",
+    "a { background-image: image-set(\"javascript:alert(1)\" 1x) }",
+    "a { background-image: -webkit-image-set('javascript:alert(1)' 1x) }",
+    "a { background: src(\"javascript:alert(1)\") }",
+    "a { width: expression(alert(1)) }",
+    "a { width: EXPRESSION(alert(1)) }",
+    "a { width: expr\\65 ssion(alert(1)) }",
+    "a { width: exp\\ression(alert(1)) }",
+    "a { behavior: url(script.htc) }",
+    "a { -ms-behavior: url(script.htc) }",
+    "a { -moz-binding: url(https://example.com/xbl.xml#exploit) }",
+    "a { -moz-b\\69 nding: url(https://example.com/xbl.xml#exploit) }",
+    "a { font-family: a\\</style><script>alert(1)</script> }",
+    "</style><script>alert(1)</script>",
+    "a { color: red } </STYLE ><script>alert(1)</script>",
+    "<!-- a { color: red } -->",
+];
+
+/// CSS which is permitted, and is unchanged by sanitization.
+const SAFE_CSS: [&str; 12] = [
+    "a { color: red; }",
+    "a:hover, .b > c[d=\"e\"] { color: #fff !important }",
+    "@media (max-width: 600px) { .a { display: none } }",
+    "@supports (display: grid) { .a { display: grid } }",
+    "@keyframes spin { from { transform: rotate(0deg) } to { transform: rotate(360deg) } }",
+    "@font-face { font-family: X; src: url(https://example.com/x.woff2) format('woff2') }",
+    ".a { background: url(/local--files/page/image.png) no-repeat }",
+    ".a { background: url('//example.com/image.png') }",
+    ".a { --main-color: #06c; color: var(--main-color) }",
+    ".a { & .b { color: red } }",
+    ".a { font: 12px/1.5 \"Helvetica\", sans-serif }",
+    ".a::before { content: \"\\201C\" }",
+];
+
+/// Whether this CSS contains anything which could end the `<style>` element.
+fn contains_end_tag(css: &str) -> bool {
+    css.contains("</") || css.contains("<!--")
+}
+
+#[test]
+fn injection_vectors() {
+    let settings = CssSettings::default();
+
+    for vector in INJECTION_VECTORS {
+        let SanitizedCss { css, issues } = sanitize_stylesheet(vector, &settings);
+
+        println!("Input:  {vector:?}");
+        println!("Output: {css:?}");
+
+        assert!(
+            !issues.is_empty(),
+            "No issues for injection vector {vector:?}"
+        );
+        assert!(
+            !contains_end_tag(&css),
+            "Output for {vector:?} could end the style element: {css:?}",
+        );
+
+        let lowercase = css.to_ascii_lowercase();
+        for needle in ["import", "javascript", "vbscript", "expression", "behavior"] {
+            assert!(
+                !lowercase.contains(needle),
+                "Output for {vector:?} still contains {needle:?}: {css:?}",
+            );
+        }
+    }
+}
+
+#[test]
+fn safe_css() {
+    let settings = CssSettings::default();
+
+    for input in SAFE_CSS {
+        let SanitizedCss { css, issues } = sanitize_stylesheet(input, &settings);
+
+        assert!(
+            matches!(css, Cow::Borrowed(_)),
+            "Safe CSS {input:?} was changed to {css:?}",
+        );
+        assert!(
+            issues.is_empty(),
+            "Issues for safe CSS {input:?}: {issues:?}"
+        );
+    }
+}
+
+#[test]
+fn stylesheets() {
+    let mut settings = CssSettings::default();
+
+    macro_rules! check {
+        ($input:expr, $expected:expr, $issues:expr $(,)?) => {{
+            let SanitizedCss { css, issues } = sanitize_stylesheet($input, &settings);
+            let issues: Vec<_> = issues
+                .into_iter()
+                .map(|issue| (issue.kind, &$input[issue.span]))
+                .collect();
+
+            assert_eq!(css, $expected, "Sanitized CSS doesn't match expected");
+            assert_eq!(
+                issues, $issues,
+                "Issues from sanitizing CSS don't match expected",
+            );
+        }};
+    }
+
+    check!(
+        "@import url(a.css);\na { color: red }",
+        "\na { color: red }",
+        [(ParseWarningKind::CssDisallowedAtRule, "@import url(a.css);")],
+    );
+    check!(
+        "a { color: red; background: url(javascript:x); width: 1px }",
+        "a { color: red;  width: 1px }",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "background: url(javascript:x);",
+        )],
+    );
+    check!(
+        "a { width: expression(1) } b { color: blue }",
+        "a { } b { color: blue }",
+        [(
+            ParseWarningKind::CssDisallowedFunction,
+            "width: expression(1) "
+        )],
+    );
+    check!(
+        "a:not(javascript:x) { color: red }",
+        "a:not(javascript:x) { color: red }",
+        [],
+    );
+    check!(
+        "a { background: url('a.png') } b { background: url(\"vbscript:x\") }",
+        "a { background: url('a.png') } b { }",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "background: url(\"vbscript:x\") ",
+        )],
+    );
+    check!(
+        "@media print { @import 'a.css'; a { color: red } }",
+        "@media print {  a { color: red } }",
+        [(ParseWarningKind::CssDisallowedAtRule, "@import 'a.css';")],
+    );
+    check!("a/**/{ color:/* comment */red }", "a{ color:red }", [],);
+    check!(
+        "a { width: expression/**/(1) }",
+        "a { width: expression (1) }",
+        [],
+    );
+    check!("a { content: \"<b>\" }", "a { content: \"\\3c b>\" }", [],);
+    check!(
+        "a { content: '</style><script>alert(1)</script>' }",
+        "a { content: \"\\3c /style>\\3c script>alert(1)\\3c /script>\" }",
+        [],
+    );
+    check!(
+        "a { color: red",
+        "a { color: red}",
+        [(ParseWarningKind::CssMalformed, "{")],
+    );
+    check!(
+        "a { content: 'abc\\",
+        "a { content: \"abc\"}",
+        [(ParseWarningKind::CssMalformed, "{")],
+    );
+    check!(
+        "a { color: red } } b { color: blue }",
+        "a { color: red }  b { color: blue }",
+        [(ParseWarningKind::CssMalformed, "}")],
+    );
+    check!(
+        "a { content: 'abc\n}",
+        "a { }",
+        [(ParseWarningKind::CssMalformed, "content: 'abc\n")],
+    );
+    check!(
+        "color: red; a { color: blue }",
+        " a { color: blue }",
+        [(ParseWarningKind::CssMalformed, "color: red;")],
+    );
+
+    // Permitting other schemes and at-rules
+    settings.allowed_url_schemes.push(Cow::Borrowed("data"));
+    settings.allowed_at_rules.push(Cow::Borrowed("import"));
+    check!(
+        "@import url(a.css); a { background: url(data:image/png;base64,AAAA) }",
+        "@import url(a.css); a { background: url(data:image/png;base64,AAAA) }",
+        [],
+    );
+    check!(
+        "@import 'javascript:x';",
+        "",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "@import 'javascript:x';"
+        )],
+    );
+
+    settings.allowed_url_schemes.clear();
+    check!(
+        "a { background: url(//example.com/a.png) } b { background: url(b.png) }",
+        "a { } b { background: url(b.png) }",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "background: url(//example.com/a.png) ",
+        )],
+    );
+}
+
+#[test]
+fn scoped_stylesheets() {
+    let settings = CssSettings {
+        scope_class: Some(Cow::Borrowed("page-content")),
+        ..CssSettings::default()
+    };
+
+    macro_rules! check {
+        ($input:expr, $expected:expr $(,)?) => {{
+            let SanitizedCss { css, issues } = sanitize_stylesheet($input, &settings);
+
+            assert_eq!(css, $expected, "Scoped CSS doesn't match expected");
+            assert!(issues.is_empty(), "Issues from scoping CSS: {issues:?}");
+        }};
+    }
+
+    check!("a { color: red }", ".page-content a { color: red }");
+    check!(
+        "a, .b > c,\n#d { color: red }",
+        ".page-content a, .page-content .b > c,\n.page-content #d { color: red }",
+    );
+    check!(
+        "body { margin: 0 } html.dark .a, :root { --x: 1 }",
+        ".page-content { margin: 0 } .page-content.dark .a, .page-content { --x: 1 }",
+    );
+    check!(
+        "@media print { a { color: red } }",
+        "@media print { .page-content a { color: red } }",
+    );
+    check!(
+        "@keyframes x { from { opacity: 0 } 50% { opacity: 1 } }",
+        "@keyframes x { from { opacity: 0 } 50% { opacity: 1 } }",
+    );
+    check!(
+        ".a { & .b { color: red } }",
+        ".page-content .a { & .b { color: red } }",
+    );
+}
+
+#[test]
+fn styles() {
+    let settings = CssSettings::default();
+
+    macro_rules! check {
+        ($input:expr, $expected:expr, $issues:expr $(,)?) => {{
+            let SanitizedCss { css, issues } = sanitize_style($input, &settings);
+            let issues: Vec<_> = issues.into_iter().map(|issue| issue.kind).collect();
+
+            assert_eq!(css, $expected, "Sanitized style doesn't match expected");
+            assert_eq!(issues, $issues, "Issues from sanitizing style don't match");
+        }};
+    }
+
+    check!(
+        "color: red; font-size: 2em",
+        "color: red; font-size: 2em",
+        []
+    );
+    check!(
+        "color: red; background: url(javascript:x)",
+        "color: red; ",
+        [ParseWarningKind::CssDisallowedUrl],
+    );
+    check!(
+        "color: red; background: url(javascript:alert(1))",
+        "color: red; ",
+        [
+            ParseWarningKind::CssMalformed,
+            ParseWarningKind::CssMalformed
+        ],
+    );
+    check!(
+        "width: expression(alert(1)); behavior: url(a.htc)",
+        " ",
+        [
+            ParseWarningKind::CssDisallowedFunction,
+            ParseWarningKind::CssDisallowedProperty,
+        ],
+    );
+    check!(
+        "color: red; } body { color: blue",
+        "color: red;  ",
+        [
+            ParseWarningKind::CssMalformed,
+            ParseWarningKind::CssMalformed,
+            ParseWarningKind::CssMalformed,
+        ],
+    );
+    check!(
+        "@import url(a.css); color: red",
+        " color: red",
+        [ParseWarningKind::CssMalformed],
+    );
+    check!("\"><script>", "", [ParseWarningKind::CssMalformed]);
+}
+
+#[test]
+fn parse_css() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! check {
+        ($input:expr, $styles:expr, $body:expr, $warnings:expr $(,)?) => {{
+            let tokens = crate::tokenize($input);
+            let (tree, warnings) = crate::parse(&tokens, &page_info, &settings).into();
+            let html = HtmlRender::default().render(&tree, &page_info, &settings);
+            let warnings: Vec<_> = warnings
+                .iter()
+                .map(|warning| (warning.kind(), &$input[warning.span()]))
+                .collect();
+
+            println!("Input:    {:?}", $input);
+            println!("Styles:   {:?}", html.styles);
+            println!("Body:     {:?}", html.body);
+            println!("Warnings: {:?}", warnings);
+
+            assert_eq!(html.styles, $styles, "Styles don't match expected");
+            assert!(
+                html.body.contains($body),
+                "Body doesn't contain {:?}",
+                $body
+            );
+            assert_eq!(warnings, $warnings, "Warnings don't match expected");
+        }};
+    }
+
+    check!(
+        "[[css]]\n@import url(evil.css);\na { color: red }\n[[/css]]",
+        ["\na { color: red }"],
+        "",
+        [(
+            ParseWarningKind::CssDisallowedAtRule,
+            "@import url(evil.css);",
+        )],
+    );
+    check!(
+        "[[module CSS]]\na { b: url(javascript:c) }\n[[/module]]",
+        ["a { }"],
+        "",
+        [(ParseWarningKind::CssDisallowedUrl, "b: url(javascript:c) ")],
+    );
+    check!(
+        "[[span style=\"color: red; background: url(javascript:x)\"]]x[[/span]]",
+        Vec::<String>::new(),
+        "<span style=\"color: red; \">x</span>",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "[[span style=\"color: red; background: url(javascript:x)\"]]x[[/span]]",
+        )],
+    );
+    check!(
+        "[[div style=\"color: red\"]]\n[[span style=\"width: expression(1)\"]]x[[/span]]\n[[/div]]",
+        Vec::<String>::new(),
+        "<div style=\"color: red\">",
+        [(
+            ParseWarningKind::CssDisallowedFunction,
+            "[[span style=\"width: expression(1)\"]]x[[/span]]",
+        )],
+    );
+    check!(
+        "[[size 2em; background: url(javascript:x)]]x[[/size]]",
+        Vec::<String>::new(),
+        "<span style=\"font-size: 2em; \">x</span>",
+        [(
+            ParseWarningKind::CssDisallowedUrl,
+            "[[size 2em; background: url(javascript:x)]]x[[/size]]",
+        )],
+    );
+    check!(
+        "##red|x## ##red; background: url(javascript:x)|y##",
+        Vec::<String>::new(),
+        "color: red;",
+        [
+            (ParseWarningKind::CssDisallowedUrl, "y"),
+            (ParseWarningKind::NoRulesMatch, "##"),
+            (ParseWarningKind::EndOfInput, ""),
+            (ParseWarningKind::NoRulesMatch, "##"),
+        ],
+    );
+}
+
+// Fuzzing
+
+fn arb_css() -> impl Strategy<Value = String> {
+    let fragment = prop_oneof![
+        proptest::sample::select(&INJECTION_VECTORS[..]).prop_map(String::from),
+        proptest::sample::select(&SAFE_CSS[..]).prop_map(String::from),
+        proptest::sample::select(
+            &[
+                "{", "}", "(", ")", "[", "]", ";", ":", ",", "<", "/", "!", "--", "\\",
+                "'", "\"", "/*", "*/", "url(", "@media ", "@import ", " ", "\n",
+            ][..]
+        )
+        .prop_map(String::from),
+        "[ -~]{0,8}",
+    ];
+
+    proptest::collection::vec(fragment, 0..16).prop_map(|fragments| fragments.concat())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn sanitize_stylesheet_prop(css in arb_css()) {
+        let settings = CssSettings::default();
+        let SanitizedCss { css: output, .. } = sanitize_stylesheet(&css, &settings);
+
+        prop_assert!(!contains_end_tag(&output), "Output could end the style element: {:?}", output);
+
+        // Sanitized output is stable, and has nothing left to remove
+        let SanitizedCss { css: again, issues } = sanitize_stylesheet(&output, &settings);
+        prop_assert_eq!(&again, &output);
+        prop_assert!(issues.is_empty(), "Sanitized output has issues: {:?}", issues);
+    }
+
+    #[test]
+    fn sanitize_style_prop(css in arb_css()) {
+        let settings = CssSettings::default();
+        let SanitizedCss { css: output, .. } = sanitize_style(&css, &settings);
+
+        prop_assert!(!contains_end_tag(&output), "Output could end the style element: {:?}", output);
+
+        let SanitizedCss { css: again, issues } = sanitize_style(&output, &settings);
+        prop_assert_eq!(&again, &output);
+        prop_assert!(issues.is_empty(), "Sanitized output has issues: {:?}", issues);
+    }
+
+    #[test]
+    fn scoped_stylesheet_prop(css in arb_css()) {
+        let settings = CssSettings {
+            scope_class: Some(Cow::Borrowed("page-content")),
+            ..CssSettings::default()
+        };
+
+        let SanitizedCss { css: output, .. } = sanitize_stylesheet(&css, &settings);
+        prop_assert!(!contains_end_tag(&output), "Output could end the style element: {:?}", output);
+
+        // Nothing is left to remove, though scoping again prefixes each selector again
+        let SanitizedCss { issues, .. } = sanitize_stylesheet(&output, &settings);
+        prop_assert!(issues.is_empty(), "Sanitized output has issues: {:?}", issues);
+    }
+}
@@ -0,0 +1,201 @@
+/*
+ * test/custom_blocks.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tests for blocks registered by the embedder.
+
+use crate::data::PageInfo;
+use crate::info::{BlockBody, BlockHead};
+use crate::parsing::{
+    CustomBlock, CustomBlockBody, CustomBlocks, ParseWarning, ParseWarningKind, Token,
+};
+use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tree::{
+    AttributeMap, Container, ContainerType, Element, Elements, SyntaxTree,
+};
+use std::borrow::Cow;
+
+fn custom_blocks() -> CustomBlocks {
+    let mut blocks = CustomBlocks::new();
+
+    // [[warning-box]] is a div with a class
+    blocks.register(
+        CustomBlock::new(
+            &["warning-box", "warnbox"],
+            BlockHead::Map,
+            BlockBody::Elements,
+            |input| {
+                let elements = match input.body {
+                    CustomBlockBody::Elements(elements) => elements,
+                    _ => unreachable!(),
+                };
+
+                let mut attributes = input.attributes;
+                attributes.insert("class", Cow::Borrowed("warning-box"));
+
+                Ok(Elements::Single(Element::Container(Container::new(
+                    ContainerType::Div,
+                    elements,
+                    attributes,
+                ))))
+            },
+        )
+        .accepts_score(true),
+    );
+
+    // [[redacted level]] replaces its raw body, if the level is valid
+    blocks.register(
+        CustomBlock::new(&["redacted"], BlockHead::Value, BlockBody::Raw, |input| {
+            let text = match input.body {
+                CustomBlockBody::Raw(text) => text,
+                _ => unreachable!(),
+            };
+
+            match input.value {
+                Some("3") => {
+                    let redacted = "█".repeat(text.chars().count());
+                    Ok(Elements::Single(Element::Text(Cow::Owned(redacted))))
+                }
+                _ => Err(ParseWarningKind::BlockMalformedArguments),
+            }
+        })
+        .accepts_newlines(false),
+    );
+
+    blocks
+}
+
+fn parse_text<'t>(
+    input: &'t mut String,
+    custom_blocks: &CustomBlocks,
+) -> (SyntaxTree<'t>, Vec<ParseWarning>) {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    crate::preprocess(input);
+    let tokens = crate::tokenize(input);
+    let (tree, warnings) =
+        crate::parse_with_blocks(&tokens, &page_info, &settings, custom_blocks).into();
+
+    (tree.to_owned(), warnings)
+}
+
+#[test]
+fn custom_block() {
+    let custom_blocks = custom_blocks();
+    let mut custom_input =
+        str!("[[warnbox id=\"notice\"]]\n**Careful!**\n\n[[warning-box_]]Nested[[/warning-box]]\n[[/WARNBOX]]");
+    let mut div_input = str!("[[div class=\"warning-box\" id=\"notice\"]]\n**Careful!**\n\n[[div_ class=\"warning-box\"]]Nested[[/div]]\n[[/div]]");
+
+    let (custom_tree, custom_warnings) = parse_text(&mut custom_input, &custom_blocks);
+    let (div_tree, div_warnings) = parse_text(&mut div_input, &custom_blocks);
+
+    assert!(custom_warnings.is_empty(), "Warnings: {custom_warnings:#?}");
+    assert!(div_warnings.is_empty(), "Warnings: {div_warnings:#?}");
+    assert_eq!(custom_tree.elements, div_tree.elements);
+}
+
+#[test]
+fn custom_block_raw() {
+    let custom_blocks = custom_blocks();
+    let mut input = str!("Name [[redacted 3]]Alice[[/redacted]]");
+    let (tree, warnings) = parse_text(&mut input, &custom_blocks);
+
+    assert!(warnings.is_empty(), "Warnings: {warnings:#?}");
+    assert_eq!(
+        tree.elements[0],
+        Element::Container(Container::new(
+            ContainerType::Paragraph,
+            vec![
+                Element::Text(Cow::Borrowed("Name")),
+                Element::Text(Cow::Borrowed(" ")),
+                Element::Text(Cow::Borrowed("█████")),
+            ],
+            AttributeMap::new(),
+        )),
+    );
+}
+
+#[test]
+fn custom_block_warnings() {
+    let custom_blocks = custom_blocks();
+
+    // Rejected by the build function
+    let mut input = str!("[[redacted 5]]Alice[[/redacted]]");
+    let (_, warnings) = parse_text(&mut input, &custom_blocks);
+    let warning = warnings.first().expect("No warnings produced");
+    assert_eq!(warning.rule(), "block-custom");
+    assert_eq!(warning.kind(), ParseWarningKind::BlockMalformedArguments);
+
+    // Flag not accepted by the block
+    let mut input = str!("[[redacted_ 3]]Alice[[/redacted]]");
+    let (_, warnings) = parse_text(&mut input, &custom_blocks);
+    let warning = warnings.first().expect("No warnings produced");
+    assert_eq!(warning.kind(), ParseWarningKind::BlockDisallowsScore);
+
+    // Not available without being registered
+    let mut input = str!("[[warning-box]]\nText\n[[/warning-box]]");
+    let (_, warnings) = parse_text(&mut input, &CustomBlocks::new());
+    let warning = warnings.first().expect("No warnings produced");
+    assert_eq!(warning.kind(), ParseWarningKind::NoSuchBlock);
+}
+
+#[test]
+fn custom_block_recursion_depth() {
+    let custom_blocks = custom_blocks();
+    let mut input = String::new();
+
+    for _ in 0..101 {
+        input.push_str("[[warning-box]]\n");
+    }
+
+    for _ in 0..101 {
+        input.push_str("[[/warning-box]]\n");
+    }
+
+    let (_, warnings) = parse_text(&mut input, &custom_blocks);
+    let warning = warnings.first().expect("No warnings produced");
+    assert_eq!(warning.token(), Token::LeftBlock);
+    assert_eq!(warning.rule(), "block-custom");
+    assert_eq!(warning.kind(), ParseWarningKind::RecursionDepthExceeded);
+}
+
+#[test]
+#[should_panic]
+fn custom_block_builtin_name() {
+    let mut custom_blocks = CustomBlocks::new();
+    custom_blocks.register(CustomBlock::new(
+        &["Div"],
+        BlockHead::None,
+        BlockBody::None,
+        |_| Ok(Elements::None),
+    ));
+}
+
+#[test]
+#[should_panic]
+fn custom_block_duplicate_name() {
+    let mut custom_blocks = custom_blocks();
+    custom_blocks.register(CustomBlock::new(
+        &["infobox", "WarnBox"],
+        BlockHead::None,
+        BlockBody::None,
+        |_| Ok(Elements::None),
+    ));
+}
@@ -0,0 +1,399 @@
+/*
+ * test/handle.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tests rendering with a custom [`Handle`], to ensure that renderers
+//! request external data through it rather than using placeholders.
+
+use crate::data::{
+    CategoryListing, JoinState, KarmaLevel, ListPagesEntry, ListPagesResult, PageInfo,
+    PageListing, PageTreeNode, UserInfo,
+};
+use crate::render::html::HtmlRender;
+use crate::render::text::TextRender;
+use crate::render::{Handle, Render};
+use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tree::{ImageSource, LinkLocation, ListPagesQuery};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::num::{NonZeroU32, NonZeroUsize};
+
+#[derive(Debug, Default)]
+struct MockHandle {
+    code_snippets: RefCell<Vec<(usize, String)>>,
+}
+
+impl Handle for MockHandle {
+    fn get_page_title(&self, link: &LinkLocation) -> String {
+        match link {
+            LinkLocation::Page(page_ref) => format!("Title of {}", page_ref.page()),
+            LinkLocation::Url(url) => format!("Title of {url}"),
+        }
+    }
+
+    fn get_user_info<'a>(&self, name: &'a str) -> Option<UserInfo<'a>> {
+        if name != "aismallard" {
+            return None;
+        }
+
+        Some(UserInfo {
+            user_id: 42,
+            user_name: Cow::Borrowed(name),
+            user_karma: KarmaLevel::new(3).unwrap(),
+            user_avatar_data: Cow::Borrowed("data:image/png;base64,"),
+            user_profile_url: Cow::Borrowed("https://example.org/users/42"),
+        })
+    }
+
+    fn get_image_link<'a>(
+        &self,
+        source: &ImageSource<'a>,
+        _info: &PageInfo,
+        _settings: &WikitextSettings,
+    ) -> Option<Cow<'a, str>> {
+        match source {
+            ImageSource::File1 { file } => {
+                Some(Cow::Owned(format!("https://files.example.org/{file}")))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_message(&self, _language: &str, message: &str) -> Cow<'static, str> {
+        match message {
+            "table-of-contents" => Cow::Borrowed("MOCK-TOC"),
+            "footnote-block-title" => Cow::Borrowed("MOCK-FOOTNOTES"),
+            _ => Cow::Borrowed("MOCK-MESSAGE"),
+        }
+    }
+
+    fn post_html(&self, _info: &PageInfo, html: &str) -> String {
+        format!("https://snippets.example.org/{}", html.len())
+    }
+
+    fn post_code(&self, index: NonZeroUsize, code: &str) {
+        self.code_snippets
+            .borrow_mut()
+            .push((index.get(), str!(code)));
+    }
+
+    fn get_backlinks(
+        &self,
+        _info: &PageInfo,
+        page: &str,
+    ) -> Option<Vec<PageListing<'_>>> {
+        if page != "scp-001" {
+            return None;
+        }
+
+        Some(vec![listing("scp-002"), listing("scp-003")])
+    }
+
+    fn get_categories(
+        &self,
+        _info: &PageInfo,
+        include_hidden: bool,
+    ) -> Option<Vec<CategoryListing<'_>>> {
+        let mut categories = vec![CategoryListing {
+            name: Cow::Borrowed("fragment"),
+            pages: vec![listing("fragment:apple")],
+        }];
+
+        if include_hidden {
+            categories.push(CategoryListing {
+                name: Cow::Borrowed("_hidden"),
+                pages: vec![],
+            });
+        }
+
+        Some(categories)
+    }
+
+    fn get_page_tree(
+        &self,
+        _info: &PageInfo,
+        root: &str,
+        _depth: Option<NonZeroU32>,
+    ) -> Option<PageTreeNode<'_>> {
+        fn node(
+            slug: &'static str,
+            children: Vec<PageTreeNode<'static>>,
+        ) -> PageTreeNode<'static> {
+            PageTreeNode {
+                page: listing(slug),
+                children,
+            }
+        }
+
+        if root != "scp-series" {
+            return None;
+        }
+
+        Some(node(
+            "scp-series",
+            vec![
+                node("scp-001", vec![node("scp-001-ex", vec![])]),
+                node("scp-002", vec![]),
+            ],
+        ))
+    }
+
+    fn get_page_rating(&self, _info: &PageInfo) -> Option<f64> {
+        Some(-12.0)
+    }
+
+    fn get_join_state(&self, _info: &PageInfo) -> Option<JoinState> {
+        Some(JoinState::Application)
+    }
+
+    fn get_list_pages(
+        &self,
+        _info: &PageInfo,
+        query: &ListPagesQuery,
+    ) -> Option<ListPagesResult<'_>> {
+        fn entry(slug: &'static str, rating: f64) -> ListPagesEntry<'static> {
+            ListPagesEntry {
+                slug: Cow::Borrowed(slug),
+                title: Cow::Owned(slug.to_uppercase()),
+                rating,
+                tags: vec![Cow::Borrowed("scp"), Cow::Borrowed("euclid")],
+                created_at: 1000000000,
+                created_by: Cow::Borrowed("aismallard"),
+                updated_at: 1600000000,
+                content: Cow::Borrowed("**Item #:** SCP-XXX"),
+            }
+        }
+
+        if query.categories.is_some() {
+            return None;
+        }
+
+        if let Some(created_by) = &query.created_by {
+            let created_by = match created_by.as_ref() {
+                "y" => "Not]] [[bold",
+                _ => "__init__",
+            };
+
+            return Some(ListPagesResult {
+                entries: vec![ListPagesEntry {
+                    title: Cow::Borrowed("**Not** [[bold]]]"),
+                    created_by: Cow::Borrowed(created_by),
+                    tags: vec![Cow::Borrowed("**not**"), Cow::Borrowed("--bold--")],
+                    ..entry("scp-003", 0.0)
+                }],
+                total: 1,
+                current_page: NonZeroU32::new(1).unwrap(),
+            });
+        }
+
+        Some(ListPagesResult {
+            entries: vec![entry("scp-001", 10.0), entry("fragment:scp-002", -3.0)],
+            total: 3,
+            current_page: NonZeroU32::new(1).unwrap(),
+        })
+    }
+}
+
+fn listing(slug: &'static str) -> PageListing<'static> {
+    PageListing {
+        slug: Cow::Borrowed(slug),
+        title: Cow::Owned(slug.to_uppercase()),
+    }
+}
+
+#[test]
+fn handle() {
+    let mut page_info = PageInfo::dummy();
+    let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+    settings.urls.main_domain = Cow::Borrowed("example.org");
+    settings.urls.path_sites = true;
+    settings.urls.custom_domains.insert(
+        Cow::Borrowed("custom-site"),
+        Cow::Borrowed("custom.example.net"),
+    );
+    let handle = MockHandle::default();
+
+    macro_rules! check {
+        ($input:expr, $html:expr, $text:expr $(,)?) => {{
+            let mut text = str!($input);
+            crate::preprocess(&mut text);
+
+            let tokens = crate::tokenize(&text);
+            let result = crate::parse(&tokens, &page_info, &settings);
+            let (tree, _warnings) = result.into();
+            let html_output =
+                HtmlRender::new(&handle).render(&tree, &page_info, &settings);
+            let text_output =
+                TextRender::new(&handle).render(&tree, &page_info, &settings);
+
+            println!();
+            println!("Input:  {:?}", $input);
+            println!("HTML:   {:?}", html_output.body);
+            println!("Text:   {:?}", text_output);
+
+            assert!(
+                html_output.body.contains($html),
+                "HTML output does not contain the expected substring {:?}",
+                $html,
+            );
+
+            assert!(
+                text_output.contains($text),
+                "Text output does not contain the expected substring {:?}",
+                $text,
+            );
+        }};
+    }
+
+    check!(
+        "[[[some-page|]]]",
+        ">Title of some-page</a>",
+        "Title of some-page"
+    );
+    check!(
+        "[[[:other-site:some-page | Label]]]",
+        "href=\"https://example.org/other-site/some-page\"",
+        "Label",
+    );
+    check!(
+        "[[user aismallard]]",
+        "href=\"https://example.org/users/42\"",
+        "aismallard",
+    );
+    check!(
+        "[[[:custom-site:Some Page | Label]]]",
+        "href=\"https://custom.example.net/some-page\"",
+        "Label",
+    );
+    check!("[[user nobody]]", "wj-error-inline", "nobody");
+    check!(
+        "[[image apple.png]]",
+        "src=\"https://files.example.org/apple.png\"",
+        "https://files.example.org/apple.png",
+    );
+    check!("[[image /page/apple.png]]", "MOCK-MESSAGE", "");
+    check!(
+        "[[module Backlinks page=\"scp-001\"]]",
+        "<ul><li><a href=\"/scp-002\">SCP-002</a></li><li><a href=\"/scp-003\">SCP-003</a></li></ul>",
+        "* SCP-002\n* SCP-003",
+    );
+    check!(
+        "[[module Backlinks]]",
+        "<div class=\"wj-module-backlinks\"></div>",
+        "",
+    );
+    check!(
+        "[[module Categories]]",
+        "<span class=\"wj-module-categories-name\">fragment</span><ul><li><a href=\"/fragment:apple\">FRAGMENT:APPLE</a></li></ul>",
+        "fragment\n * FRAGMENT:APPLE",
+    );
+    check!(
+        "[[module Categories includeHidden=\"true\"]]",
+        "<span class=\"wj-module-categories-name\">_hidden</span>",
+        "_hidden",
+    );
+    check!(
+        "[[module PageTree root=\"scp-series\" depth=\"1\"]]",
+        "<div class=\"wj-module-page-tree\"><ul><li><a href=\"/scp-001\">SCP-001</a></li><li><a href=\"/scp-002\">SCP-002</a></li></ul></div>",
+        " * SCP-001\n * SCP-002",
+    );
+    check!(
+        "[[module PageTree root=\"scp-series\" showRoot=\"true\"]]",
+        "<a href=\"/scp-series\">SCP-SERIES</a><ul><li><a href=\"/scp-001\">SCP-001</a><ul><li><a href=\"/scp-001-ex\">SCP-001-EX</a>",
+        " * SCP-SERIES\n  * SCP-001\n   * SCP-001-EX\n  * SCP-002",
+    );
+    check!(
+        "[[module Rate]]",
+        "<span class=\"wj-module-rate-value\">-12</span>",
+        "MOCK-MESSAGE -12",
+    );
+    check!(
+        "[[module Join]]",
+        "<button class=\"wj-module-join-button\" type=\"button\" data-join-state=\"application\">MOCK-MESSAGE</button>",
+        "MOCK-MESSAGE",
+    );
+    check!(
+        "[[module Join button=\"Apply now!\"]]",
+        ">Apply now!</button>",
+        "Apply now!",
+    );
+    check!(
+        "[[module ListPages category=\"fruit\"]]\n[[/module]]",
+        "<div class=\"wj-list-pages\"></div>",
+        "",
+    );
+    check!(
+        "[[module ListPages perPage=\"2\"]]\n[[/module]]",
+        ">SCP-001</a></li></ul></div><div class=\"wj-list-pages-item\"><ul><li><a href=\"/fragment:scp-002\"",
+        " * SCP-001 [/scp-001]\n * FRAGMENT:SCP-002 [/fragment:scp-002]",
+    );
+    check!(
+        "[[module ListPages perPage=\"2\"]]\n[[/module]]",
+        "<div class=\"wj-list-pages-pager\"><span class=\"wj-list-pages-pager-current\">1</span><a href=\"/some-page/p/2\">2</a></div>",
+        "",
+    );
+    check!(
+        "[[module ListPages separate=\"false\"]]\n* %%index%%/%%total%% %%name%% in %%category%% (%%rating%%) by %%created_by%%: %%tags%% %%unknown%%\n[[/module]]",
+        "<ul><li>1/3 scp-001 in _default (+10) by aismallard: scp euclid %%unknown%%</li><li>2/3 scp-002 in fragment (-3) by aismallard: scp euclid %%unknown%%</li></ul>",
+        " * 1/3 scp-001 in _default (+10) by aismallard: scp euclid %%unknown%%\n * 2/3 scp-002 in fragment (-3) by aismallard: scp euclid %%unknown%%",
+    );
+    check!(
+        "[[module ListPages created_by=\"x\"]]\n%%title%% by %%created_by%%\n%%title_linked%%\n[[/module]]",
+        "<span class=\"wj-raw\">**</span>Not<span class=\"wj-raw\">**</span> <span class=\"wj-raw\">[[</span>bold<span class=\"wj-raw\">]]</span>] by <span class=\"wj-raw\">__</span>init<span class=\"wj-raw\">__</span><br><a href=\"/scp-003\" class=\"wj-link wj-link-internal\" data-link-type=\"page\">**Not** [[bold]] ]</a></p>",
+        "**Not** [[bold]]] by __init__\n**Not** [[bold]] ] [/scp-003]",
+    );
+    check!(
+        "[[module ListPages created_by=\"x\"]]\n%%created_by_linked%%: %%tags%%\n[[/module]]",
+        "<span class=\"wj-user-info\"",
+        "__init__: **not** --bold--",
+    );
+    check!(
+        "[[module ListPages created_by=\"y\"]]\n%%created_by_linked%%: %%tags%%\n[[/module]]",
+        "<p>Not<span class=\"wj-raw\">]]</span> <span class=\"wj-raw\">[[</span>bold: <span class=\"wj-raw\">**</span>not",
+        "Not]] [[bold: **not** --bold--",
+    );
+    check!(
+        "[[module ListPages]]\n%%content%%\n\n%%created_at|%Y%%\n[[/module]]",
+        "<strong>Item #:</strong> SCP-XXX",
+        "2001",
+    );
+    check!("[[toc]]", "MOCK-TOC", "MOCK-TOC");
+    check!(
+        "[[html]]\n<b>snippet</b>\n[[/html]]",
+        "src=\"https://snippets.example.org/14\"",
+        "",
+    );
+
+    check!(
+        "[[code]]\nfn main() {}\n[[/code]]",
+        "fn main() {}",
+        "fn main() {}"
+    );
+    assert_eq!(
+        handle.code_snippets.borrow().as_slice(),
+        &[(1, str!("fn main() {}"))],
+    );
+
+    page_info.category = Some(Cow::Borrowed("theme"));
+    check!(
+        "[[module ListPages perPage=\"2\"]]\n[[/module]]",
+        "<a href=\"/theme:some-page/p/2\">2</a>",
+        "",
+    );
+}
@@ -19,7 +19,10 @@
  */
 
 use crate::data::PageInfo;
-use crate::settings::{WikitextMode, WikitextSettings, EMPTY_INTERWIKI};
+use crate::settings::{
+    CssSettings, UrlSettings, WikitextMode, WikitextSettings, DEFAULT_MAX_INCLUDE_DEPTH,
+    EMPTY_INTERWIKI,
+};
 use crate::tree::{
     AttributeMap, Container, ContainerType, Element, ImageSource, ListItem, ListType,
 };
@@ -55,9 +58,13 @@ fn isolate_user_ids() {
         enable_page_syntax: true,
         use_true_ids: true,
         use_include_compatibility: false,
+        max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
         isolate_user_ids: true,
         allow_local_paths: true,
+        record_spans: false,
         interwiki: EMPTY_INTERWIKI.clone(),
+        urls: UrlSettings::default(),
+        css: CssSettings::default(),
     };
 
     fn append_footnote_block(mut elements: Vec<Element>) -> Vec<Element> {
@@ -46,6 +46,10 @@ const COMPONENT_FRUIT_PAGE_SOURCE: &str = "
 [[/div]]
 ";
 
+const COMPONENT_EXPRESSION_PAGE_SOURCE: &str = "
+[[#if {$caption} | Caption: **{$caption}** | No caption]], total [[#expr {$count} * 2]]
+";
+
 #[derive(Debug)]
 pub struct TestIncluder;
 
@@ -75,6 +79,23 @@ impl<'t> Includer<'t> for TestIncluder {
             "[[div class=\"wj-error\"]]\nNo such page '{page_ref}'\n[[/div]]",
         )))
     }
+
+    #[inline]
+    fn include_cycle(&mut self, page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!(
+            "[[div class=\"wj-error\"]]\nPage '{page_ref}' includes itself\n[[/div]]",
+        )))
+    }
+
+    #[inline]
+    fn include_depth_exceeded(
+        &mut self,
+        page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, Void> {
+        Ok(Cow::Owned(format!(
+            "[[div class=\"wj-error\"]]\nPage '{page_ref}' is nested too deeply\n[[/div]]",
+        )))
+    }
 }
 
 fn get_page_source(page_ref: &PageRef) -> Option<Cow<'static, str>> {
@@ -85,15 +106,80 @@ fn get_page_source(page_ref: &PageRef) -> Option<Cow<'static, str>> {
     }
 
     if page_ref.site().is_some() {
-        return Some(cow!("OFF-SITE INCLUDED PAGE"));
+        return match page_ref.page() {
+            "nested" => Some(cow!("Nested\n[[include-elements fruit]]")),
+            _ => Some(cow!("OFF-SITE INCLUDED PAGE")),
+        };
     }
 
     match page_ref.page() {
         "fruit" => Some(cow!(FRUIT_PAGE_SOURCE)),
         "component:basic" => Some(cow!(COMPONENT_BASIC_PAGE_SOURCE)),
         "component:fruit" => Some(cow!(COMPONENT_FRUIT_PAGE_SOURCE)),
+        "component:expression" => Some(cow!(COMPONENT_EXPRESSION_PAGE_SOURCE)),
         "fragment:page" => Some(cow!("INCLUDED FRAGMENT")),
+        "loop" => Some(cow!("Loop\n[[include-elements loop]]")),
+        "cycle-root" => Some(cow!(
+            "Back\n[[include-elements page-include-elements-cycle-root]]"
+        )),
         "missing" => None,
         _ => Some(cow!("INCLUDED PAGE")),
     }
 }
+
+#[derive(Debug)]
+pub struct FailingIncluder;
+
+impl<'t> Includer<'t> for FailingIncluder {
+    type Error = ();
+
+    #[inline]
+    fn include_pages(
+        &mut self,
+        _includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>, ()> {
+        Err(())
+    }
+
+    #[inline]
+    fn no_such_include(&mut self, _page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, ()> {
+        Err(())
+    }
+
+    #[inline]
+    fn include_cycle(&mut self, _page_ref: &PageRef<'t>) -> Result<Cow<'t, str>, ()> {
+        Err(())
+    }
+
+    #[inline]
+    fn include_depth_exceeded(
+        &mut self,
+        _page_ref: &PageRef<'t>,
+    ) -> Result<Cow<'t, str>, ()> {
+        Err(())
+    }
+}
+
+#[test]
+fn include_elements_failure() {
+    use crate::data::PageInfo;
+    use crate::parsing::ParseWarningKind;
+    use crate::settings::{WikitextMode, WikitextSettings};
+
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let tokens = crate::tokenize("[[include-elements fruit]]");
+    let result =
+        crate::parse_with_includer(&tokens, &page_info, &settings, FailingIncluder);
+    let (_tree, warnings) = result.into();
+
+    let kinds: Vec<_> = warnings.iter().map(|warning| warning.kind()).collect();
+    assert!(
+        kinds.contains(&ParseWarningKind::IncludeFailed),
+        "Includer failure not reported: {kinds:?}",
+    );
+    assert!(
+        !kinds.contains(&ParseWarningKind::NoSuchPage),
+        "Includer failure reported as a missing page: {kinds:?}",
+    );
+}
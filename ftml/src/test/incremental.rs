@@ -0,0 +1,140 @@
+/*
+ * test/incremental.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tests that incremental parsing after edits matches parsing the full text.
+
+use crate::data::PageInfo;
+use crate::parsing::IncrementalParse;
+use crate::settings::{WikitextMode, WikitextSettings};
+use crate::tokenizer::{retokenize, TextEdit};
+use proptest::prelude::*;
+
+/// Pieces of wikitext to build inputs and edits from.
+///
+/// These are chosen to produce many paragraph breaks, as well as
+/// constructs which span (or fail to span) several paragraphs.
+const SNIPPETS: [&str; 48] = [
+    "\n",
+    "\n\n",
+    "\n\n\n",
+    " ",
+    "a",
+    "bc ",
+    "**",
+    "//",
+    "__",
+    "--",
+    "^^",
+    ",,",
+    "{{",
+    "}}",
+    "@@",
+    "[[",
+    "]]",
+    "=",
+    ":",
+    "|",
+    "||",
+    "+ ",
+    "++ ",
+    "* ",
+    "# ",
+    "> ",
+    "------",
+    "[!--",
+    "--]",
+    "[[div]]",
+    "[[div class=\"x\"]]",
+    "[[/div]]",
+    "[[span style=\"color: red\"]]",
+    "[[/span]]",
+    "[[footnote]]",
+    "[[/footnote]]",
+    "[[footnoteblock]]",
+    "[[toc]]",
+    "[[code]]",
+    "[[/code]]",
+    "[[module CSS]]",
+    "[[/module]]",
+    "[[collapsible]]",
+    "[[/collapsible]]",
+    "[[[page]]]",
+    "https://example.com/",
+    "[[*user alice]]",
+    "[[<]]",
+];
+
+fn arb_wikitext() -> impl Strategy<Value = String> {
+    proptest::collection::vec(proptest::sample::select(&SNIPPETS[..]), 0..48)
+        .prop_map(|snippets| snippets.concat())
+}
+
+fn arb_edit() -> impl Strategy<Value = (prop::sample::Index, prop::sample::Index, String)>
+{
+    (
+        any::<prop::sample::Index>(),
+        any::<prop::sample::Index>(),
+        proptest::collection::vec(proptest::sample::select(&SNIPPETS[..]), 0..4)
+            .prop_map(|snippets| snippets.concat()),
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn incremental_prop(
+        text in arb_wikitext(),
+        edits in proptest::collection::vec(arb_edit(), 1..5),
+        record_spans in any::<bool>(),
+    ) {
+        let page_info = PageInfo::dummy();
+        let mut settings = WikitextSettings::from_mode(WikitextMode::Page);
+        settings.record_spans = record_spans;
+
+        // Determine the text after each edit
+        let mut texts = vec![text];
+        let mut text_edits = Vec::new();
+
+        for (start, end, replacement) in &edits {
+            let text = texts.last().unwrap();
+            let start = start.index(text.len() + 1);
+            let end = end.index(text.len() + 1);
+            let edit = TextEdit::new(start.min(end)..start.max(end), replacement);
+
+            texts.push(edit.apply(text));
+            text_edits.push(edit);
+        }
+
+        // Apply each edit, checking against a full parse
+        let mut tokens = crate::tokenize(&texts[0]);
+        let mut incremental = IncrementalParse::new(&tokens, &page_info, &settings);
+
+        for (edit, text) in text_edits.iter().zip(&texts[1..]) {
+            tokens = retokenize(&tokens, edit, text);
+            incremental.update(&tokens, edit, &page_info, &settings);
+
+            let (tree, warnings) = crate::parse(&tokens, &page_info, &settings).into();
+            let outcome = incremental.outcome();
+            prop_assert_eq!(outcome.value(), &tree.to_owned(), "Syntax tree mismatch for {:?}", text);
+            prop_assert_eq!(outcome.warnings(), &warnings[..], "Warnings mismatch for {:?}", text);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ * test/markdown.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tests for converting Markdown into wikitext.
+//!
+//! Each case is checked by parsing the converted wikitext, and comparing
+//! its syntax tree to that of the equivalent handwritten wikitext.
+
+use crate::data::PageInfo;
+use crate::import::{markdown_to_tree, markdown_to_wikitext};
+use crate::settings::{WikitextMode, WikitextSettings};
+
+#[test]
+fn markdown_import() {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    macro_rules! check {
+        ($markdown:expr, $wikitext:expr $(,)?) => {{
+            let markdown = $markdown;
+            let converted = markdown_to_wikitext(markdown);
+            println!("Converted Markdown:\n{converted}\n");
+
+            let outcome = markdown_to_tree(markdown, &page_info, &settings);
+            assert!(
+                outcome.warnings().is_empty(),
+                "Converted wikitext produced warnings: {:#?}",
+                outcome.warnings(),
+            );
+
+            let mut wikitext = str!($wikitext);
+            crate::preprocess(&mut wikitext);
+            let tokens = crate::tokenize(&wikitext);
+            let (expected_tree, _) = crate::parse(&tokens, &page_info, &settings).into();
+
+            assert_eq!(
+                outcome.value(),
+                &expected_tree,
+                "Converted syntax tree doesn't match expected",
+            );
+        }};
+    }
+
+    check!("", "");
+    check!("Hello, world!", "Hello, world!");
+    check!(
+        "# Title\n\n## Subtitle\n\n###### Small",
+        "+ Title\n\n++ Subtitle\n\n++++++ Small",
+    );
+    check!(
+        "Some *emphasis*, __strong__, and ~~struck~~ text.",
+        "Some //emphasis//, **strong**, and --struck-- text.",
+    );
+    check!(
+        "A paragraph\nwith a soft break.\n\nAnd a hard  \nbreak.",
+        "A paragraph with a soft break.\n\nAnd a hard\nbreak.",
+    );
+    check!("Use `cargo build` here.", "Use {{cargo build}} here.");
+    check!("Not `**bold**`.", "Not {{@@**bold**@@}}.");
+    check!("Generic `Vec<T>`.", "Generic {{@@Vec<T@@>}}.");
+    check!(
+        "```rust\nfn main() {}\n```",
+        "[[code type=\"rust\"]]\nfn main() {}\n[[/code]]",
+    );
+    check!(
+        "    indented\n    code",
+        "[[code]]\nindented\ncode\n[[/code]]"
+    );
+    check!(
+        "[Example](https://example.com/), [a page](some-page), and [home](/)",
+        "[https://example.com/ Example], [[[some-page|a page]]], and [/ home]",
+    );
+    check!(
+        "See <https://example.com/> or https://example.org/",
+        "See https://example.com/ or https://example.org/",
+    );
+    check!(
+        "![An apple](apple.png \"Fruit\")",
+        "[[image apple.png alt=\"An apple\" title=\"Fruit\"]]",
+    );
+    check!(
+        "[![Logo](logo.png)](https://example.com/)",
+        "[[image logo.png alt=\"Logo\" link=\"https://example.com/\"]]",
+    );
+    check!(
+        "* Apple\n* Banana\n  * Cherry\n\n1. One\n2. Two",
+        "* Apple\n* Banana\n * Cherry\n\n# One\n# Two",
+    );
+    check!("- Loose\n\n  item\n\n- Second", "* Loose _\nitem\n* Second",);
+    check!(
+        "- [ ] Todo\n- [x] Done",
+        "* [[checkbox]] Todo\n* [[*checkbox]] Done",
+    );
+    check!(
+        "1. Step:\n\n   ```\n   run\n   ```\n2. Done",
+        "[[ol]]\n[[li]]Step:\n\n[[code]]\nrun\n[[/code]][[/li]]\n[[li]]Done[[/li]]\n[[/ol]]",
+    );
+    check!(
+        "> Quoted\n>\n> > Nested",
+        "[[blockquote]]\nQuoted\n\n[[blockquote]]\nNested\n[[/blockquote]]\n[[/blockquote]]",
+    );
+    check!(
+        "| Name | Count |\n|:-----|------:|\n| Apple | 3 |\n| *Pear* | 4 |",
+        "||~ Name ||~ Count ||\n||< Apple ||> 3 ||\n||< //Pear// ||> 4 ||",
+    );
+    check!(
+        "Fact.[^1] Another.[^note]\n\n[^1]: A *source*.\n[^note]: Another\nline.",
+        "Fact.[[footnote]]A //source//.[[/footnote]] Another.[[footnote]]Another line.[[/footnote]]",
+    );
+    check!("Above\n\n---\n\nBelow", "Above\n\n----\n\nBelow");
+    check!(
+        "<div class=\"box\">\nContent\n</div>\n\n<!-- note -->",
+        "[[html]]\n<div class=\"box\">\nContent\n</div>\n[[/html]]\n\n[!-- note --]",
+    );
+    check!("Inline <b>tags</b> shown.", "Inline <b>tags</b> shown.");
+
+    // Escaping wikitext syntax
+    check!(
+        "Literal \\*\\*stars\\*\\*, //slashes//, [[blocks]], and -- dashes.",
+        "Literal @@**@@stars@@**@@, @@//@@slashes@@//@@, @@[[@@blocks@@]]@@, and @@--@@ dashes.",
+    );
+    check!("\\+ Not a heading", "@@@@+ Not a heading");
+    check!("Email @@ and >@ raw", "Email @<@@>@ and @@>@@@ raw");
+    check!("[#anchor] and [citation]", "@@[@@#anchor] and [citation]");
+}
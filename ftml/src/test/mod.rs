@@ -19,8 +19,14 @@
  */
 
 mod ast;
+mod css;
+mod custom_blocks;
+mod handle;
 mod id_prefix;
 mod includer;
+mod incremental;
 mod large;
+#[cfg(feature = "markdown-import")]
+mod markdown;
 mod prop;
 mod settings;
@@ -18,14 +18,18 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::data::{PageInfo, PageRef};
-use crate::render::{html::HtmlRender, text::TextRender, Render};
+use crate::data::PageInfo;
+use crate::parsing::build_table_of_contents;
+use crate::render::{
+    html::HtmlRender, text::TextRender, wikitext::WikitextRender, Render,
+};
 use crate::settings::{WikitextMode, WikitextSettings};
-use crate::tree::attribute::SAFE_ATTRIBUTES;
+use crate::tree::attribute::{BOOLEAN_ATTRIBUTES, SAFE_ATTRIBUTES};
 use crate::tree::{
-    Alignment, AnchorTarget, AttributeMap, ClearFloat, Container, ContainerType, Element,
-    FloatAlignment, Heading, HeadingLevel, ImageSource, LinkLabel, LinkLocation,
-    LinkType, ListItem, ListType, Module, SyntaxTree,
+    Alignment, AnchorTarget, AttributeMap, BibliographyList, ClearFloat, Container,
+    ContainerType, Element, FloatAlignment, Heading, HeadingLevel, ImageSource,
+    LinkLabel, LinkLocation, LinkType, ListItem, ListPagesQuery, ListType, Module,
+    SyntaxTree,
 };
 use proptest::option;
 use proptest::prelude::*;
@@ -33,14 +37,48 @@ use std::borrow::Cow;
 use std::num::NonZeroU32;
 
 // Constants
+//
+// These strategies only generate syntax trees in the form the parser produces,
+// so that the wikitext rendered for them parses back into exactly the same tree.
+// For instance, text is split into separate elements for each word, space, and
+// punctuation mark, and attributes are only those which the parser keeps.
 
 lazy_static! {
-    static ref SAFE_ATTRIBUTES_VEC: Vec<&'static str> =
-        SAFE_ATTRIBUTES.iter().map(|s| s.as_ref()).collect();
+    static ref SAFE_ATTRIBUTES_VEC: Vec<&'static str> = {
+        let mut attributes: Vec<&'static str> = SAFE_ATTRIBUTES
+            .iter()
+            .filter(|key| !BOOLEAN_ATTRIBUTES.contains(*key))
+            .map(|key| key.as_ref())
+            .filter(|key| *key != "style")
+            .collect();
+
+        // Sorted, so that generated cases are reproducible
+        attributes.sort_unstable();
+        attributes
+    };
 }
 
-const SIMPLE_EMAIL_REGEX: &str = r"\w+([-+.']\w+)*@\w+([-.]\w+)*\.\w+([-.]\w+)*";
-const SIMPLE_URL_REGEX: &str = r"https?://([-.]\w)+";
+/// Single characters which are read as a token of their own.
+const PUNCTUATION_REGEX: &str = r#"[!#$%&()*+,\-./:;<=>?@\[\]^_{|}~"\\]"#;
+
+/// Characters which the preprocessor does not change.
+///
+/// Backticks and single quotes are excluded, since typography
+/// turns pairs of them into curly quotes, as is whitespace,
+/// since lines with only whitespace are emptied.
+const TEXT_REGEX: &str = r"[^\x00-\x1f`'\x7f\s]";
+const RAW_REGEX: &str = r"[^\x00-\x1f`'\x7f]{0,8}";
+
+const URL_REGEX: &str = r"https?://[a-z]{1,8}\.com(/[a-z0-9]{1,8}){0,2}";
+const PAGE_REGEX: &str = r"([a-z]{1,6}:)?[a-z0-9]{1,8}(-[a-z0-9]{1,8}){0,2}";
+const WORDS_REGEX: &str = r"[A-Za-z0-9]{1,8}( [A-Za-z0-9]{1,8}){0,3}";
+
+const STYLES: [&str; 4] = [
+    "color: red;",
+    "font-weight: bold; margin: 0 auto;",
+    "background: #eee",
+    "",
+];
 
 // Helper macros
 
@@ -56,28 +94,80 @@ macro_rules! cow {
     };
 }
 
+/// Generates a string of several lines, as used in the body of `[[code]]` and similar.
+macro_rules! lines {
+    ($line:expr) => {
+        proptest::collection::vec($line, 1..4)
+            .prop_map(|lines| lines.join("\n"))
+            .prop_filter("changed by preprocessor", |s| unchanged_by_preprocessor(s))
+    };
+}
+
+// Helper functions
+
+/// Determines if the preprocessor, which runs before parsing, leaves this text as-is.
+fn unchanged_by_preprocessor(s: &str) -> bool {
+    let mut text = str!(s);
+    crate::preprocess(&mut text);
+    text == s
+}
+
+fn text(s: &'static str) -> Element<'static> {
+    Element::Text(Cow::Borrowed(s))
+}
+
 // Leaf elements
 
 fn arb_attribute_map() -> impl Strategy<Value = AttributeMap<'static>> {
-    proptest::collection::btree_map(
+    let value = cow!(r"[A-Za-z0-9_#.,:;()\-]{1,8}( [A-Za-z0-9_#.,:;()\-]{1,8})?")
+        .prop_filter("changed by preprocessor", |s| unchanged_by_preprocessor(s));
+
+    let attributes = proptest::collection::btree_map(
         // Key
         prop_oneof![
             // Safe attribute
-            select!(SAFE_ATTRIBUTES_VEC).prop_map(|s| Cow::Owned(str!(s))),
-            // Random attribute
-            cow!(r"[A-Za-z0-9-]+"),
+            4 => select!(SAFE_ATTRIBUTES_VEC).prop_map(|s| Cow::Owned(str!(s))),
+            // Data or ARIA attribute
+            1 => cow!(r"(data|aria)-[a-z0-9]{1,8}"),
         ],
         // Value
-        cow!(".*"),
+        value,
         // Length
-        0..12,
-    )
-    .prop_map(|map| AttributeMap::from(map))
+        0..4,
+    );
+
+    // The style attribute is sanitized, so only use ones which are kept as-is
+    let style = option::of(select!(STYLES).prop_map(|s| Cow::Owned(str!(s))));
+
+    (attributes, style).prop_map(|(mut map, style)| {
+        if let Some(style) = style {
+            map.insert(Cow::Borrowed("style"), style);
+        }
+
+        AttributeMap::from(map)
+    })
 }
 
 #[inline]
 fn arb_optional_str() -> impl Strategy<Value = Option<Cow<'static, str>>> {
-    option::of(cow!(".*"))
+    option::of(cow!(WORDS_REGEX))
+}
+
+fn arb_text() -> impl Strategy<Value = Element<'static>> {
+    prop_oneof![
+        6 => cow!("[A-Za-z0-9]{1,8}"),
+        2 => cow!(PUNCTUATION_REGEX),
+        1 => cow!(TEXT_REGEX).prop_filter("ASCII", |s: &Cow<str>| !s.is_ascii()),
+    ]
+    .prop_map(Element::Text)
+}
+
+fn arb_raw() -> impl Strategy<Value = Element<'static>> {
+    cow!(RAW_REGEX)
+        .prop_filter("changed by preprocessor", |s| unchanged_by_preprocessor(s))
+        // These would be read as the end of a raw block, so they are written after one
+        .prop_filter("raw end", |s| !s.ends_with('>') && !s.contains(">@"))
+        .prop_map(Element::Raw)
 }
 
 fn arb_module() -> impl Strategy<Value = Element<'static>> {
@@ -89,9 +179,9 @@ fn arb_module() -> impl Strategy<Value = Element<'static>> {
     );
 
     let page_tree = (
-        arb_optional_str(),
+        option::of(cow!(PAGE_REGEX)),
         any::<bool>(),
-        any::<u32>().prop_map(NonZeroU32::new),
+        (1..10_u32).prop_map(NonZeroU32::new),
     )
         .prop_map(|(root, show_root, depth)| Module::PageTree {
             root,
@@ -99,92 +189,124 @@ fn arb_module() -> impl Strategy<Value = Element<'static>> {
             depth,
         });
 
+    let list_pages =
+        (lines!(WORDS_REGEX), any::<bool>()).prop_map(|(template, separate)| {
+            Module::ListPages {
+                query: ListPagesQuery {
+                    separate,
+                    ..ListPagesQuery::default()
+                },
+                template: Cow::Owned(template),
+            }
+        });
+
     prop_oneof![
         Just(Module::Rate),
-        arb_optional_str().prop_map(|page| Module::Backlinks { page }),
+        option::of(cow!(PAGE_REGEX)).prop_map(|page| Module::Backlinks { page }),
         any::<bool>().prop_map(|include_hidden| Module::Categories { include_hidden }),
         join,
         page_tree,
+        list_pages,
     ]
     .prop_map(Element::Module)
 }
 
 fn arb_target() -> impl Strategy<Value = Option<AnchorTarget>> {
-    option::of(select!([
-        AnchorTarget::NewTab,
-        AnchorTarget::Parent,
-        AnchorTarget::Top,
-        AnchorTarget::Same,
-    ]))
-}
-
-fn arb_page_ref() -> impl Strategy<Value = PageRef<'static>> {
-    let site = option::of(cow!(r"[a-z0-9\-]+"));
-    let page = cow!(r"[a-z0-9\-_:]+");
-
-    (site, page).prop_map(|(site, page)| PageRef { site, page })
+    // Other targets cannot be written in wikitext
+    option::of(Just(AnchorTarget::NewTab))
 }
 
 fn arb_link_location() -> impl Strategy<Value = LinkLocation<'static>> {
-    prop_oneof![
-        arb_page_ref().prop_map(LinkLocation::Page),
-        cow!(".+").prop_map(LinkLocation::Url),
-    ]
-}
-
-fn arb_link_type() -> impl Strategy<Value = LinkType> {
-    select!([
-        LinkType::Direct,
-        LinkType::Page,
-        LinkType::Interwiki,
-        LinkType::Anchor,
-        LinkType::TableOfContents,
-    ])
+    prop_oneof![cow!(PAGE_REGEX), cow!(URL_REGEX)]
+        .prop_map(|link| LinkLocation::parse(link).to_owned())
 }
 
 fn arb_link_element() -> impl Strategy<Value = Element<'static>> {
+    // Bare URLs, e.g. https://example.com
+    let url = cow!(URL_REGEX).prop_map(|url| Element::Link {
+        ltype: LinkType::Direct,
+        link: LinkLocation::Url(url),
+        label: LinkLabel::Url(None),
+        target: None,
+    });
+
+    // Single-bracket links, e.g. [https://example.com label]
+    let single = (cow!(URL_REGEX), cow!(WORDS_REGEX), arb_target()).prop_map(
+        |(url, label, target)| Element::Link {
+            ltype: LinkType::Direct,
+            link: LinkLocation::Url(url),
+            label: LinkLabel::Text(label),
+            target,
+        },
+    );
+
+    // Anchor links, e.g. [#anchor label]
+    let anchor =
+        (cow!("#[a-z0-9]{1,8}"), cow!(WORDS_REGEX)).prop_map(|(anchor, label)| {
+            Element::Link {
+                ltype: LinkType::Anchor,
+                link: LinkLocation::Url(anchor),
+                label: LinkLabel::Text(label),
+                target: None,
+            }
+        });
+
+    // Triple-bracket links, e.g. [[[page | label]]]
     let label = prop_oneof![
-        cow!(".*").prop_map(LinkLabel::Text),
-        option::of(cow!(SIMPLE_URL_REGEX)).prop_map(LinkLabel::Url),
+        cow!(WORDS_REGEX).prop_map(LinkLabel::Text),
         Just(LinkLabel::Page),
     ];
 
-    (arb_link_type(), arb_link_location(), label, arb_target()).prop_map(
-        |(ltype, link, label, target)| Element::Link {
-            ltype,
-            link,
-            label,
-            target,
-        },
-    )
+    let page =
+        (arb_link_location(), label, arb_target()).prop_map(|(link, label, target)| {
+            Element::Link {
+                ltype: link.link_type(),
+                link,
+                label,
+                target,
+            }
+        });
+
+    prop_oneof![url, single, anchor, page]
 }
 
 fn arb_image() -> impl Strategy<Value = Element<'static>> {
     let source = prop_oneof![
-        cow!(SIMPLE_URL_REGEX).prop_map(ImageSource::Url),
-        cow!(".*").prop_map(|file| ImageSource::File1 { file }),
-        (cow!(".*"), cow!(".*"))
-            .prop_map(|(page, file)| ImageSource::File2 { page, file }),
-        (cow!(".*"), cow!(".*"), cow!(".*"))
-            .prop_map(|(site, page, file)| ImageSource::File3 { site, page, file }),
-    ];
+        cow!(URL_REGEX),
+        cow!(r"[a-z0-9\-]{1,8}\.png"),
+        cow!(r"[a-z0-9\-]{1,8}/[a-z0-9\-]{1,8}\.png"),
+        cow!(r"[a-z0-9\-]{1,8}/[a-z0-9\-]{1,8}/[a-z0-9\-]{1,8}\.png"),
+    ]
+    .prop_map(|source| ImageSource::parse(&source).unwrap().to_owned());
 
+    // Centered images cannot be floated, and none can be justified
     let alignment = select!([
-        Alignment::Left,
-        Alignment::Right,
-        Alignment::Center,
-        Alignment::Justify,
+        FloatAlignment {
+            align: Alignment::Left,
+            float: false,
+        },
+        FloatAlignment {
+            align: Alignment::Right,
+            float: false,
+        },
+        FloatAlignment {
+            align: Alignment::Center,
+            float: false,
+        },
+        FloatAlignment {
+            align: Alignment::Left,
+            float: true,
+        },
+        FloatAlignment {
+            align: Alignment::Right,
+            float: true,
+        },
     ]);
 
-    let image_alignment = option::of(
-        (alignment, any::<bool>())
-            .prop_map(|(align, float)| FloatAlignment { align, float }),
-    );
-
     (
         source,
         option::of(arb_link_location()),
-        image_alignment,
+        option::of(alignment),
         arb_attribute_map(),
     )
         .prop_map(|(source, link, alignment, attributes)| Element::Image {
@@ -197,15 +319,14 @@ fn arb_image() -> impl Strategy<Value = Element<'static>> {
 
 fn arb_list<S>(elements: S) -> impl Strategy<Value = Element<'static>>
 where
-    S: Strategy<Value = Vec<Element<'static>>> + 'static,
+    S: Strategy<Value = Vec<Element<'static>>> + Clone + 'static,
 {
     macro_rules! make_list {
-        ($items:expr) => {{
+        ($items:expr, $attributes:expr) => {{
             let ltype = select!([ListType::Bullet, ListType::Numbered]);
             let items = $items;
-            let attributes = arb_attribute_map();
 
-            (ltype, items, attributes).prop_map(|(ltype, items, attributes)| {
+            (ltype, items, $attributes).prop_map(|(ltype, items, attributes)| {
                 Element::List {
                     ltype,
                     items,
@@ -215,30 +336,59 @@ where
         }};
     }
 
-    let list_item = (elements, arb_attribute_map()).prop_map(|(elements, attributes)| {
+    // Attributes are only available with [[ul]] and [[li]], for which
+    // the whole list must be written as blocks.
+    let attributes = prop_oneof![
+        3 => Just(AttributeMap::new()),
+        1 => arb_attribute_map(),
+    ];
+
+    let list_item = (elements, attributes.clone()).prop_map(|(elements, attributes)| {
         ListItem::Elements {
             elements,
             attributes,
         }
     });
-    let leaf = make_list!(proptest::collection::vec(list_item, 1..10));
+    let leaf = make_list!(
+        proptest::collection::vec(list_item.clone(), 1..5),
+        attributes.clone()
+    );
 
     leaf.prop_recursive(
-        5,  // Levels deep
-        30, // Number of total nodes
-        10, // Up to X items per collection
-        |inner| {
-            make_list!(inner.prop_map(|element| {
+        3,  // Levels deep
+        12, // Number of total nodes
+        4,  // Up to X items per collection
+        move |inner| {
+            let sub_list = inner.prop_map(|element| {
                 let element = Box::new(element);
-                vec![ListItem::SubList { element }]
-            }))
+                ListItem::SubList { element }
+            });
+
+            // Lists must begin with an item, as a native list
+            // cannot start more than one level deep.
+            let items = (
+                list_item.clone(),
+                proptest::collection::vec(prop_oneof![list_item.clone(), sub_list], 0..4),
+            )
+                .prop_map(|(first, mut rest)| {
+                    rest.insert(0, first);
+                    rest
+                });
+
+            make_list!(items, attributes.clone())
         },
     )
 }
 
 fn arb_code() -> impl Strategy<Value = Element<'static>> {
-    (cow!(".*"), arb_optional_str())
-        .prop_map(|(contents, language)| Element::Code { contents, language })
+    (
+        lines!(r"[a-z0-9{}();=<>+*/ ]{1,16}"),
+        option::of(cow!("[a-z]{1,8}")),
+    )
+        .prop_map(|(contents, language)| Element::Code {
+            contents: Cow::Owned(contents),
+            language,
+        })
 }
 
 fn arb_checkbox() -> impl Strategy<Value = Element<'static>> {
@@ -252,9 +402,62 @@ fn arb_checkbox() -> impl Strategy<Value = Element<'static>> {
 
 // Container elements
 
+/// Generates inline containers, such as bold, whose bodies are the given elements.
 fn arb_container<S>(elements: S) -> impl Strategy<Value = Element<'static>>
 where
     S: Strategy<Value = Vec<Element<'static>>>,
+{
+    // Only some blocks accept attributes
+    let container_type = prop_oneof![
+        Just((ContainerType::Bold, true)),
+        Just((ContainerType::Italics, true)),
+        Just((ContainerType::Underline, true)),
+        Just((ContainerType::Superscript, true)),
+        Just((ContainerType::Subscript, true)),
+        Just((ContainerType::Strikethrough, true)),
+        Just((ContainerType::Monospace, true)),
+        Just((ContainerType::Span, true)),
+        Just((ContainerType::Mark, true)),
+        Just((ContainerType::Insertion, false)),
+        Just((ContainerType::Deletion, true)),
+        Just((ContainerType::Hidden, true)),
+        Just((ContainerType::Invisible, true)),
+        Just((ContainerType::Size, false)),
+    ];
+
+    let attributes = prop_oneof![
+        3 => Just(AttributeMap::new()),
+        1 => arb_attribute_map(),
+    ];
+
+    let size = select!(["50%", "2em", "larger", "x-small"]);
+
+    (container_type, elements, attributes, size).prop_map(
+        |((ctype, has_attributes), elements, attributes, size)| {
+            let attributes = match ctype {
+                ContainerType::Size => {
+                    let style = format!("font-size: {size};");
+                    let mut map = AttributeMap::new();
+                    map.insert("style", Cow::Owned(style));
+                    map
+                }
+                _ if has_attributes => attributes,
+                _ => AttributeMap::new(),
+            };
+
+            Element::Container(Container::new(ctype, elements, attributes))
+        },
+    )
+}
+
+/// Generates containers whose bodies are either inline elements or blocks.
+fn arb_block_container<I, B>(
+    inline: I,
+    blocks: B,
+) -> impl Strategy<Value = Element<'static>>
+where
+    I: Strategy<Value = Vec<Element<'static>>> + Clone,
+    B: Strategy<Value = Vec<Element<'static>>> + Clone,
 {
     let alignment = select!([
         Alignment::Left,
@@ -264,7 +467,7 @@ where
     ]);
 
     let heading = {
-        let has_toc = select!([true, false]);
+        let has_toc = any::<bool>();
         let level = select!([
             HeadingLevel::One,
             HeadingLevel::Two,
@@ -277,33 +480,43 @@ where
         (level, has_toc).prop_map(|(level, has_toc)| Heading { level, has_toc })
     };
 
-    let container_type = prop_oneof![
-        Just(ContainerType::Bold),
-        Just(ContainerType::Italics),
-        Just(ContainerType::Underline),
-        Just(ContainerType::Superscript),
-        Just(ContainerType::Subscript),
-        Just(ContainerType::Strikethrough),
-        Just(ContainerType::Monospace),
-        Just(ContainerType::Span),
-        Just(ContainerType::Div),
-        Just(ContainerType::Mark),
-        Just(ContainerType::Blockquote),
-        Just(ContainerType::Insertion),
-        Just(ContainerType::Deletion),
-        Just(ContainerType::Hidden),
-        Just(ContainerType::Invisible),
-        Just(ContainerType::Size),
-        Just(ContainerType::Paragraph),
-        alignment.prop_map(|align| ContainerType::Align(align)),
-        heading.prop_map(|heading| ContainerType::Header(heading)),
-    ];
+    macro_rules! container {
+        ($ctype:expr, $elements:expr, $attributes:expr $(,)?) => {
+            ($ctype, $elements, $attributes).prop_map(|(ctype, elements, attributes)| {
+                Element::Container(Container::new(ctype, elements, attributes))
+            })
+        };
+    }
 
-    (container_type, elements, arb_attribute_map()).prop_map(
-        |(ctype, elements, attributes)| {
-            Element::Container(Container::new(ctype, elements, attributes))
-        },
-    )
+    prop_oneof![
+        // Blocks with inline bodies
+        container!(
+            select!([ContainerType::Paragraph, ContainerType::Div]),
+            inline.clone(),
+            arb_attribute_map(),
+        ),
+        container!(
+            heading.prop_map(ContainerType::Header),
+            inline.clone(),
+            Just(AttributeMap::new()),
+        ),
+        container!(
+            Just(ContainerType::Align(Alignment::Center)),
+            inline.clone(),
+            Just(AttributeMap::new()),
+        ),
+        // Blocks with paragraphs
+        container!(
+            select!([ContainerType::Div, ContainerType::Blockquote]),
+            blocks.clone(),
+            arb_attribute_map(),
+        ),
+        container!(
+            alignment.prop_map(ContainerType::Align),
+            blocks,
+            Just(AttributeMap::new()),
+        ),
+    ]
 }
 
 fn arb_collapsible<S>(elements: S) -> impl Strategy<Value = Element<'static>>
@@ -342,73 +555,246 @@ where
 
 // Syntax Tree
 
-fn arb_element_leaf() -> impl Strategy<Value = Element<'static>> {
+/// Generates a single inline element, other than text.
+///
+/// Footnotes cannot be nested, so `footnotes` is false for their contents.
+fn arb_element_leaf(footnotes: bool) -> impl Strategy<Value = Element<'static>> {
+    let footnote_weight = if footnotes { 1 } else { 0 };
+
     prop_oneof![
-        cow!(".*").prop_map(Element::Text),
-        cow!(".*").prop_map(Element::Raw),
-        cow!(SIMPLE_EMAIL_REGEX).prop_map(Element::Email),
-        arb_module(),
-        arb_link_element(),
-        arb_image(),
+        2 => arb_raw(),
+        1 => arb_link_element(),
+        1 => arb_image(),
         // TODO: Element::RadioButton
-        arb_checkbox(),
+        1 => arb_checkbox(),
         // TODO: Element::User
-        arb_code(),
-        cow!(".*").prop_map(|contents| Element::Html { contents }),
-        // TODO: Element::Iframe
-        Just(Element::LineBreak),
-        (1..50_u32)
+        footnote_weight => Just(Element::Footnote),
+        1 => (1..10_u32)
             .prop_map(|count| Element::LineBreaks(NonZeroU32::new(count).unwrap())),
-        select!([ClearFloat::Both, ClearFloat::Left, ClearFloat::Right])
-            .prop_map(Element::ClearFloat),
-        Just(Element::HorizontalRule),
     ]
 }
 
-fn arb_tree() -> impl Strategy<Value = SyntaxTree<'static>> {
-    let leaf = arb_element_leaf();
+/// Generates the contents of a paragraph.
+///
+/// Words and other elements are separated by spaces or line breaks,
+/// and there is no whitespace at the start or end, since it is trimmed.
+fn arb_inline_elements<S>(element: S) -> impl Strategy<Value = Vec<Element<'static>>>
+where
+    S: Strategy<Value = Element<'static>>,
+{
+    let separator = prop_oneof![
+        4 => Just(vec![text(" ")]),
+        2 => Just(vec![]),
+        1 => Just(vec![Element::LineBreak]),
+    ];
+
+    proptest::collection::vec((element, separator), 1..8).prop_map(|items| {
+        let mut elements = Vec::new();
+
+        for (element, separator) in items {
+            elements.push(element);
+            elements.extend(separator);
+        }
+
+        // No separator at the end
+        while let Some(Element::LineBreak | Element::Text(Cow::Borrowed(" "))) =
+            elements.last()
+        {
+            elements.pop();
+        }
+
+        elements
+    })
+}
+
+fn arb_inline(footnotes: bool) -> impl Strategy<Value = Vec<Element<'static>>> + Clone {
+    let leaf = prop_oneof![
+        4 => arb_text(),
+        1 => arb_element_leaf(footnotes),
+    ];
+
     let element = leaf.prop_recursive(
-        5,  // Levels deep
-        50, // Number of total nodes
-        10, // Up to X items per collection
-        |inner| {
-            // Inner strategy for recursive cases
-            macro_rules! elements {
-                () => {
-                    proptest::collection::vec(inner.clone(), 1..20)
-                };
-            }
+        3,  // Levels deep
+        16, // Number of total nodes
+        8,  // Up to X items per collection
+        |inner| arb_container(arb_inline_elements(inner)),
+    );
+
+    arb_inline_elements(element)
+        .prop_filter("empty paragraph", |elements| !elements.is_empty())
+        .boxed()
+}
+
+fn arb_paragraph<S>(elements: S) -> impl Strategy<Value = Element<'static>>
+where
+    S: Strategy<Value = Vec<Element<'static>>>,
+{
+    elements.prop_map(|elements| {
+        Element::Container(Container::new(
+            ContainerType::Paragraph,
+            elements,
+            AttributeMap::new(),
+        ))
+    })
+}
+
+fn arb_block(
+    footnotes: bool,
+    depth: u32,
+    nodes: u32,
+) -> impl Strategy<Value = Element<'static>> + Clone {
+    let inline = arb_inline(footnotes);
+
+    let leaf = prop_oneof![
+        8 => arb_paragraph(inline.clone()),
+        2 => arb_list(inline.clone()),
+        1 => arb_code(),
+        1 => lines!(r"[a-z0-9<>/=; ]{1,16}")
+            .prop_map(|contents| Element::Html { contents: Cow::Owned(contents) }),
+        1 => arb_module(),
+        1 => select!([ClearFloat::Both, ClearFloat::Left, ClearFloat::Right])
+            .prop_map(Element::ClearFloat),
+        1 => Just(Element::HorizontalRule),
+    ];
+
+    leaf.prop_recursive(
+        depth, // Levels deep
+        nodes, // Number of total nodes
+        4,     // Up to X items per collection
+        move |inner| {
+            let blocks = proptest::collection::vec(inner, 1..4);
 
             prop_oneof![
-                arb_container(elements!()),
+                arb_block_container(inline.clone(), blocks.clone()),
                 // TODO: Element::Anchor
-                arb_list(elements!()),
-                arb_collapsible(elements!()),
+                arb_collapsible(blocks),
                 // TODO: Element::IfCategory
                 // TODO: Element::IfTags
                 // TODO: Element::Color
             ]
         },
-    );
+    )
+    .boxed()
+}
 
-    let toc_elements = proptest::collection::vec(arb_element_leaf(), 1..5);
-    let toc_heading = arb_list(toc_elements);
-    let footnote = proptest::collection::vec(element.clone(), 5..10);
+fn arb_tree() -> impl Strategy<Value = SyntaxTree<'static>> {
+    arb_tree_sized(20, 3, 12)
+}
 
-    (
-        proptest::collection::vec(element, 1..100),
-        proptest::collection::vec(cow!(".*"), 0..128),
-        proptest::collection::vec(toc_heading, 0..2),
-        proptest::collection::vec(footnote, 0..2),
-    )
-        .prop_map(
-            |(elements, styles, table_of_contents, footnotes)| SyntaxTree {
-                elements,
-                styles,
-                table_of_contents,
-                footnotes,
+/// Generates syntax trees with the given limits on their size.
+///
+/// The arguments are, in order:
+/// * `length` -- The maximum number of top-level elements
+/// * `depth` -- How many levels deep blocks can be nested
+/// * `nodes` -- The number of total blocks in each top-level element
+fn arb_tree_sized(
+    length: usize,
+    depth: u32,
+    nodes: u32,
+) -> impl Strategy<Value = SyntaxTree<'static>> {
+    let elements = proptest::collection::vec(arb_block(true, depth, nodes), 1..length);
+    let styles = proptest::collection::vec(arb_style(), 0..3);
+
+    (elements, styles).prop_flat_map(|(elements, styles)| {
+        // Each footnote in the tree needs contents
+        let footnote = prop_oneof![
+            arb_inline(false),
+            proptest::collection::vec(arb_paragraph(arb_inline(false)), 2..4),
+        ];
+        let footnotes = proptest::collection::vec(footnote, count_footnotes(&elements));
+
+        (Just(elements), Just(styles), footnotes).prop_map(
+            |(mut elements, styles, footnotes)| {
+                // The table of contents is generated from the headings
+                let mut headings = Vec::new();
+                collect_headings(&elements, &mut headings);
+                let table_of_contents = build_table_of_contents(headings);
+
+                elements.push(Element::FootnoteBlock {
+                    title: None,
+                    hide: false,
+                });
+
+                SyntaxTree {
+                    elements,
+                    styles,
+                    table_of_contents,
+                    footnotes,
+                    bibliography: BibliographyList::default(),
+                }
             },
         )
+    })
+}
+
+fn arb_style() -> impl Strategy<Value = Cow<'static, str>> {
+    let rule = r"[a-z]{1,6}( \.[a-z]{1,6})? \{ (color|margin): [a-z0-9]{1,6}; \}";
+
+    lines!(prop_oneof![
+        cow!(rule).prop_map(String::from),
+        Just(str!(""))
+    ])
+    .prop_map(Cow::Owned)
+}
+
+/// Visits each element in the tree in order, as the parser would encounter them.
+fn for_each_element<'a, F>(elements: &'a [Element<'static>], f: &mut F)
+where
+    F: FnMut(&'a Element<'static>),
+{
+    for element in elements {
+        f(element);
+
+        match element {
+            Element::Container(container) => for_each_element(container.elements(), f),
+            Element::Collapsible { elements, .. } => for_each_element(elements, f),
+            Element::List { items, .. } => {
+                for item in items {
+                    match item {
+                        ListItem::Elements { elements, .. } => {
+                            for_each_element(elements, f)
+                        }
+                        ListItem::SubList { element } => {
+                            for_each_element(std::slice::from_ref(element), f)
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn count_footnotes(elements: &[Element<'static>]) -> usize {
+    let mut count = 0;
+    for_each_element(elements, &mut |element| {
+        if element == &Element::Footnote {
+            count += 1;
+        }
+    });
+    count
+}
+
+fn collect_headings(elements: &[Element<'static>], headings: &mut Vec<(usize, String)>) {
+    let page_info = PageInfo::dummy();
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+
+    for_each_element(elements, &mut |element| {
+        if let Element::Container(container) = element {
+            if let ContainerType::Header(heading) = container.ctype() {
+                if heading.has_toc {
+                    let level = usize::from(heading.level.value()) - 1;
+                    let name = TextRender::default().render_partial(
+                        container.elements(),
+                        &page_info,
+                        &settings,
+                    );
+
+                    headings.push((level, name));
+                }
+            }
+        }
+    });
 }
 
 // Page Info
@@ -449,6 +835,18 @@ fn render<R: Render>(
     render.render(&tree, &page_info, &settings)
 }
 
+fn wikitext_round_trip(
+    tree: &SyntaxTree<'static>,
+    page_info: &PageInfo<'static>,
+) -> SyntaxTree<'static> {
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let mut wikitext = WikitextRender.render(tree, page_info, &settings);
+    crate::preprocess(&mut wikitext);
+    let tokens = crate::tokenize(&wikitext);
+    let (tree, _warnings) = crate::parse(&tokens, page_info, &settings).into();
+    tree.to_owned()
+}
+
 proptest! {
     // Warning: these tests are *very* slow.
     #![proptest_config(ProptestConfig::with_cases(16))]
@@ -456,13 +854,24 @@ proptest! {
     #[test]
     #[ignore = "slow test"]
     fn render_html_prop(page_info in arb_page_info(), tree in arb_tree()) {
-        let out = render(HtmlRender, tree, page_info);
+        let out = render(HtmlRender::default(), tree, page_info);
         assert!(out.meta.len() >= 4);
     }
 
     #[test]
     #[ignore = "slow test"]
     fn render_text_prop(page_info in arb_page_info(), tree in arb_tree()) {
-        let _ = render(TextRender, tree, page_info);
+        let _ = render(TextRender::default(), tree, page_info);
+    }
+
+    #[test]
+    #[ignore = "slow test"]
+    fn render_wikitext_prop(tree in arb_tree()) {
+        // Parsing the wikitext written for a tree must produce the same tree
+        //
+        // The page is the one the table of contents was generated with.
+        let page_info = PageInfo::dummy();
+        let output = wikitext_round_trip(&tree, &page_info);
+        prop_assert_eq!(output, tree);
     }
 }
@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::includer::TestIncluder;
 use crate::data::PageInfo;
 use crate::render::{html::HtmlRender, Render};
 use crate::settings::{WikitextMode, WikitextSettings};
@@ -41,9 +42,10 @@ fn settings() {
             crate::preprocess(&mut text);
 
             let tokens = crate::tokenize(&text);
-            let result = crate::parse(&tokens, &page_info, &settings);
+            let result =
+                crate::parse_with_includer(&tokens, &page_info, &settings, TestIncluder);
             let (tree, _warnings) = result.into();
-            let html_output = HtmlRender.render(&tree, &page_info, &settings);
+            let html_output = HtmlRender::default().render(&tree, &page_info, &settings);
 
             println!();
             println!("Input:  {:?}", $input);
@@ -76,7 +78,7 @@ fn settings() {
     check!("[[toc]]", "wj-toc", [true, false, false, false, false]);
     check!(
         "[[module Rate]]",
-        "TODO: module Rate",
+        "wj-module-rate",
         [true, true, false, false, true],
     );
     check!(
@@ -20,6 +20,7 @@
 
 use crate::parsing::{ExtractedToken, Token};
 use crate::text::FullText;
+use std::ops::Range;
 
 #[derive(Debug, Clone)]
 pub struct Tokenization<'t> {
@@ -56,6 +57,179 @@ pub fn tokenize(text: &str) -> Tokenization {
     Tokenization { tokens, full_text }
 }
 
+/// A change to a piece of text, replacing one range of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit<'a> {
+    /// The byte range in the original text which is being replaced.
+    pub range: Range<usize>,
+
+    /// The text to put in its place.
+    pub replacement: &'a str,
+}
+
+impl<'a> TextEdit<'a> {
+    #[inline]
+    pub fn new(range: Range<usize>, replacement: &'a str) -> Self {
+        TextEdit { range, replacement }
+    }
+
+    /// Produces the text after this edit.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds for the string,
+    /// or does not lie on UTF-8 character boundaries.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = str!(text);
+        text.replace_range(self.range.clone(), self.replacement);
+        text
+    }
+
+    /// The end of the replacement, in the edited text.
+    #[inline]
+    pub(crate) fn new_end(&self) -> usize {
+        self.range.start + self.replacement.len()
+    }
+
+    /// How far text after the edit has moved, in bytes.
+    #[inline]
+    pub(crate) fn offset(&self) -> isize {
+        self.replacement.len() as isize - self.range.len() as isize
+    }
+}
+
+/// Produces the tokens for edited text, reusing those from before the edit.
+///
+/// The result is the same as calling `tokenize()` on the new text, but only the
+/// lines around the edit are lexed again. The rest of the tokens are taken from
+/// `previous`, which must be the tokenization of the text before the edit.
+///
+/// # Panics
+/// Panics if the length of `text` does not match the edit having been applied.
+pub fn retokenize<'t>(
+    previous: &Tokenization,
+    edit: &TextEdit,
+    text: &'t str,
+) -> Tokenization<'t> {
+    info!(
+        "Running lexer on edited text (range {}..{}, replacement length {})",
+        edit.range.start,
+        edit.range.end,
+        edit.replacement.len(),
+    );
+
+    let old_tokens = previous.tokens();
+    let old_length = previous.full_text().inner().len();
+    assert_eq!(
+        old_length - edit.range.len() + edit.replacement.len(),
+        text.len(),
+        "Edited text length does not match the edit",
+    );
+
+    // Each token depends only on the text from its start to the end of that line,
+    // so lexing can resume from the line break before the edit. The tokens before
+    // it, which never looked past the line break, are the same as before.
+    let restart = old_tokens.iter().rposition(|token| {
+        is_line_break(token.token) && token.span.start < edit.range.start
+    });
+
+    let restart = match restart {
+        Some(index) => index,
+        None => return tokenize(text),
+    };
+
+    // Likewise, lexing can stop at the start of the first line after the edit.
+    // Line breaks are always a single token, so this lines up with a token in
+    // the previous tokenization.
+    let start = old_tokens[restart].span.start;
+    let end = next_line_start(text, edit.new_end());
+
+    let mut window = Token::extract_all(&text[start..end]);
+    if window.first().map(|token| token.token) != Some(Token::InputStart) {
+        warn!("Lexing the edited lines failed, running lexer on the full text");
+        return tokenize(text);
+    }
+
+    window.remove(0);
+    if end < text.len() {
+        window.pop();
+    }
+
+    let resume = if end < text.len() {
+        let old_end = (end as isize - edit.offset()) as usize;
+
+        match old_tokens.binary_search_by_key(&old_end, |token| token.span.start) {
+            Ok(index) => index,
+            Err(_) => {
+                warn!("No previous token after the edited lines, running lexer on the full text");
+                return tokenize(text);
+            }
+        }
+    } else {
+        old_tokens.len()
+    };
+
+    // Combine the previous tokens with the new ones
+    let make_token = |token: Token, span: Range<usize>| ExtractedToken {
+        token,
+        slice: &text[span.clone()],
+        span,
+    };
+
+    let mut tokens =
+        Vec::with_capacity(restart + window.len() + old_tokens.len() - resume);
+
+    tokens.extend(
+        old_tokens[..restart]
+            .iter()
+            .map(|token| make_token(token.token, token.span.clone())),
+    );
+
+    tokens.extend(window.into_iter().map(|token| {
+        let span = token.span.start + start..token.span.end + start;
+        make_token(token.token, span)
+    }));
+
+    tokens.extend(old_tokens[resume..].iter().map(|token| {
+        let span = offset_index(token.span.start, edit.offset())
+            ..offset_index(token.span.end, edit.offset());
+
+        make_token(token.token, span)
+    }));
+
+    let full_text = FullText::new(text);
+    Tokenization { tokens, full_text }
+}
+
+#[inline]
+fn is_line_break(token: Token) -> bool {
+    matches!(token, Token::LineBreak | Token::ParagraphBreak)
+}
+
+/// Finds the start of the first line beginning after the given index.
+///
+/// That is, the first character after the next series of newlines.
+/// If there is none, then the length of the text is returned.
+fn next_line_start(text: &str, index: usize) -> usize {
+    let is_newline = |byte: &u8| matches!(byte, b'\n' | b'\r');
+    let bytes = &text.as_bytes()[index..];
+
+    match bytes.iter().position(is_newline) {
+        Some(newline) => {
+            let rest = &bytes[newline..];
+            let length = rest.iter().take_while(|byte| is_newline(byte)).count();
+            index + newline + length
+        }
+        None => text.len(),
+    }
+}
+
+#[inline]
+pub(crate) fn offset_index(index: usize, offset: isize) -> usize {
+    index
+        .checked_add_signed(offset)
+        .expect("Offset index is out of range")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -70,4 +244,33 @@ mod test {
             let _ = tokenize(&s);
         }
     }
+
+    const EDIT_TEXT_REGEX: &str =
+        r#"([a-c \t\n\r"@<>\[\]*#/\-~+|=$]|\[\[|\]\]|https://)*"#;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1024))]
+
+        #[test]
+        fn retokenize_prop(
+            text in EDIT_TEXT_REGEX,
+            replacement in EDIT_TEXT_REGEX,
+            start in any::<prop::sample::Index>(),
+            end in any::<prop::sample::Index>(),
+        ) {
+            let (start, end) = {
+                let start = start.index(text.len() + 1);
+                let end = end.index(text.len() + 1);
+                (start.min(end), start.max(end))
+            };
+
+            let edit = TextEdit::new(start..end, &replacement);
+            let new_text = edit.apply(&text);
+
+            let previous = tokenize(&text);
+            let expected = tokenize(&new_text);
+            let actual = retokenize(&previous, &edit, &new_text);
+            prop_assert_eq!(actual.tokens(), expected.tokens());
+        }
+    }
 }
@@ -0,0 +1,157 @@
+/*
+ * tree/bibliography.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::clone::{elements_to_owned, string_to_owned};
+use super::Element;
+use std::borrow::Cow;
+
+/// One entry in a `[[bibliography]]` block, which can be cited by its label.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BibliographyEntry<'t> {
+    pub label: Cow<'t, str>,
+    pub contents: Vec<Element<'t>>,
+}
+
+impl BibliographyEntry<'_> {
+    pub fn to_owned(&self) -> BibliographyEntry<'static> {
+        BibliographyEntry {
+            label: string_to_owned(&self.label),
+            contents: elements_to_owned(&self.contents),
+        }
+    }
+}
+
+/// All the bibliography entries on a page, numbered for citation.
+///
+/// Entries are numbered in the order they are first cited. Any entries
+/// which are never cited come afterwards, in the order they appear.
+/// If more than one entry has the same label, only the first can be cited.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct BibliographyList<'t> {
+    /// The entries, in order. Each entry's number is its index plus one.
+    entries: Vec<BibliographyEntry<'t>>,
+}
+
+impl<'t> BibliographyList<'t> {
+    /// Numbers the given entries according to the order of the cited labels.
+    pub(crate) fn new<'a, I>(entries: Vec<BibliographyEntry<'t>>, cited_labels: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut remaining = entries.into_iter().map(Some).collect::<Vec<_>>();
+        let mut numbered = Vec::with_capacity(remaining.len());
+
+        for label in cited_labels {
+            if numbered
+                .iter()
+                .any(|entry: &BibliographyEntry| entry.label == label)
+            {
+                continue;
+            }
+
+            let slot = remaining
+                .iter_mut()
+                .find(|entry| matches!(entry, Some(entry) if entry.label == label));
+
+            if let Some(slot) = slot {
+                numbered.extend(slot.take());
+            }
+        }
+
+        numbered.extend(remaining.into_iter().flatten());
+        BibliographyList { entries: numbered }
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[BibliographyEntry<'t>] {
+        &self.entries
+    }
+
+    #[inline]
+    pub(crate) fn entries_mut(&mut self) -> &mut [BibliographyEntry<'t>] {
+        &mut self.entries
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the number and contents of the entry with this label, if any.
+    pub fn get(&self, label: &str) -> Option<(usize, &[Element<'t>])> {
+        self.entries
+            .iter()
+            .position(|entry| entry.label == label)
+            .map(|index| (index + 1, self.entries[index].contents.as_slice()))
+    }
+
+    /// Gets the numbers and contents of the entries in a `[[bibliography]]` block.
+    ///
+    /// These are sorted by number, so they are listed in the order they are cited.
+    pub fn get_block_entries(
+        &self,
+        entries: &[BibliographyEntry],
+    ) -> Vec<(usize, &[Element<'t>])> {
+        let mut numbered = entries
+            .iter()
+            .filter_map(|entry| self.get(&entry.label))
+            .collect::<Vec<_>>();
+
+        numbered.sort_by_key(|&(number, _)| number);
+        numbered.dedup_by_key(|&mut (number, _)| number);
+        numbered
+    }
+
+    pub fn to_owned(&self) -> BibliographyList<'static> {
+        BibliographyList {
+            entries: self
+                .entries
+                .iter()
+                .map(BibliographyEntry::to_owned)
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn numbering() {
+    let entry = |label: &'static str| BibliographyEntry {
+        label: cow!(label),
+        contents: vec![text!(label)],
+    };
+
+    let list = BibliographyList::new(
+        vec![entry("a"), entry("b"), entry("c"), entry("a"), entry("d")],
+        ["c", "x", "a", "c"],
+    );
+
+    let labels = list
+        .entries()
+        .iter()
+        .map(|entry| entry.label.as_ref())
+        .collect::<Vec<_>>();
+
+    assert_eq!(labels, ["c", "a", "b", "a", "d"]);
+    assert_eq!(list.get("c"), Some((1, [text!("c")].as_slice())));
+    assert_eq!(list.get("a"), Some((2, [text!("a")].as_slice())));
+    assert_eq!(list.get("d").map(|(number, _)| number), Some(5));
+    assert_eq!(list.get("x"), None);
+}
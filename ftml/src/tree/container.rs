@@ -58,6 +58,11 @@ impl<'t> Container<'t> {
         &self.elements
     }
 
+    #[inline]
+    pub fn elements_mut(&mut self) -> &mut Vec<Element<'t>> {
+        &mut self.elements
+    }
+
     #[inline]
     pub fn attributes(&self) -> &AttributeMap<'t> {
         &self.attributes
@@ -21,13 +21,15 @@
 use crate::data::PageRef;
 use crate::tree::clone::*;
 use crate::tree::{
-    Alignment, AnchorTarget, AttributeMap, ClearFloat, Container, Date,
-    DefinitionListItem, Embed, FloatAlignment, ImageSource, LinkLabel, LinkLocation,
-    LinkType, ListItem, ListType, Module, PartialElement, Tab, Table, VariableMap,
+    Alignment, AnchorTarget, AttributeMap, BibliographyEntry, ClearFloat, Container,
+    Date, DefinitionListItem, Embed, FloatAlignment, GalleryItem, GallerySize,
+    ImageSource, LinkLabel, LinkLocation, LinkType, ListItem, ListType, Module,
+    PartialElement, Tab, Table, VariableMap,
 };
 use ref_map::*;
 use std::borrow::Cow;
 use std::num::NonZeroU32;
+use std::ops::Range;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case", tag = "element", content = "data")]
@@ -118,6 +120,16 @@ pub enum Element<'t> {
         attributes: AttributeMap<'t>,
     },
 
+    /// A grid of image thumbnails, from `[[gallery]]`.
+    ///
+    /// The "viewer" field is whether thumbnails without their own link
+    /// should link to the full image.
+    Gallery {
+        items: Vec<GalleryItem<'t>>,
+        size: GallerySize,
+        viewer: bool,
+    },
+
     /// An ordered or unordered list.
     List {
         #[serde(rename = "type")]
@@ -189,6 +201,18 @@ pub enum Element<'t> {
         hide: bool,
     },
 
+    /// A citation of an entry in a `[[bibliography]]` block, from `((bibcite label))`.
+    ///
+    /// Like footnotes, the number is not saved here. It is the position
+    /// of the entry with this label in the `bibliography` list in the syntax tree.
+    BibliographyCite { label: Cow<'t, str> },
+
+    /// A bibliography block, listing the entries which can be cited on the page.
+    BibliographyBlock {
+        title: Option<Cow<'t, str>>,
+        entries: Vec<BibliographyEntry<'t>>,
+    },
+
     /// A user block, linking to their information and possibly showing their avatar.
     #[serde(rename_all = "kebab-case")]
     User {
@@ -268,6 +292,18 @@ pub enum Element<'t> {
     /// A horizontal rule.
     HorizontalRule,
 
+    /// An element, along with the range of wikitext it was parsed from.
+    ///
+    /// The span is in bytes, unless converted with [`SyntaxTree::to_utf16_indices()`].
+    /// These are only produced if [`WikitextSettings::record_spans`] is enabled.
+    ///
+    /// [`SyntaxTree::to_utf16_indices()`]: crate::tree::SyntaxTree::to_utf16_indices
+    /// [`WikitextSettings::record_spans`]: crate::settings::WikitextSettings::record_spans
+    Spanned {
+        span: Range<usize>,
+        element: Box<Element<'t>>,
+    },
+
     /// A partial element.
     ///
     /// This will not appear in final syntax trees, but exists to
@@ -277,7 +313,23 @@ pub enum Element<'t> {
     Partial(PartialElement<'t>),
 }
 
-impl Element<'_> {
+impl<'t> Element<'t> {
+    /// Returns this element, without any source spans around it.
+    pub fn unspanned(&self) -> &Element<'t> {
+        match self {
+            Element::Spanned { element, .. } => element.unspanned(),
+            _ => self,
+        }
+    }
+
+    /// Like [`unspanned()`](Self::unspanned), but mutable.
+    pub fn unspanned_mut(&mut self) -> &mut Element<'t> {
+        match self {
+            Element::Spanned { element, .. } => element.unspanned_mut(),
+            _ => self,
+        }
+    }
+
     /// Determines if the element is "unintentional whitespace".
     ///
     /// Specifically, it returns true if the element is:
@@ -290,6 +342,7 @@ impl Element<'_> {
         match self {
             Element::LineBreak => true,
             Element::Text(string) if string.chars().all(|c| c.is_whitespace()) => true,
+            Element::Spanned { element, .. } => element.is_whitespace(),
             _ => false,
         }
     }
@@ -309,6 +362,7 @@ impl Element<'_> {
             Element::AnchorName(_) => "AnchorName",
             Element::Link { .. } => "Link",
             Element::Image { .. } => "Image",
+            Element::Gallery { .. } => "Gallery",
             Element::List { .. } => "List",
             Element::DefinitionList(_) => "DefinitionList",
             Element::RadioButton { .. } => "RadioButton",
@@ -317,6 +371,8 @@ impl Element<'_> {
             Element::TableOfContents { .. } => "TableOfContents",
             Element::Footnote => "Footnote",
             Element::FootnoteBlock { .. } => "FootnoteBlock",
+            Element::BibliographyCite { .. } => "BibliographyCite",
+            Element::BibliographyBlock { .. } => "BibliographyBlock",
             Element::User { .. } => "User",
             Element::Date { .. } => "Date",
             Element::Color { .. } => "Color",
@@ -332,6 +388,7 @@ impl Element<'_> {
             Element::LineBreaks { .. } => "LineBreaks",
             Element::ClearFloat(_) => "ClearFloat",
             Element::HorizontalRule => "HorizontalRule",
+            Element::Spanned { .. } => "Spanned",
             Element::Partial(partial) => partial.name(),
         }
     }
@@ -359,6 +416,7 @@ impl Element<'_> {
                 true
             }
             Element::Image { .. } => true,
+            Element::Gallery { .. } => false,
             Element::List { .. } => false,
             Element::DefinitionList(_) => false,
             Element::RadioButton { .. } | Element::CheckBox { .. } => true,
@@ -366,6 +424,8 @@ impl Element<'_> {
             Element::TableOfContents { .. } => false,
             Element::Footnote => true,
             Element::FootnoteBlock { .. } => false,
+            Element::BibliographyCite { .. } => true,
+            Element::BibliographyBlock { .. } => false,
             Element::User { .. } => true,
             Element::Date { .. } => true,
             Element::Color { .. } => true,
@@ -379,6 +439,7 @@ impl Element<'_> {
             Element::LineBreak | Element::LineBreaks { .. } => true,
             Element::ClearFloat(_) => false,
             Element::HorizontalRule => false,
+            Element::Spanned { element, .. } => element.paragraph_safe(),
             Element::Partial(_) => {
                 panic!("Should not check for paragraph safety of partials")
             }
@@ -443,6 +504,15 @@ impl Element<'_> {
                 alignment: *alignment,
                 attributes: attributes.to_owned(),
             },
+            Element::Gallery {
+                items,
+                size,
+                viewer,
+            } => Element::Gallery {
+                items: items.iter().map(|item| item.to_owned()).collect(),
+                size: *size,
+                viewer: *viewer,
+            },
             Element::DefinitionList(items) => Element::DefinitionList(
                 items.iter().map(|item| item.to_owned()).collect(),
             ),
@@ -488,6 +558,13 @@ impl Element<'_> {
                 title: option_string_to_owned(title),
                 hide: *hide,
             },
+            Element::BibliographyCite { label } => Element::BibliographyCite {
+                label: string_to_owned(label),
+            },
+            Element::BibliographyBlock { title, entries } => Element::BibliographyBlock {
+                title: option_string_to_owned(title),
+                entries: entries.iter().map(BibliographyEntry::to_owned).collect(),
+            },
             Element::User { name, show_avatar } => Element::User {
                 name: string_to_owned(name),
                 show_avatar: *show_avatar,
@@ -542,6 +619,10 @@ impl Element<'_> {
             Element::LineBreaks(amount) => Element::LineBreaks(*amount),
             Element::ClearFloat(clear_float) => Element::ClearFloat(*clear_float),
             Element::HorizontalRule => Element::HorizontalRule,
+            Element::Spanned { span, element } => Element::Spanned {
+                span: span.clone(),
+                element: Box::new(Element::to_owned(element)),
+            },
             Element::Partial(partial) => Element::Partial(partial.to_owned()),
         }
     }
@@ -0,0 +1,153 @@
+/*
+ * tree/gallery.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::clone::option_string_to_owned;
+use super::{ImageSource, LinkLocation};
+use std::borrow::Cow;
+use std::str::FromStr;
+use strum_macros::IntoStaticStr;
+
+/// A single image within a `[[gallery]]` block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct GalleryItem<'t> {
+    pub source: ImageSource<'t>,
+    pub link: Option<LinkLocation<'t>>,
+    pub title: Option<Cow<'t, str>>,
+}
+
+impl GalleryItem<'_> {
+    pub fn to_owned(&self) -> GalleryItem<'static> {
+        GalleryItem {
+            source: self.source.to_owned(),
+            link: self.link.as_ref().map(|link| link.to_owned()),
+            title: option_string_to_owned(&self.title),
+        }
+    }
+}
+
+/// How large the thumbnails in a gallery are.
+///
+/// These mirror the sizes available on Wikidot.
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum GallerySize {
+    Square,
+    Thumbnail,
+    Small,
+    Medium,
+}
+
+impl GallerySize {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+
+    pub fn html_class(self) -> &'static str {
+        match self {
+            GallerySize::Square => "wj-gallery-square",
+            GallerySize::Thumbnail => "wj-gallery-thumbnail",
+            GallerySize::Small => "wj-gallery-small",
+            GallerySize::Medium => "wj-gallery-medium",
+        }
+    }
+}
+
+impl Default for GallerySize {
+    #[inline]
+    fn default() -> Self {
+        GallerySize::Thumbnail
+    }
+}
+
+impl FromStr for GallerySize {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, ()> {
+        const SIZES: [(&str, GallerySize); 4] = [
+            ("square", GallerySize::Square),
+            ("thumbnail", GallerySize::Thumbnail),
+            ("small", GallerySize::Small),
+            ("medium", GallerySize::Medium),
+        ];
+
+        SIZES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|(_, size)| *size)
+            .ok_or(())
+    }
+}
+
+/// The order images in a gallery are displayed in.
+///
+/// Parsed from the `order` argument, in the form `field [asc|desc]`.
+/// The parser has no upload times for attached files, so `created_at`
+/// uses the order the files were listed in.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Default)]
+pub struct GalleryOrder {
+    pub by_name: bool,
+    pub descending: bool,
+}
+
+impl GalleryOrder {
+    /// Sorts the given items in place according to this order.
+    pub fn sort(self, items: &mut [GalleryItem]) {
+        if self.by_name {
+            items.sort_by(|a, b| a.source.file_name().cmp(b.source.file_name()));
+        }
+
+        if self.descending {
+            items.reverse();
+        }
+    }
+}
+
+impl FromStr for GalleryOrder {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, ()> {
+        let mut parts = value.split_whitespace();
+        let by_name = match parts.next() {
+            Some(field) if field.eq_ignore_ascii_case("name") => true,
+            Some(field) if field.eq_ignore_ascii_case("created_at") => false,
+            _ => return Err(()),
+        };
+        let descending = match parts.next() {
+            None => false,
+            Some(direction) if direction.eq_ignore_ascii_case("asc") => false,
+            Some(direction) if direction.eq_ignore_ascii_case("desc") => true,
+            Some(_) => return Err(()),
+        };
+
+        if parts.next().is_some() {
+            return Err(());
+        }
+
+        Ok(GalleryOrder {
+            by_name,
+            descending,
+        })
+    }
+}
@@ -83,6 +83,21 @@ impl<'t> ImageSource<'t> {
         self.into()
     }
 
+    /// Gets the name of the file, without any site or page.
+    ///
+    /// For URLs, this is the last segment of the path.
+    pub fn file_name(&self) -> &str {
+        match self {
+            ImageSource::Url(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or(url);
+                path.rsplit('/').next().unwrap_or(path)
+            }
+            ImageSource::File1 { file }
+            | ImageSource::File2 { file, .. }
+            | ImageSource::File3 { file, .. } => file,
+        }
+    }
+
     pub fn to_owned(&self) -> ImageSource<'static> {
         match self {
             ImageSource::Url(url) => ImageSource::Url(string_to_owned(url)),
@@ -0,0 +1,282 @@
+/*
+ * tree/list_pages.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Representation of the query performed by the `ListPages` module.
+
+use super::clone::{option_string_to_owned, strings_to_owned};
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use strum_macros::IntoStaticStr;
+
+/// The default number of pages to show per pagination page.
+pub const LIST_PAGES_DEFAULT_PER_PAGE: u32 = 20;
+
+/// The maximum number of pages which can be shown per pagination page.
+pub const LIST_PAGES_MAX_PER_PAGE: u32 = 250;
+
+/// The filters and options passed to `[[module ListPages]]`.
+///
+/// The embedder uses this to query which pages should be listed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListPagesQuery<'t> {
+    /// Which categories pages may be from.
+    ///
+    /// If `None`, then pages from any category are permitted.
+    pub categories: Option<Vec<Cow<'t, str>>>,
+
+    /// Which categories pages must not be from.
+    pub excluded_categories: Vec<Cow<'t, str>>,
+
+    /// Tags for which pages must have at least one of.
+    ///
+    /// If empty, this condition is not applied.
+    pub tags: Vec<Cow<'t, str>>,
+
+    /// Tags which pages must all have.
+    pub required_tags: Vec<Cow<'t, str>>,
+
+    /// Tags which pages must not have.
+    pub excluded_tags: Vec<Cow<'t, str>>,
+
+    /// The name of the user who must have created the pages.
+    pub created_by: Option<Cow<'t, str>>,
+
+    /// A condition that the rating of pages must satisfy.
+    pub rating: Option<RatingFilter>,
+
+    /// How the pages should be sorted.
+    pub order: ListPagesOrder,
+
+    /// How many matching pages to skip before listing.
+    pub offset: u32,
+
+    /// The maximum number of pages to list, in total.
+    pub limit: Option<NonZeroU32>,
+
+    /// How many pages to list on each pagination page.
+    pub per_page: NonZeroU32,
+
+    /// Whether each page should be rendered separately.
+    ///
+    /// If false, then the templates for every page are joined together
+    /// and then rendered, which permits, for instance, producing one
+    /// list or table containing each item.
+    pub separate: bool,
+}
+
+impl ListPagesQuery<'_> {
+    pub fn to_owned(&self) -> ListPagesQuery<'static> {
+        ListPagesQuery {
+            categories: self
+                .categories
+                .as_ref()
+                .map(|categories| strings_to_owned(categories)),
+            excluded_categories: strings_to_owned(&self.excluded_categories),
+            tags: strings_to_owned(&self.tags),
+            required_tags: strings_to_owned(&self.required_tags),
+            excluded_tags: strings_to_owned(&self.excluded_tags),
+            created_by: option_string_to_owned(&self.created_by),
+            rating: self.rating,
+            order: self.order,
+            offset: self.offset,
+            limit: self.limit,
+            per_page: self.per_page,
+            separate: self.separate,
+        }
+    }
+}
+
+impl Default for ListPagesQuery<'_> {
+    fn default() -> Self {
+        ListPagesQuery {
+            categories: None,
+            excluded_categories: Vec::new(),
+            tags: Vec::new(),
+            required_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            created_by: None,
+            rating: None,
+            order: ListPagesOrder::default(),
+            offset: 0,
+            limit: None,
+            per_page: NonZeroU32::new(LIST_PAGES_DEFAULT_PER_PAGE).unwrap(),
+            separate: true,
+        }
+    }
+}
+
+/// The sort order for pages in `ListPages`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListPagesOrder {
+    pub field: ListPagesOrderField,
+    pub descending: bool,
+}
+
+impl Default for ListPagesOrder {
+    #[inline]
+    fn default() -> Self {
+        ListPagesOrder {
+            field: ListPagesOrderField::CreatedAt,
+            descending: true,
+        }
+    }
+}
+
+impl FromStr for ListPagesOrder {
+    type Err = ();
+
+    /// Parses an ordering in the form `field [asc|desc]`.
+    fn from_str(value: &str) -> Result<Self, ()> {
+        let mut parts = value.split_whitespace();
+        let field = parts.next().ok_or(())?.parse()?;
+        let descending = match parts.next() {
+            None => false,
+            Some(direction) if direction.eq_ignore_ascii_case("asc") => false,
+            Some(direction) if direction.eq_ignore_ascii_case("desc") => true,
+            Some(_) => return Err(()),
+        };
+
+        if parts.next().is_some() {
+            return Err(());
+        }
+
+        Ok(ListPagesOrder { field, descending })
+    }
+}
+
+/// Which field pages in `ListPages` should be sorted by.
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ListPagesOrderField {
+    Name,
+    Title,
+    CreatedAt,
+    CreatedBy,
+    UpdatedAt,
+    Rating,
+    Random,
+}
+
+impl ListPagesOrderField {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
+
+impl FromStr for ListPagesOrderField {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, ()> {
+        const FIELDS: [(&str, ListPagesOrderField); 8] = [
+            ("name", ListPagesOrderField::Name),
+            ("fullname", ListPagesOrderField::Name),
+            ("title", ListPagesOrderField::Title),
+            ("created_at", ListPagesOrderField::CreatedAt),
+            ("created_by", ListPagesOrderField::CreatedBy),
+            ("updated_at", ListPagesOrderField::UpdatedAt),
+            ("rating", ListPagesOrderField::Rating),
+            ("random", ListPagesOrderField::Random),
+        ];
+
+        FIELDS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .map(|(_, field)| *field)
+            .ok_or(())
+    }
+}
+
+/// A condition on the rating of pages in `ListPages`.
+///
+/// Parsed from forms like `>10`, `<=0`, `=5`, or `5`.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct RatingFilter {
+    pub comparison: RatingComparison,
+    pub value: i64,
+}
+
+impl RatingFilter {
+    pub fn matches(self, rating: f64) -> bool {
+        let value = self.value as f64;
+
+        match self.comparison {
+            RatingComparison::Equal => rating == value,
+            RatingComparison::NotEqual => rating != value,
+            RatingComparison::Greater => rating > value,
+            RatingComparison::GreaterOrEqual => rating >= value,
+            RatingComparison::Less => rating < value,
+            RatingComparison::LessOrEqual => rating <= value,
+        }
+    }
+}
+
+impl FromStr for RatingFilter {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, ()> {
+        const COMPARISONS: [(&str, RatingComparison); 7] = [
+            (">=", RatingComparison::GreaterOrEqual),
+            ("<=", RatingComparison::LessOrEqual),
+            ("!=", RatingComparison::NotEqual),
+            (">", RatingComparison::Greater),
+            ("<", RatingComparison::Less),
+            ("=", RatingComparison::Equal),
+            ("", RatingComparison::Equal),
+        ];
+
+        let value = value.trim();
+
+        for (prefix, comparison) in COMPARISONS {
+            if let Some(number) = value.strip_prefix(prefix) {
+                let value = number.trim().parse().map_err(|_| ())?;
+                return Ok(RatingFilter { comparison, value });
+            }
+        }
+
+        Err(())
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, IntoStaticStr, Debug, Copy, Clone, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum RatingComparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl RatingComparison {
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+}
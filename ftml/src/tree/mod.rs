@@ -22,6 +22,7 @@ pub mod attribute;
 
 mod align;
 mod anchor;
+mod bibliography;
 mod clear_float;
 mod clone;
 mod container;
@@ -29,13 +30,16 @@ mod date;
 mod definition_list;
 mod element;
 mod embed;
+mod gallery;
 mod heading;
 mod image;
 mod link;
 mod list;
+mod list_pages;
 mod module;
 mod partial;
 mod ruby;
+mod span;
 mod tab;
 mod table;
 mod tag;
@@ -44,16 +48,19 @@ mod variables;
 pub use self::align::*;
 pub use self::anchor::*;
 pub use self::attribute::AttributeMap;
+pub use self::bibliography::*;
 pub use self::clear_float::*;
 pub use self::container::*;
 pub use self::date::Date;
 pub use self::definition_list::*;
 pub use self::element::*;
 pub use self::embed::*;
+pub use self::gallery::*;
 pub use self::heading::*;
 pub use self::image::*;
 pub use self::link::*;
 pub use self::list::*;
+pub use self::list_pages::*;
 pub use self::module::*;
 pub use self::partial::*;
 pub use self::ruby::*;
@@ -63,7 +70,14 @@ pub use self::tag::*;
 pub use self::variables::*;
 
 use self::clone::{elements_lists_to_owned, elements_to_owned, strings_to_owned};
+use self::span::spans_to_utf16;
+
+pub(crate) use self::span::offset_spans;
+
+#[cfg(test)]
+pub(crate) use self::span::strip_spans;
 use crate::parsing::{ParseOutcome, ParseWarning};
+use crate::utf16::Utf16IndexMap;
 use std::borrow::Cow;
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
@@ -90,6 +104,12 @@ pub struct SyntaxTree<'t> {
 
     /// The full footnote list for this page.
     pub footnotes: Vec<Vec<Element<'t>>>,
+
+    /// The full bibliography for this page, numbered for citation.
+    ///
+    /// This is omitted when serializing if the page has no bibliography.
+    #[serde(default, skip_serializing_if = "BibliographyList::is_empty")]
+    pub bibliography: BibliographyList<'t>,
 }
 
 impl<'t> SyntaxTree<'t> {
@@ -99,12 +119,14 @@ impl<'t> SyntaxTree<'t> {
         styles: Vec<Cow<'t, str>>,
         table_of_contents: Vec<Element<'t>>,
         footnotes: Vec<Vec<Element<'t>>>,
+        bibliography: BibliographyList<'t>,
     ) -> ParseOutcome<Self> {
         let tree = SyntaxTree {
             elements,
             styles,
             table_of_contents,
             footnotes,
+            bibliography,
         };
         ParseOutcome::new(tree, warnings)
     }
@@ -115,7 +137,29 @@ impl<'t> SyntaxTree<'t> {
             styles: strings_to_owned(&self.styles),
             table_of_contents: elements_to_owned(&self.table_of_contents),
             footnotes: elements_lists_to_owned(&self.footnotes),
+            bibliography: self.bibliography.to_owned(),
+        }
+    }
+
+    /// Converts the source spans in this tree to use UTF-16 indices.
+    ///
+    /// See [`Element::Spanned`]. Like [`ParseWarning::to_utf16_indices()`],
+    /// this is for environments which index strings in UTF-16, such as Javascript.
+    #[must_use]
+    pub fn to_utf16_indices(&self, map: &Utf16IndexMap) -> Self {
+        let mut tree = self.clone();
+        spans_to_utf16(&mut tree.elements, map);
+        spans_to_utf16(&mut tree.table_of_contents, map);
+
+        for footnote in &mut tree.footnotes {
+            spans_to_utf16(footnote, map);
         }
+
+        for entry in tree.bibliography.entries_mut() {
+            spans_to_utf16(&mut entry.contents, map);
+        }
+
+        tree
     }
 }
 
@@ -20,8 +20,8 @@
 
 //! Representation of Wikidot modules, along with their context.
 
-use super::clone::option_string_to_owned;
-use super::AttributeMap;
+use super::clone::{option_string_to_owned, string_to_owned};
+use super::{AttributeMap, ListPagesQuery};
 use std::borrow::Cow;
 use std::num::NonZeroU32;
 use strum_macros::IntoStaticStr;
@@ -45,6 +45,15 @@ pub enum Module<'t> {
         attributes: AttributeMap<'t>,
     },
 
+    /// Lists pages matching the given query.
+    ///
+    /// Each page is rendered using the template, after substituting
+    /// variables such as `%%title%%` with the page's values.
+    ListPages {
+        query: ListPagesQuery<'t>,
+        template: Cow<'t, str>,
+    },
+
     /// Lists the structure of pages as connected by parenthood.
     ///
     /// Shows the hierarchy of parent relationships present on the given page.
@@ -81,6 +90,10 @@ impl Module<'_> {
                 button_text: option_string_to_owned(button_text),
                 attributes: attributes.to_owned(),
             },
+            Module::ListPages { query, template } => Module::ListPages {
+                query: query.to_owned(),
+                template: string_to_owned(template),
+            },
             Module::PageTree {
                 root,
                 show_root,
@@ -0,0 +1,125 @@
+/*
+ * tree/span.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Utilities for working with the source spans of elements.
+//!
+//! These are only present if the tree was parsed with
+//! [`WikitextSettings::record_spans`] enabled, see [`Element::Spanned`].
+//!
+//! [`WikitextSettings::record_spans`]: crate::settings::WikitextSettings::record_spans
+
+use super::element::Element;
+use super::list::ListItem;
+use crate::tokenizer::offset_index;
+use crate::utf16::Utf16IndexMap;
+use std::slice;
+
+/// Calls the given function on each element, and then on all elements nested within it.
+///
+/// Since the function is called first, it may replace the element it is given,
+/// in which case the children of the replacement are visited instead.
+pub fn visit_elements_mut<'t, F>(elements: &mut [Element<'t>], f: &mut F)
+where
+    F: FnMut(&mut Element<'t>),
+{
+    for element in elements {
+        f(element);
+
+        match element {
+            Element::Container(container) => {
+                visit_elements_mut(container.elements_mut(), f)
+            }
+            Element::Table(table) => {
+                for row in &mut table.rows {
+                    for cell in &mut row.cells {
+                        visit_elements_mut(&mut cell.elements, f);
+                    }
+                }
+            }
+            Element::TabView(tabs) => {
+                for tab in tabs {
+                    visit_elements_mut(&mut tab.elements, f);
+                }
+            }
+            Element::List { items, .. } => {
+                for item in items {
+                    match item {
+                        ListItem::Elements { elements, .. } => {
+                            visit_elements_mut(elements, f);
+                        }
+                        ListItem::SubList { element } => {
+                            visit_elements_mut(slice::from_mut(element.as_mut()), f);
+                        }
+                    }
+                }
+            }
+            Element::DefinitionList(items) => {
+                for item in items {
+                    visit_elements_mut(&mut item.key, f);
+                    visit_elements_mut(&mut item.value, f);
+                }
+            }
+            Element::BibliographyBlock { entries, .. } => {
+                for entry in entries {
+                    visit_elements_mut(&mut entry.contents, f);
+                }
+            }
+            Element::Anchor { elements, .. }
+            | Element::Collapsible { elements, .. }
+            | Element::Color { elements, .. }
+            | Element::Include { elements, .. } => visit_elements_mut(elements, f),
+            Element::Spanned { element, .. } => {
+                visit_elements_mut(slice::from_mut(element.as_mut()), f);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Converts the spans of all elements from UTF-8 byte indices into UTF-16 ones.
+pub fn spans_to_utf16(elements: &mut [Element], map: &Utf16IndexMap) {
+    visit_elements_mut(elements, &mut |element| {
+        if let Element::Spanned { span, .. } = element {
+            span.start = map.get_index(span.start);
+            span.end = map.get_index(span.end);
+        }
+    });
+}
+
+/// Moves the spans of all elements by the given number of bytes.
+pub fn offset_spans(elements: &mut [Element], offset: isize) {
+    visit_elements_mut(elements, &mut |element| {
+        if let Element::Spanned { span, .. } = element {
+            span.start = offset_index(span.start, offset);
+            span.end = offset_index(span.end, offset);
+        }
+    });
+}
+
+/// Removes all spans, leaving the elements which were wrapped.
+#[cfg(test)]
+pub fn strip_spans(elements: &mut [Element]) {
+    visit_elements_mut(elements, &mut |element| {
+        while let Element::Spanned { element: inner, .. } = element {
+            let inner = std::mem::replace(inner.as_mut(), Element::HorizontalRule);
+            *element = inner;
+        }
+    });
+}
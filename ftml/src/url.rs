@@ -56,10 +56,10 @@ pub fn is_url(url: &str) -> bool {
     false
 }
 
-pub fn normalize_link<'a>(
-    link: &'a LinkLocation<'a>,
-    helper: &dyn BuildSiteUrl,
-) -> Cow<'a, str> {
+pub fn normalize_link<'a, B>(link: &'a LinkLocation<'a>, helper: &B) -> Cow<'a, str>
+where
+    B: BuildSiteUrl + ?Sized,
+{
     match link {
         LinkLocation::Url(url) => normalize_href(url),
         LinkLocation::Page(page_ref) => {
@@ -0,0 +1,99 @@
+/*
+ * wasm/incremental.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::page_info::PageInfo;
+use super::parsing::ParseOutcome;
+use super::prelude::*;
+use super::settings::WikitextSettings;
+use super::tokenizer::Tokenization;
+use crate::parsing::IncrementalParse as RustIncrementalParse;
+use crate::tokenizer::TextEdit as RustTextEdit;
+
+// Wrapper structures
+
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct IncrementalParse {
+    inner: RustIncrementalParse,
+    tokens: Tokenization,
+    page_info: PageInfo,
+    settings: WikitextSettings,
+}
+
+#[wasm_bindgen]
+impl IncrementalParse {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        tokens: Tokenization,
+        page_info: PageInfo,
+        settings: WikitextSettings,
+    ) -> IncrementalParse {
+        let inner =
+            RustIncrementalParse::new(tokens.get(), page_info.get(), settings.get());
+
+        IncrementalParse {
+            inner,
+            tokens,
+            page_info,
+            settings,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn tokens(&self) -> Tokenization {
+        self.tokens.copy()
+    }
+
+    #[wasm_bindgen]
+    pub fn outcome(&self) -> ParseOutcome {
+        let (syntax_tree, warnings) = self.inner.outcome().clone().into();
+
+        ParseOutcome::from_owned(
+            self.tokens.get(),
+            self.settings.get(),
+            syntax_tree,
+            warnings,
+        )
+    }
+
+    /// Replaces the given range of the text, then parses it again.
+    ///
+    /// The range is in UTF-16 indices, like Javascript strings.
+    #[wasm_bindgen]
+    pub fn update(
+        &mut self,
+        start: usize,
+        end: usize,
+        replacement: String,
+    ) -> Result<ParseOutcome, JsValue> {
+        let (tokens, range) = self.tokens.apply_edit(start, end, &replacement)?;
+        let edit = RustTextEdit::new(range, &replacement);
+
+        self.inner.update(
+            tokens.get(),
+            &edit,
+            self.page_info.get(),
+            self.settings.get(),
+        );
+
+        self.tokens = tokens;
+        Ok(self.outcome())
+    }
+}
@@ -0,0 +1,92 @@
+/*
+ * wasm/lint.rs
+ *
+ * ftml - Library to parse Wikidot text
+ * Copyright (C) 2019-2022 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::page_info::PageInfo;
+use super::prelude::*;
+use super::settings::WikitextSettings;
+use super::tokenizer::Tokenization;
+use crate::lint::Diagnostic as RustDiagnostic;
+use crate::settings::WikitextSettings as RustWikitextSettings;
+use crate::utf16::Utf16IndexMap;
+
+// Typescript declarations
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+
+export interface IDiagnostic {
+    kind: string;
+    severity: "error" | "warning" | "info" | "hint";
+    span: {
+        start: number;
+        end: number;
+    } | null;
+    message: string;
+    fix: {
+        description: string;
+        edit: {
+            span: {
+                start: number;
+                end: number;
+            };
+            text: string;
+        } | null;
+    };
+}
+
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "IDiagnostic[]")]
+    pub type IDiagnosticArray;
+}
+
+// Exported functions
+
+/// Checks the given wikitext for problems.
+///
+/// The tokens are parsed again with source spans recorded, so that
+/// each diagnostic has a location. These are in UTF-16 indices.
+#[wasm_bindgen]
+pub fn lint(
+    tokens: Tokenization,
+    page_info: PageInfo,
+    settings: WikitextSettings,
+) -> Result<IDiagnosticArray, JsValue> {
+    let tokenization = tokens.get();
+    let page_info = page_info.get();
+    let settings = RustWikitextSettings {
+        record_spans: true,
+        ..settings.get().clone()
+    };
+
+    let (syntax_tree, _) = crate::parse(tokenization, page_info, &settings).into();
+    let diagnostics = crate::lint(tokenization, &syntax_tree, &settings);
+
+    // Convert diagnostics to use UTF-16 indices
+    let utf16_map = Utf16IndexMap::new(tokenization.full_text().inner());
+    let diagnostics: Vec<RustDiagnostic> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_utf16_indices(&utf16_map))
+        .collect();
+
+    rust_to_js!(diagnostics)
+}
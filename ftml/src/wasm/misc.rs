@@ -23,7 +23,75 @@
 use super::prelude::*;
 use crate::info;
 
+// Typescript declarations
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_APPEND_CONTENT: &str = r#"
+
+export interface IArgumentInfo {
+    name: string;
+    type: "string" | "int" | "float" | "bool";
+    values: (string | number | boolean)[];
+    "min-value": number | null;
+    "max-value": number | null;
+    default: string | number | boolean | null;
+}
+
+export interface IBlockInfo {
+    name: string;
+    deprecated: boolean;
+    aliases: string[];
+    "exclude-name": boolean;
+    "accepts-star": boolean;
+    "accepts-score": boolean;
+    "accepts-newlines": boolean;
+    head: "none" | "value" | "map" | "value+map";
+    body: "none" | "raw" | "elements" | "other";
+    "html-attributes": boolean;
+    "html-output":
+        | { type: "html"; tag: string; class: string | null }
+        | { type: "none" }
+        | { type: "css" }
+        | { type: "other" };
+    special: "module" | "include-elements" | "include-messy" | null;
+    arguments: IArgumentInfo[];
+}
+
+export interface IModuleInfo {
+    name: string;
+    deprecated: boolean;
+    aliases: string[];
+    body: "none" | "raw" | "elements" | "other";
+    "html-attributes": boolean;
+    arguments: IArgumentInfo[];
+}
+
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "IBlockInfo[]")]
+    pub type IBlockInfoArray;
+
+    #[wasm_bindgen(typescript_type = "IModuleInfo[]")]
+    pub type IModuleInfoArray;
+}
+
+// Exported functions
+
 #[wasm_bindgen]
 pub fn version() -> String {
     info::VERSION.clone()
 }
+
+/// Gets information about all blocks, such as their names and arguments.
+#[wasm_bindgen]
+pub fn blocks() -> Result<IBlockInfoArray, JsValue> {
+    rust_to_js!(info::blocks())
+}
+
+/// Gets information about all modules, such as their names and arguments.
+#[wasm_bindgen]
+pub fn modules() -> Result<IModuleInfoArray, JsValue> {
+    rust_to_js!(info::modules())
+}
@@ -22,6 +22,10 @@
 mod macros;
 
 mod error;
+#[cfg(feature = "markdown-import")]
+mod import;
+mod incremental;
+mod lint;
 mod misc;
 mod page_info;
 mod parsing;
@@ -36,12 +40,17 @@ mod prelude {
     pub use wasm_bindgen::JsCast;
 }
 
-pub use self::misc::version;
+pub use self::incremental::IncrementalParse;
+pub use self::lint::lint;
+pub use self::misc::{blocks, modules, version};
 pub use self::parsing::{parse, ParseOutcome, SyntaxTree};
 pub use self::preproc::preprocess;
 pub use self::render::render_text;
 pub use self::settings::WikitextSettings;
-pub use self::tokenizer::{tokenize, Tokenization};
+pub use self::tokenizer::{retokenize, tokenize, Tokenization};
 
 #[cfg(feature = "html")]
 pub use self::render::render_html;
+
+#[cfg(feature = "markdown-import")]
+pub use self::import::markdown_to_wikitext;
@@ -25,6 +25,7 @@ use super::tokenizer::Tokenization;
 use crate::parsing::{
     ParseOutcome as RustParseOutcome, ParseWarning as RustParseWarning,
 };
+use crate::settings::WikitextSettings as RustWikitextSettings;
 use crate::tree::SyntaxTree as RustSyntaxTree;
 use crate::utf16::Utf16IndexMap;
 use crate::Tokenization as RustTokenization;
@@ -74,6 +75,32 @@ pub struct ParseOutcome {
     inner: Arc<RustParseOutcome<RustSyntaxTree<'static>>>,
 }
 
+impl ParseOutcome {
+    /// Wraps the output of parsing, converting its indices to UTF-16.
+    pub(crate) fn from_owned(
+        tokenization: &RustTokenization,
+        settings: &RustWikitextSettings,
+        syntax_tree: RustSyntaxTree<'static>,
+        warnings: Vec<RustParseWarning>,
+    ) -> Self {
+        // Convert source spans to use UTF-16 indices, if there are any
+        let syntax_tree = if settings.record_spans {
+            let utf16_map = Utf16IndexMap::new(tokenization.full_text().inner());
+            syntax_tree.to_utf16_indices(&utf16_map)
+        } else {
+            syntax_tree
+        };
+
+        // Convert warnings to use UTF-16 indices
+        let warnings = convert_warnings_utf16(tokenization, warnings);
+
+        // Create inner wrapper
+        let inner = Arc::new(RustParseOutcome::new(syntax_tree, warnings));
+
+        ParseOutcome { inner }
+    }
+}
+
 #[wasm_bindgen]
 impl ParseOutcome {
     #[wasm_bindgen]
@@ -142,13 +169,12 @@ pub fn parse(
     // safely passed to JS, where it will live for an unknown time.
     let syntax_tree = syntax_tree.to_owned();
 
-    // Convert warnings to use UTF-16 indices
-    let warnings = convert_warnings_utf16(tokenization, warnings);
-
-    // Create inner wrapper
-    let inner = Arc::new(RustParseOutcome::new(syntax_tree, warnings));
-
-    Ok(ParseOutcome { inner })
+    Ok(ParseOutcome::from_owned(
+        tokenization,
+        settings,
+        syntax_tree,
+        warnings,
+    ))
 }
 
 // Utility functions
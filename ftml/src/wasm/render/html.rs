@@ -50,6 +50,7 @@ export interface IHtmlMeta {
 
 export interface IBacklinks {
     included_pages: string[];
+    included_elements: string[];
     internal_links: string[];
     external_links: string[];
 }
@@ -117,7 +118,9 @@ pub fn render_html(
     let tree = syntax_tree.get();
     let page_info = page_info.get();
     let settings = settings.get();
-    let html = HtmlRender.render(tree, page_info, settings);
+    let html = HtmlRender::default()
+        .source_spans(settings.record_spans)
+        .render(tree, page_info, settings);
 
     HtmlOutput {
         inner: Arc::new(html),
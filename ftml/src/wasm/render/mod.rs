@@ -42,7 +42,7 @@ pub fn render_text(
     let tree = syntax_tree.get();
     let page_info = page_info.get();
     let settings = settings.get();
-    let text = TextRender.render(tree, page_info, settings);
+    let text = TextRender::default().render(tree, page_info, settings);
 
     text
 }
@@ -32,9 +32,30 @@ const TS_APPEND_CONTENT: &str = r#"
 
 export interface IWikitextSettings {
     mode: WikitextMode;
-    enable_page_syntax: boolean;
-    use_true_ids: boolean;
-    allow_local_paths: boolean;
+    'enable-page-syntax': boolean;
+    'use-include-compatibility': boolean;
+    'max-include-depth'?: number;
+    'use-true-ids': boolean;
+    'isolate-user-ids': boolean;
+    'allow-local-paths': boolean;
+    'record-spans'?: boolean;
+    interwiki: { [prefix: string]: string };
+    urls?: IUrlSettings;
+    css?: ICssSettings;
+}
+
+export interface IUrlSettings {
+    scheme: string;
+    main_domain: string;
+    files_domain: string;
+    path_sites: boolean;
+    custom_domains: { [site: string]: string };
+}
+
+export interface ICssSettings {
+    allowed_at_rules: string[];
+    allowed_url_schemes: string[];
+    scope_class: string | null;
 }
 
 export type WikitextMode =
@@ -98,4 +119,18 @@ impl WikitextSettings {
             inner: Arc::new(RustWikitextSettings::from_mode(rust_mode)),
         })
     }
+
+    /// Returns a copy of these settings, with source span recording set as given.
+    ///
+    /// When enabled, parsed syntax trees have spans in UTF-16 indices,
+    /// and rendered HTML has `data-source-start` and `data-source-end` attributes.
+    #[wasm_bindgen]
+    pub fn with_record_spans(&self, value: bool) -> WikitextSettings {
+        let mut settings = RustWikitextSettings::clone(&self.inner);
+        settings.record_spans = value;
+
+        WikitextSettings {
+            inner: Arc::new(settings),
+        }
+    }
 }
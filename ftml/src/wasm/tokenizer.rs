@@ -20,9 +20,11 @@
 
 use super::prelude::*;
 use crate::parsing::ExtractedToken as RustExtractedToken;
+use crate::tokenizer::TextEdit as RustTextEdit;
 use crate::utf16::Utf16IndexMap;
 use crate::Tokenization as RustTokenization;
 use self_cell::self_cell;
+use std::ops::Range;
 use std::sync::Arc;
 
 // Typescript declarations
@@ -73,6 +75,37 @@ impl Tokenization {
         self.inner.borrow_dependent()
     }
 
+    /// Applies an edit to the text, then tokenizes it.
+    ///
+    /// The edit range is in UTF-16 indices, and the range in
+    /// UTF-8 byte indices is returned with the new tokenization.
+    pub(crate) fn apply_edit(
+        &self,
+        start: usize,
+        end: usize,
+        replacement: &str,
+    ) -> Result<(Tokenization, Range<usize>), JsValue> {
+        let text = self.inner.borrow_owner();
+        let start = utf16_to_utf8_index(text, start)?;
+        let end = utf16_to_utf8_index(text, end)?;
+
+        if start > end {
+            return Err(JsValue::from_str("Edit range start is after its end"));
+        }
+
+        let edit = RustTextEdit::new(start..end, replacement);
+        let previous = self.get();
+        let inner = TokenizationInner::new(edit.apply(text), |text: &String| {
+            crate::retokenize(previous, &edit, text)
+        });
+
+        let tokenization = Tokenization {
+            inner: Arc::new(inner),
+        };
+
+        Ok((tokenization, start..end))
+    }
+
     #[wasm_bindgen]
     pub fn copy(&self) -> Tokenization {
         Tokenization {
@@ -103,8 +136,46 @@ pub fn tokenize(text: String) -> Tokenization {
     }
 }
 
+#[wasm_bindgen]
+pub fn retokenize(
+    tokens: Tokenization,
+    start: usize,
+    end: usize,
+    replacement: String,
+) -> Result<Tokenization, JsValue> {
+    let (tokenization, _) = tokens.apply_edit(start, end, &replacement)?;
+    Ok(tokenization)
+}
+
 // Utility functions
 
+fn utf16_to_utf8_index(text: &str, index: usize) -> Result<usize, JsValue> {
+    let mut utf16_index = 0;
+
+    for (utf8_index, ch) in text.char_indices() {
+        if utf16_index >= index {
+            break;
+        }
+
+        utf16_index += ch.len_utf16();
+        if utf16_index == index {
+            return Ok(utf8_index + ch.len_utf8());
+        }
+    }
+
+    if index == 0 {
+        return Ok(0);
+    }
+
+    let message = format!(
+        "UTF-16 index out of range or not on a character boundary: {} (text length {})",
+        index,
+        text.len(),
+    );
+
+    Err(JsValue::from_str(&message))
+}
+
 fn convert_tokens_utf16<'a>(
     tokenization: &'a RustTokenization,
 ) -> Vec<RustExtractedToken<'a>> {
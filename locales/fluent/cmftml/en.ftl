@@ -63,8 +63,22 @@ cmftml-lint =
 
   .no-such-page = The page '{ $slice }' doesn't exist.
 
+  .include-depth-exceeded = This include is nested too deeply, and won't be rendered.
+
+  .include-failed = The page '{ $slice }' couldn't be fetched to be included.
+
   .invalid-url = The URL '{ $slice }' is invalid.
 
+  .css-disallowed-at-rule = This CSS at-rule isn't permitted, and was removed.
+
+  .css-disallowed-url = This CSS contains a URL which isn't permitted, and was removed.
+
+  .css-disallowed-property = This CSS property isn't permitted, and was removed.
+
+  .css-disallowed-function = This CSS function isn't permitted, and was removed.
+
+  .css-malformed = This CSS is malformed, and was removed.
+
 ## Block Acceptance
 
 cmftml-accepts =
@@ -9,3 +9,35 @@ wiki-page-last-edit = last-edited: { $date } ({ $days ->
   [1] yesterday
   *[other] { $days } days ago
 })
+
+## Rendered Page Elements
+
+wiki-page-button-copy-clipboard = Copy to Clipboard
+
+wiki-page-collapsible-open = + open block
+
+wiki-page-collapsible-hide = - hide block
+
+wiki-page-table-of-contents = Table of Contents
+
+wiki-page-footnote = Footnote
+
+wiki-page-footnote-block-title = Footnotes
+
+wiki-page-bibliography-reference = Reference
+
+wiki-page-bibliography-block-title = Bibliography
+
+wiki-page-image-context-bad = No images in this context
+
+wiki-page-gallery-label = Image gallery
+
+wiki-page-include-cycle = The page { $page } includes itself.
+
+wiki-page-include-depth-exceeded = The page { $page } is nested too deeply to be included.
+
+wiki-page-button-join-site = Join this site
+
+wiki-page-button-apply-site = Apply to join this site
+
+wiki-page-rating = rating:
@@ -9,6 +9,7 @@ export interface Block {
   "exclude-name"?: boolean
   "deprecated"?: boolean
   "aliases"?: string[]
+  "deprecated-aliases"?: string[]
   "accepts-star"?: boolean
   "accepts-score"?: boolean
   "accepts-newlines"?: boolean
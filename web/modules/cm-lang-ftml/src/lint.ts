@@ -43,7 +43,14 @@ const warningConfig: Record<string, "info" | "warning" | "error" | null> = {
   "no-such-module": "error",
   "module-missing-name": "error",
   "no-such-page": "error",
-  "invalid-url": "warning"
+  "include-depth-exceeded": "error",
+  "include-failed": "error",
+  "invalid-url": "warning",
+  "css-disallowed-at-rule": "warning",
+  "css-disallowed-url": "warning",
+  "css-disallowed-property": "warning",
+  "css-disallowed-function": "warning",
+  "css-malformed": "warning"
 }
 
 // generate warnings from configuration
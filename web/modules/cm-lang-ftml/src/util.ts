@@ -2,7 +2,11 @@ import { hasSigil } from "@wikijump/util"
 import type { Block, Module } from "./data/types"
 
 export function aliasesRaw([name, block]: [string, Block | Module]) {
-  const aliases = block.aliases ?? []
+  const aliases = [
+    ...(block.aliases ?? []),
+    // @ts-ignore TS doesn't like a `[]` property access on a union sometimes
+    ...(block["deprecated-aliases"] ?? [])
+  ]
   // @ts-ignore TS doesn't like a `[]` property access on a union sometimes
   if (block["exclude-name"]) return [...aliases]
   return [name, ...aliases]